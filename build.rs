@@ -0,0 +1,23 @@
+use std::{path::Path, process::Command};
+
+// Build info for `limp version --verbose`
+fn main() {
+    println!(
+        "cargo:rustc-env=LIMP_TARGET={}",
+        std::env::var("TARGET").unwrap_or_default()
+    );
+    let sha = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string());
+    if let Some(sha) = sha {
+        println!("cargo:rustc-env=LIMP_GIT_SHA={}", sha);
+    }
+    for path in [".git/HEAD", ".git/refs/heads"] {
+        if Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+    }
+}
@@ -1,74 +1,885 @@
-use std::io::{Read, Write};
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::{IsTerminal, Write},
+    path::PathBuf,
+};
 
-use clap::{Arg, ArgMatches, Command};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use clap_complete::Shell;
 
 use crate::{
+    completions,
+    context::Context,
+    crates::{CratesIoSearch, SearchSort, Version},
+    doctor,
     error::LimpError,
-    files::{config_path, create_project, find_toml, open},
-    storage::{JsonDependency, JsonStorage},
+    files::{find_toml, open, ProjectKind},
+    history,
+    logging::{self, Level},
+    manifest::CargoManifest,
+    ops::{self, AddOptions, InitOptions, LinkOptions, NewOptions, SyncChoice},
+    parser::Vars,
+    report::{Format, Reporter, Style, Verbosity},
+    scaffold::{self, Scaffold},
+    snippets,
+    storage::{
+        DependencyKind, DependencySource, FeatureMismatch, GroupKey, JsonDependency, JsonStorage,
+        SortKey, UpdatePolicy,
+    },
+    templates::Template,
+    version,
 };
+#[cfg(feature = "sqlite")]
+use crate::{sqlite, storage::StorageFormat};
 pub enum Action {
     Init {
         name: String,
         dependencies: Option<Vec<String>>,
+        kind: ProjectKind,
+        workspace: Option<Vec<String>>,
+        template: Option<String>,
+        vars: Vec<(String, String)>,
+        check: bool,
+        /// package name, defaults to the last component of `name`
+        package: Option<String>,
+        /// from `--vcs`, the `git_init` setting when unset
+        git: Option<bool>,
+        scaffold: Scaffold,
+        /// standalone snippets to merge into the entry file
+        snippets: Vec<String>,
+        /// extra binaries as `name` or `name:snippet`
+        bins: Vec<String>,
+        /// project type, `--type`
+        preset: Option<String>,
+        /// `--manifest-extra key=value`
+        manifest_extras: Vec<(String, String)>,
+        /// asked for on a terminal when unset
+        description: Option<String>,
+        repository: Option<String>,
+        keywords: Vec<String>,
+        /// run `rustfmt` on the generated sources
+        fmt: bool,
+        /// git repository to start from
+        from_git: Option<String>,
+        branch: Option<String>,
+        /// run the repository's own post-init hooks
+        template_hooks: bool,
     },
     NewDependency {
         name: String,
         version: Option<String>,
         features: Option<Vec<String>>,
         path_to_snippet: Option<String>,
+        kind: DependencyKind,
+        allow_yanked: bool,
+        msrv: Option<String>,
+        registry: Option<String>,
+        source: DependencySource,
+        no_default_features: bool,
+        optional: bool,
+        rename: Option<String>,
+        /// choose the features interactively
+        pick_features: bool,
+        allow_license_violation: bool,
+        /// replace a stored dependency of the same name
+        force: bool,
+        /// keep a stored dependency of the same name
+        if_missing: bool,
+        /// list of dependencies to store instead of `name`, `-` for stdin
+        from_file: Option<String>,
     },
     Delete {
         name: String,
+        /// keep the snippets as ones of their own instead of trashing them
+        keep_snippet: bool,
     },
     Add {
+        /// `name` or `name@version`
+        names: Vec<String>,
+        /// `crate:feature`, or `feature` with a single crate
+        features: Vec<String>,
+        kind: Option<DependencyKind>,
+        no_default_features: bool,
+        optional: bool,
+        rename: Option<String>,
+        with_snippet: bool,
+        module: Option<String>,
+        allow_license_violation: bool,
+        /// workspace member to add to
+        package: Option<String>,
+        /// add to `[workspace.dependencies]`
+        workspace: bool,
+    },
+    Remove {
+        name: String,
+    },
+    Import {
+        path: Option<String>,
+        bundle: Option<String>,
+        on_conflict: Option<bool>,
+    },
+    Export {
+        dir: String,
+    },
+    Search {
+        /// `None` with `mine` lists all owned crates
+        query: Option<String>,
+        /// search the crates of the logged in user
+        mine: bool,
+        sort: SearchSort,
+    },
+    Info {
+        name: String,
+        versions: usize,
+    },
+    Which {
+        name: String,
+    },
+    Deps {
+        name: String,
+        /// the latest version when unset
+        version: Option<String>,
+        recursive: bool,
+    },
+    TemplateSave {
+        name: String,
+    },
+    TemplateList,
+    SnippetList,
+    SnippetShow {
+        name: String,
+    },
+    SnippetEdit {
+        name: String,
+    },
+    SnippetRename {
+        from: String,
+        to: String,
+    },
+    SnippetRemove {
+        name: String,
+    },
+    SnippetRestore {
+        name: String,
+    },
+    SnippetTrash,
+    /// `None` refreshes every snippet downloaded from a URL
+    SnippetRefresh {
+        /// a snippet, or a dependency whose snippets are refreshed
+        name: Option<String>,
+    },
+    SnippetVerify {
+        /// also compare with the files and URLs the snippets came from
+        sources: bool,
+    },
+    Link {
+        name: String,
+        /// snippet file or URL
+        source: String,
+        tags: Option<Vec<String>>,
+        variant: Option<String>,
+    },
+    SnippetFind {
+        keyword: String,
+    },
+    SnippetAdd {
+        name: String,
+        path: String,
+    },
+    Apply {
+        name: String,
+        module: Option<String>,
+        /// merge into the entry file instead of a module
+        entry: bool,
+    },
+    Update {
+        names: Vec<String>,
+        dry_run: bool,
+        jobs: Option<usize>,
+        /// list the skipped releases of every change
+        changes: bool,
+        /// what to do when stored features are gone from the latest version
+        on_feature_mismatch: FeatureMismatch,
+        /// move to pre-releases, from `--pre`
+        pre: bool,
+    },
+    ConfigGet {
+        key: String,
+    },
+    ConfigSet {
+        key: String,
+        value: String,
+    },
+    ConfigList,
+    List {
+        sort: SortKey,
+        filter: Option<String>,
+        with_snippets: bool,
+        /// group the dependencies instead of listing them flat
+        tree: Option<GroupKey>,
+        /// print bare names, one per line
+        names_only: bool,
+    },
+    Completions {
+        shell: Shell,
+    },
+    CompleteDependencies,
+    Doctor,
+    Gc {
+        dry_run: bool,
+    },
+    Sync {
+        /// from `--prefer`/`--apply-all`, asked per dependency when unset
+        choice: Option<SyncChoice>,
+    },
+    Pin {
+        name: String,
+        /// false for `unpin`
+        pinned: bool,
+    },
+    Constrain {
         name: String,
+        /// `None` for `unconstrain`
+        constraint: Option<String>,
+    },
+    Prerelease {
+        name: String,
+        /// false with `--off`
+        allow: bool,
+    },
+    Rename {
+        old: String,
+        new: String,
+        /// look the new name up in the registry
+        resolve: bool,
+    },
+    Version {
+        /// with the commit and target
+        verbose: bool,
     },
-    Update,
-    List,
+    SelfUpdate {
+        /// run `cargo install` instead of printing instructions
+        install: bool,
+    },
+    SelfVersion {
+        /// fail when a newer release is out
+        check: bool,
+    },
+    CacheInfo,
+    CacheClear,
+    CacheWarm {
+        jobs: Option<usize>,
+    },
+    History,
+    Stats,
+    Undo,
+    /// `None` reads the token from stdin
+    Login {
+        token: Option<String>,
+    },
+}
+
+const DEFAULT_INFO_VERSIONS: &str = "5";
+
+fn kind_args() -> [Arg; 2] {
+    [
+        Arg::new("dev")
+            .required(false)
+            .long("dev")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("build")
+            .help("Use as a dev-dependency"),
+        Arg::new("build")
+            .required(false)
+            .long("build")
+            .action(ArgAction::SetTrue)
+            .help("Use as a build-dependency"),
+    ]
+}
+
+/// Flags changing how a dependency is declared in Cargo.toml.
+fn entry_args() -> [Arg; 3] {
+    [
+        Arg::new("no_default_features")
+            .required(false)
+            .long("no-default-features")
+            .action(ArgAction::SetTrue)
+            .help("Disable the crate's default features"),
+        Arg::new("optional")
+            .required(false)
+            .long("optional")
+            .action(ArgAction::SetTrue)
+            .help("Declare as an optional dependency"),
+        Arg::new("rename")
+            .required(false)
+            .long("rename")
+            .help("Declare under another name, with `package` naming the crate"),
+    ]
+}
+
+fn license_arg() -> Arg {
+    Arg::new("allow_license_violation")
+        .required(false)
+        .long("allow-license-violation")
+        .action(ArgAction::SetTrue)
+        .help("Only warn about licenses the [licenses] policy in config.toml fails on")
+}
+
+/// Asks a yes/no question on the terminal. Returns `default` when stdin is
+/// not a terminal or the answer is empty.
+fn confirm(question: &str, default: bool) -> Result<bool, LimpError> {
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        return Ok(default);
+    }
+    eprint!("{} [{}] ", question, if default { "Y/n" } else { "y/N" });
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    stdin.read_line(&mut answer)?;
+    Ok(match answer.trim().to_lowercase().as_str() {
+        "y" | "yes" => true,
+        "n" | "no" => false,
+        _ => default,
+    })
+}
+
+/// Lists what is about to be deleted and asks `question` on the terminal,
+/// unless `yes` is set. Declining, or having no terminal to ask on, cancels.
+fn confirm_deletion(yes: bool, question: &str, what: &[String]) -> Result<(), LimpError> {
+    if yes {
+        return Ok(());
+    }
+    if std::io::stdin().is_terminal() {
+        what.iter().for_each(|line| eprintln!("  {}", line));
+    }
+    if confirm(question, false)? {
+        Ok(())
+    } else {
+        Err(LimpError::Cancelled(question.to_string()))
+    }
+}
+
+/// Asks for a line of text on the terminal. Returns `None` when stdin is not
+/// a terminal or the answer is empty.
+fn ask(question: &str) -> Result<Option<String>, LimpError> {
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        return Ok(None);
+    }
+    eprint!("{} ", question);
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    stdin.read_line(&mut answer)?;
+    let answer = answer.trim();
+    Ok((!answer.is_empty()).then(|| answer.to_string()))
+}
+
+/// Reads a token from stdin like `cargo login`, asking for it on a terminal.
+fn read_token() -> Result<String, LimpError> {
+    let stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        eprint!("Paste your token from https://crates.io/me: ");
+        std::io::stderr().flush()?;
+    }
+    let mut token = String::new();
+    stdin.read_line(&mut token)?;
+    Ok(token.trim().to_string())
+}
+
+/// Asks what `limp sync` should do about a differing version, skipping it
+/// when there is no terminal to ask.
+fn choose_sync(project: &JsonDependency, stored: &JsonDependency) -> Result<SyncChoice, LimpError> {
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        return Ok(SyncChoice::Skip);
+    }
+    eprint!(
+        "{}: project {}, stored {}. [b]ump project, [u]pdate storage or [s]kip? [s] ",
+        project.name, project.version, stored.version
+    );
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    stdin.read_line(&mut answer)?;
+    Ok(match answer.trim().to_lowercase().as_str() {
+        "b" | "bump" => SyncChoice::BumpProject,
+        "u" | "update" => SyncChoice::UpdateStorage,
+        _ => SyncChoice::Skip,
+    })
+}
+
+/// Warns about what `limp new` let through for a stored dependency.
+fn new_warnings(reporter: &dyn Reporter, report: &ops::NewReport) {
+    for feature in report.meta_features.iter() {
+        reporter.warning(&format!(
+            "feature `{}` of {} enables all of its features",
+            feature, report.dependency.name
+        ))
+    }
+    for violation in report.license_violations.iter() {
+        reporter.warning(violation);
+    }
+}
+
+/// Lets the user toggle the features of `version` by number, starting from
+/// its default features, which are kept when there is no terminal to ask.
+fn pick_features(version: &Version) -> Result<Vec<String>, LimpError> {
+    let graph = version.feature_graph();
+    let available = graph
+        .available()
+        .into_iter()
+        .filter(|f| f != "default")
+        .collect::<Vec<String>>();
+    let mut picked = graph.defaults().into_iter().collect::<BTreeSet<String>>();
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() || available.is_empty() {
+        return Ok(picked.into_iter().collect());
+    }
+    loop {
+        for (i, feature) in available.iter().enumerate() {
+            let mark = if picked.contains(feature) { "x" } else { " " };
+            eprintln!("{:>3} [{}] {}", i + 1, mark, feature);
+        }
+        eprint!("Toggle features by number (e.g. 1 4) or press enter to accept: ");
+        std::io::stderr().flush()?;
+        let mut answer = String::new();
+        stdin.read_line(&mut answer)?;
+        if answer.trim().is_empty() {
+            break;
+        }
+        for n in answer
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter_map(|n| n.parse::<usize>().ok())
+        {
+            if let Some(feature) = n.checked_sub(1).and_then(|i| available.get(i)) {
+                if !picked.remove(feature) {
+                    picked.insert(feature.clone());
+                }
+            }
+        }
+    }
+    Ok(picked.into_iter().collect())
+}
+
+fn dependency_source(args: &ArgMatches) -> DependencySource {
+    let get = |id: &str| args.get_one::<String>(id).cloned();
+    match (get("git"), get("path")) {
+        (Some(url), _) => DependencySource::Git {
+            url,
+            branch: get("branch"),
+            tag: get("tag"),
+            rev: get("rev"),
+        },
+        (None, Some(path)) => DependencySource::Path { path },
+        (None, None) => DependencySource::Registry,
+    }
+}
+
+fn dependency_kind(args: &ArgMatches) -> Option<DependencyKind> {
+    if args.get_flag("dev") {
+        Some(DependencyKind::Dev)
+    } else if args.get_flag("build") {
+        Some(DependencyKind::Build)
+    } else {
+        None
+    }
 }
 
 #[derive(Default)]
 pub struct CommandHandler {
     pub action: Option<Action>,
+    pub offline: bool,
+    pub format: Format,
+    pub verbosity: Verbosity,
+    pub no_color: bool,
+    /// storage directory from `--config-dir`, `LIMP_HOME` or the platform
+    /// default when unset
+    pub config_dir: Option<PathBuf>,
+    /// answer every prompt with yes
+    pub yes: bool,
+    /// fail instead of using the network or resolving unstored dependencies
+    pub frozen: bool,
+    /// `--timeout` in seconds, the configured timeouts when unset
+    pub timeout: Option<u64>,
+    /// `--log-level`, from `RUST_LOG` when unset
+    pub log_level: Option<Level>,
+    /// append the log to `limp.log` in the storage directory
+    pub log_file: bool,
 }
 impl CommandHandler {
     pub fn build() -> Command {
         Command::new("limp")
             .about("Limp is a simple CLI tool for managing your rust projects.")
-            .version("v0.2.0")
+            .version(version::VERSION)
             .subcommand_required(true)
+            .arg(
+                Arg::new("offline")
+                    .required(false)
+                    .long("offline")
+                    .global(true)
+                    .action(ArgAction::SetTrue)
+                    .help("Resolve crates from the local cache only"),
+            )
+            .arg(
+                Arg::new("format")
+                    .required(false)
+                    .long("format")
+                    .global(true)
+                    .value_parser(Format::NAMES)
+                    .default_value("text")
+                    .help("Output format"),
+            )
+            .arg(
+                Arg::new("quiet")
+                    .required(false)
+                    .short('q')
+                    .long("quiet")
+                    .global(true)
+                    .action(ArgAction::SetTrue)
+                    .help("Don't show progress"),
+            )
+            .arg(
+                Arg::new("verbose")
+                    .required(false)
+                    .short('v')
+                    .long("verbose")
+                    .action(ArgAction::Count)
+                    .help("Show every looked up crate, -vv with timings (before the command, e.g. limp -v update)"),
+            )
+            .arg(
+                Arg::new("no_color")
+                    .required(false)
+                    .long("no-color")
+                    .global(true)
+                    .action(ArgAction::SetTrue)
+                    .help("Don't color the output, also set by NO_COLOR"),
+            )
+            .arg(
+                Arg::new("config_dir")
+                    .required(false)
+                    .long("config-dir")
+                    .global(true)
+                    .value_name("DIR")
+                    .help("Keep dependencies, snippets and settings in DIR, overrides LIMP_HOME"),
+            )
+            .arg(
+                Arg::new("yes")
+                    .required(false)
+                    .short('y')
+                    .long("yes")
+                    .global(true)
+                    .action(ArgAction::SetTrue)
+                    .help("Answer yes to every prompt, e.g. to replace stored dependencies"),
+            )
+            .arg(
+                Arg::new("frozen")
+                    .required(false)
+                    .long("frozen")
+                    .global(true)
+                    .action(ArgAction::SetTrue)
+                    .help("Fail instead of using the network or resolving dependencies that aren't stored"),
+            )
+            .arg(
+                Arg::new("timeout")
+                    .required(false)
+                    .long("timeout")
+                    .global(true)
+                    .value_name("SECONDS")
+                    .value_parser(clap::value_parser!(u64))
+                    .help("Seconds to wait for the registry to connect and answer"),
+            )
+            .arg(
+                Arg::new("log_level")
+                    .required(false)
+                    .long("log-level")
+                    .global(true)
+                    .value_parser(Level::NAMES)
+                    .help("Log registry requests, cache lookups and file writes, overrides RUST_LOG"),
+            )
+            .arg(
+                Arg::new("log_file")
+                    .required(false)
+                    .long("log-file")
+                    .global(true)
+                    .action(ArgAction::SetTrue)
+                    .help("Append the log to limp.log in the storage directory instead of stderr"),
+            )
             .subcommand(
                 Command::new("init")
                     .about("Initialize a new project")
-                    .arg(Arg::new("name").required(true))
+                    .arg(
+                        Arg::new("name")
+                            .required(true)
+                            .help("Directory to create, also the package name unless --name is given"),
+                    )
+                    .arg(
+                        Arg::new("package")
+                            .required(false)
+                            .long("name")
+                            .conflicts_with("workspace")
+                            .help("Package name, defaults to the directory name"),
+                    )
+                    .arg(
+                        Arg::new("vcs")
+                            .required(false)
+                            .long("vcs")
+                            .value_parser(["git", "none"])
+                            .help("Initialize a git repository or none, overrides the git_init setting"),
+                    )
+                    .arg(
+                        Arg::new("bin")
+                            .required(false)
+                            .long("bin")
+                            .action(ArgAction::SetTrue)
+                            .conflicts_with("lib")
+                            .help("Create a binary crate (the default)"),
+                    )
                     .arg(
                         Arg::new("dependencies")
                             .required(false)
                             .short('d')
                             .long("dependencies")
                             .num_args(0..)
-                            .help("Optional dependencies"),
+                            .help("Optional dependencies, `name:variant` picks a snippet variant"),
+                    )
+                    .arg(
+                        Arg::new("lib")
+                            .required(false)
+                            .long("lib")
+                            .action(ArgAction::SetTrue)
+                            .help("Create a library crate instead of a binary"),
+                    )
+                    .arg(
+                        Arg::new("workspace")
+                            .required(false)
+                            .short('w')
+                            .long("workspace")
+                            .num_args(1..)
+                            .help("Create a workspace with the given members"),
+                    )
+                    .arg(
+                        Arg::new("template")
+                            .required(false)
+                            .short('t')
+                            .long("template")
+                            .conflicts_with("workspace")
+                            .help("Create the project from a saved template"),
+                    )
+                    .arg(
+                        Arg::new("var")
+                            .required(false)
+                            .long("var")
+                            .num_args(1)
+                            .action(ArgAction::Append)
+                            .value_parser(|s: &str| Vars::parse_assignment(s))
+                            .help("Template variable as key=value, can be repeated"),
+                    )
+                    .arg(
+                        Arg::new("check")
+                            .required(false)
+                            .long("check")
+                            .action(ArgAction::SetTrue)
+                            .help("Run cargo check on the new project and report broken snippets"),
+                    )
+                    .arg(
+                        Arg::new("license")
+                            .required(false)
+                            .long("license")
+                            .value_parser(scaffold::LICENSES)
+                            .help("Write a LICENSE file and set the license in Cargo.toml"),
+                    )
+                    .arg(
+                        Arg::new("readme")
+                            .required(false)
+                            .long("readme")
+                            .action(ArgAction::SetTrue)
+                            .help("Write a README.md"),
+                    )
+                    .arg(
+                        Arg::new("ci")
+                            .required(false)
+                            .long("ci")
+                            .value_parser(scaffold::CI)
+                            .help("Write a CI workflow that runs fmt, clippy and tests"),
+                    )
+                    .arg(
+                        Arg::new("with_examples")
+                            .required(false)
+                            .long("with-examples")
+                            .action(ArgAction::SetTrue)
+                            .help("Write an example in examples/basic.rs"),
+                    )
+                    .arg(
+                        Arg::new("with_benches")
+                            .required(false)
+                            .long("with-benches")
+                            .action(ArgAction::SetTrue)
+                            .help("Write a bench in benches/bench.rs without the test harness"),
+                    )
+                    .arg(
+                        Arg::new("with_integration_tests")
+                            .required(false)
+                            .long("with-integration-tests")
+                            .action(ArgAction::SetTrue)
+                            .help("Write an integration test in tests/integration.rs"),
+                    )
+                    .arg(
+                        Arg::new("snippets")
+                            .required(false)
+                            .long("snippets")
+                            .value_delimiter(',')
+                            .help("Standalone snippets to include, e.g. error,cli"),
+                    )
+                    .arg(
+                        Arg::new("bins")
+                            .required(false)
+                            .long("bins")
+                            .value_delimiter(',')
+                            .conflicts_with("workspace")
+                            .help("Extra binaries in src/bin, `name:snippet` starts one from a snippet, e.g. cli,server:tcp-server"),
+                    )
+                    .arg(
+                        Arg::new("type")
+                            .required(false)
+                            .long("type")
+                            .value_parser(scaffold::PRESETS)
+                            .conflicts_with_all(["template", "workspace", "bin", "lib"])
+                            .help("Start from a project type, or a saved template of the same name"),
+                    )
+                    .arg(
+                        Arg::new("manifest_extra")
+                            .required(false)
+                            .long("manifest-extra")
+                            .num_args(1)
+                            .action(ArgAction::Append)
+                            .value_parser(|s: &str| Vars::parse_assignment(s))
+                            .help("Cargo.toml setting as key=value over the [manifest] setting, e.g. profile.release.lto=true, can be repeated"),
+                    )
+                    .arg(
+                        Arg::new("description")
+                            .required(false)
+                            .long("description")
+                            .help("Package description, asked for on a terminal when not given"),
+                    )
+                    .arg(
+                        Arg::new("repository")
+                            .required(false)
+                            .long("repository")
+                            .help("URL of the package's repository"),
+                    )
+                    .arg(
+                        Arg::new("keywords")
+                            .required(false)
+                            .long("keywords")
+                            .value_delimiter(',')
+                            .help("Package keywords for crates.io, at most 5, e.g. cli,parser"),
+                    )
+                    .arg(
+                        Arg::new("fmt")
+                            .required(false)
+                            .long("fmt")
+                            .action(ArgAction::SetTrue)
+                            .help("Run rustfmt on the generated sources"),
+                    )
+                    .arg(
+                        Arg::new("from_git")
+                            .required(false)
+                            .long("from-git")
+                            .value_name("URL")
+                            .conflicts_with_all(["template", "type", "workspace", "bins"])
+                            .help("Start from a clone of a git template repository, rendering its placeholders"),
+                    )
+                    .arg(
+                        Arg::new("branch")
+                            .required(false)
+                            .long("branch")
+                            .requires("from_git")
+                            .help("Branch or tag of the --from-git repository"),
+                    )
+                    .arg(
+                        Arg::new("template_hooks")
+                            .required(false)
+                            .long("template-hooks")
+                            .action(ArgAction::SetTrue)
+                            .requires("from_git")
+                            .help("Run the post-init hooks in .limp/hooks of the --from-git repository"),
                     ),
             )
             .subcommand(
                 Command::new("new")
                     .about("Add a new dependency")
-                    .arg(Arg::new("name").required(true))
+                    .arg(
+                        Arg::new("name")
+                            .required_unless_present("from_file")
+                            .help("Crate name, or - to read a list from stdin"),
+                    )
+                    .arg(
+                        Arg::new("from_file")
+                            .required(false)
+                            .long("from-file")
+                            .conflicts_with_all([
+                                "name",
+                                "version",
+                                "features",
+                                "pick_features",
+                                "path_to_snippet",
+                                "rename",
+                                "git",
+                                "path",
+                            ])
+                            .help("Store every `name[@version] [features...]` line of a file"),
+                    )
                     .arg(
                         Arg::new("version")
                             .required(false)
                             .short('v')
                             .long("version")
-                            .help("Specify version"),
+                            .help("Specify version requirement (e.g. 1.0, ^1, ~0.12, \">=2, <3\", latest)"),
                     )
                     .arg(
                         Arg::new("path_to_snippet")
                             .required(false)
                             .short('p')
-                            .long("path")
+                            .long("snippet")
                             .help("Path to snippet"),
                     )
+                    .arg(
+                        Arg::new("git")
+                            .required(false)
+                            .long("git")
+                            .conflicts_with_all(["path", "registry"])
+                            .help("Use the crate from this git repository"),
+                    )
+                    .arg(
+                        Arg::new("branch")
+                            .required(false)
+                            .long("branch")
+                            .requires("git")
+                            .conflicts_with_all(["tag", "rev"])
+                            .help("Git branch"),
+                    )
+                    .arg(
+                        Arg::new("tag")
+                            .required(false)
+                            .long("tag")
+                            .requires("git")
+                            .conflicts_with("rev")
+                            .help("Git tag"),
+                    )
+                    .arg(
+                        Arg::new("rev")
+                            .required(false)
+                            .long("rev")
+                            .requires("git")
+                            .help("Git commit"),
+                    )
+                    .arg(
+                        Arg::new("path")
+                            .required(false)
+                            .long("path")
+                            .conflicts_with("registry")
+                            .help("Use the crate from this local directory"),
+                    )
                     .arg(
                         Arg::new("features")
                             .required(false)
@@ -76,21 +887,623 @@ impl CommandHandler {
                             .long("features")
                             .num_args(0..)
                             .help("Optional features"),
-                    ),
+                    )
+                    .arg(
+                        Arg::new("pick_features")
+                            .required(false)
+                            .long("pick-features")
+                            .action(ArgAction::SetTrue)
+                            .conflicts_with_all(["features", "no_default_features"])
+                            .help("Choose the features from a list, with the default ones marked"),
+                    )
+                    .arg(
+                        Arg::new("allow_yanked")
+                            .required(false)
+                            .long("allow-yanked")
+                            .action(ArgAction::SetTrue)
+                            .help("Accept a yanked version"),
+                    )
+                    .arg(
+                        Arg::new("msrv")
+                            .required(false)
+                            .long("msrv")
+                            .help("Pick the newest version supporting this rust version (e.g. 1.70)"),
+                    )
+                    .arg(
+                        Arg::new("registry")
+                            .required(false)
+                            .long("registry")
+                            .help("Registry from config.toml to resolve the crate from"),
+                    )
+                    .arg(
+                        Arg::new("force")
+                            .required(false)
+                            .long("force")
+                            .action(ArgAction::SetTrue)
+                            .help("Replace the dependency if it is already stored"),
+                    )
+                    .arg(
+                        Arg::new("if_missing")
+                            .required(false)
+                            .long("if-missing")
+                            .action(ArgAction::SetTrue)
+                            .conflicts_with("force")
+                            .help("Do nothing if the dependency is already stored"),
+                    )
+                    .arg(license_arg())
+                    .args(kind_args())
+                    .args(entry_args()),
             )
             .subcommand(
                 Command::new("del")
                     .about("Delete dependency")
-                    .arg(Arg::new("name").required(true)),
+                    .arg(Arg::new("name").required(true))
+                    .arg(
+                        Arg::new("keep_snippet")
+                            .required(false)
+                            .long("keep-snippet")
+                            .action(ArgAction::SetTrue)
+                            .help("Keep its snippets as ones of their own instead of moving them to the trash"),
+                    ),
             )
             .subcommand(
                 Command::new("add")
-                    .about("Add dependency to existing project")
+                    .about("Add dependencies to existing project")
+                    .arg(
+                        Arg::new("names")
+                            .required(true)
+                            .num_args(1..)
+                            .help("Dependencies to add, optionally as name@version"),
+                    )
+                    .arg(
+                        Arg::new("features")
+                            .required(false)
+                            .short('f')
+                            .long("features")
+                            .num_args(1..)
+                            .value_delimiter(',')
+                            .help("Extra features as crate:feature, e.g. serde:derive"),
+                    )
+                    .args(kind_args())
+                    .args(entry_args())
+                    .arg(
+                        Arg::new("with_snippet")
+                            .required(false)
+                            .long("with-snippet")
+                            .action(ArgAction::SetTrue)
+                            .help("Also insert the dependency's linked snippet"),
+                    )
+                    .arg(
+                        Arg::new("module")
+                            .required(false)
+                            .short('m')
+                            .long("module")
+                            .requires("with_snippet")
+                            .help("Put the snippet into src/<module>.rs instead of the entry file"),
+                    )
+                    .arg(license_arg())
+                    .arg(
+                        Arg::new("package")
+                            .required(false)
+                            .short('p')
+                            .long("package")
+                            .help("Workspace member to add to, by package or directory name"),
+                    )
+                    .arg(
+                        Arg::new("workspace")
+                            .required(false)
+                            .long("workspace")
+                            .action(ArgAction::SetTrue)
+                            .help("Add to [workspace.dependencies] and inherit them in the member"),
+                    ),
+            )
+            .subcommand(
+                Command::new("remove")
+                    .about("Remove dependency from existing project")
                     .arg(Arg::new("name").required(true)),
             )
-            .subcommand(Command::new("list").about("List dependencies"))
-            .subcommand(Command::new("update").about("Update dependencies"))
-            .subcommand(Command::new("version").about("Print version"))
+            .subcommand(
+                Command::new("import")
+                    .about("Import the dependencies of a Cargo.toml into storage")
+                    .arg(
+                        Arg::new("path")
+                            .required(false)
+                            .help("Path to Cargo.toml, the current project's by default"),
+                    )
+                    .arg(
+                        Arg::new("bundle")
+                            .required(false)
+                            .long("bundle")
+                            .conflicts_with("path")
+                            .help("Import a directory created by `limp export` instead"),
+                    )
+                    .arg(
+                        Arg::new("overwrite")
+                            .required(false)
+                            .long("overwrite")
+                            .action(ArgAction::SetTrue)
+                            .conflicts_with("keep")
+                            .help("Replace stored dependencies that differ without asking"),
+                    )
+                    .arg(
+                        Arg::new("keep")
+                            .required(false)
+                            .long("keep")
+                            .action(ArgAction::SetTrue)
+                            .help("Keep stored dependencies that differ without asking"),
+                    ),
+            )
+            .subcommand(
+                Command::new("export")
+                    .about("Export dependencies and snippets to a shareable directory")
+                    .arg(Arg::new("dir").required(true)),
+            )
+            .subcommand(
+                Command::new("search")
+                    .about("Search crates on crates.io")
+                    .arg(Arg::new("query").required_unless_present("mine"))
+                    .arg(
+                        Arg::new("mine")
+                            .required(false)
+                            .long("mine")
+                            .action(ArgAction::SetTrue)
+                            .help("Search the crates you own, needs `limp login`"),
+                    )
+                    .arg(
+                        Arg::new("sort")
+                            .required(false)
+                            .long("sort")
+                            .value_parser(SearchSort::NAMES)
+                            .default_value("relevance")
+                            .help("Sort order"),
+                    ),
+            )
+            .subcommand(
+                Command::new("info")
+                    .about("Show crate metadata from crates.io")
+                    .arg(Arg::new("name").required(true))
+                    .arg(
+                        Arg::new("versions")
+                            .required(false)
+                            .short('n')
+                            .long("versions")
+                            .value_parser(clap::value_parser!(usize))
+                            .default_value(DEFAULT_INFO_VERSIONS)
+                            .help("Number of latest versions to show"),
+                    ),
+            )
+            .subcommand(
+                Command::new("deps")
+                    .about("Show the dependencies of a crate on crates.io")
+                    .arg(Arg::new("name").required(true))
+                    .arg(
+                        Arg::new("version")
+                            .required(false)
+                            .short('v')
+                            .long("version")
+                            .help("Version or requirement to look at, the latest by default"),
+                    )
+                    .arg(
+                        Arg::new("recursive")
+                            .required(false)
+                            .short('r')
+                            .long("recursive")
+                            .action(ArgAction::SetTrue)
+                            .help("Resolve the dependencies of the dependencies too"),
+                    ),
+            )
+            .subcommand(
+                Command::new("which")
+                    .about("Show where a dependency is stored, declared and published")
+                    .arg(Arg::new("name").required(true)),
+            )
+            .subcommand(
+                Command::new("template")
+                    .about("Manage project templates")
+                    .subcommand_required(true)
+                    .subcommand(
+                        Command::new("save")
+                            .about("Save the current project as a template")
+                            .arg(Arg::new("name").required(true)),
+                    )
+                    .subcommand(Command::new("list").about("List saved templates")),
+            )
+            .subcommand(
+                Command::new("snippet")
+                    .about("Manage snippets in the limp storage directory")
+                    .subcommand_required(true)
+                    .subcommand(
+                        Command::new("list").about("List snippets and their dependencies"),
+                    )
+                    .subcommand(
+                        Command::new("show")
+                            .about("Print a snippet")
+                            .arg(Arg::new("name").required(true)),
+                    )
+                    .subcommand(
+                        Command::new("edit")
+                            .about("Open a snippet in $EDITOR, creating it if needed")
+                            .arg(Arg::new("name").required(true)),
+                    )
+                    .subcommand(
+                        Command::new("mv")
+                            .about("Rename a snippet")
+                            .arg(Arg::new("from").required(true))
+                            .arg(Arg::new("to").required(true)),
+                    )
+                    .subcommand(
+                        Command::new("rm")
+                            .about("Move a snippet to the trash and unlink it")
+                            .arg(Arg::new("name").required(true)),
+                    )
+                    .subcommand(
+                        Command::new("restore")
+                            .about("Bring a snippet back from the trash")
+                            .arg(Arg::new("name").required(true)),
+                    )
+                    .subcommand(
+                        Command::new("trash").about("List deleted snippets that can be restored"),
+                    )
+                    .subcommand(
+                        Command::new("refresh")
+                            .about("Import snippets again from the URL or file they came from")
+                            .arg(
+                                Arg::new("name")
+                                    .required(false)
+                                    .help("Snippet, or dependency whose snippets to refresh"),
+                            ),
+                    )
+                    .subcommand(
+                        Command::new("verify")
+                            .about("Check whether snippets were edited since they were stored")
+                            .arg(
+                                Arg::new("sources")
+                                    .required(false)
+                                    .long("sources")
+                                    .action(ArgAction::SetTrue)
+                                    .help("Also check whether their sources changed, downloading URLs"),
+                            ),
+                    )
+                    .subcommand(
+                        Command::new("add")
+                            .about("Store a snippet that isn't tied to a dependency")
+                            .arg(Arg::new("name").required(true))
+                            .arg(Arg::new("path").required(true)),
+                    )
+                    .subcommand(
+                        Command::new("find")
+                            .about("Find snippets by tag, name or contents")
+                            .arg(Arg::new("keyword").required(true)),
+                    ),
+            )
+            .subcommand(
+                Command::new("list")
+                    .about("List dependencies")
+                    .arg(
+                        Arg::new("sort")
+                            .required(false)
+                            .long("sort")
+                            .value_parser(SortKey::NAMES)
+                            .default_value("name")
+                            .help("Sort order"),
+                    )
+                    .arg(
+                        Arg::new("filter")
+                            .required(false)
+                            .long("filter")
+                            .help("Only show dependencies whose name contains this"),
+                    )
+                    .arg(
+                        Arg::new("with_snippets")
+                            .required(false)
+                            .long("with-snippets")
+                            .action(ArgAction::SetTrue)
+                            .help("Only show dependencies with a linked snippet"),
+                    )
+                    .arg(
+                        Arg::new("tree")
+                            .required(false)
+                            .long("tree")
+                            .num_args(0..=1)
+                            .value_parser(GroupKey::NAMES)
+                            .default_missing_value("kind")
+                            .help("Group by kind, first letter, linked snippet or snippet tag"),
+                    )
+                    .arg(
+                        Arg::new("names_only")
+                            .required(false)
+                            .long("names-only")
+                            .action(ArgAction::SetTrue)
+                            .conflicts_with("tree")
+                            .help("Print only the names, one per line, e.g. for piping"),
+                    ),
+            )
+            .subcommand(
+                Command::new("update")
+                    .about("Update dependencies")
+                    .arg(
+                        Arg::new("names")
+                            .required(false)
+                            .num_args(0..)
+                            .help("Dependencies to update, all when omitted"),
+                    )
+                    .arg(
+                        Arg::new("dry_run")
+                            .required(false)
+                            .long("dry-run")
+                            .action(ArgAction::SetTrue)
+                            .help("Only print the new versions, don't save them"),
+                    )
+                    .arg(
+                        Arg::new("jobs")
+                            .required(false)
+                            .short('j')
+                            .long("jobs")
+                            .value_parser(clap::value_parser!(usize))
+                            .help(
+                                "Number of parallel crates.io lookups (default: update_jobs setting)",
+                            ),
+                    )
+                    .arg(
+                        Arg::new("changes")
+                            .required(false)
+                            .long("changes")
+                            .action(ArgAction::SetTrue)
+                            .help("List the releases between the old and the new versions"),
+                    )
+                    .arg(
+                        Arg::new("on_feature_mismatch")
+                            .required(false)
+                            .long("on-feature-mismatch")
+                            .value_parser(FeatureMismatch::NAMES)
+                            .default_value("keep")
+                            .help(
+                                "When stored features are missing from the latest version: keep the old version, drop the features or fail",
+                            ),
+                    )
+                    .arg(
+                        Arg::new("pre")
+                            .required(false)
+                            .long("pre")
+                            .action(ArgAction::SetTrue)
+                            .help("Also update to alpha, beta and rc releases"),
+                    ),
+            )
+            .subcommand(
+                Command::new("config")
+                    .about("Manage limp settings")
+                    .subcommand_required(true)
+                    .subcommand(
+                        Command::new("get")
+                            .about("Print a setting")
+                            .arg(Arg::new("key").required(true)),
+                    )
+                    .subcommand(
+                        Command::new("set")
+                            .about("Change a setting, an empty value resets it")
+                            .arg(Arg::new("key").required(true))
+                            .arg(Arg::new("value").required(true)),
+                    )
+                    .subcommand(Command::new("list").about("List all settings")),
+            )
+            .subcommand(
+                Command::new("completions")
+                    .about("Print a shell completion script")
+                    .arg(
+                        Arg::new("shell")
+                            .required(true)
+                            .value_parser(clap::value_parser!(Shell)),
+                    ),
+            )
+            .subcommand(
+                Command::new("__complete")
+                    .about("Print stored dependency names for shell completion")
+                    .hide(true),
+            )
+            .subcommand(
+                Command::new("doctor")
+                    .about("Check the storage, the dependency database, network access and git"),
+            )
+            .subcommand(
+                Command::new("gc")
+                    .about("Remove orphaned snippet files and repair links to missing ones")
+                    .arg(
+                        Arg::new("dry_run")
+                            .required(false)
+                            .long("dry-run")
+                            .action(ArgAction::SetTrue)
+                            .help("Only list what would be cleaned up"),
+                    ),
+            )
+            .subcommand(
+                Command::new("apply")
+                    .about("Inject a stored snippet into the current project")
+                    .arg(Arg::new("name").required(true))
+                    .arg(
+                        Arg::new("module")
+                            .required(false)
+                            .short('m')
+                            .long("module")
+                            .help("Module to put the snippet in, named after the snippet by default"),
+                    )
+                    .arg(
+                        Arg::new("entry")
+                            .required(false)
+                            .long("entry")
+                            .action(ArgAction::SetTrue)
+                            .conflicts_with("module")
+                            .help("Merge the snippet into src/main.rs or src/lib.rs instead"),
+                    ),
+            )
+            .subcommand(
+                Command::new("link")
+                    .about("Link a snippet file or URL to a stored dependency")
+                    .arg(Arg::new("name").required(true))
+                    .arg(
+                        Arg::new("source")
+                            .required(true)
+                            .help("Path or URL of the snippet, e.g. a GitHub gist"),
+                    )
+                    .arg(
+                        Arg::new("tags")
+                            .required(false)
+                            .long("tags")
+                            .value_delimiter(',')
+                            .help("Tags to find the snippet by, e.g. web,http"),
+                    )
+                    .arg(
+                        Arg::new("variant")
+                            .required(false)
+                            .long("as")
+                            .help("Link the snippet as a named variant, e.g. tcp-server"),
+                    ),
+            )
+            .subcommand(
+                Command::new("rename")
+                    .about("Rename a stored dependency and the snippets named after it")
+                    .arg(Arg::new("old").required(true))
+                    .arg(Arg::new("new").required(true))
+                    .arg(
+                        Arg::new("resolve")
+                            .required(false)
+                            .long("resolve")
+                            .action(ArgAction::SetTrue)
+                            .help("Store the latest version of the new name from crates.io"),
+                    ),
+            )
+            .subcommand(
+                Command::new("pin")
+                    .about("Keep a stored dependency at its version during `limp update`")
+                    .arg(Arg::new("name").required(true)),
+            )
+            .subcommand(
+                Command::new("unpin")
+                    .about("Let `limp update` update a pinned dependency again")
+                    .arg(Arg::new("name").required(true)),
+            )
+            .subcommand(
+                Command::new("constrain")
+                    .about("Keep a stored dependency within a version requirement during `limp update`")
+                    .arg(Arg::new("name").required(true))
+                    .arg(
+                        Arg::new("requirement")
+                            .required(true)
+                            .help("Version requirement, e.g. `<1` or `^1`"),
+                    ),
+            )
+            .subcommand(
+                Command::new("unconstrain")
+                    .about("Let `limp update` move a stored dependency to its latest version again")
+                    .arg(Arg::new("name").required(true)),
+            )
+            .subcommand(
+                Command::new("prerelease")
+                    .about("Let `limp update` move a stored dependency to pre-releases")
+                    .arg(Arg::new("name").required(true))
+                    .arg(
+                        Arg::new("off")
+                            .required(false)
+                            .long("off")
+                            .action(ArgAction::SetTrue)
+                            .help("Keep the dependency on stable releases again"),
+                    ),
+            )
+            .subcommand(
+                Command::new("history").about("Show the changes made to the stored dependencies"),
+            )
+            .subcommand(Command::new("stats").about(
+                "Count the stored dependencies and their changes, with the sqlite storage format",
+            ))
+            .subcommand(
+                Command::new("undo")
+                    .about("Take back the last change to the stored dependencies and snippets"),
+            )
+            .subcommand(
+                Command::new("login")
+                    .about("Save a crates.io API token for authenticated requests")
+                    .arg(
+                        Arg::new("token")
+                            .required(false)
+                            .help("Token from https://crates.io/me, read from stdin when left out"),
+                    ),
+            )
+            .subcommand(
+                Command::new("sync")
+                    .about("Reconcile the versions in Cargo.toml with the stored dependencies")
+                    .arg(
+                        Arg::new("prefer")
+                            .required(false)
+                            .long("prefer")
+                            .value_parser(["project", "storage"])
+                            .help("Resolve every difference with the project's or the stored version"),
+                    )
+                    .arg(
+                        Arg::new("apply_all")
+                            .required(false)
+                            .long("apply-all")
+                            .action(ArgAction::SetTrue)
+                            .help("Resolve every difference without asking, with the stored version unless --prefer is given"),
+                    ),
+            )
+            .subcommand(
+                Command::new("self")
+                    .about("Check for and install limp updates")
+                    .subcommand_required(true)
+                    .subcommand(
+                        Command::new("update")
+                            .about("Compare limp with its latest release on crates.io")
+                            .arg(
+                                Arg::new("install")
+                                    .required(false)
+                                    .long("install")
+                                    .action(ArgAction::SetTrue)
+                                    .help("Run `cargo install limp --locked` if a newer release is out"),
+                            ),
+                    )
+                    .subcommand(
+                        Command::new("version")
+                            .about("Print the version of limp and its latest release")
+                            .arg(
+                                Arg::new("check")
+                                    .required(false)
+                                    .long("check")
+                                    .action(ArgAction::SetTrue)
+                                    .help("Fail if a newer release is out, e.g. in CI"),
+                            ),
+                    ),
+            )
+            .subcommand(
+                Command::new("cache")
+                    .about("Inspect, clear or fill the cache of registry responses")
+                    .subcommand_required(true)
+                    .subcommand(
+                        Command::new("info").about("Print the size, entry count and age of the cache"),
+                    )
+                    .subcommand(Command::new("clear").about("Remove every cached response"))
+                    .subcommand(
+                        Command::new("warm")
+                            .about("Fetch the metadata of all stored dependencies before going offline")
+                            .arg(
+                                Arg::new("jobs")
+                                    .required(false)
+                                    .short('j')
+                                    .long("jobs")
+                                    .value_parser(clap::value_parser!(usize))
+                                    .help(
+                                        "Number of parallel crates.io lookups (default: update_jobs setting)",
+                                    ),
+                            ),
+                    ),
+            )
+            .subcommand(
+                Command::new("version").about("Print version").arg(
+                    Arg::new("verbose")
+                        .required(false)
+                        .long("verbose")
+                        .action(ArgAction::SetTrue)
+                        .help("Also print the commit and target limp was built for"),
+                ),
+            )
     }
     pub fn parse(args: &ArgMatches) -> Self {
         Self {
@@ -101,133 +1514,1014 @@ impl CommandHandler {
                         dependencies: subargs
                             .get_many::<String>("dependencies")
                             .map(|d| d.cloned().collect()),
+                        kind: if subargs.get_flag("lib") {
+                            ProjectKind::Lib
+                        } else {
+                            ProjectKind::Bin
+                        },
+                        workspace: subargs
+                            .get_many::<String>("workspace")
+                            .map(|m| m.cloned().collect()),
+                        template: subargs.get_one::<String>("template").cloned(),
+                        vars: subargs
+                            .get_many::<(String, String)>("var")
+                            .map(|v| v.cloned().collect())
+                            .unwrap_or_default(),
+                        check: subargs.get_flag("check"),
+                        package: subargs.get_one::<String>("package").cloned(),
+                        git: subargs.get_one::<String>("vcs").map(|v| v == "git"),
+                        scaffold: Scaffold {
+                            license: subargs.get_one::<String>("license").cloned(),
+                            readme: subargs.get_flag("readme"),
+                            ci: subargs.get_one::<String>("ci").cloned(),
+                            examples: subargs.get_flag("with_examples"),
+                            benches: subargs.get_flag("with_benches"),
+                            tests: subargs.get_flag("with_integration_tests"),
+                        },
+                        snippets: subargs
+                            .get_many::<String>("snippets")
+                            .map(|s| s.cloned().collect())
+                            .unwrap_or_default(),
+                        bins: subargs
+                            .get_many::<String>("bins")
+                            .map(|b| b.cloned().collect())
+                            .unwrap_or_default(),
+                        preset: subargs.get_one::<String>("type").cloned(),
+                        manifest_extras: subargs
+                            .get_many::<(String, String)>("manifest_extra")
+                            .map(|v| v.cloned().collect())
+                            .unwrap_or_default(),
+                        description: subargs.get_one::<String>("description").cloned(),
+                        repository: subargs.get_one::<String>("repository").cloned(),
+                        keywords: subargs
+                            .get_many::<String>("keywords")
+                            .map(|k| k.cloned().collect())
+                            .unwrap_or_default(),
+                        fmt: subargs.get_flag("fmt"),
+                        from_git: subargs.get_one::<String>("from_git").cloned(),
+                        branch: subargs.get_one::<String>("branch").cloned(),
+                        template_hooks: subargs.get_flag("template_hooks"),
                     }),
                     "new" => Some(Action::NewDependency {
-                        name: subargs.get_one::<String>("name").unwrap().clone(),
-                        version: subargs.get_one::<String>("version").map(|v| {
-                            match v
-                                .split(".")
-                                .map(|s| s.parse::<u16>().unwrap_or_default())
-                                .collect::<Vec<u16>>()
-                                .len()
-                            {
-                                // 1.1.1 -> nothing change
-                                3 => v.to_string(),
-                                // 0.25 -> 0.25.0
-                                2 => format!("{}.0", v),
-                                // 1 -> 1.0.0
-                                1 => format!("{}.0.0", v),
-                                // no way
-                                _ => unreachable!(),
-                            }
-                        }),
+                        name: subargs
+                            .get_one::<String>("name")
+                            .cloned()
+                            .unwrap_or_default(),
+                        version: subargs.get_one::<String>("version").cloned(),
                         features: subargs
                             .get_many::<String>("features")
                             .map(|f| f.cloned().collect()),
                         path_to_snippet: subargs.get_one::<String>("path_to_snippet").cloned(),
+                        kind: dependency_kind(subargs).unwrap_or_default(),
+                        allow_yanked: subargs.get_flag("allow_yanked"),
+                        msrv: subargs.get_one::<String>("msrv").cloned(),
+                        registry: subargs.get_one::<String>("registry").cloned(),
+                        source: dependency_source(subargs),
+                        no_default_features: subargs.get_flag("no_default_features"),
+                        optional: subargs.get_flag("optional"),
+                        rename: subargs.get_one::<String>("rename").cloned(),
+                        pick_features: subargs.get_flag("pick_features"),
+                        allow_license_violation: subargs.get_flag("allow_license_violation"),
+                        force: subargs.get_flag("force"),
+                        if_missing: subargs.get_flag("if_missing"),
+                        from_file: subargs
+                            .get_one::<String>("from_file")
+                            .or(subargs.get_one::<String>("name").filter(|n| *n == "-"))
+                            .cloned(),
                     }),
                     "del" => Some(Action::Delete {
                         name: subargs.get_one::<String>("name").unwrap().clone(),
+                        keep_snippet: subargs.get_flag("keep_snippet"),
                     }),
                     "add" => Some(Action::Add {
+                        names: subargs
+                            .get_many::<String>("names")
+                            .map(|n| n.cloned().collect())
+                            .unwrap_or_default(),
+                        features: subargs
+                            .get_many::<String>("features")
+                            .map(|f| f.cloned().collect())
+                            .unwrap_or_default(),
+                        kind: dependency_kind(subargs),
+                        no_default_features: subargs.get_flag("no_default_features"),
+                        optional: subargs.get_flag("optional"),
+                        rename: subargs.get_one::<String>("rename").cloned(),
+                        with_snippet: subargs.get_flag("with_snippet"),
+                        module: subargs.get_one::<String>("module").cloned(),
+                        allow_license_violation: subargs.get_flag("allow_license_violation"),
+                        package: subargs.get_one::<String>("package").cloned(),
+                        workspace: subargs.get_flag("workspace"),
+                    }),
+                    "remove" => Some(Action::Remove {
+                        name: subargs.get_one::<String>("name").unwrap().clone(),
+                    }),
+                    "import" => Some(Action::Import {
+                        path: subargs.get_one::<String>("path").cloned(),
+                        bundle: subargs.get_one::<String>("bundle").cloned(),
+                        on_conflict: if subargs.get_flag("overwrite") {
+                            Some(true)
+                        } else if subargs.get_flag("keep") {
+                            Some(false)
+                        } else {
+                            None
+                        },
+                    }),
+                    "export" => Some(Action::Export {
+                        dir: subargs.get_one::<String>("dir").unwrap().clone(),
+                    }),
+                    "search" => Some(Action::Search {
+                        query: subargs.get_one::<String>("query").cloned(),
+                        mine: subargs.get_flag("mine"),
+                        sort: subargs
+                            .get_one::<String>("sort")
+                            .map(|s| SearchSort::from_name(s))
+                            .unwrap_or_default(),
+                    }),
+                    "info" => Some(Action::Info {
+                        name: subargs.get_one::<String>("name").unwrap().clone(),
+                        versions: *subargs.get_one::<usize>("versions").unwrap(),
+                    }),
+                    "deps" => Some(Action::Deps {
+                        name: subargs.get_one::<String>("name").unwrap().clone(),
+                        version: subargs.get_one::<String>("version").cloned(),
+                        recursive: subargs.get_flag("recursive"),
+                    }),
+                    "which" => Some(Action::Which {
+                        name: subargs.get_one::<String>("name").unwrap().clone(),
+                    }),
+                    "template" => match subargs.subcommand() {
+                        Some(("save", template_args)) => Some(Action::TemplateSave {
+                            name: template_args.get_one::<String>("name").unwrap().clone(),
+                        }),
+                        Some(("list", _)) => Some(Action::TemplateList),
+                        _ => None,
+                    },
+                    "snippet" => match subargs.subcommand() {
+                        Some(("list", _)) => Some(Action::SnippetList),
+                        Some(("show", snippet_args)) => Some(Action::SnippetShow {
+                            name: snippet_args.get_one::<String>("name").unwrap().clone(),
+                        }),
+                        Some(("edit", snippet_args)) => Some(Action::SnippetEdit {
+                            name: snippet_args.get_one::<String>("name").unwrap().clone(),
+                        }),
+                        Some(("mv", snippet_args)) => Some(Action::SnippetRename {
+                            from: snippet_args.get_one::<String>("from").unwrap().clone(),
+                            to: snippet_args.get_one::<String>("to").unwrap().clone(),
+                        }),
+                        Some(("rm", snippet_args)) => Some(Action::SnippetRemove {
+                            name: snippet_args.get_one::<String>("name").unwrap().clone(),
+                        }),
+                        Some(("restore", snippet_args)) => Some(Action::SnippetRestore {
+                            name: snippet_args.get_one::<String>("name").unwrap().clone(),
+                        }),
+                        Some(("trash", _)) => Some(Action::SnippetTrash),
+                        Some(("refresh", snippet_args)) => Some(Action::SnippetRefresh {
+                            name: snippet_args.get_one::<String>("name").cloned(),
+                        }),
+                        Some(("verify", snippet_args)) => Some(Action::SnippetVerify {
+                            sources: snippet_args.get_flag("sources"),
+                        }),
+                        Some(("add", snippet_args)) => Some(Action::SnippetAdd {
+                            name: snippet_args.get_one::<String>("name").unwrap().clone(),
+                            path: snippet_args.get_one::<String>("path").unwrap().clone(),
+                        }),
+                        Some(("find", snippet_args)) => Some(Action::SnippetFind {
+                            keyword: snippet_args.get_one::<String>("keyword").unwrap().clone(),
+                        }),
+                        _ => None,
+                    },
+                    "list" => Some(Action::List {
+                        sort: subargs
+                            .get_one::<String>("sort")
+                            .map(|s| SortKey::from_name(s))
+                            .unwrap_or_default(),
+                        filter: subargs.get_one::<String>("filter").cloned(),
+                        with_snippets: subargs.get_flag("with_snippets"),
+                        tree: subargs
+                            .get_one::<String>("tree")
+                            .map(|s| GroupKey::from_name(s)),
+                        names_only: subargs.get_flag("names_only"),
+                    }),
+                    "update" => Some(Action::Update {
+                        names: subargs
+                            .get_many::<String>("names")
+                            .map(|n| n.cloned().collect())
+                            .unwrap_or_default(),
+                        dry_run: subargs.get_flag("dry_run"),
+                        jobs: subargs.get_one::<usize>("jobs").copied(),
+                        changes: subargs.get_flag("changes"),
+                        on_feature_mismatch: subargs
+                            .get_one::<String>("on_feature_mismatch")
+                            .map(|s| FeatureMismatch::from_name(s))
+                            .unwrap_or_default(),
+                        pre: subargs.get_flag("pre"),
+                    }),
+                    "version" => Some(Action::Version {
+                        verbose: subargs.get_flag("verbose"),
+                    }),
+                    "self" => match subargs.subcommand() {
+                        Some(("update", self_args)) => Some(Action::SelfUpdate {
+                            install: self_args.get_flag("install"),
+                        }),
+                        Some(("version", self_args)) => Some(Action::SelfVersion {
+                            check: self_args.get_flag("check"),
+                        }),
+                        _ => None,
+                    },
+                    "cache" => match subargs.subcommand() {
+                        Some(("info", _)) => Some(Action::CacheInfo),
+                        Some(("clear", _)) => Some(Action::CacheClear),
+                        Some(("warm", cache_args)) => Some(Action::CacheWarm {
+                            jobs: cache_args.get_one::<usize>("jobs").copied(),
+                        }),
+                        _ => None,
+                    },
+                    "config" => match subargs.subcommand() {
+                        Some(("get", config_args)) => Some(Action::ConfigGet {
+                            key: config_args.get_one::<String>("key").unwrap().clone(),
+                        }),
+                        Some(("set", config_args)) => Some(Action::ConfigSet {
+                            key: config_args.get_one::<String>("key").unwrap().clone(),
+                            value: config_args.get_one::<String>("value").unwrap().clone(),
+                        }),
+                        Some(("list", _)) => Some(Action::ConfigList),
+                        _ => None,
+                    },
+                    "completions" => Some(Action::Completions {
+                        shell: *subargs.get_one::<Shell>("shell").unwrap(),
+                    }),
+                    "__complete" => Some(Action::CompleteDependencies),
+                    "doctor" => Some(Action::Doctor),
+                    "gc" => Some(Action::Gc {
+                        dry_run: subargs.get_flag("dry_run"),
+                    }),
+                    "apply" => Some(Action::Apply {
+                        name: subargs.get_one::<String>("name").unwrap().clone(),
+                        module: subargs.get_one::<String>("module").cloned(),
+                        entry: subargs.get_flag("entry"),
+                    }),
+                    "link" => Some(Action::Link {
                         name: subargs.get_one::<String>("name").unwrap().clone(),
+                        source: subargs.get_one::<String>("source").unwrap().clone(),
+                        tags: subargs
+                            .get_many::<String>("tags")
+                            .map(|tags| tags.cloned().collect()),
+                        variant: subargs.get_one::<String>("variant").cloned(),
+                    }),
+                    "rename" => Some(Action::Rename {
+                        old: subargs.get_one::<String>("old").unwrap().clone(),
+                        new: subargs.get_one::<String>("new").unwrap().clone(),
+                        resolve: subargs.get_flag("resolve"),
+                    }),
+                    "pin" | "unpin" => Some(Action::Pin {
+                        name: subargs.get_one::<String>("name").unwrap().clone(),
+                        pinned: subname == "pin",
+                    }),
+                    "prerelease" => Some(Action::Prerelease {
+                        name: subargs.get_one::<String>("name").unwrap().clone(),
+                        allow: !subargs.get_flag("off"),
+                    }),
+                    "constrain" | "unconstrain" => Some(Action::Constrain {
+                        name: subargs.get_one::<String>("name").unwrap().clone(),
+                        constraint: (subname == "constrain")
+                            .then(|| subargs.get_one::<String>("requirement").unwrap().clone()),
+                    }),
+                    "history" => Some(Action::History),
+                    "stats" => Some(Action::Stats),
+                    "undo" => Some(Action::Undo),
+                    "login" => Some(Action::Login {
+                        token: subargs.get_one::<String>("token").cloned(),
+                    }),
+                    "sync" => Some(Action::Sync {
+                        choice: match subargs.get_one::<String>("prefer").map(String::as_str) {
+                            Some("project") => Some(SyncChoice::UpdateStorage),
+                            Some(_) => Some(SyncChoice::BumpProject),
+                            None if subargs.get_flag("apply_all") => Some(SyncChoice::BumpProject),
+                            None => None,
+                        },
                     }),
-                    "list" => Some(Action::List),
-                    "update" => Some(Action::Update),
                     _ => None,
                 },
 
                 None => None,
             },
+            offline: args.get_flag("offline"),
+            format: args
+                .get_one::<String>("format")
+                .map(|f| Format::from_name(f))
+                .unwrap_or_default(),
+            verbosity: Verbosity::from_flags(args.get_flag("quiet"), args.get_count("verbose")),
+            no_color: args.get_flag("no_color"),
+            config_dir: args.get_one::<String>("config_dir").map(PathBuf::from),
+            yes: args.get_flag("yes"),
+            frozen: args.get_flag("frozen"),
+            timeout: args.get_one::<u64>("timeout").copied(),
+            log_level: args
+                .get_one::<String>("log_level")
+                .and_then(|l| Level::from_name(l)),
+            log_file: args.get_flag("log_file"),
         }
     }
     pub fn make_action(&self) -> Result<(), LimpError> {
+        // --frozen implies --offline, cached lookups need no network
+        let offline = self.offline || self.frozen;
+        let mut ctx = match &self.config_dir {
+            Some(dir) => Context::load(dir, offline)?,
+            None => Context::from_env(offline)?,
+        };
+        if let Some(secs) = self.timeout {
+            ctx = ctx.with_timeout(secs)?;
+        }
+        ctx.frozen = self.frozen;
+        ctx.registry = ctx.registry.with_verbosity(self.verbosity);
+        let log_file = self.log_file || ctx.config.log_file;
+        // a log file without a level is for reporting problems, so it gets
+        // the details
+        let level = self
+            .log_level
+            .or_else(|| {
+                std::env::var("RUST_LOG")
+                    .ok()
+                    .and_then(|f| Level::from_env_filter(&f))
+            })
+            .or(log_file.then_some(Level::Debug));
+        if let Some(level) = level {
+            logging::init(level, log_file.then(|| ctx.log_path()).as_deref())?;
+        }
+        self.make_action_in(&ctx)
+    }
+    /// Runs the action against `ctx` instead of the storage directory.
+    pub fn make_action_in(&self, ctx: &Context) -> Result<(), LimpError> {
+        let registry = &ctx.registry;
+        let reporter = self.format.reporter(Style::detect(self.no_color));
         if let Some(act) = &self.action {
             match act {
-                Action::Init { name, dependencies } => {
-                    let js = JsonStorage::load(config_path())?;
-                    let mut odeps = None;
-                    if let Some(deps) = dependencies {
-                        let mut result_deps = vec![];
-                        for d in deps.iter() {
-                            result_deps.push(match js.get(d) {
-                                Some(d) => d.to_string(),
-                                None => JsonDependency::new(d)?.to_string(),
-                            });
-                        }
-                        if !result_deps.is_empty() {
-                            odeps = Some(result_deps);
-                        }
+                Action::Init {
+                    name,
+                    dependencies,
+                    kind,
+                    workspace,
+                    template,
+                    vars,
+                    check,
+                    package,
+                    git,
+                    scaffold,
+                    snippets,
+                    bins,
+                    preset,
+                    manifest_extras,
+                    description,
+                    repository,
+                    keywords,
+                    fmt,
+                    from_git,
+                    branch,
+                    template_hooks,
+                } => {
+                    let description = match description {
+                        Some(description) => Some(description.clone()),
+                        // a git template brings its own manifest
+                        None if self.yes || from_git.is_some() => None,
+                        None => ask("description:")?,
+                    };
+                    let options = InitOptions {
+                        path: name.clone(),
+                        dependencies: dependencies.clone(),
+                        kind: *kind,
+                        workspace: workspace.clone(),
+                        template: template.clone(),
+                        vars: vars.clone(),
+                        check: *check,
+                        package: package.clone(),
+                        git: *git,
+                        scaffold: scaffold.clone(),
+                        snippets: snippets.clone(),
+                        bins: bins.clone(),
+                        preset: preset.clone(),
+                        manifest_extras: manifest_extras.clone(),
+                        description,
+                        repository: repository.clone(),
+                        keywords: keywords.clone(),
+                        fmt: *fmt,
+                        from_git: from_git.clone(),
+                        branch: branch.clone(),
+                        template_hooks: *template_hooks,
+                    };
+                    let report = ops::init(ctx, &options)?;
+                    for hook in report.skipped_hooks.iter() {
+                        reporter.warning(&format!(
+                            "template hook {} not run, pass --template-hooks to run it",
+                            hook.file_name().unwrap_or_default().to_string_lossy()
+                        ));
+                    }
+                    if let Some(error) = &report.format_error {
+                        reporter.warning(&format!("sources left unformatted: {}", error));
+                    }
+                    for error in report.errors.iter() {
+                        reporter.message(&error.to_string());
+                    }
+                    if !report.errors.is_empty() {
+                        return Err(LimpError::CheckFailed(format!(
+                            "{}, {} errors",
+                            name,
+                            report.errors.len()
+                        )));
                     }
-
-                    create_project(name, odeps.as_deref())?
                 }
                 Action::NewDependency {
                     name,
                     version,
                     features,
                     path_to_snippet,
+                    kind,
+                    allow_yanked,
+                    msrv,
+                    registry: registry_name,
+                    source,
+                    no_default_features,
+                    optional,
+                    rename,
+                    pick_features: pick,
+                    allow_license_violation,
+                    force,
+                    if_missing,
+                    from_file,
                 } => {
-                    let mut js = JsonStorage::load(config_path())?;
-
-                    let jd = JsonDependency::new_full(
-                        name,
-                        version.as_deref(),
-                        features.as_deref(),
-                        path_to_snippet.as_deref(),
-                    )?;
-                    js.add(jd);
-
-                    js.save(config_path())?;
-                }
-                Action::Delete { name } => {
-                    let mut js = JsonStorage::load(config_path())?;
-
-                    js.remove(name);
-
-                    js.save(config_path())?;
+                    let options = NewOptions {
+                        name: name.clone(),
+                        version: version.clone(),
+                        features: features.clone(),
+                        path_to_snippet: path_to_snippet.clone(),
+                        kind: *kind,
+                        allow_yanked: *allow_yanked,
+                        msrv: msrv.clone(),
+                        registry: registry_name.clone(),
+                        source: source.clone(),
+                        no_default_features: *no_default_features,
+                        optional: *optional,
+                        rename: rename.clone(),
+                        allow_license_violation: *allow_license_violation,
+                        force: *force,
+                        if_missing: *if_missing,
+                    };
+                    let Some(file) = from_file else {
+                        let report = ops::new_dependency_with(
+                            ctx,
+                            &options,
+                            (*pick && !self.yes).then_some(pick_features),
+                        )?;
+                        new_warnings(reporter.as_ref(), &report);
+                        return Ok(());
+                    };
+                    let list = match file.as_str() {
+                        "-" => std::io::read_to_string(std::io::stdin())?,
+                        file => fs::read_to_string(file)?,
+                    };
+                    let list = ops::new_list(&options, &list);
+                    let report = ops::new_dependencies(ctx, &list, ctx.config.update_jobs);
+                    for stored in report.reports.iter() {
+                        new_warnings(reporter.as_ref(), stored);
+                    }
+                    let kept = report.reports.iter().filter(|r| r.already_stored).count();
+                    reporter.message(&format!(
+                        "{} stored, {} already stored, {} failed",
+                        report.reports.len() - kept,
+                        kept,
+                        report.errors.len()
+                    ));
+                    if !report.errors.is_empty() {
+                        return Err(LimpError::ResolveFailed(
+                            report
+                                .errors
+                                .iter()
+                                .map(|(name, e)| format!("{}: {}", name, e))
+                                .collect::<Vec<String>>()
+                                .join("\n"),
+                        ));
+                    }
                 }
-                Action::Add { name } => {
-                    if let Some(path) = find_toml() {
-                        let mut file = open(path)?;
-                        let js = JsonStorage::load(config_path())?;
-
-                        let mut content = String::new();
-                        file.read_to_string(&mut content)?;
-
-                        let deps = if let Some(existing_deps) = js.get(name) {
-                            existing_deps.to_string()
+                Action::Delete { name, keep_snippet } => {
+                    let js = JsonStorage::load(ctx.config_path())?;
+                    if let Some(dep) = js.get(name) {
+                        let mut what = vec![dep.to_string()];
+                        let fate = if *keep_snippet {
+                            "kept as a snippet of its own"
                         } else {
-                            JsonDependency::new(name)?.to_string()
+                            "moved to the trash"
                         };
-                        if content.contains("[dependencies]") {
-                            writeln!(file, "{}", deps)?
-                        } else {
-                            writeln!(file, "\n[dependencies]")?;
-                            writeln!(file, "{}", deps)?
+                        what.extend(
+                            ops::owned_snippets(ctx, &js, name)
+                                .iter()
+                                .map(|path| format!("{} ({})", path.display(), fate)),
+                        );
+                        confirm_deletion(
+                            self.yes,
+                            &format!("delete {} from the storage?", name),
+                            &what,
+                        )?;
+                    }
+                    for path in ops::delete(ctx, name, *keep_snippet)? {
+                        let file = path.file_name().unwrap_or_default().to_string_lossy();
+                        reporter.message(&format!(
+                            "moved {} to the trash, `limp snippet restore {}` brings it back",
+                            file, file
+                        ));
+                    }
+                }
+                Action::Add {
+                    names,
+                    features,
+                    kind,
+                    no_default_features,
+                    optional,
+                    rename,
+                    with_snippet,
+                    module,
+                    allow_license_violation,
+                    package,
+                    workspace,
+                } => {
+                    let options = AddOptions {
+                        names: names.clone(),
+                        features: features.clone(),
+                        kind: *kind,
+                        no_default_features: *no_default_features,
+                        optional: *optional,
+                        rename: rename.clone(),
+                        with_snippet: *with_snippet,
+                        module: module.clone(),
+                        allow_license_violation: *allow_license_violation,
+                        package: package.clone(),
+                        workspace: *workspace,
+                    };
+                    let report = ops::add(ctx, &options)?;
+                    for violation in report.license_violations.iter() {
+                        reporter.warning(violation);
+                    }
+                    for injection in report.injections.iter() {
+                        reporter.message(&injection.to_string());
+                    }
+                }
+                Action::Remove { name } => {
+                    ops::remove(name)?.iter().for_each(|k| {
+                        reporter.message(&format!("removed {} from [{}]", name, k.table_name()))
+                    });
+                }
+                Action::Import {
+                    path,
+                    bundle: bundle_dir,
+                    on_conflict,
+                } => {
+                    let replace = |stored: &JsonDependency, new: &JsonDependency| match on_conflict
+                    {
+                        Some(overwrite) => Ok(*overwrite),
+                        None if self.yes => Ok(true),
+                        None => confirm(
+                            &format!("replace stored `{}` with `{}`?", stored, new),
+                            false,
+                        ),
+                    };
+                    if let Some(dir) = bundle_dir {
+                        let summary = ops::import_bundle(ctx, dir, replace)?;
+                        reporter.message(&format!("imported bundle {}: {}", dir, summary));
+                        return Ok(());
+                    }
+                    let summary = ops::import(ctx, path.as_deref(), replace)?;
+                    reporter.message(&format!("imported dependencies: {}", summary));
+                }
+                Action::Export { dir } => {
+                    let count = ops::export(ctx, dir)?;
+                    reporter.message(&format!("exported {} dependencies to {}", count, dir));
+                }
+                Action::Search { query, mine, sort } => {
+                    let search = match query {
+                        _ if *mine => {
+                            let mut owned = registry.owned()?;
+                            owned.crates.retain(|c| {
+                                query.as_ref().map_or(true, |q| c.name.contains(q.as_str()))
+                            });
+                            owned.sort(*sort);
+                            owned
                         }
-                    } else {
-                        return Err(LimpError::CargoTomlNotFound(format!(
-                            "dep: {}\npath: {}",
+                        Some(query) => registry.search(query, *sort)?,
+                        None => CratesIoSearch { crates: vec![] },
+                    };
+                    reporter.crates(&search.crates);
+                }
+                Action::Info { name, versions } => {
+                    reporter.crate_info(&registry.get_crate(name)?, *versions);
+                }
+                Action::Deps {
+                    name,
+                    version,
+                    recursive,
+                } => {
+                    let tree = ops::deps(ctx, name, version.as_deref(), *recursive)?;
+                    reporter.message(&tree.to_string());
+                    if *recursive {
+                        reporter.message(&format!("{} crates in total", tree.count()));
+                    }
+                }
+                Action::Which { name } => {
+                    let which = ops::which(ctx, name, find_toml().as_deref())?;
+                    reporter.message(&which.to_string());
+                    for version in which.stale_locks() {
+                        reporter.warning(&format!(
+                            "Cargo.lock pins {} {}, older than the stored {}",
                             name,
-                            std::env::current_dir().unwrap().display()
+                            version,
+                            which.stored.as_ref().map_or("", |d| d.version.as_str())
+                        ));
+                    }
+                }
+                Action::TemplateSave { name } => {
+                    let path = find_toml()
+                        .ok_or_else(|| ops::current_dir_error(&format!("template: {}", name)))?;
+                    let template = Template::save_from_project(
+                        ctx.templates_dir(),
+                        name,
+                        &CargoManifest::load(path)?,
+                    )?;
+                    reporter.message(&format!(
+                        "saved template {} with {} dependencies",
+                        template.name,
+                        template.dependencies.len()
+                    ));
+                }
+                Action::TemplateList => {
+                    reporter.list(&Template::list(ctx.templates_dir())?);
+                }
+                Action::ConfigGet { key } => {
+                    reporter.message(
+                        &ctx.config
+                            .get(key)?
+                            .unwrap_or_else(|| "(unset)".to_string()),
+                    );
+                }
+                Action::ConfigSet { key, value } => {
+                    let mut config = ctx.config.clone();
+                    config.set(key, value)?;
+                    config.save(ctx.settings_path())?;
+                }
+                Action::ConfigList => {
+                    reporter.list(
+                        &ctx.config
+                            .entries()?
+                            .into_iter()
+                            .map(|(k, v)| format!("{} = {}", k, v.as_deref().unwrap_or("(unset)")))
+                            .collect::<Vec<String>>(),
+                    );
+                }
+                Action::Completions { shell } => {
+                    completions::generate(*shell, &mut std::io::stdout())?;
+                }
+                Action::CompleteDependencies => {
+                    let js = JsonStorage::load(ctx.config_path())?;
+                    let mut names = js.dependencies.keys().cloned().collect::<Vec<String>>();
+                    names.sort();
+                    reporter.names(&names);
+                }
+                Action::Gc { dry_run } => {
+                    let garbage = ops::gc(ctx, *dry_run)?;
+                    if garbage.is_empty() {
+                        reporter.message("nothing to clean up");
+                    }
+                    for path in garbage.orphans.iter() {
+                        let verb = if *dry_run { "would remove" } else { "removed" };
+                        reporter.message(&format!("{} {}", verb, path.display()));
+                    }
+                    for link in garbage.dangling.iter() {
+                        reporter.message(&link.to_string());
+                    }
+                    for name in garbage.forgotten.iter() {
+                        reporter.message(&format!("{}: file is missing, forgotten", name));
+                    }
+                }
+                Action::Doctor => {
+                    let checks = doctor::run(&ctx.root, registry);
+                    checks.iter().for_each(|c| reporter.message(&c.to_string()));
+                    let failed = checks.iter().filter(|c| !c.passed()).count();
+                    if failed > 0 {
+                        return Err(LimpError::DoctorFailed(format!("{} checks failed", failed)));
+                    }
+                }
+                Action::Pin { name, pinned } => {
+                    ops::pin(ctx, name, *pinned)?;
+                }
+                Action::Prerelease { name, allow } => {
+                    ops::allow_prerelease(ctx, name, *allow)?;
+                }
+                Action::Constrain { name, constraint } => {
+                    ops::constrain(ctx, name, constraint.as_deref())?;
+                }
+                Action::Version { verbose } => {
+                    if *verbose {
+                        reporter.message(&version::verbose());
+                    } else {
+                        reporter.message(&format!("limp {}", version::VERSION));
+                    }
+                }
+                Action::SelfUpdate { install } => {
+                    let version = ops::self_update(ctx, *install)?;
+                    if !version.is_outdated() {
+                        reporter.message(&version.to_string());
+                    } else if *install {
+                        reporter.message(&format!(
+                            "updated limp {} -> {}",
+                            version.current, version.latest
+                        ));
+                    } else {
+                        reporter.message(&format!(
+                            "{}, update with `cargo install limp --locked` or `limp self update --install`",
+                            version
+                        ));
+                    }
+                }
+                Action::SelfVersion { check } => {
+                    let version = ops::self_version(ctx)?;
+                    reporter.message(&version.to_string());
+                    if *check && version.is_outdated() {
+                        return Err(LimpError::Outdated(format!(
+                            "{} -> {}",
+                            version.current, version.latest
                         )));
                     }
                 }
-                Action::List => {
-                    let js = JsonStorage::load(config_path())?;
-                    js.dependencies
+                Action::CacheInfo => {
+                    let cache = ctx.registry.cache();
+                    reporter.message(&cache.dir().display().to_string());
+                    reporter.message(&cache.info()?.to_string());
+                }
+                Action::CacheClear => {
+                    let removed = ctx.registry.cache().clear()?;
+                    reporter.message(&format!("removed {} cache entries", removed));
+                }
+                Action::CacheWarm { jobs } => {
+                    let report = ops::warm_cache(ctx, jobs.unwrap_or(ctx.config.update_jobs))?;
+                    reporter.message(&format!("cached {} dependencies", report.warmed.len()));
+                    if !report.errors.is_empty() {
+                        return Err(LimpError::ResolveFailed(
+                            report
+                                .errors
+                                .iter()
+                                .map(|(name, e)| format!("{}: {}", name, e))
+                                .collect::<Vec<String>>()
+                                .join("\n"),
+                        ));
+                    }
+                }
+                Action::Rename { old, new, resolve } => {
+                    for path in ops::rename(ctx, old, new, *resolve)? {
+                        reporter.message(&format!("moved snippet to {}", path.display()));
+                    }
+                    reporter.message(&format!("renamed {} to {}", old, new));
+                }
+                Action::History => {
+                    #[cfg(feature = "sqlite")]
+                    if ctx.config.storage_format == StorageFormat::Sqlite {
+                        reporter.list(
+                            &sqlite::events(&ctx.config_path(), None)?
+                                .iter()
+                                .map(|e| e.to_string())
+                                .collect::<Vec<String>>(),
+                        );
+                        return Ok(());
+                    }
+                    reporter.list(
+                        &history::load(ctx.history_path())?
+                            .iter()
+                            .enumerate()
+                            .map(|(i, e)| format!("{}: {}", i, e))
+                            .collect::<Vec<String>>(),
+                    );
+                }
+                Action::Login { token } => {
+                    let token = match token {
+                        Some(token) => token.clone(),
+                        None => read_token()?,
+                    };
+                    if token.is_empty() {
+                        return Err(LimpError::InvalidArguments("empty token".to_string()));
+                    }
+                    let mut config = ctx.config.clone();
+                    config.token = Some(token);
+                    config.save(ctx.settings_path())?;
+                    reporter.message(&format!(
+                        "saved crates.io token to {}",
+                        ctx.settings_path().display()
+                    ));
+                }
+                #[cfg(feature = "sqlite")]
+                Action::Stats => {
+                    if ctx.config.storage_format != StorageFormat::Sqlite {
+                        return Err(LimpError::InvalidArguments(
+                            "limp stats needs storage_format = \"sqlite\"".to_string(),
+                        ));
+                    }
+                    reporter.message(&sqlite::stats(&ctx.config_path())?.to_string());
+                }
+                #[cfg(not(feature = "sqlite"))]
+                Action::Stats => {
+                    return Err(LimpError::InvalidArguments(
+                        "limp stats needs limp built with --features sqlite".to_string(),
+                    ));
+                }
+                Action::Undo => match ops::undo(ctx)? {
+                    Some(entry) => reporter.message(&format!("undone: {}", entry)),
+                    None => reporter.message("nothing to undo"),
+                },
+                Action::Sync { choice } => {
+                    let entries = ops::sync(ctx, |project, stored| match choice {
+                        Some(choice) => Ok(*choice),
+                        None if self.yes => Ok(SyncChoice::BumpProject),
+                        None => choose_sync(project, stored),
+                    })?;
+                    if entries.is_empty() {
+                        reporter.message("project and storage agree");
+                    }
+                    entries
                         .iter()
-                        .enumerate()
-                        .for_each(|(i, (_, d))| println!("{}: {}", i + 1, d));
-                }
-                Action::Update => {
-                    let mut js = JsonStorage::load(config_path())?;
-                    js.dependencies
-                        .iter_mut()
-                        .map(|(_, d)| d)
-                        .try_for_each(|d| d.update())?;
-                    js.save(config_path())?
+                        .for_each(|e| reporter.message(&e.to_string()));
+                }
+                Action::SnippetList => {
+                    let js = JsonStorage::load(ctx.config_path())?;
+                    reporter.list(
+                        &snippets::list(ctx.snippets_dir(), &js)?
+                            .iter()
+                            .map(|s| s.to_string())
+                            .collect::<Vec<String>>(),
+                    );
+                }
+                Action::SnippetShow { name } => {
+                    reporter.message(&snippets::read(ctx.snippets_dir(), name)?);
+                }
+                Action::SnippetEdit { name } => {
+                    let path = snippets::path(ctx.snippets_dir(), name);
+                    open(&path)?;
+                    snippets::edit(&path)?;
+                }
+                Action::SnippetRename { from, to } => {
+                    ops::rename_snippet(ctx, from, to)?
+                        .iter()
+                        .for_each(|n| reporter.message(&format!("relinked {} to {}", n, to)));
+                }
+                Action::SnippetRemove { name } => {
+                    let js = JsonStorage::load(ctx.config_path())?;
+                    let path = snippets::existing(ctx.snippets_dir(), name)?;
+                    if let Some(snippet) = snippets::list(ctx.snippets_dir(), &js)?
+                        .into_iter()
+                        .find(|s| s.path == path)
+                    {
+                        let mut what = vec![snippet.path.display().to_string()];
+                        what.extend(
+                            snippet
+                                .dependencies
+                                .iter()
+                                .map(|d| format!("its link from {}", d)),
+                        );
+                        confirm_deletion(
+                            self.yes,
+                            &format!("move snippet {} to the trash?", name),
+                            &what,
+                        )?;
+                    }
+                    ops::remove_snippet(ctx, name)?
+                        .iter()
+                        .for_each(|n| reporter.message(&format!("unlinked {} from {}", name, n)));
+                }
+                Action::SnippetRestore { name } => {
+                    let path = ops::restore_snippet(ctx, name)?;
+                    reporter.message(&format!("restored {}", path.display()));
+                }
+                Action::SnippetTrash => {
+                    reporter.list(
+                        &snippets::trashed(ctx.trash_dir())?
+                            .iter()
+                            .map(|t| t.to_string())
+                            .collect::<Vec<String>>(),
+                    );
+                }
+                Action::SnippetRefresh { name } => {
+                    ops::refresh_snippets(ctx, name.as_deref())?
+                        .iter()
+                        .for_each(|n| reporter.message(&format!("refreshed {}", n)));
+                }
+                Action::Link {
+                    name,
+                    source,
+                    tags,
+                    variant,
+                } => {
+                    let options = LinkOptions {
+                        name: name.clone(),
+                        source: source.clone(),
+                        tags: tags.clone(),
+                        variant: variant.clone(),
+                    };
+                    let path = ops::link(ctx, &options)?;
+                    reporter.message(&format!("linked {} to {}", path.display(), name));
+                }
+                Action::Apply {
+                    name,
+                    module,
+                    entry,
+                } => {
+                    let injection = ops::apply(ctx, name, module.as_deref(), *entry)?;
+                    reporter.message(&injection.to_string());
+                }
+                Action::SnippetVerify { sources } => {
+                    let verified = ops::verify_snippets(ctx, *sources)?;
+                    for snippet in verified.iter() {
+                        reporter.message(&snippet.to_string());
+                    }
+                    let drifted = verified
+                        .iter()
+                        .filter(|s| s.is_drifted())
+                        .map(|s| s.name.clone())
+                        .collect::<Vec<String>>();
+                    if !drifted.is_empty() {
+                        return Err(LimpError::SnippetsDrifted(drifted.join(", ")));
+                    }
+                }
+                Action::SnippetAdd { name, path } => {
+                    let target = ops::add_snippet(ctx, name, path)?;
+                    reporter.message(&format!("stored {}", target.display()));
+                }
+                Action::SnippetFind { keyword } => {
+                    let js = JsonStorage::load(ctx.config_path())?;
+                    reporter.list(
+                        &snippets::find(ctx.snippets_dir(), &js, keyword)?
+                            .iter()
+                            .map(|s| s.to_string())
+                            .collect::<Vec<String>>(),
+                    );
+                }
+                Action::List {
+                    sort,
+                    filter,
+                    with_snippets,
+                    tree,
+                    names_only,
+                } => {
+                    let js = JsonStorage::load(ctx.config_path())?;
+                    let deps = js.list(*sort, filter.as_deref(), *with_snippets);
+                    match tree {
+                        _ if *names_only => reporter
+                            .names(&deps.iter().map(|d| d.name.clone()).collect::<Vec<String>>()),
+                        Some(by) => reporter.groups(&js.group(&deps, *by)),
+                        None => reporter.dependencies(&deps),
+                    }
+                }
+                Action::Update {
+                    names,
+                    dry_run,
+                    jobs,
+                    changes,
+                    on_feature_mismatch,
+                    pre,
+                } => {
+                    let policy = UpdatePolicy {
+                        on_feature_mismatch: *on_feature_mismatch,
+                        allow_prerelease: *pre || ctx.config.allow_prerelease,
+                    };
+                    let report = ops::update(
+                        ctx,
+                        names,
+                        *dry_run,
+                        jobs.unwrap_or(ctx.config.update_jobs),
+                        policy,
+                    )?;
+                    for (name, old, new) in report.changes.iter() {
+                        if *changes {
+                            reporter.message(&ops::changelog(ctx, name, old, new)?.to_string());
+                        } else {
+                            reporter.message(&format!("{}: {} -> {}", name, old, new));
+                        }
+                    }
+                    for name in report.pinned.iter() {
+                        reporter.warning(&format!("{}: pinned, skipped", name));
+                    }
+                    for (name, features) in report.mismatches.iter() {
+                        let features = features.join(", ");
+                        reporter.warning(&match on_feature_mismatch {
+                            FeatureMismatch::Keep => format!(
+                                "{}: latest version has no {}, kept the current version",
+                                name, features
+                            ),
+                            _ => format!("{}: dropped features {}", name, features),
+                        });
+                    }
+                    if !report.errors.is_empty() {
+                        return Err(LimpError::UpdateFailed(
+                            report
+                                .errors
+                                .iter()
+                                .map(|(name, e)| format!("{}: {}", name, e))
+                                .collect::<Vec<String>>()
+                                .join("\n"),
+                        ));
+                    }
                 }
             }
         }
@@ -0,0 +1,93 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    error::LimpError,
+    storage::{JsonDependency, JsonStorage, MergeSummary, DEFAULT_VARIANT},
+};
+
+const BUNDLE_FILE: &str = "bundle.json";
+const SNIPPETS_DIR: &str = "snippets";
+
+/// Writes the dependencies to `<dir>/bundle.json` and copies their snippets
+/// to `<dir>/snippets/<dependency>.rs`, or `<dependency>-<variant>.rs` for
+/// variants other than the default one, so the directory can be shared and
+/// imported elsewhere. Returns the number of exported dependencies.
+pub fn export<P: AsRef<Path>>(storage: &JsonStorage, dir: P) -> Result<usize, LimpError> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir.join(SNIPPETS_DIR))?;
+
+    let mut bundle = storage.clone();
+    for dep in bundle.dependencies.values_mut() {
+        for (variant, path) in dep.snippets.iter_mut() {
+            if !Path::new(path).exists() {
+                return Err(LimpError::SnippetNotFound(path.to_string()));
+            }
+            let relative = match variant.as_str() {
+                DEFAULT_VARIANT => format!("{}/{}.rs", SNIPPETS_DIR, dep.name),
+                _ => format!("{}/{}-{}.rs", SNIPPETS_DIR, dep.name, variant),
+            };
+            let target = dir.join(&relative);
+            fs::copy(&*path, &target)?;
+            *path = target.to_string_lossy().to_string();
+        }
+    }
+    bundle.save(dir.join(BUNDLE_FILE))?;
+    Ok(bundle.dependencies.len())
+}
+
+/// Merges the bundle in `dir` into `storage` like `JsonStorage::merge`. The
+/// snippets of added and replaced dependencies are copied to `snippets`,
+/// next to existing files rather than over them.
+pub fn import<P, S, F>(
+    storage: &mut JsonStorage,
+    dir: P,
+    snippets: S,
+    replace: F,
+) -> Result<MergeSummary, LimpError>
+where
+    P: AsRef<Path>,
+    S: AsRef<Path>,
+    F: FnMut(&JsonDependency, &JsonDependency) -> Result<bool, LimpError>,
+{
+    let dir = dir.as_ref();
+    let path = dir.join(BUNDLE_FILE);
+    if !path.exists() {
+        return Err(LimpError::InvalidBundle(dir.display().to_string()));
+    }
+    // snippet paths are loaded relative to the bundle directory
+    let bundle = JsonStorage::load(path)?;
+    let deps = bundle.dependencies.into_values().collect::<Vec<_>>();
+    let summary = storage.merge(deps, replace)?;
+
+    fs::create_dir_all(snippets.as_ref())?;
+    for name in summary.added.iter().chain(&summary.replaced) {
+        let Some(dep) = storage.get_mut(name) else {
+            continue;
+        };
+        for path in dep.snippets.values_mut() {
+            let source = PathBuf::from(&*path);
+            if !source.starts_with(dir) {
+                continue;
+            }
+            let target = free_path(snippets.as_ref(), name, &fs::read(&source)?);
+            fs::copy(&source, &target)?;
+            *path = target.to_string_lossy().to_string();
+        }
+    }
+    Ok(summary)
+}
+
+/// `<dir>/<name>.rs`, or `<name>-1.rs`, `<name>-2.rs`, ... when a file with
+/// other content already has that name.
+fn free_path(dir: &Path, name: &str, content: &[u8]) -> PathBuf {
+    let mut path = dir.join(format!("{}.rs", name));
+    let mut n = 1;
+    while path.exists() && fs::read(&path).ok().as_deref() != Some(content) {
+        path = dir.join(format!("{}-{}.rs", name, n));
+        n += 1;
+    }
+    path
+}
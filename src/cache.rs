@@ -0,0 +1,162 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::{error::LimpError, files, logging};
+
+// one day
+const DEFAULT_TTL: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// What `limp cache info` reports about the entries of a cache.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheInfo {
+    pub entries: usize,
+    /// total size of the entries in bytes
+    pub bytes: u64,
+    /// entries older than the TTL, only used offline or as a fallback
+    pub expired: usize,
+    /// age of the oldest entry
+    pub oldest: Option<Duration>,
+    /// age of the newest entry
+    pub newest: Option<Duration>,
+}
+
+/// `duration` in its largest whole unit, like `3h` or `2d`.
+pub(crate) fn age(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
+
+impl std::fmt::Display for CacheInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} entries, {:.1} KiB, {} expired",
+            self.entries,
+            self.bytes as f64 / 1024.0,
+            self.expired
+        )?;
+        if let (Some(oldest), Some(newest)) = (self.oldest, self.newest) {
+            write!(
+                f,
+                "\noldest: {} ago, newest: {} ago",
+                age(oldest),
+                age(newest)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Raw crates.io responses stored as `<cache_dir>/<crate>.json`.
+#[derive(Debug, Clone)]
+pub struct Cache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Self::new(files::cache_dir(), DEFAULT_TTL)
+    }
+}
+
+impl Cache {
+    pub fn new<P: AsRef<Path>>(dir: P, ttl: Duration) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            ttl,
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn entry_path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", name))
+    }
+
+    /// Returns the cached body if it is younger than the TTL, or any cached
+    /// body when `allow_stale` is set.
+    pub fn get(&self, name: &str, allow_stale: bool) -> Option<String> {
+        let path = self.entry_path(name);
+        let Some(modified) = fs::metadata(&path).and_then(|m| m.modified()).ok() else {
+            logging::trace("cache", &format!("{}: miss", name));
+            return None;
+        };
+        let age = SystemTime::now()
+            .duration_since(modified)
+            .unwrap_or_default();
+        if !allow_stale && age >= self.ttl {
+            logging::trace(
+                "cache",
+                &format!("{}: expired {}s ago", name, (age - self.ttl).as_secs()),
+            );
+            return None;
+        }
+        logging::trace("cache", &format!("{}: hit", name));
+        fs::read_to_string(path).ok()
+    }
+
+    pub fn put(&self, name: &str, body: &str) -> Result<(), LimpError> {
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.entry_path(name), body)?;
+        logging::trace("cache", &format!("{}: stored", name));
+        Ok(())
+    }
+
+    /// The files of the entries with their metadata. A missing cache
+    /// directory has none.
+    fn entries(&self) -> Result<Vec<(PathBuf, fs::Metadata)>, LimpError> {
+        if !self.dir.is_dir() {
+            return Ok(vec![]);
+        }
+        let mut entries = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "json") {
+                let metadata = fs::metadata(&path)?;
+                if metadata.is_file() {
+                    entries.push((path, metadata));
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    pub fn info(&self) -> Result<CacheInfo, LimpError> {
+        let now = SystemTime::now();
+        let mut info = CacheInfo::default();
+        for (_, metadata) in self.entries()? {
+            let age = metadata
+                .modified()
+                .map(|m| now.duration_since(m).unwrap_or_default())
+                .unwrap_or_default();
+            info.entries += 1;
+            info.bytes += metadata.len();
+            if age >= self.ttl {
+                info.expired += 1;
+            }
+            info.oldest = Some(info.oldest.map_or(age, |o| o.max(age)));
+            info.newest = Some(info.newest.map_or(age, |n| n.min(age)));
+        }
+        Ok(info)
+    }
+
+    /// Removes every entry, returning how many there were.
+    pub fn clear(&self) -> Result<usize, LimpError> {
+        let entries = self.entries()?;
+        for (path, _) in entries.iter() {
+            fs::remove_file(path)?;
+        }
+        Ok(entries.len())
+    }
+}
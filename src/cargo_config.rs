@@ -0,0 +1,126 @@
+//! Settings limp shares with cargo, read from `config.toml` in `CARGO_HOME`:
+//! the HTTP proxy, named registries and the source crates.io is replaced
+//! with, so limp talks to the same mirror as cargo.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use toml::Value;
+
+use crate::{crates::CRATES_IO, error::LimpError, files};
+
+/// Proxy variables cargo falls back to without `http.proxy`, in order.
+const PROXY_VARS: [&str; 4] = ["HTTPS_PROXY", "https_proxy", "HTTP_PROXY", "http_proxy"];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CargoConfig {
+    /// `[http] proxy`
+    pub http_proxy: Option<String>,
+    /// index URLs of `[registries.<name>]` and of `[source.<name>]` with a
+    /// `registry`, by name
+    pub indexes: BTreeMap<String, String>,
+    /// `replace-with` of the `[source.<name>]` tables, by name
+    pub replacements: BTreeMap<String, String>,
+}
+
+/// `CARGO_HOME`, or `~/.cargo` when it is unset.
+pub fn cargo_home() -> PathBuf {
+    std::env::var_os("CARGO_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| files::home_dir().join(".cargo"))
+}
+
+/// Hosts from `NO_PROXY` that are reached without the proxy.
+pub fn no_proxy() -> Vec<String> {
+    std::env::var("NO_PROXY")
+        .or_else(|_| std::env::var("no_proxy"))
+        .unwrap_or_default()
+        .split(',')
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty())
+        .collect()
+}
+
+impl CargoConfig {
+    /// Reads `config.toml`, or the older `config`, in `cargo_home`. Without
+    /// either the config is empty.
+    pub fn load<P: AsRef<Path>>(cargo_home: P) -> Result<Self, LimpError> {
+        let cargo_home = cargo_home.as_ref();
+        for name in ["config.toml", "config"] {
+            let path = cargo_home.join(name);
+            if path.is_file() {
+                return Self::parse(&fs::read_to_string(&path)?).map_err(|e| match e {
+                    LimpError::InvalidConfig(e) => {
+                        LimpError::InvalidConfig(format!("{}: {}", path.display(), e))
+                    }
+                    e => e,
+                });
+            }
+        }
+        Ok(Self::default())
+    }
+
+    pub fn parse(content: &str) -> Result<Self, LimpError> {
+        let table = content
+            .parse::<toml::Table>()
+            .map_err(|e| LimpError::InvalidConfig(e.to_string()))?;
+        let string =
+            |value: &Value, key: &str| value.get(key).and_then(Value::as_str).map(String::from);
+        let tables = |key: &str| {
+            table
+                .get(key)
+                .and_then(Value::as_table)
+                .into_iter()
+                .flatten()
+        };
+
+        let mut config = Self {
+            http_proxy: table.get("http").and_then(|http| string(http, "proxy")),
+            ..Self::default()
+        };
+        for (name, registry) in tables("registries") {
+            if let Some(index) = string(registry, "index") {
+                config.indexes.insert(name.clone(), index);
+            }
+        }
+        for (name, source) in tables("source") {
+            if let Some(index) = string(source, "registry") {
+                config.indexes.insert(name.clone(), index);
+            }
+            if let Some(with) = string(source, "replace-with") {
+                config.replacements.insert(name.clone(), with);
+            }
+        }
+        Ok(config)
+    }
+
+    /// The proxy cargo uses: `http.proxy`, or the first of `HTTPS_PROXY`,
+    /// `https_proxy`, `HTTP_PROXY` and `http_proxy` that is set.
+    pub fn proxy(&self) -> Option<String> {
+        self.http_proxy.clone().or_else(|| {
+            PROXY_VARS
+                .iter()
+                .filter_map(|v| std::env::var(v).ok())
+                .find(|v| !v.trim().is_empty())
+        })
+    }
+
+    /// The index crates.io is replaced with, following chains of
+    /// `replace-with`. `None` when crates.io isn't replaced, or by a source
+    /// that isn't a registry, like a vendored directory.
+    pub fn crates_io_index(&self) -> Option<&str> {
+        let mut name: &str = CRATES_IO;
+        // a cycle of replacements can't take more steps than there are
+        for _ in 0..self.replacements.len() {
+            name = self.replacements.get(name)?.as_str();
+            if let Some(index) = self.indexes.get(name) {
+                return Some(index);
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,62 @@
+use std::io::Write;
+
+use clap_complete::Shell;
+
+use crate::{actions::CommandHandler, error::LimpError};
+
+/// Subcommands whose `name` argument is a stored dependency.
+pub const DEPENDENCY_SUBCOMMANDS: [&str; 9] = [
+    "add",
+    "del",
+    "link",
+    "rename",
+    "pin",
+    "unpin",
+    "constrain",
+    "unconstrain",
+    "prerelease",
+];
+
+/// Writes the completion script for `shell` to `out`. For bash, zsh and fish
+/// the script also completes stored dependency names using `limp __complete`.
+pub fn generate<W: Write>(shell: Shell, out: &mut W) -> Result<(), LimpError> {
+    clap_complete::generate(shell, &mut CommandHandler::build(), "limp", out);
+    if let Some(extra) = dependency_completion(shell) {
+        out.write_all(extra.as_bytes())?;
+    }
+    Ok(())
+}
+
+fn dependency_completion(shell: Shell) -> Option<String> {
+    let subcommands = DEPENDENCY_SUBCOMMANDS.join(" ");
+    match shell {
+        Shell::Bash => Some(format!(
+            r#"
+_limp_dependencies() {{
+    if [[ ${{COMP_CWORD}} -eq 2 && " {subcommands} " == *" ${{COMP_WORDS[1]}} "* ]]; then
+        COMPREPLY=( $(compgen -W "$(limp __complete 2>/dev/null)" -- "${{COMP_WORDS[COMP_CWORD]}}") )
+        return 0
+    fi
+    _limp "$@"
+}}
+complete -F _limp_dependencies -o bashdefault -o default limp
+"#
+        )),
+        Shell::Zsh => Some(format!(
+            r#"
+_limp_dependencies() {{
+    if (( CURRENT == 3 )) && [[ " {subcommands} " == *" ${{words[2]}} "* ]]; then
+        compadd -- ${{(f)"$(limp __complete 2>/dev/null)"}}
+    else
+        _limp "$@"
+    fi
+}}
+compdef _limp_dependencies limp
+"#
+        )),
+        Shell::Fish => Some(format!(
+            "\ncomplete -c limp -n \"__fish_seen_subcommand_from {subcommands}\" -f -a \"(limp __complete 2>/dev/null)\"\n"
+        )),
+        _ => None,
+    }
+}
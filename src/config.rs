@@ -0,0 +1,264 @@
+use std::{collections::BTreeMap, fs, path::Path, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    crates::{parse_rust_version, HttpOptions, RegistrySource},
+    error::LimpError,
+    license::LicensePolicy,
+    storage::StorageFormat,
+};
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SnippetMerge {
+    /// merge imports into the entry file and append the code
+    #[default]
+    Entry,
+    /// write the snippet to `src/<dependency>.rs` and declare the module
+    Module,
+}
+
+/// User settings stored in `config.toml` next to the dependency database.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct Config {
+    pub edition: String,
+    pub author: Option<String>,
+    /// used with `author` for `authors` in generated manifests
+    pub email: Option<String>,
+    pub git_init: bool,
+    pub snippet_merge: SnippetMerge,
+    pub msrv: Option<String>,
+    pub update_jobs: usize,
+    /// let `limp update` move to pre-releases even without `--pre`
+    pub allow_prerelease: bool,
+    pub cache_ttl_hours: u64,
+    /// how long deleted snippets are kept in the trash
+    pub trash_days: u64,
+    /// how long to wait for a connection to the registry
+    pub connect_timeout_secs: u64,
+    /// how long to wait for the next bytes of an answer
+    pub read_timeout_secs: u64,
+    /// retries of rate limited or failed registry requests
+    pub retries: u32,
+    /// wait before the first retry, doubled for every further one
+    pub retry_backoff_ms: u64,
+    pub default_registry: Option<String>,
+    /// run `cargo check` after `limp init` even without `--check`
+    pub check_on_init: bool,
+    /// run `rustfmt` on the sources of `limp init` even without `--fmt`
+    pub fmt_on_init: bool,
+    /// append the log to `limp.log` in the storage directory even without
+    /// `--log-file`
+    pub log_file: bool,
+    pub storage_format: StorageFormat,
+    /// Edited in `config.toml` only, e.g. `[registries.company]` with `api`
+    /// and an optional `token`.
+    pub registries: BTreeMap<String, RegistrySource>,
+    /// crates.io API token set by `limp login`, kept out of `limp config`
+    pub token: Option<String>,
+    /// Edited in `config.toml` only, `[licenses]` with `allow`, `deny` and
+    /// `fail`.
+    pub licenses: LicensePolicy,
+    /// Edited in `config.toml` only, `[manifest]` with tables merged into
+    /// every Cargo.toml `limp init` writes, e.g. `profile.release.lto = true`.
+    pub manifest: toml::Table,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            edition: "2021".to_string(),
+            author: None,
+            email: None,
+            git_init: true,
+            snippet_merge: SnippetMerge::default(),
+            msrv: None,
+            update_jobs: 8,
+            allow_prerelease: false,
+            cache_ttl_hours: 24,
+            trash_days: 30,
+            connect_timeout_secs: 10,
+            read_timeout_secs: 30,
+            retries: 3,
+            retry_backoff_ms: 500,
+            default_registry: None,
+            check_on_init: false,
+            fmt_on_init: false,
+            log_file: false,
+            storage_format: StorageFormat::default(),
+            registries: BTreeMap::new(),
+            token: None,
+            licenses: LicensePolicy::default(),
+            manifest: toml::Table::new(),
+        }
+    }
+}
+
+impl Config {
+    pub const KEYS: [&'static str; 19] = [
+        "edition",
+        "author",
+        "email",
+        "git_init",
+        "snippet_merge",
+        "msrv",
+        "update_jobs",
+        "allow_prerelease",
+        "cache_ttl_hours",
+        "trash_days",
+        "connect_timeout_secs",
+        "read_timeout_secs",
+        "retries",
+        "retry_backoff_ms",
+        "default_registry",
+        "check_on_init",
+        "fmt_on_init",
+        "log_file",
+        "storage_format",
+    ];
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, LimpError> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        toml::from_str(&fs::read_to_string(path)?)
+            .map_err(|e| LimpError::InvalidConfig(e.to_string()))
+    }
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), LimpError> {
+        let path = path.as_ref();
+        fs::create_dir_all(path.parent().unwrap_or(Path::new("./")))?;
+        let content =
+            toml::to_string_pretty(self).map_err(|e| LimpError::InvalidConfig(e.to_string()))?;
+        #[cfg(unix)]
+        if self.has_tokens() {
+            use std::{
+                io::Write,
+                os::unix::fs::{OpenOptionsExt, PermissionsExt},
+            };
+            // restrict the file before the token is written to it, `mode`
+            // only applies when the file is created
+            if path.exists() {
+                fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+            }
+            fs::OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .mode(0o600)
+                .open(path)?
+                .write_all(content.as_bytes())?;
+            return Ok(());
+        }
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Whether the settings hold a registry token, making the file readable
+    /// by its owner only.
+    fn has_tokens(&self) -> bool {
+        self.token.is_some() || self.registries.values().any(|r| r.token.is_some())
+    }
+
+    /// The configured rust version to resolve dependencies for, if any.
+    pub fn msrv(&self) -> Result<Option<semver::Version>, LimpError> {
+        self.msrv
+            .as_deref()
+            .map(|m| parse_rust_version(m).ok_or_else(|| LimpError::InvalidVersion(m.to_string())))
+            .transpose()
+    }
+
+    pub fn cache_ttl(&self) -> Duration {
+        Duration::from_secs(self.cache_ttl_hours * 60 * 60)
+    }
+
+    pub fn trash_retention(&self) -> Duration {
+        Duration::from_secs(self.trash_days * 24 * 60 * 60)
+    }
+
+    /// Timeouts and retries of registry requests, without a proxy.
+    pub fn http_options(&self) -> HttpOptions {
+        HttpOptions {
+            connect_timeout: Duration::from_secs(self.connect_timeout_secs),
+            read_timeout: Duration::from_secs(self.read_timeout_secs),
+            retries: self.retries,
+            backoff: Duration::from_millis(self.retry_backoff_ms),
+            ..HttpOptions::default()
+        }
+    }
+
+    fn to_table(&self) -> Result<toml::Table, LimpError> {
+        match toml::Value::try_from(self) {
+            Ok(toml::Value::Table(table)) => Ok(table),
+            Ok(_) => Err(LimpError::InvalidConfig("not a table".to_string())),
+            Err(e) => Err(LimpError::InvalidConfig(e.to_string())),
+        }
+    }
+
+    fn check_key(key: &str) -> Result<(), LimpError> {
+        if Self::KEYS.contains(&key) {
+            Ok(())
+        } else {
+            Err(LimpError::InvalidConfig(format!(
+                "unknown key `{}`, expected one of: {}",
+                key,
+                Self::KEYS.join(", ")
+            )))
+        }
+    }
+
+    /// Returns the value of `key`, or `None` when it is unset.
+    pub fn get(&self, key: &str) -> Result<Option<String>, LimpError> {
+        Self::check_key(key)?;
+        Ok(self.to_table()?.get(key).map(|v| match v {
+            toml::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        }))
+    }
+
+    /// Sets `key` from its command line representation. An empty value resets
+    /// the key to its default.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), LimpError> {
+        Self::check_key(key)?;
+        let mut table = self.to_table()?;
+        if value.is_empty() {
+            table.remove(key);
+            *self = toml::Value::Table(table)
+                .try_into()
+                .map_err(|e: toml::de::Error| LimpError::InvalidConfig(e.to_string()))?;
+            return Ok(());
+        }
+
+        // "2021" may be a string or a number depending on the key, so try
+        // the typed value first and fall back to a plain string
+        let typed = value
+            .parse::<bool>()
+            .map(toml::Value::Boolean)
+            .or_else(|_| value.parse::<i64>().map(toml::Value::Integer))
+            .unwrap_or_else(|_| toml::Value::String(value.to_string()));
+        let mut last_error = String::new();
+        for candidate in [typed, toml::Value::String(value.to_string())] {
+            table.insert(key.to_string(), candidate);
+            match toml::Value::Table(table.clone()).try_into::<Config>() {
+                Ok(config) => {
+                    *self = config;
+                    return Ok(());
+                }
+                Err(e) => last_error = e.to_string(),
+            }
+        }
+        Err(LimpError::InvalidConfig(format!(
+            "{} = {}: {}",
+            key, value, last_error
+        )))
+    }
+
+    pub fn entries(&self) -> Result<Vec<(String, Option<String>)>, LimpError> {
+        Self::KEYS
+            .iter()
+            .map(|k| Ok((k.to_string(), self.get(k)?)))
+            .collect()
+    }
+}
@@ -0,0 +1,129 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    cache::Cache,
+    cargo_config::{self, CargoConfig},
+    config::Config,
+    crates::{HttpClient, HttpOptions, Registry, RegistryClient},
+    error::LimpError,
+    files::storage_path,
+    logging,
+    storage::{StorageLock, LOCK_TIMEOUT},
+};
+
+/// The client for registry requests with the timeouts and retries of
+/// `config` and the proxy of `cargo`.
+fn http_client(config: &Config, cargo: &CargoConfig) -> Result<HttpClient, LimpError> {
+    HttpClient::new(HttpOptions {
+        proxy: cargo.proxy(),
+        no_proxy: cargo_config::no_proxy(),
+        ..config.http_options()
+    })
+}
+
+/// Where the commands keep their data and how they reach the registry. The
+/// CLI uses the storage directory (see `LIMP_HOME`), tests a temporary one
+/// with a `MockClient` so they don't touch the user's data or the network.
+#[derive(Debug, Clone)]
+pub struct Context {
+    pub root: PathBuf,
+    pub config: Config,
+    pub registry: Registry,
+    /// `--frozen`: fail instead of resolving dependencies that aren't stored
+    pub frozen: bool,
+}
+
+impl Context {
+    /// Loads the context of the storage directory.
+    pub fn from_env(offline: bool) -> Result<Self, LimpError> {
+        Self::load(storage_path(), offline)
+    }
+
+    /// Loads `config.toml` from `root` and sets the registry up from it and
+    /// from cargo's config, whose proxy and registries limp uses too.
+    pub fn load<P: AsRef<Path>>(root: P, offline: bool) -> Result<Self, LimpError> {
+        let root = root.as_ref().to_path_buf();
+        let config = Config::load(root.join("config.toml"))?;
+        let cargo = CargoConfig::load(cargo_config::cargo_home())?;
+        let client = http_client(&config, &cargo)?;
+        let registry =
+            Registry::with_cache(offline, Cache::new(root.join("cache"), config.cache_ttl()))
+                .with_client(Arc::new(client))
+                .with_msrv(config.msrv()?)
+                .with_sources(config.registries.clone())
+                .with_cargo_config(&cargo)
+                .with_token(config.token.clone())
+                .using(config.default_registry.as_deref())?;
+        Ok(Self {
+            root,
+            config,
+            registry,
+            frozen: false,
+        })
+    }
+
+    /// Waits `secs` for connections and answers instead of the configured
+    /// timeouts, like `--timeout`.
+    pub fn with_timeout(mut self, secs: u64) -> Result<Self, LimpError> {
+        self.config.connect_timeout_secs = secs;
+        self.config.read_timeout_secs = secs;
+        let cargo = CargoConfig::load(cargo_config::cargo_home())?;
+        let client = http_client(&self.config, &cargo)?;
+        Ok(self.with_client(Arc::new(client)))
+    }
+
+    pub fn with_client(self, client: Arc<dyn RegistryClient>) -> Self {
+        Self {
+            registry: self.registry.with_client(client),
+            ..self
+        }
+    }
+
+    /// `dependencies.json`, or `dependencies.toml` with the `storage_format`
+    /// setting.
+    pub fn config_path(&self) -> PathBuf {
+        self.root
+            .join("dependencies")
+            .with_extension(self.config.storage_format.extension())
+    }
+
+    /// Locks the storage at `config_path` for a command that loads, changes
+    /// and saves it, until the lock is dropped. See
+    /// [`crate::storage::JsonStorage::load_locked`].
+    pub fn lock_storage(&self) -> Result<StorageLock, LimpError> {
+        StorageLock::acquire(self.config_path(), LOCK_TIMEOUT)
+    }
+
+    /// `history.jsonl`, see [`crate::history`]
+    pub fn history_path(&self) -> PathBuf {
+        self.root.join("history.jsonl")
+    }
+
+    pub fn log_path(&self) -> PathBuf {
+        self.root.join(logging::FILE_NAME)
+    }
+
+    pub fn settings_path(&self) -> PathBuf {
+        self.root.join("config.toml")
+    }
+
+    pub fn snippets_dir(&self) -> PathBuf {
+        self.root.join("snippets")
+    }
+
+    /// Where deleted snippets are kept for `trash_days`.
+    pub fn trash_dir(&self) -> PathBuf {
+        self.root.join("trash")
+    }
+
+    pub fn templates_dir(&self) -> PathBuf {
+        self.root.join("templates")
+    }
+
+    pub fn hooks_dir(&self) -> PathBuf {
+        self.root.join("hooks")
+    }
+}
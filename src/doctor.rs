@@ -0,0 +1,203 @@
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use crate::{
+    crates::Registry,
+    migrations::{self, SCHEMA_VERSION},
+    storage::JsonStorage,
+};
+
+/// Outcome of a single `limp doctor` check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    /// what was found when the check passed, or what is wrong
+    pub details: String,
+    /// how to fix the problem, `None` when the check passed
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &'static str, details: impl Into<String>) -> Self {
+        Self {
+            name,
+            details: details.into(),
+            fix: None,
+        }
+    }
+    fn failed(name: &'static str, details: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name,
+            details: details.into(),
+            fix: Some(fix.into()),
+        }
+    }
+    pub fn passed(&self) -> bool {
+        self.fix.is_none()
+    }
+}
+
+impl std::fmt::Display for Check {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // [ok] storage: /home/user/.config/limp
+        // [fail] git: not found
+        //     fix: install git or set git_init to false
+        match &self.fix {
+            None => write!(f, "[ok] {}: {}", self.name, self.details),
+            Some(fix) => write!(
+                f,
+                "[fail] {}: {}\n    fix: {}",
+                self.name, self.details, fix
+            ),
+        }
+    }
+}
+
+/// Runs every check against the storage directory `dir`.
+pub fn run<P: AsRef<Path>>(dir: P, registry: &Registry) -> Vec<Check> {
+    let dir = dir.as_ref();
+    let mut checks = vec![storage_dir(dir)];
+    match load_database(&dir.join("dependencies.json")) {
+        Ok(storage) => {
+            checks.push(Check::ok(
+                "database",
+                format!("{} dependencies", storage.dependencies.len()),
+            ));
+            checks.push(snippets(&storage));
+        }
+        Err(check) => checks.push(check),
+    }
+    checks.push(network(registry));
+    checks.push(git());
+    checks
+}
+
+/// The storage directory exists and files can be created in it.
+pub fn storage_dir(dir: &Path) -> Check {
+    const NAME: &str = "storage";
+    if !dir.is_dir() {
+        return Check::failed(
+            NAME,
+            format!("{} does not exist", dir.display()),
+            format!(
+                "create it with `mkdir -p {}`, or use another one with LIMP_HOME or --config-dir",
+                dir.display()
+            ),
+        );
+    }
+    let probe = dir.join(".limp-doctor");
+    match fs::write(&probe, b"").and_then(|_| fs::remove_file(&probe)) {
+        Ok(()) => Check::ok(NAME, dir.display().to_string()),
+        Err(e) => Check::failed(
+            NAME,
+            format!("{} is not writable: {}", dir.display(), e),
+            "fix the permissions of the directory, or use another one with LIMP_HOME or --config-dir",
+        ),
+    }
+}
+
+/// Parses `dependencies.json` the way `JsonStorage::load` does, without
+/// writing migrated files back.
+fn load_database(path: &Path) -> Result<JsonStorage, Check> {
+    const NAME: &str = "database";
+    let fix = format!(
+        "repair {} by hand, or move it away to start over",
+        path.display()
+    );
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(_) if !path.exists() => return Ok(JsonStorage::default()),
+        Err(e) => return Err(Check::failed(NAME, e.to_string(), fix)),
+    };
+    if content.trim().is_empty() {
+        return Ok(JsonStorage::default());
+    }
+    let mut doc = serde_json::from_str(&content)
+        .map_err(|e| Check::failed(NAME, format!("invalid JSON: {}", e), fix.clone()))?;
+    if migrations::schema_version(&doc) > SCHEMA_VERSION {
+        return Err(Check::failed(
+            NAME,
+            format!(
+                "schema version {} is newer than {}",
+                migrations::schema_version(&doc),
+                SCHEMA_VERSION
+            ),
+            "upgrade limp with `cargo install limp`",
+        ));
+    }
+    let root = path.parent().unwrap_or(Path::new(""));
+    migrations::migrate(&mut doc, root)
+        .map_err(|e| Check::failed(NAME, e.to_string(), fix.clone()))?;
+    let mut storage: JsonStorage =
+        serde_json::from_value(doc).map_err(|e| Check::failed(NAME, e.to_string(), fix))?;
+    storage.resolve_paths(root);
+    Ok(storage)
+}
+
+/// Every linked snippet variant points at an existing file.
+pub fn snippets(storage: &JsonStorage) -> Check {
+    const NAME: &str = "snippets";
+    let linked = storage
+        .dependencies
+        .values()
+        .filter(|d| !d.snippets.is_empty())
+        .collect::<Vec<_>>();
+    let missing = linked
+        .iter()
+        .filter(|d| d.snippets.values().any(|p| !Path::new(p).is_file()))
+        .map(|d| d.name.clone())
+        .collect::<Vec<String>>();
+    if missing.is_empty() {
+        return Check::ok(NAME, format!("{} linked", linked.len()));
+    }
+    Check::failed(
+        NAME,
+        format!("missing for {}", missing.join(", ")),
+        "relink them with `limp new <name> -p <snippet>` or restore the files",
+    )
+}
+
+/// The registry answers, skipped in offline mode.
+pub fn network(registry: &Registry) -> Check {
+    const NAME: &str = "network";
+    if registry.offline {
+        return Check::ok(NAME, "skipped in offline mode");
+    }
+    match registry.ping() {
+        Ok(()) => Check::ok(
+            NAME,
+            format!(
+                "{} reachable",
+                registry.api().unwrap_or_else(|_| "registry".to_string())
+            ),
+        ),
+        Err(e) => Check::failed(
+            NAME,
+            e.to_string(),
+            "check your connection or proxy, or use --offline",
+        ),
+    }
+}
+
+/// `git` can be run, which `limp init` needs unless `git_init` is off.
+pub fn git() -> Check {
+    const NAME: &str = "git";
+    match Command::new("git")
+        .arg("--version")
+        .stderr(Stdio::null())
+        .output()
+    {
+        Ok(output) if output.status.success() => Check::ok(
+            NAME,
+            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        ),
+        _ => Check::failed(
+            NAME,
+            "not found",
+            "install git or run `limp config set git_init false`",
+        ),
+    }
+}
@@ -12,14 +12,92 @@ pub enum LimpError {
     GitError(String),
     #[error("HTTP error: {0}")]
     HttpError(#[from] Box<ureq::Error>),
+    #[error("Network unreachable, try again with --offline: {0}")]
+    Offline(String),
+    #[error("Rate limited by the registry, retry in {retry_after}s")]
+    RateLimited { retry_after: u64 },
+    #[error("Registry unavailable: {0}")]
+    RegistryUnavailable(String),
     #[error("Crate not found: {0}")]
     CrateNotFound(String),
     #[error("Version not found: {0}")]
     VersionNotFound(String),
+    #[error("Version is yanked, pass --allow-yanked to use it anyway: {0}")]
+    YankedVersion(String),
+    #[error("No version compatible with the rust version: {0}")]
+    IncompatibleRustVersion(String),
+    #[error("Invalid version requirement: {0}")]
+    InvalidVersion(String),
     #[error("Snippet not found: {0}")]
     SnippetNotFound(String),
+    #[error("Snippet conflict: {0}")]
+    SnippetConflict(String),
+    #[error("Not a Rust source file: {0}")]
+    InvalidSnippet(String),
+    #[error("Snippet includes itself: {0}")]
+    IncludeCycle(String),
     #[error("Incompatible features: {0}")]
     IncompatibleFeatures(String),
     #[error("Cannot add dependency: {0}")]
     CargoTomlNotFound(String),
+    #[error("Manifest parse error: {0}")]
+    ManifestError(#[from] toml_edit::TomlError),
+    #[error("Invalid manifest: {0}")]
+    InvalidManifest(String),
+    #[error("Dependency not found in Cargo.toml: {0}")]
+    DependencyNotInManifest(String),
+    #[error("Invalid arguments: {0}")]
+    InvalidArguments(String),
+    #[error("Invalid variable, expected key=value: {0}")]
+    InvalidVariable(String),
+    #[error("Unknown scaffold option: {0}")]
+    UnknownScaffold(String),
+    #[error("Template not found: {0}")]
+    TemplateNotFound(String),
+    #[error("Dependency not found in storage: {0}")]
+    DependencyNotFound(String),
+    #[error("Dependency already stored: {0}")]
+    DependencyAlreadyExists(String),
+    #[error("Failed to update dependencies:\n{0}")]
+    UpdateFailed(String),
+    #[error("Failed to resolve dependencies:\n{0}")]
+    ResolveFailed(String),
+    #[error("Invalid config: {0}")]
+    InvalidConfig(String),
+    #[error("Unknown registry, add it to [registries] in config.toml: {0}")]
+    UnknownRegistry(String),
+    #[error("Not a bundle directory, bundle.json is missing: {0}")]
+    InvalidBundle(String),
+    #[error("Storage was written by a newer limp: {0}")]
+    UnsupportedSchema(String),
+    #[error("Editor exited with an error: {0}")]
+    EditorFailed(String),
+    #[error("Failed to run cargo: {0}")]
+    CargoFailed(String),
+    #[error("Hook failed: {0}")]
+    HookFailed(String),
+    #[error("Generated project doesn't compile: {0}")]
+    CheckFailed(String),
+    #[error("Doctor found problems: {0}")]
+    DoctorFailed(String),
+    #[error("Not available offline: {0}")]
+    OfflineUnavailable(String),
+    #[error("Storage is locked by another limp, remove the lock file if none is running: {0}")]
+    StorageLocked(String),
+    #[error("Invalid storage file: {0}")]
+    InvalidStorage(String),
+    #[error("Not allowed with --frozen: {0}")]
+    Frozen(String),
+    #[error("Not logged in or the token was rejected, run `limp login`: {0}")]
+    NotLoggedIn(String),
+    #[error("License not allowed, pass --allow-license-violation to use it anyway: {0}")]
+    LicenseViolation(String),
+    #[error("A newer limp is available, run `limp self update`: {0}")]
+    Outdated(String),
+    #[error("Failed to run rustfmt: {0}")]
+    FormatFailed(String),
+    #[error("Snippets differ from what was stored: {0}")]
+    SnippetsDrifted(String),
+    #[error("Cancelled, pass --yes to go ahead without asking: {0}")]
+    Cancelled(String),
 }
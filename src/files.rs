@@ -5,12 +5,32 @@ use std::{
     process::Stdio,
 };
 
-use crate::error::LimpError;
+use crate::{
+    error::LimpError,
+    manifest::CargoManifest,
+    parser::{self, SnippetEntity, Vars},
+    scaffold::{Preset, Scaffold},
+    storage::{DependencyKind, JsonDependency},
+};
 
 const MAIN_SNIP: &str = r#"
 fn main() {
     println!("Hello, limp!");
 }"#;
+const LIB_SNIP: &str = r#"
+pub fn add(left: u64, right: u64) -> u64 {
+    left + right
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_works() {
+        assert_eq!(add(2, 2), 4);
+    }
+}"#;
 const NAME: &str = "limp";
 const CRATE_INFO_FILE: &str = "dependencies.db";
 const SNIPPETS_DIR: &str = "snippets";
@@ -104,12 +124,46 @@ pub fn username() -> String {
     std::env::var("USER").unwrap_or(std::env::var("USERNAME").unwrap_or("unknown".to_string()))
 }
 
+fn env_path(var: &str) -> Option<PathBuf> {
+    std::env::var_os(var)
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+}
+
+pub fn home_dir() -> PathBuf {
+    env_path("HOME")
+        .or_else(|| env_path("USERPROFILE"))
+        .or_else(|| {
+            let mut home = env_path("HOMEDRIVE")?.into_os_string();
+            home.push(env_path("HOMEPATH")?);
+            Some(PathBuf::from(home))
+        })
+        .unwrap_or_else(|| match std::env::consts::OS {
+            "windows" => PathBuf::from(format!("C:\\Users\\{}", username())),
+            "macos" => PathBuf::from(format!("/Users/{}", username())),
+            _ => PathBuf::from(format!("/home/{}", username())),
+        })
+}
+
+/// `LIMP_HOME` if set, otherwise the platform config directory:
+/// `%APPDATA%\limp` on Windows, `$XDG_CONFIG_HOME/limp` (defaulting to
+/// `~/.config/limp`) on Linux and `~/Library/Application Support/limp` on macOS
+/// unless `XDG_CONFIG_HOME` is set there too.
 pub fn storage_path() -> PathBuf {
-    let uname = username();
+    if let Some(home) = env_path("LIMP_HOME") {
+        return home;
+    }
 
     match std::env::consts::OS {
-        "windows" => PathBuf::from(format!("C:\\Users\\{}\\AppData\\Roaming\\limp", &uname)),
-        _ => PathBuf::from(format!("/home/{}/.config/limp/", &uname)),
+        "windows" => env_path("APPDATA")
+            .unwrap_or_else(|| home_dir().join("AppData").join("Roaming"))
+            .join(NAME),
+        "macos" => env_path("XDG_CONFIG_HOME")
+            .unwrap_or_else(|| home_dir().join("Library").join("Application Support"))
+            .join(NAME),
+        _ => env_path("XDG_CONFIG_HOME")
+            .unwrap_or_else(|| home_dir().join(".config"))
+            .join(NAME),
     }
 }
 
@@ -121,6 +175,22 @@ pub fn snippets_dir() -> PathBuf {
     storage_path().join("snippets")
 }
 
+pub fn settings_path() -> PathBuf {
+    storage_path().join("config.toml")
+}
+
+pub fn cache_dir() -> PathBuf {
+    storage_path().join("cache")
+}
+
+pub fn templates_dir() -> PathBuf {
+    storage_path().join("templates")
+}
+
+pub fn hooks_dir() -> PathBuf {
+    storage_path().join("hooks")
+}
+
 pub fn find_toml() -> Option<PathBuf> {
     if let Ok(mut path) = std::env::current_dir() {
         let pre_toml = path.join("Cargo.toml");
@@ -149,40 +219,487 @@ pub fn open<P: AsRef<Path>>(path: P) -> Result<File, LimpError> {
     Ok(file)
 }
 
-pub fn create_project(name: &str, deps: Option<&[String]>) -> Result<(), LimpError> {
-    let project = PathBuf::from(format!("./{}", name));
-    if project.exists() && project.read_dir()?.count() > 0 {
-        return Err(LimpError::CrateExistsNotEmpty(name.to_string()));
+/// Lists all files under `root` as paths relative to it, skipping entries
+/// whose file name is in `skip`.
+pub fn walk_files<P: AsRef<Path>>(root: P, skip: &[&str]) -> Result<Vec<PathBuf>, LimpError> {
+    fn walk(
+        root: &Path,
+        dir: &Path,
+        skip: &[&str],
+        out: &mut Vec<PathBuf>,
+    ) -> Result<(), LimpError> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            if skip.iter().any(|s| entry.file_name() == *s) {
+                continue;
+            }
+            if entry.file_type()?.is_dir() {
+                walk(root, &entry.path(), skip, out)?;
+            } else if let Ok(relative) = entry.path().strip_prefix(root) {
+                out.push(relative.to_path_buf());
+            }
+        }
+        Ok(())
     }
+    let root = root.as_ref();
+    let mut out = vec![];
+    walk(root, root, skip, &mut out)?;
+    out.sort();
+    Ok(out)
+}
 
-    let mut toml = open(project.join("Cargo.toml"))?;
+/// Copies the files under `from` into `to`, rendering placeholders in the
+/// contents of text files and in paths. Binary files are copied as they are.
+pub fn copy_rendered<P: AsRef<Path>, Q: AsRef<Path>>(
+    from: P,
+    to: Q,
+    skip: &[&str],
+    project_name: &str,
+    vars: &Vars,
+) -> Result<(), LimpError> {
+    let (from, to) = (from.as_ref(), to.as_ref());
+    for file in walk_files(from, skip)? {
+        let target = to.join(vars.render(project_name, &file.to_string_lossy()));
+        fs::create_dir_all(target.parent().unwrap_or(to))?;
+        match fs::read_to_string(from.join(&file)) {
+            Ok(text) => fs::write(target, vars.render(project_name, &text))?,
+            Err(_) => {
+                fs::copy(from.join(&file), target)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Recursively copies `from` into `to`, skipping entries whose file name is in `skip`.
+pub fn copy_dir<P: AsRef<Path>, Q: AsRef<Path>>(
+    from: P,
+    to: Q,
+    skip: &[&str],
+) -> Result<(), LimpError> {
+    let (from, to) = (from.as_ref(), to.as_ref());
+    for file in walk_files(from, skip)? {
+        let target = to.join(&file);
+        fs::create_dir_all(target.parent().unwrap_or(to))?;
+        fs::copy(from.join(&file), target)?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProjectKind {
+    #[default]
+    Bin,
+    Lib,
+}
+
+impl ProjectKind {
+    pub fn entry_file(&self) -> &'static str {
+        match self {
+            ProjectKind::Bin => "main.rs",
+            ProjectKind::Lib => "lib.rs",
+        }
+    }
+    pub fn snippet(&self) -> &'static str {
+        match self {
+            ProjectKind::Bin => MAIN_SNIP,
+            ProjectKind::Lib => LIB_SNIP,
+        }
+    }
+}
+
+/// Publishing fields of a new project's `[package]`, written when set.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metadata {
+    /// as `Name <email>`
+    pub authors: Vec<String>,
+    pub description: Option<String>,
+    pub repository: Option<String>,
+    pub keywords: Vec<String>,
+}
+
+/// An extra binary of a new project, written to `src/bin/<name>.rs`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Binary {
+    pub name: String,
+    /// snippet the binary starts from instead of the default `main`
+    pub snippet: Option<PathBuf>,
+}
+
+/// Everything `create_project` and `create_workspace` need besides the
+/// project path and its dependencies.
+#[derive(Debug, Clone)]
+pub struct ProjectOptions {
+    /// package name, the directory's name when unset
+    pub name: Option<String>,
+    pub kind: ProjectKind,
+    pub edition: String,
+    pub git: bool,
+    pub vars: Vars,
+    pub scaffold: Scaffold,
+    /// snippets merged in after those of the dependencies
+    pub snippets: Vec<PathBuf>,
+    /// where `// limp:include` lines in snippets are looked up
+    pub snippets_dir: PathBuf,
+    /// binaries next to the entry file, each with a `[[bin]]` section
+    pub bins: Vec<Binary>,
+    /// project type the entry file and manifest extras come from
+    pub preset: Option<Preset>,
+    /// merged into the project's Cargo.toml, or the workspace's
+    pub manifest_extras: toml::Table,
+    pub metadata: Metadata,
+}
+
+impl Default for ProjectOptions {
+    fn default() -> Self {
+        Self {
+            name: None,
+            kind: ProjectKind::default(),
+            edition: "2021".to_string(),
+            git: true,
+            vars: Vars::default(),
+            scaffold: Scaffold::default(),
+            snippets: vec![],
+            snippets_dir: snippets_dir(),
+            bins: vec![],
+            preset: None,
+            manifest_extras: toml::Table::new(),
+            metadata: Metadata::default(),
+        }
+    }
+}
+
+impl ProjectOptions {
+    /// The package name of a project created at `path`.
+    /// `options.name`, or the name of the directory at `path`, which may be
+    /// `.` like in `limp init .`.
+    pub fn package_name<P: AsRef<Path>>(&self, path: P) -> String {
+        self.name.clone().unwrap_or_else(|| {
+            let path = path.as_ref();
+            path.file_name()
+                .filter(|n| *n != "." && *n != "..")
+                .map(PathBuf::from)
+                .or_else(|| fs::canonicalize(path).ok())
+                .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+                .unwrap_or_else(|| path.display().to_string())
+        })
+    }
+}
+
+fn write_crate(
+    dir: &Path,
+    name: &str,
+    deps: Option<&[JsonDependency]>,
+    options: &ProjectOptions,
+    from_workspace: bool,
+) -> Result<(), LimpError> {
+    let mut toml = open(dir.join("Cargo.toml"))?;
     writeln!(toml, "[package]")?;
     writeln!(toml, "name = \"{}\"", name)?;
     writeln!(toml, "version = \"0.1.0\"")?;
-    writeln!(toml, "edition = \"2021\"")?;
-    writeln!(toml)?;
-    writeln!(toml, "[dependencies]")?;
-    if let Some(deps) = deps {
-        for dep in deps.iter() {
-            writeln!(toml, "{}", dep)?
+    writeln!(toml, "edition = \"{}\"", options.edition)?;
+    let metadata = &options.metadata;
+    if !metadata.authors.is_empty() {
+        writeln!(
+            toml,
+            "authors = {}",
+            toml::Value::from(metadata.authors.clone())
+        )?;
+    }
+    if let Some(description) = &metadata.description {
+        writeln!(
+            toml,
+            "description = {}",
+            toml::Value::from(description.as_str())
+        )?;
+    }
+    if let Some(license) = &options.scaffold.license {
+        writeln!(toml, "license = \"{}\"", license)?;
+    }
+    if let Some(repository) = &metadata.repository {
+        writeln!(
+            toml,
+            "repository = {}",
+            toml::Value::from(repository.as_str())
+        )?;
+    }
+    if !metadata.keywords.is_empty() {
+        writeln!(
+            toml,
+            "keywords = {}",
+            toml::Value::from(metadata.keywords.clone())
+        )?;
+    }
+    for bin in options.bins.iter() {
+        writeln!(toml)?;
+        writeln!(toml, "[[bin]]")?;
+        writeln!(toml, "name = \"{}\"", bin.name)?;
+        writeln!(toml, "path = \"src/bin/{}.rs\"", bin.name)?;
+    }
+    if options.scaffold.benches {
+        writeln!(toml)?;
+        writeln!(toml, "[[bench]]")?;
+        writeln!(toml, "name = \"bench\"")?;
+        writeln!(toml, "harness = false")?;
+    }
+    for dep_kind in DependencyKind::ALL {
+        let kind_deps = deps
+            .unwrap_or_default()
+            .iter()
+            .filter(|d| d.kind == dep_kind)
+            .collect::<Vec<&JsonDependency>>();
+        // [dependencies] is always emitted, other tables only when used
+        if dep_kind != DependencyKind::Normal && kind_deps.is_empty() {
+            continue;
+        }
+        writeln!(toml)?;
+        writeln!(toml, "[{}]", dep_kind.table_name())?;
+        for dep in kind_deps {
+            if from_workspace {
+                writeln!(toml, "{}", dep.as_workspace())?
+            } else {
+                writeln!(toml, "{}", dep)?
+            }
         }
     }
 
-    let mut main = open(project.join("src").join("main.rs"))?;
-    main.write_all(MAIN_SNIP.as_bytes())?;
+    if let Some(extras) = options.preset.and_then(|p| p.manifest_extras) {
+        writeln!(toml)?;
+        write!(toml, "{}", extras)?;
+    }
+
+    // linked snippets replace the default one; binaries still need a main
+    let kind = options.kind;
+    let default = options.preset.map_or(kind.snippet(), |p| p.entry);
+    let source = match parser::load_from_deps(
+        deps.unwrap_or_default(),
+        &options.snippets,
+        &options.snippets_dir,
+    )? {
+        Some(snippet) if kind == ProjectKind::Bin && !snippet.is_main => {
+            let main = SnippetEntity::parse("", default);
+            SnippetEntity::merge(&[snippet, main])?.to_string()
+        }
+        Some(snippet) => snippet.to_string(),
+        None => default.to_string(),
+    };
+    let mut entry = open(dir.join("src").join(kind.entry_file()))?;
+    entry.write_all(options.vars.render(name, &source).as_bytes())?;
+
+    for bin in options.bins.iter() {
+        let source =
+            match parser::load_from_deps(&[], bin.snippet.as_slice(), &options.snippets_dir)? {
+                Some(snippet) if !snippet.is_main => {
+                    let main = SnippetEntity::parse("", MAIN_SNIP);
+                    SnippetEntity::merge(&[snippet, main])?.to_string()
+                }
+                Some(snippet) => snippet.to_string(),
+                None => MAIN_SNIP.to_string(),
+            };
+        let mut file = open(dir.join("src").join("bin").join(format!("{}.rs", bin.name)))?;
+        file.write_all(options.vars.render(name, &source).as_bytes())?;
+    }
+    options
+        .scaffold
+        .write_targets(dir, name, kind, &options.vars)?;
+    Ok(())
+}
+
+fn merge_manifest_extras(dir: &Path, options: &ProjectOptions) -> Result<(), LimpError> {
+    if options.manifest_extras.is_empty() {
+        return Ok(());
+    }
+    let mut manifest = CargoManifest::load(dir.join("Cargo.toml"))?;
+    manifest.merge_extras(&options.manifest_extras)?;
+    manifest.save()
+}
+
+/// Whether `dir` is already part of a git repository, like a project
+/// created with `limp init .` inside a clone.
+fn in_git_repo(dir: &Path) -> bool {
+    fs::canonicalize(dir)
+        .map(|dir| dir.ancestors().any(|d| d.join(".git").exists()))
+        .unwrap_or(false)
+}
 
+/// Runs `git init` unless the project is in a repository already, and
+/// ignores `/target`.
+fn init_git(project: &Path) -> Result<(), LimpError> {
+    let ignore = project.join(".gitignore");
+    let ignored = fs::read_to_string(&ignore).unwrap_or_default();
+    if !ignored.lines().any(|l| l.trim() == "/target") {
+        let mut gitignore = open(&ignore)?;
+        if !ignored.is_empty() && !ignored.ends_with('\n') {
+            writeln!(gitignore)?;
+        }
+        gitignore.write_all(b"/target")?;
+    }
+    if in_git_repo(project) {
+        return Ok(());
+    }
     if !std::process::Command::new("git")
-        .args(["init", name])
+        .arg("init")
+        .arg(project)
         .stdout(Stdio::null())
         .stderr(Stdio::null())
         .spawn()?
         .wait()?
         .success()
     {
-        return Err(LimpError::GitError(name.to_string()));
+        return Err(LimpError::GitError(project.display().to_string()));
     }
-    let mut gitignore = open(project.join(".gitignore"))?;
-    gitignore.write_all(b"/target")?;
+    Ok(())
+}
+
+/// Shallow clones `url` into `dest`, at `branch` (or tag) when given.
+fn clone_git(url: &str, branch: Option<&str>, dest: &Path) -> Result<(), LimpError> {
+    let mut command = std::process::Command::new("git");
+    command.args(["clone", "--quiet", "--depth", "1"]);
+    if let Some(branch) = branch {
+        command.args(["--branch", branch]);
+    }
+    let output = command
+        .arg(url)
+        .arg(dest)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| LimpError::GitError(format!("{}: {}", url, e)))?;
+    if !output.status.success() {
+        return Err(LimpError::GitError(format!(
+            "{}: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
 
+/// The directory for a new project at `path`. It may exist already, like
+/// for `limp init .`, as long as it doesn't hold a crate.
+fn empty_project_dir(path: &str) -> Result<PathBuf, LimpError> {
+    let project = PathBuf::from(path);
+    if project.join("Cargo.toml").exists() || project.join("src").exists() {
+        return Err(LimpError::CrateExistsNotEmpty(path.to_string()));
+    }
+    Ok(project)
+}
+
+/// Creates a crate in the directory `path`, named after it unless
+/// `options.name` is set.
+pub fn create_project(
+    path: &str,
+    deps: Option<&[JsonDependency]>,
+    options: &ProjectOptions,
+) -> Result<(), LimpError> {
+    let project = empty_project_dir(path)?;
+
+    let name = options.package_name(&project);
+    write_crate(&project, &name, deps, options, false)?;
+    merge_manifest_extras(&project, options)?;
+    options.scaffold.write(&project, &name, &options.vars)?;
+    if options.git {
+        init_git(&project)?;
+    }
+    Ok(())
+}
+
+/// Creates a project in the directory `path` from the git repository `url`:
+/// a shallow clone without its history, with placeholders rendered like in
+/// saved templates. `deps` and the snippets are added on top, into its
+/// `Cargo.toml` (`[workspace.dependencies]` of a virtual manifest) and its
+/// entry file.
+pub fn create_from_git(
+    path: &str,
+    url: &str,
+    branch: Option<&str>,
+    deps: Option<&[JsonDependency]>,
+    options: &ProjectOptions,
+) -> Result<(), LimpError> {
+    let project = empty_project_dir(path)?;
+    let name = options.package_name(&project);
+    let clone = std::env::temp_dir().join(format!("limp-clone-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&clone);
+    clone_git(url, branch, &clone)?;
+    let copied = copy_rendered(&clone, &project, &[".git"], &name, &options.vars);
+    let _ = fs::remove_dir_all(&clone);
+    copied?;
+
+    let toml = project.join("Cargo.toml");
+    if !toml.is_file() {
+        return Err(LimpError::InvalidManifest(format!(
+            "{} has no Cargo.toml",
+            url
+        )));
+    }
+    let mut manifest = CargoManifest::load(toml)?;
+    for dep in deps.unwrap_or_default() {
+        if manifest.is_virtual() {
+            manifest.add_workspace_dependency(dep)?;
+        } else {
+            manifest.add_dependency(dep)?;
+        }
+    }
+    manifest.merge_extras(&options.manifest_extras)?;
+    manifest.save()?;
+
+    let snippet = parser::load_from_deps(
+        deps.unwrap_or_default(),
+        &options.snippets,
+        &options.snippets_dir,
+    )?;
+    if let Some(snippet) = snippet.filter(|_| !manifest.is_virtual()) {
+        let source = options.vars.render(&name, &snippet.to_string());
+        parser::inject(
+            &SnippetEntity::parse("", &source),
+            &manifest.entry_file(),
+            None,
+        )?;
+    }
+    if options.git {
+        init_git(&project)?;
+    }
+    Ok(())
+}
+
+pub fn create_workspace(
+    path: &str,
+    members: &[String],
+    deps: Option<&[JsonDependency]>,
+    options: &ProjectOptions,
+) -> Result<(), LimpError> {
+    let project = empty_project_dir(path)?;
+
+    let mut toml = open(project.join("Cargo.toml"))?;
+    writeln!(toml, "[workspace]")?;
+    writeln!(toml, "resolver = \"2\"")?;
+    let quoted_members = members
+        .iter()
+        .map(|m| format!("\"{m}\""))
+        .collect::<Vec<String>>()
+        .join(", ");
+    writeln!(toml, "members = [{}]", quoted_members)?;
+    writeln!(toml)?;
+    writeln!(toml, "[workspace.dependencies]")?;
+    if let Some(deps) = deps {
+        for dep in deps.iter() {
+            // members declare optional dependencies, the workspace can't
+            let dep = JsonDependency {
+                optional: false,
+                ..dep.clone()
+            };
+            writeln!(toml, "{}", dep)?
+        }
+    }
+
+    drop(toml);
+    merge_manifest_extras(&project, options)?;
+    for member in members.iter() {
+        write_crate(&project.join(member), member, deps, options, true)?;
+    }
+    let name = options.package_name(&project);
+    options.scaffold.write(&project, &name, &options.vars)?;
+    if options.git {
+        init_git(&project)?;
+    }
     Ok(())
 }
@@ -0,0 +1,164 @@
+//! Journal of the changes made by limp commands, appended to `history.jsonl`
+//! in the storage directory one entry per line, so they can be listed with
+//! `limp history` and taken back with `limp undo`.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Display,
+    fs,
+    io::{BufRead, BufReader, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::LimpError,
+    files,
+    storage::{now, JsonDependency, JsonStorage, SnippetMeta},
+};
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct Entry {
+    /// unix time of the change
+    pub at: u64,
+    /// the command that made the change, e.g. `new serde`
+    pub action: String,
+    /// changed dependencies as they were before, `None` for new ones
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dependencies: BTreeMap<String, Option<JsonDependency>>,
+    /// changed snippet files as they were before, `None` for new ones
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub snippets: BTreeMap<String, Option<String>>,
+    /// changed snippet metadata as it was before by file name, `None` for
+    /// new entries
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub snippet_meta: BTreeMap<String, Option<SnippetMeta>>,
+    /// index of the entry this one took back
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub undoes: Option<usize>,
+}
+
+impl Entry {
+    pub fn new<S: Into<String>>(action: S) -> Self {
+        Self {
+            at: now(),
+            action: action.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Remembers the dependencies and snippet metadata that differ between
+    /// `before` and `after`.
+    pub fn diff(&mut self, before: &JsonStorage, after: &JsonStorage) {
+        let same = |a: &JsonDependency, b: &JsonDependency| {
+            serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+        };
+        for (name, dep) in &after.dependencies {
+            match before.dependencies.get(name) {
+                Some(old) if same(old, dep) => {}
+                old => {
+                    self.dependencies.insert(name.clone(), old.cloned());
+                }
+            }
+        }
+        for (name, old) in &before.dependencies {
+            if !after.dependencies.contains_key(name) {
+                self.dependencies.insert(name.clone(), Some(old.clone()));
+            }
+        }
+        for (file, meta) in &after.snippets {
+            match before.snippets.get(file) {
+                Some(old) if old == meta => {}
+                old => {
+                    self.snippet_meta.insert(file.clone(), old.cloned());
+                }
+            }
+        }
+        for (file, old) in &before.snippets {
+            if !after.snippets.contains_key(file) {
+                self.snippet_meta.insert(file.clone(), Some(old.clone()));
+            }
+        }
+    }
+
+    /// Remembers the snippet file at `path` as it is before being written.
+    pub fn snapshot<P: AsRef<Path>>(&mut self, path: P) {
+        let path = path.as_ref();
+        self.snippets.insert(
+            path.to_string_lossy().to_string(),
+            fs::read_to_string(path).ok(),
+        );
+    }
+
+    /// Remembers that the file at `path` didn't exist before, for files whose
+    /// path is only known once they are written, like those in the trash.
+    pub fn created<P: AsRef<Path>>(&mut self, path: P) {
+        self.snippets
+            .entry(path.as_ref().to_string_lossy().to_string())
+            .or_insert(None);
+    }
+
+    /// Forgets the snapshots of files that are still as they were.
+    pub fn forget_unchanged(&mut self) {
+        self.snippets
+            .retain(|path, old| fs::read_to_string(path).ok() != *old);
+    }
+
+    /// Whether undoing the entry changes anything.
+    pub fn is_undoable(&self) -> bool {
+        self.undoes.is_none()
+            && !(self.dependencies.is_empty()
+                && self.snippets.is_empty()
+                && self.snippet_meta.is_empty())
+    }
+}
+
+impl Display for Entry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.action)?;
+        let mut names = self.dependencies.keys().cloned().collect::<Vec<_>>();
+        names.extend(self.snippets.keys().map(|s| {
+            Path::new(s)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| s.clone())
+        }));
+        for file in self.snippet_meta.keys() {
+            if !names.contains(file) {
+                names.push(file.clone());
+            }
+        }
+        if !names.is_empty() {
+            write!(f, " ({})", names.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Appends `entry` to the journal at `path`.
+pub fn record<P: AsRef<Path>>(path: P, entry: &Entry) -> Result<(), LimpError> {
+    let mut file = files::open(path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+/// Reads the journal at `path`, oldest entry first.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Vec<Entry>, LimpError> {
+    BufReader::new(files::open(path)?)
+        .lines()
+        .filter(|line| !line.as_ref().is_ok_and(|l| l.trim().is_empty()))
+        .map(|line| Ok(serde_json::from_str(&line?)?))
+        .collect()
+}
+
+/// Index of the last entry that can be undone and wasn't yet.
+pub fn last_undoable(entries: &[Entry]) -> Option<usize> {
+    let undone = entries
+        .iter()
+        .filter_map(|e| e.undoes)
+        .collect::<HashSet<usize>>();
+    (0..entries.len())
+        .rev()
+        .find(|i| entries[*i].is_undoable() && !undone.contains(i))
+}
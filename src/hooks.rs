@@ -0,0 +1,121 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use serde::Serialize;
+
+use crate::{error::LimpError, files::hooks_dir};
+
+/// Directory of a git template's own files for limp, removed from the
+/// projects created from it. Its hooks are in `hooks` inside of it.
+pub const TEMPLATE_DIR: &str = ".limp";
+
+/// Points at which executables from the hooks directory are run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hook {
+    PreInit,
+    PostInit,
+    PreAdd,
+    PostAdd,
+}
+
+impl Hook {
+    /// File name of the hook without extension, e.g. `post-init` matches
+    /// `post-init` and `post-init.sh`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Hook::PreInit => "pre-init",
+            Hook::PostInit => "post-init",
+            Hook::PreAdd => "pre-add",
+            Hook::PostAdd => "post-add",
+        }
+    }
+}
+
+/// What a hook gets to know about the project, as JSON on stdin and as
+/// `LIMP_*` environment variables.
+#[derive(Serialize, Debug, Clone)]
+pub struct HookContext {
+    pub hook: &'static str,
+    pub project_dir: PathBuf,
+    pub project_name: String,
+    /// dependencies being added, or the ones a new project is created with
+    pub dependencies: Vec<String>,
+}
+
+impl HookContext {
+    pub fn new<P: AsRef<Path>>(hook: Hook, project_dir: P, project_name: &str) -> Self {
+        Self {
+            hook: hook.name(),
+            project_dir: project_dir.as_ref().to_path_buf(),
+            project_name: project_name.to_string(),
+            dependencies: vec![],
+        }
+    }
+
+    pub fn with_dependencies(mut self, dependencies: Vec<String>) -> Self {
+        self.dependencies = dependencies;
+        self
+    }
+}
+
+/// Runs the hooks in the limp storage directory, see `run_in`.
+pub fn run(context: &HookContext) -> Result<Vec<PathBuf>, LimpError> {
+    run_in(hooks_dir(), context)
+}
+
+/// Runs every executable in `dir` named after `context.hook`, in alphabetical
+/// order, inside the project directory (the current one while it doesn't
+/// exist yet). A hook exiting with an error stops the command. Returns the
+/// hooks that were run.
+pub fn run_in<P: AsRef<Path>>(dir: P, context: &HookContext) -> Result<Vec<PathBuf>, LimpError> {
+    let hooks = find(dir.as_ref(), context.hook)?;
+    let input = serde_json::to_string(context)?;
+    let cwd = if context.project_dir.is_dir() {
+        context.project_dir.clone()
+    } else {
+        std::env::current_dir()?
+    };
+    for hook in hooks.iter() {
+        let mut child = Command::new(hook)
+            .current_dir(&cwd)
+            .env("LIMP_HOOK", context.hook)
+            .env("LIMP_PROJECT_DIR", &context.project_dir)
+            .env("LIMP_PROJECT_NAME", &context.project_name)
+            .env("LIMP_DEPENDENCIES", context.dependencies.join(","))
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| LimpError::HookFailed(format!("{}: {}", hook.display(), e)))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            // hooks that don't read their input close stdin early
+            let _ = stdin.write_all(input.as_bytes());
+        }
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(LimpError::HookFailed(format!(
+                "{} exited with {}",
+                hook.display(),
+                status
+            )));
+        }
+    }
+    Ok(hooks)
+}
+
+/// The hooks in `dir` named `name`, sorted.
+pub(crate) fn find(dir: &Path, name: &str) -> Result<Vec<PathBuf>, LimpError> {
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut hooks = fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .filter(|p| p.file_stem().is_some_and(|s| s == name))
+        .collect::<Vec<PathBuf>>();
+    hooks.sort();
+    Ok(hooks)
+}
@@ -1,7 +1,32 @@
 pub mod actions;
+pub mod bundle;
+pub mod cache;
+pub mod cargo_config;
+pub mod completions;
+pub mod config;
+pub mod context;
 pub mod crates;
+pub mod doctor;
 pub mod error;
 pub mod files;
-// pub mod parser;
+pub mod history;
+pub mod hooks;
+pub mod license;
+pub mod lockfile;
+pub mod logging;
+pub mod manifest;
+pub mod migrations;
+pub mod ops;
+pub mod parser;
+pub mod paths;
+pub mod process;
+pub mod report;
+pub mod scaffold;
+pub mod snippets;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
 pub mod storage;
+pub mod suggest;
+pub mod templates;
+pub mod version;
 // pub mod toml;
@@ -0,0 +1,99 @@
+//! License policy from the `[licenses]` table of `config.toml`, checked
+//! against the license expressions crates.io reports for a version, e.g.
+//! `MIT OR Apache-2.0`.
+
+use std::iter::Peekable;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct LicensePolicy {
+    /// licenses that may be used, any of them when empty
+    pub allow: Vec<String>,
+    /// licenses that may never be used
+    pub deny: Vec<String>,
+    /// fail instead of warning about a violation
+    pub fail: bool,
+}
+
+impl LicensePolicy {
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    /// Whether `expression` can be used, i.e. one side of every `OR` and
+    /// both sides of every `AND` are accepted. `/` is read as `OR` like in
+    /// older license fields.
+    pub fn accepts(&self, expression: &str) -> bool {
+        let expression = expression
+            .replace('(', " ( ")
+            .replace(')', " ) ")
+            .replace('/', " OR ");
+        let mut tokens = expression.split_whitespace().peekable();
+        self.any(&mut tokens)
+    }
+
+    /// Describes why `name` can't be used under `license`, `None` when it
+    /// can. Crates without a license only violate an allowlist.
+    pub fn violation(&self, name: &str, license: Option<&str>) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+        match license {
+            Some(license) if self.accepts(license) => None,
+            Some(license) => Some(format!("{} is licensed under {}", name, license)),
+            None if self.allow.is_empty() => None,
+            None => Some(format!("{} has no license", name)),
+        }
+    }
+
+    fn any<'a, I: Iterator<Item = &'a str>>(&self, tokens: &mut Peekable<I>) -> bool {
+        let mut accepted = self.all(tokens);
+        while tokens.next_if(|t| t.eq_ignore_ascii_case("OR")).is_some() {
+            accepted |= self.all(tokens);
+        }
+        accepted
+    }
+
+    fn all<'a, I: Iterator<Item = &'a str>>(&self, tokens: &mut Peekable<I>) -> bool {
+        let mut accepted = self.one(tokens);
+        while tokens.next_if(|t| t.eq_ignore_ascii_case("AND")).is_some() {
+            accepted &= self.one(tokens);
+        }
+        accepted
+    }
+
+    fn one<'a, I: Iterator<Item = &'a str>>(&self, tokens: &mut Peekable<I>) -> bool {
+        match tokens.next() {
+            Some("(") => {
+                let accepted = self.any(tokens);
+                tokens.next_if_eq(&")");
+                accepted
+            }
+            Some(id) => {
+                // exceptions like `Apache-2.0 WITH LLVM-exception` only loosen a license
+                if tokens.next_if(|t| t.eq_ignore_ascii_case("WITH")).is_some() {
+                    tokens.next();
+                }
+                self.accepts_id(id)
+            }
+            None => false,
+        }
+    }
+
+    fn accepts_id(&self, id: &str) -> bool {
+        let id = normalize(id);
+        let listed = |list: &[String]| list.iter().any(|l| normalize(l) == id);
+        !listed(&self.deny) && (self.allow.is_empty() || listed(&self.allow))
+    }
+}
+
+/// `GPL-3.0`, `GPL-3.0-only`, `GPL-3.0-or-later` and `GPL-3.0+` all name
+/// the same license for the policy.
+fn normalize(id: &str) -> String {
+    let id = id.trim().to_lowercase();
+    let id = id.trim_end_matches('+');
+    let id = id.strip_suffix("-or-later").unwrap_or(id);
+    id.strip_suffix("-only").unwrap_or(id).to_string()
+}
@@ -0,0 +1,78 @@
+//! The versions `Cargo.lock` resolved a project's dependencies to, as
+//! opposed to the requirements in its `Cargo.toml`.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{crates::parse_rust_version, error::LimpError, manifest::CargoManifest};
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    /// `registry+<index>` or `git+<url>`, `None` for path dependencies
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct Lockfile {
+    #[serde(default, rename = "package")]
+    pub packages: Vec<LockedPackage>,
+}
+
+impl Lockfile {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, LimpError> {
+        let path = path.as_ref();
+        Self::parse(&fs::read_to_string(path)?)
+            .map_err(|e| LimpError::InvalidManifest(format!("{}: {}", path.display(), e)))
+    }
+
+    pub fn parse(content: &str) -> Result<Self, LimpError> {
+        toml::from_str(content).map_err(|e| LimpError::InvalidManifest(e.to_string()))
+    }
+
+    /// `Cargo.lock` of the project with the manifest at `manifest`, which
+    /// lives next to the workspace's manifest in a workspace.
+    pub fn path_of(manifest: &Path) -> Result<PathBuf, LimpError> {
+        let root = match CargoManifest::workspace_of(manifest)? {
+            Some(workspace) => workspace.path().to_path_buf(),
+            None => manifest.to_path_buf(),
+        };
+        Ok(root.with_file_name("Cargo.lock"))
+    }
+
+    /// The lockfile of the project with the manifest at `manifest`, `None`
+    /// when it hasn't been built or resolved yet.
+    pub fn find(manifest: &Path) -> Result<Option<Self>, LimpError> {
+        let path = Self::path_of(manifest)?;
+        if !path.is_file() {
+            return Ok(None);
+        }
+        Self::load(path).map(Some)
+    }
+
+    /// Versions `name` is locked at, several when different major versions
+    /// are used in the dependency graph.
+    pub fn versions(&self, name: &str) -> Vec<&str> {
+        self.packages
+            .iter()
+            .filter(|p| p.name == name)
+            .map(|p| p.version.as_str())
+            .collect()
+    }
+}
+
+/// Whether the `locked` version is older than the lowest version the
+/// requirement `version` allows, e.g. `1.0.100` for `1.0.210`.
+pub fn is_older(locked: &str, version: &str) -> bool {
+    let lowest = parse_rust_version(version.trim_start_matches(['^', '=', '~']));
+    match (semver::Version::parse(locked), lowest) {
+        (Ok(locked), Some(lowest)) => locked < lowest,
+        _ => false,
+    }
+}
@@ -0,0 +1,172 @@
+//! Diagnostic log of what limp does behind the scenes: registry requests,
+//! cache lookups, storage and manifest writes. It is off unless
+//! `--log-level` or `RUST_LOG` turn it on. Records go to stderr, or are
+//! appended to `limp.log` in the storage directory with `--log-file`, so
+//! failures that only happen now and then can be looked into afterwards.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::Path,
+    sync::{Mutex, OnceLock},
+    time::Instant,
+};
+
+use crate::{error::LimpError, storage::now};
+
+/// Name of the log file in the storage directory.
+pub const FILE_NAME: &str = "limp.log";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    pub const NAMES: [&'static str; 5] = ["error", "warn", "info", "debug", "trace"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "error" => Some(Level::Error),
+            "warn" => Some(Level::Warn),
+            "info" => Some(Level::Info),
+            "debug" => Some(Level::Debug),
+            "trace" => Some(Level::Trace),
+            _ => None,
+        }
+    }
+
+    /// The level a `RUST_LOG` filter sets for limp, e.g. `debug`,
+    /// `limp=trace` or `warn,limp=debug`. Directives naming limp win over
+    /// plain levels, and those naming other crates are ignored.
+    pub fn from_env_filter(filter: &str) -> Option<Self> {
+        let mut general = None;
+        let mut specific = None;
+        for directive in filter.split(',') {
+            match directive.split_once('=') {
+                Some((target, level)) => {
+                    let target = target.trim();
+                    if target == "limp" || target.starts_with("limp::") {
+                        specific = Self::from_name(level).or(specific);
+                    }
+                }
+                None => general = Self::from_name(directive).or(general),
+            }
+        }
+        specific.or(general)
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Logger {
+    level: Level,
+    /// where records are appended instead of stderr
+    file: Option<Mutex<File>>,
+}
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+/// Turns the log on at `level`, appending to `file` instead of writing to
+/// stderr when given. Only the first call has an effect.
+pub fn init(level: Level, file: Option<&Path>) -> Result<(), LimpError> {
+    let file = match file {
+        Some(path) => {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let file = OpenOptions::new().create(true).append(true).open(path)?;
+            Some(Mutex::new(file))
+        }
+        None => None,
+    };
+    let _ = LOGGER.set(Logger { level, file });
+    Ok(())
+}
+
+/// Whether records of `level` are written anywhere.
+pub fn enabled(level: Level) -> bool {
+    LOGGER.get().is_some_and(|l| level <= l.level)
+}
+
+/// Writes `message` about `target`, e.g. `registry`, if `level` is enabled.
+pub fn log(level: Level, target: &str, message: &str) {
+    let Some(logger) = LOGGER.get().filter(|l| level <= l.level) else {
+        return;
+    };
+    // 1734567890 DEBUG registry: GET https://crates.io/api/v1/crates/serde
+    let line = format!("{} {:<5} {}: {}", now(), level.label(), target, message);
+    match &logger.file {
+        Some(file) => {
+            let _ = writeln!(file.lock().unwrap(), "{}", line);
+        }
+        None => eprintln!("{}", line),
+    }
+}
+
+pub fn error(target: &str, message: &str) {
+    log(Level::Error, target, message)
+}
+
+pub fn warn(target: &str, message: &str) {
+    log(Level::Warn, target, message)
+}
+
+pub fn info(target: &str, message: &str) {
+    log(Level::Info, target, message)
+}
+
+pub fn debug(target: &str, message: &str) {
+    log(Level::Debug, target, message)
+}
+
+pub fn trace(target: &str, message: &str) {
+    log(Level::Trace, target, message)
+}
+
+/// A step that is logged when it starts and again with the time it took
+/// when dropped, see `span`.
+#[must_use]
+#[derive(Debug)]
+pub struct Span {
+    /// `None` when the level is disabled
+    inner: Option<(Level, &'static str, String, Instant)>,
+}
+
+/// Starts a span named by `name`, which is only called when `level` is
+/// enabled.
+pub fn span<F: FnOnce() -> String>(level: Level, target: &'static str, name: F) -> Span {
+    if !enabled(level) {
+        return Span { inner: None };
+    }
+    let name = name();
+    log(level, target, &format!("{} started", name));
+    Span {
+        inner: Some((level, target, name, Instant::now())),
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        if let Some((level, target, name, start)) = &self.inner {
+            log(
+                *level,
+                target,
+                &format!("{} finished in {}ms", name, start.elapsed().as_millis()),
+            );
+        }
+    }
+}
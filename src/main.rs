@@ -1,10 +1,10 @@
-use limp::actions::CommandHandler;
+use limp::{actions::CommandHandler, report::Style};
 
 fn main() {
     let matches = CommandHandler::build().get_matches();
     let ch = CommandHandler::parse(&matches);
     if let Err(e) = ch.make_action() {
-        eprintln!("ERROR: {e}");
+        ch.format.reporter(Style::detect(ch.no_color)).error(&e);
         std::process::exit(1);
     }
 }
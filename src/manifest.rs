@@ -0,0 +1,528 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use toml_edit::{Array, DocumentMut, InlineTable, Item, Table, Value};
+
+use crate::{
+    error::LimpError,
+    logging,
+    storage::{DependencyKind, DependencySource, JsonDependency},
+};
+
+pub struct CargoManifest {
+    path: PathBuf,
+    doc: DocumentMut,
+}
+
+impl CargoManifest {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, LimpError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            doc: content.parse()?,
+        })
+    }
+    pub fn save(&self) -> Result<(), LimpError> {
+        fs::write(&self.path, self.doc.to_string())?;
+        logging::info("manifest", &format!("wrote {}", self.path.display()));
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn package_name(&self) -> Option<&str> {
+        self.doc
+            .get("package")
+            .and_then(|p| p.get("name"))
+            .and_then(Item::as_str)
+    }
+
+    /// Whether this is the manifest of a workspace without a package.
+    pub fn is_virtual(&self) -> bool {
+        !self.doc.contains_key("package") && self.doc.contains_key("workspace")
+    }
+
+    /// The manifest of the workspace `path` belongs to: the nearest
+    /// `Cargo.toml` with a `[workspace]` table, starting with `path` itself.
+    pub fn workspace_of<P: AsRef<Path>>(path: P) -> Result<Option<Self>, LimpError> {
+        for dir in path.as_ref().ancestors().skip(1) {
+            let path = dir.join("Cargo.toml");
+            if path.is_file() {
+                let manifest = Self::load(path)?;
+                if manifest.doc.contains_key("workspace") {
+                    return Ok(Some(manifest));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Manifests of the `[workspace] members`, where a last component of `*`
+    /// stands for every directory with a `Cargo.toml`.
+    pub fn members(&self) -> Vec<PathBuf> {
+        let root = self.path.parent().unwrap_or(Path::new("./"));
+        let members = self
+            .doc
+            .get("workspace")
+            .and_then(|w| w.get("members"))
+            .and_then(Item::as_array)
+            .map(|a| a.iter().filter_map(Value::as_str).collect::<Vec<&str>>())
+            .unwrap_or_default();
+        let mut paths = vec![];
+        for member in members {
+            match member.strip_suffix('*') {
+                Some(prefix) => {
+                    let mut found = fs::read_dir(root.join(prefix))
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|e| e.ok())
+                        .map(|e| e.path().join("Cargo.toml"))
+                        .filter(|p| p.is_file())
+                        .collect::<Vec<PathBuf>>();
+                    found.sort();
+                    paths.extend(found);
+                }
+                None => paths.push(root.join(member).join("Cargo.toml")),
+            }
+        }
+        paths
+    }
+
+    /// The member whose package or directory is called `name`.
+    pub fn member(&self, name: &str) -> Result<Self, LimpError> {
+        let mut names = vec![];
+        for path in self.members() {
+            let Ok(member) = Self::load(&path) else {
+                continue;
+            };
+            let dir = path
+                .parent()
+                .and_then(Path::file_name)
+                .map(|d| d.to_string_lossy().to_string())
+                .unwrap_or_default();
+            if member.package_name() == Some(name) || dir == name {
+                return Ok(member);
+            }
+            names.push(member.package_name().unwrap_or(&dir).to_string());
+        }
+        Err(LimpError::InvalidArguments(format!(
+            "{} isn't a member of the workspace at {}, members: {}",
+            name,
+            self.path.display(),
+            names.join(", ")
+        )))
+    }
+
+    /// `src/main.rs`, or `src/lib.rs` for library crates.
+    pub fn entry_file(&self) -> PathBuf {
+        let src = self.path.parent().unwrap_or(Path::new("./")).join("src");
+        let main = src.join("main.rs");
+        if main.exists() {
+            main
+        } else {
+            src.join("lib.rs")
+        }
+    }
+
+    /// Inserts the dependency into the table matching its kind, keeping the
+    /// table sorted if it was. An existing entry with the same name is updated
+    /// in place instead of duplicated, keeping its comments and formatting.
+    pub fn add_dependency(&mut self, dep: &JsonDependency) -> Result<(), LimpError> {
+        let table = self.table_mut(dep.kind.table_name())?;
+        insert_dependency(table, dep.toml_name(), dependency_item(dep));
+        self.log_edit(dep, dep.kind.table_name());
+        Ok(())
+    }
+
+    /// Inserts the dependency into `[workspace.dependencies]` like
+    /// `add_dependency`. Members declare whether it is optional, so it isn't
+    /// here.
+    pub fn add_workspace_dependency(&mut self, dep: &JsonDependency) -> Result<(), LimpError> {
+        let dep = JsonDependency {
+            optional: false,
+            ..dep.clone()
+        };
+        let table = self.workspace_dependencies_mut()?;
+        insert_dependency(table, dep.toml_name(), dependency_item(&dep));
+        self.log_edit(&dep, "workspace.dependencies");
+        Ok(())
+    }
+
+    /// Inserts `name = { workspace = true }` into the table matching the
+    /// dependency's kind, with its features and whether it is optional.
+    pub fn add_inherited_dependency(&mut self, dep: &JsonDependency) -> Result<(), LimpError> {
+        let table = self.table_mut(dep.kind.table_name())?;
+        insert_dependency(table, dep.toml_name(), inherited_item(dep));
+        self.log_edit(dep, dep.kind.table_name());
+        Ok(())
+    }
+
+    fn log_edit(&self, dep: &JsonDependency, table: &str) {
+        logging::debug(
+            "manifest",
+            &format!("{}: {} in [{}]", self.path.display(), dep, table),
+        );
+    }
+
+    /// Removes the dependency from every dependency table it appears in and
+    /// returns the kinds it was removed from.
+    pub fn remove_dependency(&mut self, name: &str) -> Vec<DependencyKind> {
+        let removed = DependencyKind::ALL
+            .into_iter()
+            .filter(|kind| {
+                self.doc
+                    .get_mut(kind.table_name())
+                    .and_then(Item::as_table_like_mut)
+                    .is_some_and(|t| t.remove(name).is_some())
+            })
+            .collect::<Vec<DependencyKind>>();
+        for kind in removed.iter() {
+            logging::debug(
+                "manifest",
+                &format!(
+                    "{}: removed {} from [{}]",
+                    self.path.display(),
+                    name,
+                    kind.table_name()
+                ),
+            );
+        }
+        removed
+    }
+
+    /// Changes only the version of the entry `key` in the table of `kind`,
+    /// keeping its other fields and formatting. Returns whether it exists.
+    pub fn set_version(&mut self, key: &str, kind: DependencyKind, version: &str) -> bool {
+        let Some(item) = self
+            .doc
+            .get_mut(kind.table_name())
+            .and_then(Item::as_table_like_mut)
+            .and_then(|t| t.get_mut(key))
+        else {
+            return false;
+        };
+        let value = if item.is_str() {
+            item.as_value_mut()
+        } else {
+            item.as_table_like_mut()
+                .and_then(|t| t.get_mut("version"))
+                .and_then(Item::as_value_mut)
+        };
+        match value {
+            Some(value) => {
+                let decor = value.decor().clone();
+                *value = version.into();
+                *value.decor_mut() = decor;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn contains_dependency(&self, name: &str, kind: DependencyKind) -> bool {
+        self.doc
+            .get(kind.table_name())
+            .and_then(Item::as_table_like)
+            .is_some_and(|t| t.contains_key(name))
+    }
+
+    /// Reads every versioned dependency back as a `JsonDependency`. Entries
+    /// without a version (path, git, workspace) are skipped.
+    pub fn dependencies(&self) -> Vec<JsonDependency> {
+        DependencyKind::ALL
+            .into_iter()
+            .flat_map(|kind| {
+                self.doc
+                    .get(kind.table_name())
+                    .and_then(Item::as_table_like)
+                    .map(|t| {
+                        t.iter()
+                            .filter_map(|(name, item)| parse_dependency(name, item, kind))
+                            .collect::<Vec<JsonDependency>>()
+                    })
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    /// Everything except `[package]` and the dependency tables, e.g. profiles,
+    /// features or lints.
+    pub fn extras(&self) -> String {
+        let mut extras = DocumentMut::new();
+        for (key, item) in self.doc.iter() {
+            if key == "package" || DependencyKind::ALL.iter().any(|k| k.table_name() == key) {
+                continue;
+            }
+            extras.insert(key, item.clone());
+        }
+        extras.to_string()
+    }
+
+    pub fn dependency_names(&self, kind: DependencyKind) -> Vec<String> {
+        self.doc
+            .get(kind.table_name())
+            .and_then(Item::as_table_like)
+            .map(|t| t.iter().map(|(k, _)| k.to_string()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Merges `extras` into the manifest key by key, e.g. the `[manifest]`
+    /// table of `config.toml`. Tables it adds go to the end.
+    pub fn merge_extras(&mut self, extras: &toml::Table) -> Result<(), LimpError> {
+        let extras = toml::to_string(extras)
+            .map_err(|e| LimpError::InvalidManifest(e.to_string()))?
+            .parse::<DocumentMut>()?;
+        let offset = last_position(self.doc.as_table()).map_or(0, |p| p + 1);
+        merge_table(self.doc.as_table_mut(), extras.as_table(), offset);
+        Ok(())
+    }
+
+    /// The table `name`, created after `[package]` and the other dependency
+    /// tables rather than after unrelated ones like `[profile.release]`.
+    fn table_mut(&mut self, name: &str) -> Result<&mut Table, LimpError> {
+        if !self.doc.contains_key(name) {
+            let mut table = Table::new();
+            let position = self
+                .doc
+                .iter()
+                .filter(|(key, _)| {
+                    *key == "package" || DependencyKind::ALL.iter().any(|k| k.table_name() == *key)
+                })
+                .filter_map(|(_, item)| item.as_table())
+                .filter_map(last_position)
+                .max();
+            if let Some(position) = position {
+                table.set_position(position);
+            }
+            self.doc.insert(name, Item::Table(table));
+        }
+        self.doc[name]
+            .as_table_mut()
+            .ok_or_else(|| LimpError::InvalidManifest(format!("`{}` is not a table", name)))
+    }
+
+    /// `[workspace.dependencies]`, created after the rest of `[workspace]`.
+    fn workspace_dependencies_mut(&mut self) -> Result<&mut Table, LimpError> {
+        let workspace = self
+            .doc
+            .get_mut("workspace")
+            .and_then(Item::as_table_mut)
+            .ok_or_else(|| {
+                LimpError::InvalidManifest(format!("{} has no [workspace]", self.path.display()))
+            })?;
+        if !workspace.contains_key("dependencies") {
+            let mut table = Table::new();
+            if let Some(position) = last_position(workspace) {
+                table.set_position(position);
+            }
+            workspace.insert("dependencies", Item::Table(table));
+        }
+        workspace["dependencies"].as_table_mut().ok_or_else(|| {
+            LimpError::InvalidManifest("`workspace.dependencies` is not a table".to_string())
+        })
+    }
+}
+
+impl std::fmt::Display for CargoManifest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.doc)
+    }
+}
+
+// name = "version"
+// or
+// name = { version = "version", git = "url", package = "crate", default-features = false, features = [...features], optional = true, registry = "registry" }
+fn dependency_item(dep: &JsonDependency) -> Item {
+    if dep.is_plain() {
+        return toml_edit::value(dep.version.as_str());
+    }
+    let mut table = InlineTable::new();
+    for (key, value) in dep.source_fields() {
+        table.insert(key, value.into());
+    }
+    if dep.rename.is_some() {
+        table.insert("package", dep.name.as_str().into());
+    }
+    if let Some(default_features) = dep.default_features {
+        table.insert("default-features", default_features.into());
+    }
+    if let Some(features) = &dep.features {
+        table.insert(
+            "features",
+            Value::Array(features.iter().map(|f| f.as_str()).collect::<Array>()),
+        );
+    }
+    if dep.optional {
+        table.insert("optional", true.into());
+    }
+    if let Some(registry) = &dep.registry {
+        table.insert("registry", registry.as_str().into());
+    }
+    Item::Value(Value::InlineTable(table))
+}
+
+/// Inserts `item` as `name`, keeping the table sorted if it was. An existing
+/// entry is updated in place instead, keeping its comments and formatting.
+fn insert_dependency(table: &mut Table, name: &str, item: Item) {
+    if let Some(existing) = table.get_mut(name) {
+        return update_item(existing, item);
+    }
+    let keys = table.iter().map(|(k, _)| k).collect::<Vec<&str>>();
+    let sorted = keys.windows(2).all(|w| w[0] <= w[1]);
+    table.insert(name, item);
+    if sorted {
+        table.sort_values();
+    }
+}
+
+// name = { workspace = true, features = [...features], optional = true }
+fn inherited_item(dep: &JsonDependency) -> Item {
+    let mut table = InlineTable::new();
+    table.insert("workspace", true.into());
+    if let Some(features) = &dep.features {
+        table.insert(
+            "features",
+            Value::Array(features.iter().map(|f| f.as_str()).collect::<Array>()),
+        );
+    }
+    if dep.optional {
+        table.insert("optional", true.into());
+    }
+    Item::Value(Value::InlineTable(table))
+}
+
+/// Position of `table` or of the last of its subtables in the document.
+fn last_position(table: &Table) -> Option<usize> {
+    table
+        .iter()
+        .filter_map(|(_, item)| item.as_table())
+        .filter_map(last_position)
+        .chain(table.position())
+        .max()
+}
+
+/// Merges `from` into `into`, moving the tables it adds `offset` positions
+/// down so they keep their order after the existing ones.
+fn merge_table(into: &mut Table, from: &Table, offset: usize) {
+    for (key, item) in from.iter() {
+        if let (Some(Item::Table(into)), Item::Table(from)) = (into.get_mut(key), item) {
+            merge_table(into, from, offset);
+            continue;
+        }
+        let mut item = item.clone();
+        if let Item::Table(table) = &mut item {
+            shift_positions(table, offset);
+        }
+        into.insert(key, item);
+    }
+}
+
+fn shift_positions(table: &mut Table, offset: usize) {
+    if let Some(position) = table.position() {
+        table.set_position(position + offset);
+    }
+    for (_, item) in table.iter_mut() {
+        if let Item::Table(table) = item {
+            shift_positions(table, offset);
+        }
+    }
+}
+
+/// Changes `item` into `new`, keeping the fields both have in common and
+/// the comments around them. A table stays a table even for a plain version.
+fn update_item(item: &mut Item, new: Item) {
+    let new = match new.as_str() {
+        Some(version) if item.is_table_like() => {
+            let mut table = InlineTable::new();
+            table.insert("version", version.into());
+            Item::Value(Value::InlineTable(table))
+        }
+        _ => new,
+    };
+    if !(item.is_table_like() && new.is_table_like()) {
+        return replace_value(item, new);
+    }
+    let (Some(old), Some(new)) = (item.as_table_like_mut(), new.as_table_like()) else {
+        return;
+    };
+    let stale = old
+        .iter()
+        .map(|(k, _)| k.to_string())
+        .filter(|k| !new.contains_key(k))
+        .collect::<Vec<String>>();
+    for key in stale {
+        old.remove(&key);
+    }
+    for (key, value) in new.iter() {
+        match old.get_mut(key) {
+            Some(existing) => replace_value(existing, value.clone()),
+            None => {
+                old.insert(key, value.clone());
+            }
+        }
+    }
+}
+
+fn replace_value(item: &mut Item, mut new: Item) {
+    if let (Some(old), Some(value)) = (item.as_value(), new.as_value_mut()) {
+        *value.decor_mut() = old.decor().clone();
+    }
+    *item = new;
+}
+
+fn parse_dependency(name: &str, item: &Item, kind: DependencyKind) -> Option<JsonDependency> {
+    if let Some(version) = item.as_str() {
+        return Some(JsonDependency {
+            name: name.to_string(),
+            version: version.to_string(),
+            kind,
+            ..Default::default()
+        });
+    }
+    let table = item.as_table_like()?;
+    let get = |key: &str| table.get(key).and_then(Item::as_str).map(String::from);
+    let source = match (get("git"), get("path")) {
+        (Some(url), _) => DependencySource::Git {
+            url,
+            branch: get("branch"),
+            tag: get("tag"),
+            rev: get("rev"),
+        },
+        (None, Some(path)) => DependencySource::Path { path },
+        (None, None) => DependencySource::Registry,
+    };
+    let version = match get("version") {
+        Some(version) => version,
+        None if source != DependencySource::Registry => String::new(),
+        None => return None,
+    };
+    let features = table.get("features").and_then(Item::as_array).map(|a| {
+        a.iter()
+            .filter_map(|f| f.as_str().map(String::from))
+            .collect::<Vec<String>>()
+    });
+    let (name, rename) = match get("package") {
+        Some(package) => (package, Some(name.to_string())),
+        None => (name.to_string(), None),
+    };
+    Some(JsonDependency {
+        name,
+        rename,
+        version,
+        features,
+        kind,
+        registry: get("registry"),
+        default_features: table.get("default-features").and_then(Item::as_bool),
+        optional: table
+            .get("optional")
+            .and_then(Item::as_bool)
+            .unwrap_or_default(),
+        source,
+        ..Default::default()
+    })
+}
@@ -0,0 +1,89 @@
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::{error::LimpError, paths, storage::DEFAULT_VARIANT};
+
+/// Version of the `dependencies.json` format written by this build.
+pub const SCHEMA_VERSION: u64 = 3;
+
+/// `MIGRATIONS[n]` upgrades a document from schema `n` to `n + 1`. Add a step
+/// here and bump `SCHEMA_VERSION` whenever the stored format changes.
+/// Steps get the storage directory the document is kept in.
+const MIGRATIONS: [fn(&mut Value, &Path); SCHEMA_VERSION as usize] = [v0_to_v1, v1_to_v2, v2_to_v3];
+
+/// Upgrades a document stored in the storage directory `root` to
+/// `SCHEMA_VERSION` in place. Returns whether anything had to be migrated.
+pub fn migrate(doc: &mut Value, root: &Path) -> Result<bool, LimpError> {
+    let version = schema_version(doc);
+    if version > SCHEMA_VERSION {
+        return Err(LimpError::UnsupportedSchema(format!(
+            "version {}, this limp supports up to {}",
+            version, SCHEMA_VERSION
+        )));
+    }
+    for step in &MIGRATIONS[version as usize..] {
+        step(doc, root);
+    }
+    if let Some(obj) = doc.as_object_mut() {
+        obj.insert("schema_version".to_string(), json!(SCHEMA_VERSION));
+    }
+    Ok(version < SCHEMA_VERSION)
+}
+
+/// Files without a version predate versioning and count as schema 0.
+pub fn schema_version(doc: &Value) -> u64 {
+    doc.get("schema_version")
+        .and_then(Value::as_u64)
+        .unwrap_or_default()
+}
+
+// schema 0 files may lack the `dependencies` map and entries may lack their
+// `name`, which is also the map key
+fn v0_to_v1(doc: &mut Value, _root: &Path) {
+    let Some(obj) = doc.as_object_mut() else {
+        return;
+    };
+    let deps = obj.entry("dependencies").or_insert_with(|| json!({}));
+    if let Some(deps) = deps.as_object_mut() {
+        for (name, dep) in deps.iter_mut() {
+            if let Some(dep) = dep.as_object_mut() {
+                dep.entry("name").or_insert_with(|| json!(name));
+            }
+        }
+    }
+}
+
+// schema 1 linked a single `path_to_snippet`, which becomes the default
+// snippet variant
+fn v1_to_v2(doc: &mut Value, _root: &Path) {
+    let Some(deps) = doc.get_mut("dependencies").and_then(Value::as_object_mut) else {
+        return;
+    };
+    for dep in deps.values_mut() {
+        let Some(dep) = dep.as_object_mut() else {
+            continue;
+        };
+        if let Some(path) = dep.remove("path_to_snippet").filter(|p| p.is_string()) {
+            dep.insert("snippets".to_string(), json!({ DEFAULT_VARIANT: path }));
+        }
+    }
+}
+
+// schema 2 linked snippets by absolute path, which become relative to the
+// storage directory when they are inside it
+fn v2_to_v3(doc: &mut Value, root: &Path) {
+    let Some(deps) = doc.get_mut("dependencies").and_then(Value::as_object_mut) else {
+        return;
+    };
+    for dep in deps.values_mut() {
+        let Some(snippets) = dep.get_mut("snippets").and_then(Value::as_object_mut) else {
+            continue;
+        };
+        for path in snippets.values_mut() {
+            if let Some(stored) = path.as_str() {
+                *path = json!(paths::to_stored(root, paths::from_stored(root, stored)));
+            }
+        }
+    }
+}
@@ -0,0 +1,1974 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    bundle,
+    config::SnippetMerge,
+    context::Context,
+    crates::{parse_rust_version, Version},
+    error::LimpError,
+    files::{
+        create_from_git, create_project, create_workspace, find_toml, Binary, Metadata,
+        ProjectKind, ProjectOptions,
+    },
+    history,
+    hooks::{self, Hook, HookContext},
+    lockfile::{self, Lockfile},
+    manifest::CargoManifest,
+    parser::{inject, Injection, SnippetEntity, Vars},
+    process::{self, Diagnostic},
+    scaffold::{Preset, Scaffold},
+    snippets,
+    storage::{
+        DependencyKind, DependencySource, JsonDependency, JsonStorage, MergeSummary, StorageLock,
+        UpdatePolicy,
+    },
+    templates::Template,
+    version,
+};
+
+pub(crate) fn current_dir_error(context: &str) -> LimpError {
+    LimpError::CargoTomlNotFound(format!(
+        "{}\npath: {}",
+        context,
+        std::env::current_dir()
+            .map(|d| d.display().to_string())
+            .unwrap_or_default()
+    ))
+}
+
+/// Saves `js` under the `lock` it was loaded with and journals `entry` with
+/// the changes made to it since `before`.
+fn save_logged(
+    ctx: &Context,
+    lock: &StorageLock,
+    before: &JsonStorage,
+    js: &JsonStorage,
+    mut entry: history::Entry,
+) -> Result<(), LimpError> {
+    js.save_locked(ctx.config_path(), lock)?;
+    entry.diff(before, js);
+    history::record(ctx.history_path(), &entry)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct InitOptions {
+    /// directory of the new project
+    pub path: String,
+    pub dependencies: Option<Vec<String>>,
+    pub kind: ProjectKind,
+    pub workspace: Option<Vec<String>>,
+    pub template: Option<String>,
+    pub vars: Vec<(String, String)>,
+    /// run `cargo check` on the new project, also done when
+    /// `check_on_init` is set
+    pub check: bool,
+    /// package name, defaults to the last component of `path`
+    pub package: Option<String>,
+    /// the `git_init` setting when unset
+    pub git: Option<bool>,
+    pub scaffold: Scaffold,
+    /// standalone snippets from the snippets directory, by name
+    pub snippets: Vec<String>,
+    /// extra binaries as `name` or `name:snippet`
+    pub bins: Vec<String>,
+    /// project type out of `scaffold::PRESETS`, or a saved template of
+    /// that name
+    pub preset: Option<String>,
+    /// `key = value` overrides of the `[manifest]` setting, where `key` may
+    /// be dotted like `profile.release.lto`
+    pub manifest_extras: Vec<(String, String)>,
+    pub description: Option<String>,
+    pub repository: Option<String>,
+    pub keywords: Vec<String>,
+    /// run `rustfmt` on the generated sources, also done when
+    /// `fmt_on_init` is set
+    pub fmt: bool,
+    /// git repository to create the project from instead
+    pub from_git: Option<String>,
+    /// branch or tag of `from_git`, its default branch when unset
+    pub branch: Option<String>,
+    /// run the post-init hooks the `from_git` repository ships in
+    /// `.limp/hooks`
+    pub template_hooks: bool,
+}
+
+/// A `cargo check` error with the stored snippets it may come from.
+#[derive(Debug, Clone)]
+pub struct Blamed {
+    pub diagnostic: Diagnostic,
+    pub snippets: Vec<PathBuf>,
+}
+
+impl std::fmt::Display for Blamed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.snippets.is_empty() {
+            return write!(f, "{}", self.diagnostic);
+        }
+        let from = self
+            .snippets
+            .iter()
+            .map(|s| s.display().to_string())
+            .collect::<Vec<String>>();
+        write!(f, "{} (from {})", self.diagnostic, from.join(", "))
+    }
+}
+
+/// The `[package]` metadata of `limp init`. The author comes from the
+/// `author` and `email` settings, or from git's `user.name` and `user.email`.
+fn metadata(ctx: &Context, options: &InitOptions) -> Result<Metadata, LimpError> {
+    if options.keywords.len() > 5 {
+        return Err(LimpError::InvalidArguments(format!(
+            "at most 5 keywords are allowed, got {}",
+            options.keywords.len()
+        )));
+    }
+    if let Some(keyword) = options.keywords.iter().find(|k| {
+        k.len() > 20
+            || !k.starts_with(|c: char| c.is_ascii_alphabetic())
+            || !k
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '+')
+    }) {
+        return Err(LimpError::InvalidArguments(format!(
+            "keyword `{}`, keywords start with a letter and have at most 20 letters, numbers, `-`, `_` or `+`",
+            keyword
+        )));
+    }
+    let name = ctx
+        .config
+        .author
+        .clone()
+        .or_else(|| process::git_config("user.name"));
+    let email = ctx
+        .config
+        .email
+        .clone()
+        .or_else(|| process::git_config("user.email"));
+    let authors = match (name, email) {
+        (Some(name), Some(email)) => vec![format!("{} <{}>", name, email)],
+        (Some(name), None) => vec![name],
+        (None, _) => vec![],
+    };
+    Ok(Metadata {
+        authors,
+        description: options.description.clone(),
+        repository: options.repository.clone(),
+        keywords: options.keywords.clone(),
+    })
+}
+
+/// Reads a `limp init --bins` entry, `name` or `name:snippet`.
+fn binary(ctx: &Context, spec: &str) -> Result<Binary, LimpError> {
+    let (name, snippet) = match spec.split_once(':') {
+        Some((name, snippet)) => (name, Some(snippet)),
+        None => (spec, None),
+    };
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(LimpError::InvalidArguments(format!(
+            "`{}` is not a binary name",
+            name
+        )));
+    }
+    Ok(Binary {
+        name: name.to_string(),
+        snippet: snippet
+            .map(|s| snippets::existing(ctx.snippets_dir(), s))
+            .transpose()?,
+    })
+}
+
+#[derive(Debug, Default)]
+pub struct InitReport {
+    /// dependencies written to the new project
+    pub dependencies: Vec<JsonDependency>,
+    /// errors of `cargo check`, empty when it passed or didn't run
+    pub errors: Vec<Blamed>,
+    /// why `rustfmt` left the sources as they were
+    pub format_error: Option<String>,
+    /// hooks of the git template that weren't run without `template_hooks`
+    pub skipped_hooks: Vec<PathBuf>,
+}
+
+/// The `[manifest]` setting with the `--manifest-extra` overrides merged in.
+/// Values that aren't valid TOML are taken as strings.
+fn manifest_extras(
+    ctx: &Context,
+    overrides: &[(String, String)],
+) -> Result<toml::Table, LimpError> {
+    let mut extras = ctx.config.manifest.clone();
+    for (key, value) in overrides {
+        let table = format!("{} = {}", key, value)
+            .parse::<toml::Table>()
+            .or_else(|_| {
+                format!("{} = {}", key, toml::Value::String(value.clone())).parse::<toml::Table>()
+            })
+            .map_err(|e| LimpError::InvalidArguments(format!("manifest extra {}: {}", key, e)))?;
+        merge_toml(&mut extras, table);
+    }
+    Ok(extras)
+}
+
+fn merge_toml(into: &mut toml::Table, from: toml::Table) {
+    for (key, value) in from {
+        if let (Some(toml::Value::Table(into)), toml::Value::Table(from)) =
+            (into.get_mut(&key), &value)
+        {
+            merge_toml(into, from.clone());
+            continue;
+        }
+        into.insert(key, value);
+    }
+}
+
+/// Resolves the latest versions of dependencies that aren't stored for
+/// `limp init`, failing with all errors at once.
+fn resolve_unknown(ctx: &Context, unknown: &[String]) -> Result<Vec<JsonDependency>, LimpError> {
+    if ctx.frozen && !unknown.is_empty() {
+        return Err(LimpError::Frozen(format!(
+            "not stored: {}",
+            unknown.join(", ")
+        )));
+    }
+    let (resolved, errors) =
+        JsonDependency::new_many(unknown, &ctx.registry, ctx.config.update_jobs);
+    if !errors.is_empty() {
+        return Err(LimpError::ResolveFailed(
+            errors
+                .iter()
+                .map(|(name, e)| format!("{}: {}", name, e))
+                .collect::<Vec<String>>()
+                .join("\n"),
+        ));
+    }
+    Ok(resolved)
+}
+
+/// The dependencies of `preset`, stored ones as they are and the others
+/// resolved with the preset's features.
+fn preset_dependencies(
+    ctx: &Context,
+    js: &JsonStorage,
+    preset: &Preset,
+) -> Result<Vec<JsonDependency>, LimpError> {
+    let unknown = preset
+        .dependencies
+        .iter()
+        .filter(|(name, _)| js.get(name).is_none())
+        .map(|(name, _)| name.to_string())
+        .collect::<Vec<String>>();
+    let resolved = resolve_unknown(ctx, &unknown)?;
+    Ok(preset
+        .dependencies
+        .iter()
+        .filter_map(|(name, features)| match js.get(name) {
+            Some(dep) => Some(dep.clone()),
+            None => resolved
+                .iter()
+                .find(|r| r.name == *name)
+                .map(|dep| JsonDependency {
+                    features: (!features.is_empty())
+                        .then(|| features.iter().map(|f| f.to_string()).collect()),
+                    ..dep.clone()
+                }),
+        })
+        .collect())
+}
+
+/// Creates a project like `limp init`. Dependencies that aren't stored are
+/// resolved from `ctx.registry` but not stored.
+pub fn init(ctx: &Context, options: &InitOptions) -> Result<InitReport, LimpError> {
+    let config = &ctx.config;
+    let name = &options.path;
+    let js = JsonStorage::load(ctx.config_path())?;
+    // a saved template of the same name overrides a preset
+    let (template, preset) = match (&options.template, &options.preset) {
+        (Some(template), _) => (Some(Template::load(ctx.templates_dir(), template)?), None),
+        (None, Some(preset)) => match Template::load(ctx.templates_dir(), preset) {
+            Ok(template) => (Some(template), None),
+            Err(LimpError::TemplateNotFound(_)) => (None, Some(Preset::from_name(preset)?)),
+            Err(e) => return Err(e),
+        },
+        (None, None) => (None, None),
+    };
+    let base_deps = match (&template, &preset) {
+        (Some(template), _) => template.dependencies.clone(),
+        (None, Some(preset)) => preset_dependencies(ctx, &js, preset)?,
+        (None, None) => vec![],
+    };
+    let project_options = ProjectOptions {
+        name: options.package.clone(),
+        kind: preset.map_or(options.kind, |p| p.kind),
+        edition: config.edition.clone(),
+        git: options.git.unwrap_or(config.git_init),
+        vars: Vars::from_pairs(&options.vars).with_author(config.author.clone()),
+        scaffold: options.scaffold.clone(),
+        snippets: options
+            .snippets
+            .iter()
+            .map(|s| snippets::existing(ctx.snippets_dir(), s))
+            .collect::<Result<Vec<PathBuf>, LimpError>>()?,
+        snippets_dir: ctx.snippets_dir(),
+        bins: options
+            .bins
+            .iter()
+            .map(|b| binary(ctx, b))
+            .collect::<Result<Vec<Binary>, LimpError>>()?,
+        preset,
+        manifest_extras: manifest_extras(ctx, &options.manifest_extras)?,
+        metadata: metadata(ctx, options)?,
+    };
+    let mut odeps = None;
+    if let Some(deps) = &options.dependencies {
+        let mut result_deps = base_deps.clone();
+        // `name:variant` picks one of the dependency's snippets
+        let deps = deps
+            .iter()
+            .map(|d| match d.split_once(':') {
+                Some((name, variant)) => (name.to_string(), Some(variant)),
+                None => (d.clone(), None),
+            })
+            .collect::<Vec<_>>();
+        let unknown = deps
+            .iter()
+            .filter(|(d, _)| js.get(d).is_none())
+            .map(|(d, _)| d.clone())
+            .collect::<Vec<String>>();
+        let resolved = resolve_unknown(ctx, &unknown)?;
+        for (d, variant) in deps.iter() {
+            result_deps.retain(|td| &td.name != d);
+            let dep = js
+                .get(d)
+                .or_else(|| resolved.iter().find(|r| &r.name == d))
+                .cloned();
+            result_deps.extend(match (dep, variant) {
+                (Some(dep), Some(variant)) => Some(dep.with_variant(variant)?),
+                (dep, _) => dep,
+            });
+        }
+        if !result_deps.is_empty() {
+            odeps = Some(result_deps);
+        }
+    }
+
+    if odeps.is_none() && !base_deps.is_empty() {
+        odeps = Some(base_deps);
+    }
+
+    let package_name = project_options.package_name(name);
+    let dep_names = odeps
+        .iter()
+        .flatten()
+        .map(|d| d.name.clone())
+        .collect::<Vec<String>>();
+    hooks::run_in(
+        ctx.hooks_dir(),
+        &HookContext::new(Hook::PreInit, name, &package_name).with_dependencies(dep_names.clone()),
+    )?;
+
+    let mut skipped_hooks = vec![];
+    if let Some(url) = &options.from_git {
+        create_from_git(
+            name,
+            url,
+            options.branch.as_deref(),
+            odeps.as_deref(),
+            &project_options,
+        )?;
+        let template_dir = Path::new(name).join(hooks::TEMPLATE_DIR);
+        let context = HookContext::new(Hook::PostInit, name, &package_name)
+            .with_dependencies(dep_names.clone());
+        if options.template_hooks {
+            hooks::run_in(template_dir.join("hooks"), &context)?;
+        } else {
+            skipped_hooks = hooks::find(&template_dir.join("hooks"), Hook::PostInit.name())?;
+        }
+        if template_dir.is_dir() {
+            fs::remove_dir_all(template_dir)?;
+        }
+    } else if let Some(members) = &options.workspace {
+        create_workspace(name, members, odeps.as_deref(), &project_options)?
+    } else {
+        create_project(name, odeps.as_deref(), &project_options)?;
+        if let Some(template) = template {
+            template.apply(&package_name, name, &project_options.vars)?
+        }
+    }
+
+    // without rustfmt the project is still usable, just unformatted
+    let mut format_error = None;
+    if options.fmt || config.fmt_on_init {
+        format_error = process::rustfmt(name, &config.edition)
+            .err()
+            .map(|e| e.to_string());
+    }
+
+    hooks::run_in(
+        ctx.hooks_dir(),
+        &HookContext::new(Hook::PostInit, name, &package_name).with_dependencies(dep_names),
+    )?;
+
+    let dependencies = odeps.unwrap_or_default();
+    let mut errors = vec![];
+    if options.check || config.check_on_init {
+        let snippets = dependencies
+            .iter()
+            .filter_map(|d| d.snippet().map(PathBuf::from))
+            .chain(project_options.snippets.iter().cloned())
+            .collect::<Vec<PathBuf>>();
+        errors = process::cargo(name, "check")?
+            .into_iter()
+            .map(|diagnostic| Blamed {
+                snippets: process::blame(name, &diagnostic, &snippets),
+                diagnostic,
+            })
+            .collect();
+    }
+    Ok(InitReport {
+        dependencies,
+        errors,
+        format_error,
+        skipped_hooks,
+    })
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NewOptions {
+    pub name: String,
+    /// version requirement, the latest version when unset
+    pub version: Option<String>,
+    pub features: Option<Vec<String>>,
+    pub path_to_snippet: Option<String>,
+    pub kind: DependencyKind,
+    pub allow_yanked: bool,
+    pub msrv: Option<String>,
+    pub registry: Option<String>,
+    pub source: DependencySource,
+    pub no_default_features: bool,
+    pub optional: bool,
+    pub rename: Option<String>,
+    /// only warn about licenses the `[licenses]` policy fails on
+    pub allow_license_violation: bool,
+    /// replace a stored dependency of the same name
+    pub force: bool,
+    /// leave a stored dependency of the same name as it is
+    pub if_missing: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewReport {
+    pub dependency: JsonDependency,
+    /// requested features that enable all features of the crate
+    pub meta_features: Vec<String>,
+    /// license policy violations that were let through
+    pub license_violations: Vec<String>,
+    /// the dependency was already stored and kept because of `if_missing`
+    pub already_stored: bool,
+}
+
+/// Checks the license of `version` against the `[licenses]` policy. Returns
+/// the violation to warn about, or fails with it when the policy says so
+/// and `allow` isn't set.
+fn check_license(
+    ctx: &Context,
+    name: &str,
+    version: &Version,
+    allow: bool,
+) -> Result<Option<String>, LimpError> {
+    let policy = &ctx.config.licenses;
+    match policy.violation(name, version.license.as_deref()) {
+        Some(violation) if policy.fail && !allow => Err(LimpError::LicenseViolation(violation)),
+        violation => Ok(violation),
+    }
+}
+
+/// Resolves and stores a dependency like `limp new`.
+pub fn new_dependency(ctx: &Context, options: &NewOptions) -> Result<NewReport, LimpError> {
+    new_dependency_with(
+        ctx,
+        options,
+        None::<fn(&Version) -> Result<Vec<String>, LimpError>>,
+    )
+}
+
+/// Like [`new_dependency`], but with the features chosen by `pick` from the
+/// resolved version of a registry crate, like `limp new --pick-features`.
+/// `pick` returns every feature to enable, default ones included.
+///
+/// Fails with [`LimpError::DependencyAlreadyExists`] when the name is
+/// already stored, unless `force` or `if_missing` is set.
+pub fn new_dependency_with<F>(
+    ctx: &Context,
+    options: &NewOptions,
+    pick: Option<F>,
+) -> Result<NewReport, LimpError>
+where
+    F: FnOnce(&Version) -> Result<Vec<String>, LimpError>,
+{
+    let lock = ctx.lock_storage()?;
+    let mut js = JsonStorage::load_locked(ctx.config_path(), &lock)?;
+    if let Some(stored) = js.get(&options.name) {
+        if options.if_missing {
+            return Ok(NewReport {
+                dependency: stored.clone(),
+                meta_features: vec![],
+                license_violations: vec![],
+                already_stored: true,
+            });
+        }
+        if !options.force {
+            return Err(LimpError::DependencyAlreadyExists(options.name.clone()));
+        }
+    }
+    let before = js.clone();
+    let action = history::Entry::new(format!("new {}", options.name));
+    let default_features = options.no_default_features.then_some(false);
+    if options.source != DependencySource::Registry {
+        let dependency = JsonDependency {
+            kind: options.kind,
+            default_features,
+            optional: options.optional,
+            rename: options.rename.clone(),
+            ..JsonDependency::from_source(
+                &options.name,
+                options.source.clone(),
+                options.version.as_deref(),
+                options.features.as_deref(),
+                options.path_to_snippet.as_deref(),
+            )?
+        };
+        js.add(dependency.clone());
+        save_logged(ctx, &lock, &before, &js, action)?;
+        return Ok(NewReport {
+            dependency,
+            meta_features: vec![],
+            license_violations: vec![],
+            already_stored: false,
+        });
+    }
+    let mut registry = ctx.registry.clone();
+    if let Some(msrv) = &options.msrv {
+        registry = registry.with_msrv(Some(
+            parse_rust_version(msrv).ok_or_else(|| LimpError::InvalidVersion(msrv.to_string()))?,
+        ));
+    }
+    if options.registry.is_some() {
+        registry = registry.using(options.registry.as_deref())?;
+    }
+
+    let mut dependency = JsonDependency {
+        kind: options.kind,
+        default_features,
+        optional: options.optional,
+        rename: options.rename.clone(),
+        ..JsonDependency::new_full(
+            &options.name,
+            options.version.as_deref(),
+            options.features.as_deref(),
+            options.path_to_snippet.as_deref(),
+            options.allow_yanked,
+            &registry,
+        )?
+    };
+    // the stored version may be a requirement like `^1`
+    let version = registry
+        .get_crate_from(&options.name, dependency.registry.as_deref())?
+        .resolve(&dependency.version, registry.msrv.as_ref())
+        .ok_or_else(|| {
+            LimpError::VersionNotFound(format!("{}/{}", options.name, dependency.version))
+        })?;
+    let license_violations = check_license(
+        ctx,
+        &options.name,
+        &version,
+        options.allow_license_violation,
+    )?
+    .into_iter()
+    .collect();
+    if let Some(pick) = pick {
+        let picked = pick(&version)?;
+        version.check_features(&picked)?;
+        let defaults = version.feature_graph().defaults();
+        // picking all default features keeps them on, so only the others are
+        // listed; dropping any of them turns default features off
+        let features = if defaults.iter().all(|f| picked.contains(f)) {
+            picked
+                .into_iter()
+                .filter(|f| !defaults.contains(f))
+                .collect::<Vec<String>>()
+        } else {
+            dependency.default_features = Some(false);
+            picked
+        };
+        dependency.features = (!features.is_empty()).then_some(features);
+    }
+    let graph = version.feature_graph();
+    let meta_features = dependency
+        .features
+        .iter()
+        .flatten()
+        .filter(|f| graph.is_meta(f))
+        .cloned()
+        .collect();
+    js.add(dependency.clone());
+    save_logged(ctx, &lock, &before, &js, action)?;
+    Ok(NewReport {
+        dependency,
+        meta_features,
+        license_violations,
+        already_stored: false,
+    })
+}
+
+/// Reads a `limp new --from-file` list into options based on `base`, one
+/// `name[@version] [features...]` per line. Blank lines and `#` comments
+/// are skipped.
+pub fn new_list(base: &NewOptions, list: &str) -> Vec<NewOptions> {
+    list.lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            let (name, version) = split_version(words.next()?);
+            let features = words.map(String::from).collect::<Vec<String>>();
+            Some(NewOptions {
+                name: name.to_string(),
+                version: version.map(String::from),
+                features: (!features.is_empty()).then_some(features),
+                ..base.clone()
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Default)]
+pub struct NewListReport {
+    pub reports: Vec<NewReport>,
+    /// entries that couldn't be stored, by name
+    pub errors: Vec<(String, LimpError)>,
+}
+
+/// Stores every entry of `list` like `limp new --from-file`. Crates of the
+/// default registry are looked up with up to `jobs` concurrent requests
+/// first; an entry that fails doesn't stop the others.
+pub fn new_dependencies(ctx: &Context, list: &[NewOptions], jobs: usize) -> NewListReport {
+    let names = list
+        .iter()
+        .filter(|o| o.source == DependencySource::Registry && o.registry.is_none())
+        .map(|o| o.name.clone())
+        .collect::<Vec<String>>();
+    // the lookups end up in the cache, where the entries below find them
+    ctx.registry.get_crates(&names, jobs);
+    let mut report = NewListReport::default();
+    for options in list {
+        match new_dependency(ctx, options) {
+            Ok(stored) => report.reports.push(stored),
+            Err(e) => report.errors.push((options.name.clone(), e)),
+        }
+    }
+    report
+}
+
+/// Snippet files in the storage that no other dependency than `name` links
+/// to, which `delete` moves to the trash, sorted.
+pub fn owned_snippets(ctx: &Context, js: &JsonStorage, name: &str) -> Vec<PathBuf> {
+    let Some(dep) = js.get(name) else {
+        return vec![];
+    };
+    let dir = ctx.snippets_dir();
+    let shared = |path: &Path| {
+        js.dependencies
+            .values()
+            .filter(|d| d.name != name)
+            .any(|d| d.snippets.values().any(|p| Path::new(p) == path))
+    };
+    let mut owned = dep
+        .snippets
+        .values()
+        .map(PathBuf::from)
+        .filter(|p| p.starts_with(&dir) && p.is_file() && !shared(p))
+        .collect::<Vec<PathBuf>>();
+    owned.sort();
+    owned.dedup();
+    owned
+}
+
+/// Removes a dependency from the storage like `limp del`. The snippet files
+/// only it links to are moved to the trash, or kept as snippets stored on
+/// their own with `keep_snippet`. Returns the files moved to the trash.
+pub fn delete(ctx: &Context, name: &str, keep_snippet: bool) -> Result<Vec<PathBuf>, LimpError> {
+    let lock = ctx.lock_storage()?;
+    let mut js = JsonStorage::load_locked(ctx.config_path(), &lock)?;
+    let before = js.clone();
+    let mut entry = history::Entry::new(format!("del {}", name));
+    let owned = owned_snippets(ctx, &js, name);
+    for path in owned.iter() {
+        if keep_snippet {
+            snippets::keep(&mut js, path);
+        } else {
+            entry.snapshot(path);
+            entry.created(snippets::trash(ctx.trash_dir(), path)?);
+            if let Some(file) = path.file_name() {
+                js.snippets.remove(&*file.to_string_lossy());
+            }
+        }
+    }
+    js.remove(name);
+    save_logged(ctx, &lock, &before, &js, entry)?;
+    snippets::purge_trash(ctx.trash_dir(), ctx.config.trash_retention())?;
+    Ok(if keep_snippet { vec![] } else { owned })
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AddOptions {
+    /// crates to add, each either `name` or `name@version`
+    pub names: Vec<String>,
+    /// extra features as `crate:feature`, or only `feature` when adding a
+    /// single crate
+    pub features: Vec<String>,
+    /// overrides the stored kind
+    pub kind: Option<DependencyKind>,
+    pub no_default_features: bool,
+    pub optional: bool,
+    /// only with a single crate
+    pub rename: Option<String>,
+    pub with_snippet: bool,
+    /// module to write the snippet to, see the `snippet_merge` setting; only
+    /// with a single crate
+    pub module: Option<String>,
+    /// only warn about licenses the `[licenses]` policy fails on
+    pub allow_license_violation: bool,
+    /// workspace member to add to, by package or directory name, instead of
+    /// the project around the current directory
+    pub package: Option<String>,
+    /// add to `[workspace.dependencies]`, and inherit them in the member
+    pub workspace: bool,
+}
+
+#[derive(Debug)]
+pub struct AddReport {
+    pub dependencies: Vec<JsonDependency>,
+    /// the member's manifest, or the workspace's without one
+    pub manifest: PathBuf,
+    pub injections: Vec<Injection>,
+    /// license policy violations that were let through
+    pub license_violations: Vec<String>,
+}
+
+/// Splits `name@version` into the name and the version requirement.
+fn split_version(spec: &str) -> (&str, Option<&str>) {
+    match spec.split_once('@') {
+        Some((name, version)) => (name, Some(version)),
+        None => (spec, None),
+    }
+}
+
+/// Groups `crate:feature` entries by crate, where a bare `feature` belongs to
+/// the only crate in `names`.
+fn features_by_crate<'a>(
+    features: &'a [String],
+    names: &[&'a str],
+) -> Result<HashMap<&'a str, Vec<String>>, LimpError> {
+    let mut by_crate = HashMap::<&str, Vec<String>>::new();
+    for entry in features.iter() {
+        let (name, feature) = match entry.split_once(':') {
+            Some((name, feature)) => (name, feature),
+            None if names.len() == 1 => (names[0], entry.as_str()),
+            None => {
+                return Err(LimpError::IncompatibleFeatures(format!(
+                    "`{}` needs a crate when adding several, e.g. `{}:{}`",
+                    entry, names[0], entry
+                )))
+            }
+        };
+        if !names.contains(&name) {
+            return Err(LimpError::IncompatibleFeatures(format!(
+                "`{}` is for {}, which isn't being added",
+                entry, name
+            )));
+        }
+        by_crate.entry(name).or_default().push(feature.to_string());
+    }
+    Ok(by_crate)
+}
+
+/// The manifests `limp add` writes to: the workspace's with `--workspace`,
+/// and the member picked with `--package`, or else the project around the
+/// current directory unless it is a virtual manifest.
+fn add_targets(
+    path: &Path,
+    options: &AddOptions,
+) -> Result<(Option<CargoManifest>, Option<CargoManifest>), LimpError> {
+    let nearest = CargoManifest::load(path)?;
+    let workspace = match options.workspace || options.package.is_some() {
+        true => Some(CargoManifest::workspace_of(path)?.ok_or_else(|| {
+            LimpError::InvalidArguments(format!("{} isn't part of a workspace", path.display()))
+        })?),
+        false => None,
+    };
+    let member = match (&options.package, &workspace) {
+        (Some(package), Some(workspace)) => Some(workspace.member(package)?),
+        _ if !nearest.is_virtual() => Some(nearest),
+        _ if options.workspace => None,
+        _ => {
+            let members = nearest
+                .members()
+                .iter()
+                .filter_map(|p| p.parent()?.file_name())
+                .map(|n| n.to_string_lossy().to_string())
+                .collect::<Vec<String>>();
+            return Err(LimpError::InvalidArguments(format!(
+                "{} is a virtual manifest, pick a member with --package ({}) or use --workspace",
+                path.display(),
+                members.join(", ")
+            )));
+        }
+    };
+    Ok((workspace.filter(|_| options.workspace), member))
+}
+
+/// Adds dependencies to the project around the current directory like
+/// `limp add`. Every crate is resolved before the manifest is written once.
+pub fn add(ctx: &Context, options: &AddOptions) -> Result<AddReport, LimpError> {
+    let names = options
+        .names
+        .iter()
+        .map(|s| split_version(s).0)
+        .collect::<Vec<&str>>();
+    let path =
+        find_toml().ok_or_else(|| current_dir_error(&format!("dep: {}", names.join(", "))))?;
+    if names.len() > 1 && (options.rename.is_some() || options.module.is_some()) {
+        return Err(LimpError::InvalidArguments(
+            "--rename and --module work with a single dependency".to_string(),
+        ));
+    }
+    let features = features_by_crate(&options.features, &names)?;
+    let (mut workspace, mut member) = add_targets(&path, options)?;
+    if options.with_snippet && member.is_none() {
+        return Err(LimpError::InvalidArguments(
+            "--with-snippet needs a package, pick one with --package".to_string(),
+        ));
+    }
+    let js = JsonStorage::load(ctx.config_path())?;
+
+    let mut deps = vec![];
+    for spec in options.names.iter() {
+        let (name, version) = split_version(spec);
+        let extra = features.get(name).cloned().unwrap_or_default();
+        let mut dep = match (js.get(name), version) {
+            (Some(stored), None) => stored.clone(),
+            (Some(stored), Some(version)) => {
+                let resolved = JsonDependency::new_full(
+                    name,
+                    Some(version),
+                    None,
+                    None,
+                    false,
+                    &ctx.registry,
+                )?;
+                JsonDependency {
+                    version: resolved.version,
+                    rust_version: resolved.rust_version,
+                    ..stored.clone()
+                }
+            }
+            (None, _) if ctx.frozen => {
+                return Err(LimpError::Frozen(format!("not stored: {}", name)))
+            }
+            (None, version) => JsonDependency::new_full(
+                name,
+                version,
+                (!extra.is_empty()).then_some(extra.as_slice()),
+                None,
+                false,
+                &ctx.registry,
+            )?,
+        };
+        if !extra.is_empty() {
+            let stored = dep.features.get_or_insert_with(Vec::new);
+            for feature in extra {
+                if !stored.contains(&feature) {
+                    stored.push(feature);
+                }
+            }
+        }
+        if let Some(kind) = options.kind {
+            dep.kind = kind;
+        }
+        if options.no_default_features {
+            dep.default_features = Some(false);
+        }
+        if options.optional {
+            dep.optional = true;
+        }
+        if options.rename.is_some() {
+            dep.rename = options.rename.clone();
+        }
+        deps.push(dep);
+    }
+    let mut license_violations = vec![];
+    for dep in deps.iter() {
+        if ctx.config.licenses.is_empty() || dep.source != DependencySource::Registry {
+            continue;
+        }
+        let krate = match ctx
+            .registry
+            .get_crate_from(&dep.name, dep.registry.as_deref())
+        {
+            Ok(krate) => krate,
+            // the license of a stored dependency is unknown offline
+            Err(LimpError::OfflineUnavailable(_)) => continue,
+            Err(e) => return Err(e),
+        };
+        let version = krate
+            .resolve(&dep.version, ctx.registry.msrv.as_ref())
+            .ok_or_else(|| LimpError::VersionNotFound(format!("{}/{}", dep.name, dep.version)))?;
+        license_violations.extend(check_license(
+            ctx,
+            &dep.name,
+            &version,
+            options.allow_license_violation,
+        )?);
+    }
+
+    let manifest_path = match (&member, &workspace) {
+        (Some(manifest), _) | (None, Some(manifest)) => manifest.path().to_path_buf(),
+        (None, None) => path.clone(),
+    };
+    let project_dir = manifest_path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let project_name = member
+        .as_ref()
+        .and_then(|m| m.package_name())
+        .unwrap_or(names.first().copied().unwrap_or_default())
+        .to_string();
+    let dep_names = deps.iter().map(|d| d.name.clone()).collect::<Vec<String>>();
+    hooks::run_in(
+        ctx.hooks_dir(),
+        &HookContext::new(Hook::PreAdd, &project_dir, &project_name)
+            .with_dependencies(dep_names.clone()),
+    )?;
+    if let Some(workspace) = workspace.as_mut() {
+        for dep in deps.iter() {
+            workspace.add_workspace_dependency(dep)?;
+        }
+        workspace.save()?;
+    }
+    if let Some(member) = member.as_mut() {
+        if workspace.is_some() {
+            // a root package shares its manifest with the workspace
+            *member = CargoManifest::load(member.path())?;
+        }
+        for dep in deps.iter() {
+            match workspace {
+                Some(_) => member.add_inherited_dependency(dep)?,
+                None => member.add_dependency(dep)?,
+            }
+        }
+        member.save()?;
+    }
+
+    let mut injections = vec![];
+    if let Some(manifest) = member.as_ref().filter(|_| options.with_snippet) {
+        for dep in deps.iter() {
+            let path = dep
+                .snippet()
+                .ok_or_else(|| LimpError::SnippetNotFound(dep.name.clone()))?;
+            let snippet = SnippetEntity::parse(
+                path,
+                &Vars::default()
+                    .with_author(ctx.config.author.clone())
+                    .render(
+                        &project_name,
+                        &SnippetEntity::load(path, ctx.snippets_dir())?.to_string(),
+                    ),
+            );
+            let module = match (&options.module, ctx.config.snippet_merge) {
+                (Some(module), _) => Some(module.clone()),
+                (None, SnippetMerge::Module) => Some(dep.name.replace('-', "_")),
+                (None, SnippetMerge::Entry) => None,
+            };
+            injections.push(inject(&snippet, &manifest.entry_file(), module.as_deref())?);
+        }
+    }
+    history::record(
+        ctx.history_path(),
+        &history::Entry::new(format!("add {}", options.names.join(" "))),
+    )?;
+    hooks::run_in(
+        ctx.hooks_dir(),
+        &HookContext::new(Hook::PostAdd, &project_dir, &project_name).with_dependencies(dep_names),
+    )?;
+    Ok(AddReport {
+        dependencies: deps,
+        manifest: manifest_path,
+        injections,
+        license_violations,
+    })
+}
+
+/// Injects the stored snippet `name` into the project around the current
+/// directory like `limp apply`: as `src/<module>.rs`, named after the snippet
+/// unless `module` is given, or into the entry file when `entry` is set.
+pub fn apply(
+    ctx: &Context,
+    name: &str,
+    module: Option<&str>,
+    entry: bool,
+) -> Result<Injection, LimpError> {
+    let path = find_toml().ok_or_else(|| current_dir_error(&format!("snippet: {}", name)))?;
+    let manifest = CargoManifest::load(path)?;
+    let file = snippets::existing(ctx.snippets_dir(), name)?;
+    let project_name = manifest.package_name().unwrap_or(name).to_string();
+    let snippet = SnippetEntity::parse(
+        &file,
+        &Vars::default()
+            .with_author(ctx.config.author.clone())
+            .render(
+                &project_name,
+                &SnippetEntity::load(&file, ctx.snippets_dir())?.to_string(),
+            ),
+    );
+    let module = match (module, entry) {
+        (_, true) => None,
+        (Some(module), false) => Some(module.to_string()),
+        (None, false) => file
+            .file_stem()
+            .map(|s| s.to_string_lossy().replace('-', "_")),
+    };
+    inject(&snippet, &manifest.entry_file(), module.as_deref())
+}
+
+/// Removes a dependency from the project around the current directory like
+/// `limp remove` and returns the tables it was removed from.
+pub fn remove(name: &str) -> Result<Vec<DependencyKind>, LimpError> {
+    let path = find_toml().ok_or_else(|| current_dir_error(&format!("dep: {}", name)))?;
+    let mut manifest = CargoManifest::load(path)?;
+    let removed_from = manifest.remove_dependency(name);
+    if removed_from.is_empty() {
+        return Err(LimpError::DependencyNotInManifest(name.to_string()));
+    }
+    manifest.save()?;
+    Ok(removed_from)
+}
+
+/// Stores the dependencies of a `Cargo.toml` (the current project's when
+/// `path` is unset) like `limp import`, asking `replace` about conflicts.
+pub fn import<F>(ctx: &Context, path: Option<&str>, replace: F) -> Result<MergeSummary, LimpError>
+where
+    F: FnMut(&JsonDependency, &JsonDependency) -> Result<bool, LimpError>,
+{
+    let path = match path {
+        Some(path) => path.into(),
+        None => find_toml().ok_or_else(|| current_dir_error("import"))?,
+    };
+    let lock = ctx.lock_storage()?;
+    let mut js = JsonStorage::load_locked(ctx.config_path(), &lock)?;
+    let before = js.clone();
+    let summary = js.merge(CargoManifest::load(path)?.dependencies(), replace)?;
+    save_logged(ctx, &lock, &before, &js, history::Entry::new("import"))?;
+    Ok(summary)
+}
+
+/// Merges a bundle written by `export` into the storage like
+/// `limp import --bundle`.
+pub fn import_bundle<F>(ctx: &Context, dir: &str, replace: F) -> Result<MergeSummary, LimpError>
+where
+    F: FnMut(&JsonDependency, &JsonDependency) -> Result<bool, LimpError>,
+{
+    let lock = ctx.lock_storage()?;
+    let mut js = JsonStorage::load_locked(ctx.config_path(), &lock)?;
+    let before = js.clone();
+    let summary = bundle::import(&mut js, dir, ctx.snippets_dir(), replace)?;
+    save_logged(
+        ctx,
+        &lock,
+        &before,
+        &js,
+        history::Entry::new(format!("import --bundle {}", dir)),
+    )?;
+    Ok(summary)
+}
+
+/// Writes the stored dependencies and their snippets to `dir` and returns
+/// how many were exported.
+pub fn export(ctx: &Context, dir: &str) -> Result<usize, LimpError> {
+    bundle::export(&JsonStorage::load(ctx.config_path())?, dir)
+}
+
+/// What `sync` does with a dependency whose version differs between the
+/// project and the storage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncChoice {
+    /// write the stored version to Cargo.toml
+    BumpProject,
+    /// store the project's version
+    UpdateStorage,
+    Skip,
+}
+
+/// A dependency whose version differs between the project and the storage.
+#[derive(Debug, Clone)]
+pub struct SyncEntry {
+    pub name: String,
+    pub kind: DependencyKind,
+    pub project: String,
+    pub stored: String,
+    pub choice: SyncChoice,
+}
+
+impl std::fmt::Display for SyncEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.choice {
+            SyncChoice::BumpProject => write!(
+                f,
+                "{}: project {} -> {}",
+                self.name, self.project, self.stored
+            ),
+            SyncChoice::UpdateStorage => write!(
+                f,
+                "{}: stored {} -> {}",
+                self.name, self.stored, self.project
+            ),
+            SyncChoice::Skip => write!(
+                f,
+                "{}: skipped (project {}, stored {})",
+                self.name, self.project, self.stored
+            ),
+        }
+    }
+}
+
+/// Compares the dependencies of the project around the current directory
+/// with the stored ones like `limp sync`, asking `choose` with the project's
+/// and the stored entry what to do about every differing version.
+pub fn sync<F>(ctx: &Context, mut choose: F) -> Result<Vec<SyncEntry>, LimpError>
+where
+    F: FnMut(&JsonDependency, &JsonDependency) -> Result<SyncChoice, LimpError>,
+{
+    let path = find_toml().ok_or_else(|| current_dir_error("sync"))?;
+    let mut manifest = CargoManifest::load(path)?;
+    let lock = ctx.lock_storage()?;
+    let mut js = JsonStorage::load_locked(ctx.config_path(), &lock)?;
+    let before = js.clone();
+    let mut entries = vec![];
+    for dep in manifest.dependencies() {
+        let Some(stored) = js.get(&dep.name).cloned() else {
+            continue;
+        };
+        if dep.version.is_empty() || stored.version.is_empty() || dep.version == stored.version {
+            continue;
+        }
+        let choice = choose(&dep, &stored)?;
+        match choice {
+            SyncChoice::BumpProject => {
+                manifest.set_version(&dep.toml_name(), dep.kind, &stored.version);
+            }
+            SyncChoice::UpdateStorage => js.add(JsonDependency {
+                version: dep.version.clone(),
+                ..stored.clone()
+            }),
+            SyncChoice::Skip => {}
+        }
+        entries.push(SyncEntry {
+            name: dep.name,
+            kind: dep.kind,
+            project: dep.version,
+            stored: stored.version,
+            choice,
+        });
+    }
+    manifest.save()?;
+    save_logged(ctx, &lock, &before, &js, history::Entry::new("sync"))?;
+    Ok(entries)
+}
+
+#[derive(Debug, Default)]
+pub struct UpdateReport {
+    /// `(name, old version, new version)` sorted by name
+    pub changes: Vec<(String, String, String)>,
+    /// pinned dependencies that were left alone, sorted
+    pub pinned: Vec<String>,
+    /// `(name, features)` of the dependencies with stored features their
+    /// latest version doesn't have, kept back or updated without them
+    pub mismatches: Vec<(String, Vec<String>)>,
+    pub errors: Vec<(String, LimpError)>,
+}
+
+/// Updates the dependencies in `names`, or all of them, like `limp update`.
+/// Nothing is saved with `dry_run`.
+pub fn update(
+    ctx: &Context,
+    names: &[String],
+    dry_run: bool,
+    jobs: usize,
+    policy: UpdatePolicy,
+) -> Result<UpdateReport, LimpError> {
+    let lock = ctx.lock_storage()?;
+    let js = JsonStorage::load_locked(ctx.config_path(), &lock)?;
+    let mut updated = js.clone();
+    let outcome = updated.update_many(names, &ctx.registry, jobs, policy);
+    if !dry_run {
+        let action = match names {
+            [] => "update".to_string(),
+            names => format!("update {}", names.join(" ")),
+        };
+        save_logged(ctx, &lock, &js, &updated, history::Entry::new(action))?;
+    }
+    Ok(UpdateReport {
+        changes: js.version_changes(&updated),
+        pinned: js.pinned(names),
+        mismatches: outcome.mismatches,
+        errors: outcome.errors,
+    })
+}
+
+#[derive(Debug, Default)]
+pub struct WarmReport {
+    /// dependencies whose metadata is cached, sorted
+    pub warmed: Vec<String>,
+    pub errors: Vec<(String, LimpError)>,
+}
+
+/// Fetches the metadata of every stored registry dependency into the cache
+/// with up to `jobs` concurrent requests, like `limp cache warm`, so they
+/// can be used with `--offline` later.
+pub fn warm_cache(ctx: &Context, jobs: usize) -> Result<WarmReport, LimpError> {
+    let js = JsonStorage::load(ctx.config_path())?;
+    let mut by_registry: BTreeMap<Option<String>, Vec<String>> = BTreeMap::new();
+    for dep in js.dependencies.values() {
+        if dep.source == DependencySource::Registry {
+            by_registry
+                .entry(dep.registry.clone())
+                .or_default()
+                .push(dep.name.clone());
+        }
+    }
+    let mut report = WarmReport::default();
+    for (registry, names) in by_registry {
+        let results = match ctx.registry.clone().using(registry.as_deref()) {
+            Ok(registry) => registry.get_crates(&names, jobs),
+            Err(_) => names
+                .iter()
+                .map(|_| {
+                    Err(LimpError::UnknownRegistry(
+                        registry.clone().unwrap_or_default(),
+                    ))
+                })
+                .collect(),
+        };
+        for (name, result) in names.into_iter().zip(results) {
+            match result {
+                Ok(_) => report.warmed.push(name),
+                Err(e) => report.errors.push((name, e)),
+            }
+        }
+    }
+    report.warmed.sort();
+    report.errors.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(report)
+}
+
+/// The releases a dependency skips over when updated, see `changelog`.
+#[derive(Debug, Clone)]
+pub struct Changelog {
+    pub name: String,
+    pub old: String,
+    pub new: String,
+    pub repository: Option<String>,
+    /// crates.io pages are only linked for crates from crates.io
+    pub crates_io: bool,
+    pub versions: Vec<Version>,
+}
+
+impl std::fmt::Display for Changelog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // name: old -> new (repository)
+        //   version  date  link
+        write!(f, "{}: {} -> {}", self.name, self.old, self.new)?;
+        if let Some(repository) = &self.repository {
+            write!(f, " ({})", repository)?;
+        }
+        for version in self.versions.iter() {
+            write!(f, "\n  {}", version.num)?;
+            if let Some(date) = &version.created_at {
+                write!(f, "  {}", date.get(..10).unwrap_or(date))?;
+            }
+            if version.yanked {
+                write!(f, "  (yanked)")?;
+            }
+            if self.crates_io {
+                write!(
+                    f,
+                    "  https://crates.io/crates/{}/{}",
+                    self.name, version.num
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Looks up the versions published after `old` up to `new` for the stored
+/// dependency `name`, for `limp update --changes`.
+pub fn changelog(ctx: &Context, name: &str, old: &str, new: &str) -> Result<Changelog, LimpError> {
+    let registry = JsonStorage::load(ctx.config_path())?
+        .get(name)
+        .and_then(|d| d.registry.clone());
+    let krate = ctx.registry.get_crate_from(name, registry.as_deref())?;
+    Ok(Changelog {
+        name: name.to_string(),
+        old: old.to_string(),
+        new: new.to_string(),
+        repository: krate.crate_info.repository.clone(),
+        crates_io: registry.is_none(),
+        versions: krate.versions_between(old, new),
+    })
+}
+
+/// Pins or unpins a stored dependency like `limp pin` and `limp unpin`.
+pub fn pin(ctx: &Context, name: &str, pinned: bool) -> Result<(), LimpError> {
+    let lock = ctx.lock_storage()?;
+    let mut js = JsonStorage::load_locked(ctx.config_path(), &lock)?;
+    let before = js.clone();
+    js.set_pinned(name, pinned)?;
+    let action = format!("{} {}", if pinned { "pin" } else { "unpin" }, name);
+    save_logged(ctx, &lock, &before, &js, history::Entry::new(action))
+}
+
+/// Lets a stored dependency be updated to pre-releases, or stops it, like
+/// `limp prerelease`.
+pub fn allow_prerelease(ctx: &Context, name: &str, allow: bool) -> Result<(), LimpError> {
+    let lock = ctx.lock_storage()?;
+    let mut js = JsonStorage::load_locked(ctx.config_path(), &lock)?;
+    let before = js.clone();
+    js.set_allow_prerelease(name, allow)?;
+    let action = format!("prerelease {}{}", name, if allow { "" } else { " --off" });
+    save_logged(ctx, &lock, &before, &js, history::Entry::new(action))
+}
+
+/// Sets or clears the version requirement of a stored dependency like
+/// `limp constrain` and `limp unconstrain`.
+pub fn constrain(ctx: &Context, name: &str, constraint: Option<&str>) -> Result<(), LimpError> {
+    let lock = ctx.lock_storage()?;
+    let mut js = JsonStorage::load_locked(ctx.config_path(), &lock)?;
+    let before = js.clone();
+    js.set_constraint(name, constraint)?;
+    let action = match constraint {
+        Some(constraint) => format!("constrain {} {}", name, constraint),
+        None => format!("unconstrain {}", name),
+    };
+    save_logged(ctx, &lock, &before, &js, history::Entry::new(action))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LinkOptions {
+    /// stored dependency to link the snippet to
+    pub name: String,
+    /// snippet file or URL
+    pub source: String,
+    /// replace the snippet's tags
+    pub tags: Option<Vec<String>>,
+    /// variant to link the snippet as, the default one when unset
+    pub variant: Option<String>,
+}
+
+/// Links the snippet at `source`, a file or a URL, to the stored dependency
+/// like `limp link`. Files are copied into the snippets directory and URLs
+/// are downloaded there under the file name they end in, or as
+/// `<name>.rs` (`<name>-<variant>.rs` for a variant). Returns the path of
+/// the linked snippet.
+pub fn link(ctx: &Context, options: &LinkOptions) -> Result<PathBuf, LimpError> {
+    let (name, source) = (options.name.as_str(), options.source.as_str());
+    let lock = ctx.lock_storage()?;
+    let mut js = JsonStorage::load_locked(ctx.config_path(), &lock)?;
+    if js.get(name).is_none() {
+        return Err(LimpError::DependencyNotFound(name.to_string()));
+    }
+    let before = js.clone();
+    let mut entry = history::Entry::new(format!("link {} {}", name, source));
+    let path = if snippets::is_url(source) {
+        let file = source
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .filter(|f| f.ends_with(".rs"))
+            .map(String::from)
+            .unwrap_or_else(|| match &options.variant {
+                Some(variant) => format!("{}-{}.rs", name, variant),
+                None => format!("{}.rs", name),
+            });
+        let target = snippets::path(ctx.snippets_dir(), &file);
+        let same_origin = js
+            .snippets
+            .get(&file)
+            .is_some_and(|m| m.url.as_deref() == Some(source));
+        if target.exists() && !same_origin {
+            return Err(LimpError::SnippetConflict(file));
+        }
+        entry.snapshot(&target);
+        snippets::download(&ctx.registry, ctx.snippets_dir(), &mut js, source, &file)?
+    } else {
+        let target = ctx
+            .snippets_dir()
+            .join(Path::new(source).file_name().unwrap_or_default());
+        if !target.exists() {
+            entry.snapshot(&target);
+        }
+        let path = snippets::store(ctx.snippets_dir(), source)?;
+        snippets::record_copy(&mut js, &path, Path::new(source))?;
+        path
+    };
+    if let Some(tags) = &options.tags {
+        snippets::set_tags(&mut js, &path, tags);
+    }
+    if let Some(dep) = js.get_mut(name) {
+        dep.set_snippet(
+            options.variant.as_deref(),
+            path.to_string_lossy().to_string(),
+        );
+    }
+    save_logged(ctx, &lock, &before, &js, entry)?;
+    Ok(path)
+}
+
+/// Renames the stored dependency `old` to `new` like `limp rename`, moving
+/// the snippets named after it (`<old>.rs` and `<old>-<variant>.rs`) along.
+/// With `resolve` the latest version of `new` is looked up and stored.
+/// Returns the new paths of the moved snippets.
+pub fn rename(
+    ctx: &Context,
+    old: &str,
+    new: &str,
+    resolve: bool,
+) -> Result<Vec<PathBuf>, LimpError> {
+    let lock = ctx.lock_storage()?;
+    let mut js = JsonStorage::load_locked(ctx.config_path(), &lock)?;
+    let mut dep = js
+        .get(old)
+        .cloned()
+        .ok_or_else(|| LimpError::DependencyNotFound(old.to_string()))?;
+    if js.get(new).is_some() {
+        return Err(LimpError::DependencyAlreadyExists(new.to_string()));
+    }
+    let before = js.clone();
+    let mut entry = history::Entry::new(format!("rename {} {}", old, new));
+    if resolve {
+        let registry = ctx.registry.clone().using(dep.registry.as_deref())?;
+        let resolved =
+            JsonDependency::new_full(new, None, dep.features.as_deref(), None, false, &registry)?;
+        dep.version = resolved.version;
+        dep.rust_version = resolved.rust_version;
+    }
+    dep.name = new.to_string();
+    js.remove(old);
+    js.add(dep.clone());
+
+    let dir = ctx.snippets_dir();
+    let mut moves = BTreeSet::new();
+    for path in dep.snippets.values() {
+        let Some(stem) = Path::new(path)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+        else {
+            continue;
+        };
+        let Some(rest) = stem
+            .strip_prefix(old)
+            .filter(|rest| rest.is_empty() || rest.starts_with('-'))
+        else {
+            continue;
+        };
+        let from = snippets::path(&dir, &stem);
+        if !from.is_file() || fs::canonicalize(path).ok() != fs::canonicalize(&from).ok() {
+            continue;
+        }
+        let to = format!("{}{}", new, rest);
+        if snippets::path(&dir, &to).exists() {
+            return Err(LimpError::SnippetConflict(to));
+        }
+        moves.insert((stem, to));
+    }
+    let mut moved = vec![];
+    for (from, to) in moves {
+        entry.snapshot(snippets::path(&dir, &from));
+        entry.snapshot(snippets::path(&dir, &to));
+        snippets::rename(&dir, &mut js, &from, &to)?;
+        moved.push(snippets::path(&dir, &to));
+    }
+    save_logged(ctx, &lock, &before, &js, entry)?;
+    Ok(moved)
+}
+
+/// Deletes orphaned snippet files and repairs the storage's links to
+/// missing ones like `limp gc`, see `snippets::collect_garbage`. Nothing is
+/// changed with `dry_run`.
+pub fn gc(ctx: &Context, dry_run: bool) -> Result<snippets::Garbage, LimpError> {
+    let lock = ctx.lock_storage()?;
+    let mut js = JsonStorage::load_locked(ctx.config_path(), &lock)?;
+    let before = js.clone();
+    let garbage = snippets::collect_garbage(ctx.snippets_dir(), &mut js)?;
+    if dry_run || garbage.is_empty() {
+        return Ok(garbage);
+    }
+    let mut entry = history::Entry::new("gc");
+    for path in garbage.orphans.iter() {
+        entry.snapshot(path);
+        fs::remove_file(path)?;
+    }
+    save_logged(ctx, &lock, &before, &js, entry)?;
+    Ok(garbage)
+}
+
+/// Takes back the last change to the storage like `limp undo`, restoring
+/// the dependencies, snippet files and snippet metadata it changed. Returns the undone entry,
+/// `None` when there is nothing left to undo.
+pub fn undo(ctx: &Context) -> Result<Option<history::Entry>, LimpError> {
+    let entries = history::load(ctx.history_path())?;
+    let Some(index) = history::last_undoable(&entries) else {
+        return Ok(None);
+    };
+    let entry = entries[index].clone();
+    let lock = ctx.lock_storage()?;
+    let mut js = JsonStorage::load_locked(ctx.config_path(), &lock)?;
+    let before = js.clone();
+    for (name, dep) in &entry.dependencies {
+        match dep {
+            Some(dep) => js.dependencies.insert(name.clone(), dep.clone()),
+            None => js.dependencies.remove(name),
+        };
+    }
+    for (file, meta) in &entry.snippet_meta {
+        match meta {
+            Some(meta) => js.snippets.insert(file.clone(), meta.clone()),
+            None => js.snippets.remove(file),
+        };
+    }
+    let mut undo = history::Entry {
+        undoes: Some(index),
+        ..history::Entry::new(format!("undo {}", entry.action))
+    };
+    for (path, content) in &entry.snippets {
+        undo.snapshot(path);
+        match content {
+            Some(content) => fs::write(path, content)?,
+            None if Path::new(path).exists() => fs::remove_file(path)?,
+            None => {}
+        }
+    }
+    save_logged(ctx, &lock, &before, &js, undo)?;
+    Ok(Some(entry))
+}
+
+/// Imports snippets again from the URL or file they came from like
+/// `limp snippet refresh`, see [`snippets::refresh`].
+pub fn refresh_snippets(ctx: &Context, name: Option<&str>) -> Result<Vec<String>, LimpError> {
+    let lock = ctx.lock_storage()?;
+    let mut js = JsonStorage::load_locked(ctx.config_path(), &lock)?;
+    let before = js.clone();
+    let mut entry = history::Entry::new(match name {
+        Some(name) => format!("snippet refresh {}", name),
+        None => "snippet refresh".to_string(),
+    });
+    // only files with recorded metadata are refreshed
+    for file in js.snippets.keys() {
+        entry.snapshot(ctx.snippets_dir().join(file));
+    }
+    let refreshed = snippets::refresh(&ctx.registry, ctx.snippets_dir(), &mut js, name)?;
+    entry.forget_unchanged();
+    save_logged(ctx, &lock, &before, &js, entry)?;
+    Ok(refreshed)
+}
+
+/// Stores the file at `source` as the standalone snippet `name` like
+/// `limp snippet add`, recording where it came from.
+pub fn add_snippet(ctx: &Context, name: &str, source: &str) -> Result<PathBuf, LimpError> {
+    let lock = ctx.lock_storage()?;
+    let mut js = JsonStorage::load_locked(ctx.config_path(), &lock)?;
+    let before = js.clone();
+    let mut entry = history::Entry::new(format!("snippet add {}", name));
+    entry.snapshot(snippets::path(ctx.snippets_dir(), name));
+    let path = snippets::add(ctx.snippets_dir(), name, source)?;
+    snippets::record_copy(&mut js, &path, Path::new(source))?;
+    save_logged(ctx, &lock, &before, &js, entry)?;
+    Ok(path)
+}
+
+/// Renames the snippet `from` to `to` like `limp snippet mv`, see
+/// [`snippets::rename`]. Returns the relinked dependencies.
+pub fn rename_snippet(ctx: &Context, from: &str, to: &str) -> Result<Vec<String>, LimpError> {
+    let dir = ctx.snippets_dir();
+    let lock = ctx.lock_storage()?;
+    let mut js = JsonStorage::load_locked(ctx.config_path(), &lock)?;
+    let before = js.clone();
+    let mut entry = history::Entry::new(format!("snippet mv {} {}", from, to));
+    entry.snapshot(snippets::existing(&dir, from)?);
+    entry.snapshot(snippets::path(&dir, to));
+    let relinked = snippets::rename(&dir, &mut js, from, to)?;
+    save_logged(ctx, &lock, &before, &js, entry)?;
+    Ok(relinked)
+}
+
+/// Moves the snippet `name` to the trash like `limp snippet rm`, see
+/// [`snippets::remove`]. Returns the unlinked dependencies.
+pub fn remove_snippet(ctx: &Context, name: &str) -> Result<Vec<String>, LimpError> {
+    let lock = ctx.lock_storage()?;
+    let mut js = JsonStorage::load_locked(ctx.config_path(), &lock)?;
+    let before = js.clone();
+    let mut entry = history::Entry::new(format!("snippet rm {}", name));
+    entry.snapshot(snippets::existing(ctx.snippets_dir(), name)?);
+    let (unlinked, trashed) = snippets::remove(ctx.snippets_dir(), ctx.trash_dir(), &mut js, name)?;
+    entry.created(trashed);
+    save_logged(ctx, &lock, &before, &js, entry)?;
+    snippets::purge_trash(ctx.trash_dir(), ctx.config.trash_retention())?;
+    Ok(unlinked)
+}
+
+/// Moves the snippet `name` back out of the trash like
+/// `limp snippet restore`, see [`snippets::restore`].
+pub fn restore_snippet(ctx: &Context, name: &str) -> Result<PathBuf, LimpError> {
+    let lock = ctx.lock_storage()?;
+    let mut js = JsonStorage::load_locked(ctx.config_path(), &lock)?;
+    let before = js.clone();
+    let mut entry = history::Entry::new(format!("snippet restore {}", name));
+    let target = snippets::path(ctx.snippets_dir(), name);
+    let file = target.file_name().map(|f| f.to_string_lossy().to_string());
+    // the version `snippets::restore` picks
+    if let Some(trashed) = snippets::trashed(ctx.trash_dir())?
+        .into_iter()
+        .find(|t| Some(&t.name) == file.as_ref())
+    {
+        entry.snapshot(trashed.path);
+    }
+    entry.snapshot(&target);
+    let path = snippets::restore(ctx.snippets_dir(), ctx.trash_dir(), &mut js, name)?;
+    save_logged(ctx, &lock, &before, &js, entry)?;
+    Ok(path)
+}
+
+/// Checks the stored snippets against their checksums, and their sources
+/// with `sources`, like `limp snippet verify`.
+pub fn verify_snippets(ctx: &Context, sources: bool) -> Result<Vec<snippets::Verified>, LimpError> {
+    let js = JsonStorage::load(ctx.config_path())?;
+    snippets::verify(&ctx.registry, ctx.snippets_dir(), &js, sources)
+}
+
+/// Where a dependency is defined, see `which`.
+#[derive(Debug)]
+pub struct Which {
+    pub name: String,
+    pub stored: Option<JsonDependency>,
+    /// entries of the project's Cargo.toml, `None` outside a project
+    pub project: Option<Vec<JsonDependency>>,
+    /// versions the project's Cargo.lock resolved, `None` without one
+    pub locked: Option<Vec<String>>,
+    /// newest version in the dependency's registry
+    pub latest: Result<String, LimpError>,
+}
+
+impl std::fmt::Display for Which {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // name
+        //   stored: name = "version"
+        //   snippets: variant: file
+        //   project: [table] name = "version"
+        //   latest: version
+        write!(f, "{}", self.name)?;
+        match &self.stored {
+            Some(dep) => {
+                write!(f, "\n  stored: {}", dep)?;
+                if !dep.snippets.is_empty() {
+                    let snippets = dep
+                        .snippets
+                        .iter()
+                        .map(|(variant, path)| {
+                            let file = Path::new(path).file_name().unwrap_or_default();
+                            format!("{}: {}", variant, file.to_string_lossy())
+                        })
+                        .collect::<Vec<String>>();
+                    write!(f, "\n  snippets: {}", snippets.join(", "))?;
+                }
+            }
+            None => write!(f, "\n  stored: no")?,
+        }
+        match &self.project {
+            Some(deps) if !deps.is_empty() => {
+                for dep in deps.iter() {
+                    write!(f, "\n  project: [{}] {}", dep.kind.table_name(), dep)?;
+                }
+            }
+            Some(_) => write!(f, "\n  project: no")?,
+            None => write!(f, "\n  project: no Cargo.toml found")?,
+        }
+        match &self.locked {
+            Some(versions) if !versions.is_empty() => {
+                write!(f, "\n  locked: {}", versions.join(", "))?
+            }
+            Some(_) => write!(f, "\n  locked: no")?,
+            None => {}
+        }
+        match &self.latest {
+            Ok(version) => write!(f, "\n  latest: {}", version),
+            Err(e) => write!(f, "\n  latest: unavailable ({})", e),
+        }
+    }
+}
+
+impl Which {
+    /// Locked versions older than the stored one, which `cargo update` or
+    /// `limp sync` would bring up to date.
+    pub fn stale_locks(&self) -> Vec<&str> {
+        let (Some(stored), Some(locked)) = (&self.stored, &self.locked) else {
+            return vec![];
+        };
+        locked
+            .iter()
+            .map(String::as_str)
+            .filter(|v| lockfile::is_older(v, &stored.version))
+            .collect()
+    }
+}
+
+/// Looks `name` up in the storage, the project's Cargo.toml at `manifest`
+/// and its Cargo.lock, and its registry like `limp which`. Failing to reach the registry is
+/// reported in [`Which::latest`] instead of failing the lookup.
+pub fn which(ctx: &Context, name: &str, manifest: Option<&Path>) -> Result<Which, LimpError> {
+    let stored = JsonStorage::load(ctx.config_path())?.get(name).cloned();
+    let project = match manifest {
+        Some(path) => Some(
+            CargoManifest::load(path)?
+                .dependencies()
+                .into_iter()
+                .filter(|d| d.name == name || d.rename.as_deref() == Some(name))
+                .collect(),
+        ),
+        None => None,
+    };
+    let locked = match manifest {
+        Some(path) => Lockfile::find(path)?.map(|lock| {
+            let names = project
+                .iter()
+                .flatten()
+                .map(|d| d.name.as_str())
+                .chain([name])
+                .collect::<BTreeSet<&str>>();
+            names
+                .into_iter()
+                .flat_map(|n| lock.versions(n))
+                .map(String::from)
+                .collect()
+        }),
+        None => None,
+    };
+    let registry = stored.as_ref().and_then(|d| d.registry.as_deref());
+    let latest = ctx
+        .registry
+        .get_crate_from(name, registry)
+        .and_then(|krate| krate.latest(None))
+        .map(|version| version.num);
+    Ok(Which {
+        name: name.to_string(),
+        stored,
+        project,
+        locked,
+        latest,
+    })
+}
+
+/// A crate version and the crates it depends on, see `deps`.
+#[derive(Debug, Clone, Default)]
+pub struct DepNode {
+    pub name: String,
+    /// requirement declared by the parent, `None` for the crate looked up
+    pub req: Option<String>,
+    /// resolved version, `None` when not resolved
+    pub version: Option<String>,
+    /// `dev` or `build`, `None` for normal dependencies
+    pub kind: Option<String>,
+    pub optional: bool,
+    /// listed further up the tree, so its dependencies aren't repeated
+    pub repeated: bool,
+    pub dependencies: Vec<DepNode>,
+}
+
+impl DepNode {
+    /// Distinct crates below this one.
+    pub fn count(&self) -> usize {
+        let mut names = BTreeSet::new();
+        let mut stack = self.dependencies.iter().collect::<Vec<&DepNode>>();
+        while let Some(node) = stack.pop() {
+            names.insert(&node.name);
+            stack.extend(node.dependencies.iter());
+        }
+        names.len()
+    }
+
+    fn write(&self, f: &mut std::fmt::Formatter<'_>, depth: usize) -> std::fmt::Result {
+        // name req -> version (kind, optional) (*)
+        write!(f, "{:indent$}{}", "", self.name, indent = depth * 2)?;
+        match (&self.req, &self.version) {
+            (Some(req), Some(version)) => write!(f, " {} -> {}", req, version)?,
+            (Some(req), None) => write!(f, " {}", req)?,
+            (None, Some(version)) => write!(f, " {}", version)?,
+            (None, None) => {}
+        }
+        let tags = self
+            .kind
+            .iter()
+            .map(String::as_str)
+            .chain(self.optional.then_some("optional"))
+            .collect::<Vec<&str>>();
+        if !tags.is_empty() {
+            write!(f, " ({})", tags.join(", "))?;
+        }
+        if self.repeated {
+            write!(f, " (*)")?;
+        }
+        for dep in self.dependencies.iter() {
+            writeln!(f)?;
+            dep.write(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for DepNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write(f, 0)
+    }
+}
+
+/// Lists the dependencies of `name` at `version`, the latest one when
+/// unset, like `limp deps`. With `recursive` the normal and build
+/// dependencies that aren't optional are resolved to their newest matching
+/// version and listed in turn, each crate once.
+pub fn deps(
+    ctx: &Context,
+    name: &str,
+    version: Option<&str>,
+    recursive: bool,
+) -> Result<DepNode, LimpError> {
+    let krate = ctx.registry.get_crate(name)?;
+    let version = match version {
+        Some(v) => {
+            let req = match semver::Version::parse(v) {
+                Ok(_) => semver::VersionReq::parse(&format!("={}", v)),
+                Err(_) => semver::VersionReq::parse(v),
+            }
+            .map_err(|_| LimpError::InvalidVersion(v.to_string()))?;
+            krate
+                .find_matching(&req, true, None)
+                .ok_or_else(|| LimpError::VersionNotFound(format!("{}/{}", name, v)))?
+        }
+        None => krate.latest(ctx.registry.msrv.as_ref())?,
+    };
+    let mut root = DepNode {
+        name: name.to_string(),
+        version: Some(version.num),
+        ..Default::default()
+    };
+    let mut seen = HashSet::new();
+    resolve_deps(ctx, &mut root, recursive, &mut seen)?;
+    Ok(root)
+}
+
+fn resolve_deps(
+    ctx: &Context,
+    node: &mut DepNode,
+    recursive: bool,
+    seen: &mut HashSet<String>,
+) -> Result<(), LimpError> {
+    let Some(version) = node.version.clone() else {
+        return Ok(());
+    };
+    if !seen.insert(format!("{}@{}", node.name, version)) {
+        node.repeated = true;
+        return Ok(());
+    }
+    let is_root = node.req.is_none();
+    let registry = ctx.registry.default.as_deref();
+    for dep in ctx
+        .registry
+        .get_dependencies(&node.name, &version, registry)?
+    {
+        // dev-dependencies of dependencies are never built
+        if !is_root && dep.kind == "dev" {
+            continue;
+        }
+        let mut child = DepNode {
+            name: dep.name.clone(),
+            req: Some(dep.req.clone()),
+            kind: Some(dep.kind.clone()).filter(|k| !k.is_empty() && k != "normal"),
+            optional: dep.optional,
+            ..Default::default()
+        };
+        if recursive && !dep.optional && dep.kind != "dev" {
+            let req = semver::VersionReq::parse(&dep.req)
+                .map_err(|_| LimpError::InvalidVersion(format!("{} {}", dep.name, dep.req)))?;
+            child.version = ctx
+                .registry
+                .get_crate(&dep.name)?
+                .find_matching(&req, false, ctx.registry.msrv.as_ref())
+                .map(|v| v.num);
+            resolve_deps(ctx, &mut child, recursive, seen)?;
+        }
+        node.dependencies.push(child);
+    }
+    Ok(())
+}
+
+/// The running limp and its latest release, see `limp self version`.
+#[derive(Debug, Clone)]
+pub struct SelfVersion {
+    pub current: String,
+    pub latest: String,
+}
+
+impl SelfVersion {
+    pub fn is_outdated(&self) -> bool {
+        match (
+            semver::Version::parse(&self.current),
+            semver::Version::parse(&self.latest),
+        ) {
+            (Ok(current), Ok(latest)) => latest > current,
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for SelfVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_outdated() {
+            write!(f, "limp {} (latest: {})", self.current, self.latest)
+        } else {
+            write!(f, "limp {} (up to date)", self.current)
+        }
+    }
+}
+
+/// Looks the latest limp release up on crates.io.
+pub fn self_version(ctx: &Context) -> Result<SelfVersion, LimpError> {
+    let latest = ctx.registry.get_crate_from("limp", None)?.latest(None)?;
+    Ok(SelfVersion {
+        current: version::VERSION.to_string(),
+        latest: latest.num,
+    })
+}
+
+/// Updates limp like `limp self update --install`, with `cargo install` when
+/// a newer release is out. Returns the versions compared.
+pub fn self_update(ctx: &Context, install: bool) -> Result<SelfVersion, LimpError> {
+    let version = self_version(ctx)?;
+    if install && version.is_outdated() {
+        process::install("limp")?;
+    }
+    Ok(version)
+}
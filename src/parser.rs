@@ -1,96 +1,479 @@
-// #[derive(Debug, Clone, Default)]
-// pub struct Parser {
-//     path: String,
-//     imports: Option<String>,
-//     body: Option<String>,
-//     is_main: bool,
-// }
-//
-// impl std::fmt::Display for Parser {
-//     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-//         if let Some(imps) = &self.imports {
-//             write!(f, "{}", imps)?
-//         }
-//         if let Some(bd) = &self.body {
-//             write!(f, "{}", bd)?
-//         }
-//         write!(f, "")
-//     }
-// }
-//
-// impl Parser {
-//     pub fn from_file(path: &str) -> Self {
-//         use std::io::{BufRead, BufReader};
-//
-//         let fm = FileManager::default();
-//         let file = fm.copen(path);
-//         let rr = BufReader::new(file);
-//
-//         let mut imports = vec![];
-//         let mut body = vec![];
-//         let mut is_main = false;
-//
-//         let mut found_code = false;
-//         let mut in_imp_block = false;
-//
-//         rr.lines().for_each(|l| {
-//             if let Ok(l) = l {
-//                 let tl = l.trim();
-//
-//                 if found_code {
-//                     body.push(l.clone())
-//                 } else if in_imp_block {
-//                     imports.push(l.clone());
-//                     if tl.ends_with("};") {
-//                         in_imp_block = false;
-//                     }
-//                 } else if tl.starts_with("use") {
-//                     imports.push(l.clone());
-//                     if tl.ends_with('{') {
-//                         in_imp_block = true;
-//                     }
-//                 } else {
-//                     if l.contains("main") {
-//                         is_main = true;
-//                     }
-//                     body.push(l.clone());
-//                     found_code = true;
-//                 }
-//             }
-//         });
-//
-//         Parser {
-//             path: path.to_string(),
-//             imports: if imports.is_empty() {
-//                 None
-//             } else {
-//                 Some(imports.join("\n"))
-//             },
-//             body: if body.is_empty() {
-//                 None
-//             } else {
-//                 Some(body.join("\n"))
-//             },
-//             is_main,
-//         }
-//     }
-//     pub fn default_save(&self) -> std::io::Result<()> {
-//         let fm = FileManager::default();
-//         let snip_dir = fm.storage_dir();
-//         println!("{}", &snip_dir);
-//         println!("{}", &self.path);
-//         Ok(())
-//     }
-// }
-//
-// #[cfg(test)]
-// mod test {
-//     use super::Parser;
-//
-//     #[test]
-//     fn default_save_test() {
-//         let p = Parser::from_file("./src/parser.rs");
-//         p.default_save().unwrap();
-//         println!("{p}");
-//     }
-// }
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{error::LimpError, files, snippets, storage::JsonDependency};
+
+pub mod ast;
+
+/// Starts a line that pulls another snippet from the snippets directory into
+/// this one, e.g. `// limp:include logging`.
+pub const INCLUDE: &str = "// limp:include";
+
+/// A stored snippet split into its `use` items and the code around them.
+#[derive(Debug, Clone, Default)]
+pub struct SnippetEntity {
+    pub path: PathBuf,
+    pub imports: Vec<String>,
+    pub body: Vec<String>,
+    pub is_main: bool,
+}
+
+impl std::fmt::Display for SnippetEntity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.imports.is_empty() {
+            writeln!(f, "{}", self.imports.join("\n"))?;
+            if !self.body.is_empty() {
+                writeln!(f)?;
+            }
+        }
+        if !self.body.is_empty() {
+            writeln!(f, "{}", self.body.join("\n"))?;
+        }
+        Ok(())
+    }
+}
+
+impl SnippetEntity {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, LimpError> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Err(LimpError::SnippetNotFound(path.display().to_string()));
+        }
+        Ok(Self::parse(path, &fs::read_to_string(path)?))
+    }
+
+    /// Reads the snippet at `path` with its [`INCLUDE`] lines replaced by the
+    /// snippets they name in `dir`, recursively. A snippet included more than
+    /// once is only pulled in the first time, and cycles are an error.
+    pub fn load<P: AsRef<Path>, D: AsRef<Path>>(path: P, dir: D) -> Result<Self, LimpError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).ok();
+        let expanded = expand_includes(path, dir.as_ref(), &mut vec![], &mut HashSet::new())?;
+        let mut snippet = Self::parse(path, &expanded);
+        if content.as_deref() != Some(expanded.as_str()) {
+            snippet.imports = merge_imports(snippet.imports);
+        }
+        Ok(snippet)
+    }
+
+    /// Splits `content` along its syntax tree, falling back to a line based
+    /// split for snippets that aren't valid Rust on their own.
+    pub fn parse<P: AsRef<Path>>(path: P, content: &str) -> Self {
+        let Some(items) = ast::items(content) else {
+            return Self::parse_lines(path, content);
+        };
+        let mut imports = vec![];
+        let mut body = String::new();
+        let mut is_main = false;
+        for item in items {
+            match item.kind {
+                ast::ItemKind::Import => {
+                    let leading = item.leading.trim();
+                    if !leading.is_empty() {
+                        imports.extend(leading.lines().map(String::from));
+                    }
+                    imports.extend(item.text.lines().map(String::from));
+                }
+                kind => {
+                    is_main |= kind == ast::ItemKind::Main;
+                    if body.is_empty() {
+                        body.push_str(item.leading.trim_start());
+                    } else {
+                        body.push_str(&item.leading);
+                    }
+                    body.push_str(&item.text);
+                }
+            }
+        }
+        let mut body = body.lines().map(String::from).collect::<Vec<String>>();
+        while body.last().is_some_and(|l| l.trim().is_empty()) {
+            body.pop();
+        }
+
+        Self {
+            path: path.as_ref().to_path_buf(),
+            imports,
+            body,
+            is_main,
+        }
+    }
+
+    fn parse_lines<P: AsRef<Path>>(path: P, content: &str) -> Self {
+        let mut imports = vec![];
+        let mut body = vec![];
+        let mut is_main = false;
+
+        let mut found_code = false;
+        let mut in_imp_block = false;
+
+        for l in content.lines() {
+            let tl = l.trim();
+
+            if found_code {
+                body.push(l.to_string())
+            } else if in_imp_block {
+                imports.push(l.to_string());
+                if tl.ends_with("};") {
+                    in_imp_block = false;
+                }
+            } else if tl.starts_with("use ") || tl.starts_with("pub use ") {
+                imports.push(l.to_string());
+                if tl.ends_with('{') {
+                    in_imp_block = true;
+                }
+            } else if !tl.is_empty() {
+                body.push(l.to_string());
+                found_code = true;
+            }
+
+            if found_code && tl.contains("fn main") {
+                is_main = true;
+            }
+        }
+        while body.last().is_some_and(|l| l.trim().is_empty()) {
+            body.pop();
+        }
+
+        Self {
+            path: path.as_ref().to_path_buf(),
+            imports,
+            body,
+            is_main,
+        }
+    }
+
+    /// Concatenates the bodies of all snippets, in order, under their merged
+    /// imports. When several snippets have a `main`, their statements are
+    /// combined into a single one at the end, which fails if the mains have
+    /// different signatures or return different values.
+    pub fn merge(snippets: &[SnippetEntity]) -> Result<Self, LimpError> {
+        let mut merged = Self::default();
+        let split_mains = snippets.iter().filter(|s| s.is_main).count() > 1;
+        let mut mains = vec![];
+        for snippet in snippets {
+            merged.imports.extend(snippet.imports.iter().cloned());
+            let mut body = snippet.body.clone();
+            if split_mains && snippet.is_main {
+                let name = snippet.path.display().to_string();
+                let main = ast::main_fn(&body.join("\n"))
+                    .ok_or_else(|| LimpError::SnippetConflict(name.clone()))?;
+                body = format!("{}{}", main.before.trim_end(), main.after)
+                    .trim()
+                    .lines()
+                    .map(String::from)
+                    .collect();
+                mains.push((name, main));
+            }
+            if !merged.body.is_empty() && !body.is_empty() {
+                merged.body.push(String::new());
+            }
+            merged.body.extend(body);
+            merged.is_main |= snippet.is_main;
+        }
+        if !mains.is_empty() {
+            if !merged.body.is_empty() {
+                merged.body.push(String::new());
+            }
+            merged
+                .body
+                .extend(merge_mains(&mains)?.lines().map(String::from));
+        }
+        merged.imports = merge_imports(merged.imports);
+        Ok(merged)
+    }
+}
+
+fn expand_includes(
+    path: &Path,
+    dir: &Path,
+    stack: &mut Vec<PathBuf>,
+    seen: &mut HashSet<PathBuf>,
+) -> Result<String, LimpError> {
+    if !path.is_file() {
+        return Err(LimpError::SnippetNotFound(path.display().to_string()));
+    }
+    let key = fs::canonicalize(path)?;
+    if stack.contains(&key) {
+        let chain = stack
+            .iter()
+            .chain([&key])
+            .map(|p| p.file_stem().unwrap_or_default().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" -> ");
+        return Err(LimpError::IncludeCycle(chain));
+    }
+    if !seen.insert(key.clone()) {
+        return Ok(String::new());
+    }
+    stack.push(key);
+    let mut expanded = String::new();
+    for line in fs::read_to_string(path)?.lines() {
+        match line.trim().strip_prefix(INCLUDE).map(str::trim) {
+            Some(name) if !name.is_empty() => {
+                expanded.push_str(&expand_includes(
+                    &snippets::path(dir, name),
+                    dir,
+                    stack,
+                    seen,
+                )?);
+            }
+            _ => {
+                expanded.push_str(line);
+                expanded.push('\n');
+            }
+        }
+    }
+    stack.pop();
+    Ok(expanded)
+}
+
+/// Builds one `main` out of the statements of `mains`, which are named after
+/// their snippet for error messages.
+fn merge_mains(mains: &[(String, ast::MainFn)]) -> Result<String, LimpError> {
+    let (first, main) = &mains[0];
+    let normalize = |s: &str| s.split_whitespace().collect::<String>();
+    for (name, other) in mains.iter().skip(1) {
+        if normalize(&other.signature) != normalize(&main.signature) {
+            return Err(LimpError::SnippetConflict(format!(
+                "{} and {} have different main signatures",
+                first, name
+            )));
+        }
+        if other.tail != main.tail {
+            return Err(LimpError::SnippetConflict(format!(
+                "{} and {} return different values from main",
+                first, name
+            )));
+        }
+    }
+    let mut merged = format!("{} {{\n", main.signature);
+    let statements = mains
+        .iter()
+        .map(|(_, m)| m.statements.as_str())
+        .filter(|s| !s.trim().is_empty())
+        .collect::<Vec<&str>>();
+    merged.push_str(&statements.join("\n\n"));
+    if let Some(tail) = &main.tail {
+        merged.push_str(&format!("\n    {}", tail));
+    }
+    merged.push_str("\n}");
+    Ok(merged)
+}
+
+/// Dedupes and groups `use` items, or only drops repeated lines when they
+/// can't be parsed.
+fn merge_imports(imports: Vec<String>) -> Vec<String> {
+    if let Some(merged) = ast::merge_imports(&imports.join("\n")) {
+        return merged;
+    }
+    let mut seen = HashSet::new();
+    imports
+        .into_iter()
+        .filter(|i| i.trim().is_empty() || seen.insert(i.clone()))
+        .collect()
+}
+
+/// What [`inject`] changed in an existing project.
+#[derive(Debug, Clone, Default)]
+pub struct Injection {
+    pub file: PathBuf,
+    pub imports: Vec<String>,
+    pub body_lines: usize,
+    pub module: Option<String>,
+}
+
+impl std::fmt::Display for Injection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.module {
+            Some(module) => write!(
+                f,
+                "created module {} in {} ({} imports, {} lines)",
+                module,
+                self.file.display(),
+                self.imports.len(),
+                self.body_lines
+            ),
+            None => write!(
+                f,
+                "inserted {} imports and {} lines into {}",
+                self.imports.len(),
+                self.body_lines,
+                self.file.display()
+            ),
+        }
+    }
+}
+
+/// Injects a snippet into the crate rooted at `entry` (`src/main.rs` or
+/// `src/lib.rs`). With a module name the snippet becomes `src/<module>.rs`
+/// declared from the entry file, or is merged into that file when it exists;
+/// otherwise its missing imports are merged into the entry file and its body
+/// appended.
+pub fn inject(
+    snippet: &SnippetEntity,
+    entry: &Path,
+    module: Option<&str>,
+) -> Result<Injection, LimpError> {
+    let content = fs::read_to_string(entry)?;
+    let mut existing = SnippetEntity::parse(entry, &content);
+
+    if let Some(module) = module {
+        let file = entry.with_file_name(format!("{}.rs", module));
+        let injection = if file.exists() {
+            inject(snippet, &file, None)?
+        } else {
+            fs::write(&file, snippet.to_string())?;
+            Injection {
+                file,
+                imports: snippet.imports.clone(),
+                body_lines: snippet.body.len(),
+                module: None,
+            }
+        };
+        if !declares_module(&content, module) {
+            fs::write(entry, format!("mod {};\n{}", module, content))?;
+        }
+        return Ok(Injection {
+            module: Some(module.to_string()),
+            ..injection
+        });
+    }
+
+    if snippet.is_main && existing.is_main {
+        return Err(LimpError::SnippetConflict(format!(
+            "{} already has a main function, use a module instead",
+            entry.display()
+        )));
+    }
+    let imports = snippet
+        .imports
+        .iter()
+        .filter(|i| !existing.imports.contains(i))
+        .cloned()
+        .collect::<Vec<String>>();
+    existing.imports.extend(imports.iter().cloned());
+    if !existing.body.is_empty() && !snippet.body.is_empty() {
+        existing.body.push(String::new());
+    }
+    existing.body.extend(snippet.body.iter().cloned());
+    fs::write(entry, existing.to_string())?;
+
+    Ok(Injection {
+        file: entry.to_path_buf(),
+        imports,
+        body_lines: snippet.body.len(),
+        module: None,
+    })
+}
+
+/// Whether `content` has a `mod <module>;` item, with any visibility.
+fn declares_module(content: &str, module: &str) -> bool {
+    let declaration = format!("mod {};", module);
+    content.lines().any(|l| {
+        let l = l.trim();
+        let l = match l.strip_prefix("pub") {
+            Some(rest) if rest.starts_with('(') => rest.split_once(')').map_or(l, |(_, r)| r),
+            Some(rest) => rest,
+            None => l,
+        };
+        l.trim_start() == declaration
+    })
+}
+
+/// Merges the snippets linked to `deps` and then the `standalone` ones, with
+/// their includes resolved from `dir`, or returns `None` when there are none.
+pub fn load_from_deps<P: AsRef<Path>>(
+    deps: &[JsonDependency],
+    standalone: &[P],
+    dir: &Path,
+) -> Result<Option<SnippetEntity>, LimpError> {
+    let snippets = deps
+        .iter()
+        .filter_map(|d| d.snippet().map(Path::new))
+        .chain(standalone.iter().map(AsRef::as_ref))
+        .map(|path| SnippetEntity::load(path, dir))
+        .collect::<Result<Vec<SnippetEntity>, LimpError>>()?;
+    if snippets.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(SnippetEntity::merge(&snippets)?))
+}
+
+/// User-defined template variables, rendered on top of the built-in
+/// `project_name`, `crate_name` and `author`.
+#[derive(Debug, Clone, Default)]
+pub struct Vars {
+    user: HashMap<String, String>,
+    author: Option<String>,
+}
+
+impl Vars {
+    pub fn from_pairs(pairs: &[(String, String)]) -> Self {
+        Self {
+            user: pairs.iter().cloned().collect(),
+            author: None,
+        }
+    }
+
+    /// Overrides the built-in `author`, which defaults to the current user.
+    pub fn with_author(mut self, author: Option<String>) -> Self {
+        self.author = author;
+        self
+    }
+
+    /// Parses a `key=value` assignment as passed to `--var`.
+    pub fn parse_assignment(s: &str) -> Result<(String, String), LimpError> {
+        match s.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => {
+                Ok((key.trim().to_string(), value.to_string()))
+            }
+            _ => Err(LimpError::InvalidVariable(s.to_string())),
+        }
+    }
+
+    pub fn values(&self, project_name: &str) -> HashMap<String, String> {
+        let mut values = HashMap::from([
+            ("project_name".to_string(), project_name.to_string()),
+            ("crate_name".to_string(), project_name.replace('-', "_")),
+            (
+                "author".to_string(),
+                self.author.clone().unwrap_or_else(files::username),
+            ),
+        ]);
+        values.extend(self.user.clone());
+        values
+    }
+
+    pub fn render(&self, project_name: &str, text: &str) -> String {
+        substitute(text, &self.values(project_name))
+    }
+}
+
+/// Replaces `{{ key }}` placeholders with their values. Unknown placeholders are
+/// left untouched.
+pub fn substitute(text: &str, values: &HashMap<String, String>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match after.find("}}") {
+            Some(end) => {
+                match values.get(after[..end].trim()) {
+                    Some(value) => result.push_str(value),
+                    None => result.push_str(&rest[start..start + 2 + end + 2]),
+                }
+                rest = &after[end + 2..];
+            }
+            None => {
+                result.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
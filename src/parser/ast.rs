@@ -0,0 +1,240 @@
+use std::collections::BTreeMap;
+
+use proc_macro2::LineColumn;
+use syn::spanned::Spanned;
+
+/// What a top-level item of a snippet is, as far as merging is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemKind {
+    Import,
+    Main,
+    Other,
+}
+
+/// A top-level item with its source text, including attributes, doc comments
+/// and the plain comments right above it.
+#[derive(Debug, Clone)]
+pub struct Item {
+    pub kind: ItemKind,
+    pub text: String,
+    /// whitespace and comments between the previous item and this one
+    pub leading: String,
+}
+
+/// Splits `content` into its top-level items, or returns `None` when it isn't
+/// a valid Rust file.
+pub fn items(content: &str) -> Option<Vec<Item>> {
+    let file = syn::parse_file(content).ok()?;
+    let lines = line_offsets(content);
+    let mut prev_end = file
+        .attrs
+        .last()
+        .map(|a| offset(content, &lines, a.span().end()))
+        .unwrap_or_default();
+    let mut items = vec![];
+    for item in file.items.iter() {
+        let span = item.span();
+        let start = offset(content, &lines, span.start());
+        let end = offset(content, &lines, span.end());
+        items.push(Item {
+            kind: match item {
+                syn::Item::Use(_) => ItemKind::Import,
+                syn::Item::Fn(f) if f.sig.ident == "main" => ItemKind::Main,
+                _ => ItemKind::Other,
+            },
+            text: content[start..end].to_string(),
+            leading: content[prev_end.min(start)..start].to_string(),
+        });
+        prev_end = end;
+    }
+    // a trailing comment is kept with the last item
+    let trailing = content[prev_end..].trim_end();
+    if let Some(last) = items.last_mut() {
+        last.text.push_str(trailing);
+    }
+    Some(items)
+}
+
+/// A `fn main` cut out of a snippet.
+#[derive(Debug, Clone)]
+pub struct MainFn {
+    /// attributes and signature, e.g. `#[tokio::main]\nasync fn main()`
+    pub signature: String,
+    pub statements: String,
+    /// the trailing expression such as `Ok(())`, if any
+    pub tail: Option<String>,
+    /// the code around the function
+    pub before: String,
+    pub after: String,
+}
+
+/// Finds the top-level `fn main` in `content` and splits it up.
+pub fn main_fn(content: &str) -> Option<MainFn> {
+    let file = syn::parse_file(content).ok()?;
+    let lines = line_offsets(content);
+    let at = |lc: LineColumn| offset(content, &lines, lc);
+    let main = file.items.iter().find_map(|item| match item {
+        syn::Item::Fn(f) if f.sig.ident == "main" => Some(f),
+        _ => None,
+    })?;
+    let start = at(main.span().start());
+    let end = at(main.span().end());
+    let braces = main.block.brace_token.span;
+    let open = at(braces.open().end());
+    let close = at(braces.close().start());
+    let (statements_end, tail) = match main.block.stmts.last() {
+        Some(syn::Stmt::Expr(expr, None)) => {
+            let tail_start = at(expr.span().start());
+            let tail = content[tail_start..at(expr.span().end())].to_string();
+            (tail_start, Some(tail))
+        }
+        _ => (close, None),
+    };
+    let statements = content[open..statements_end].trim_matches('\n').trim_end();
+    let statements = if content[open..close].contains('\n') {
+        statements.to_string()
+    } else {
+        format!("    {}", statements.trim())
+    };
+    Some(MainFn {
+        signature: content[start..at(braces.open().start())]
+            .trim_end()
+            .to_string(),
+        statements,
+        tail,
+        before: content[..start].to_string(),
+        after: content[end..].to_string(),
+    })
+}
+
+/// Merges `use` items, dropping duplicate paths and grouping the rest by their
+/// root, e.g. `use std::{fs, io::Read};`. Imports only merge with imports
+/// carrying the same attributes and visibility. Returns `None` when `imports`
+/// isn't made of `use` items only.
+pub fn merge_imports(imports: &str) -> Option<Vec<String>> {
+    let file = syn::parse_file(imports).ok()?;
+    let lines = line_offsets(imports);
+    let text = |span: proc_macro2::Span| {
+        &imports[offset(imports, &lines, span.start())..offset(imports, &lines, span.end())]
+    };
+    let mut groups: Vec<(String, UseTrie)> = vec![];
+    for item in file.items.iter() {
+        let syn::Item::Use(item) = item else {
+            return None;
+        };
+        let mut prefix = String::new();
+        for attr in item.attrs.iter() {
+            prefix.push_str(text(attr.span()));
+            prefix.push('\n');
+        }
+        if !matches!(item.vis, syn::Visibility::Inherited) {
+            prefix.push_str(text(item.vis.span()));
+            prefix.push(' ');
+        }
+        let index = match groups.iter().position(|(p, _)| *p == prefix) {
+            Some(index) => index,
+            None => {
+                groups.push((prefix, UseTrie::default()));
+                groups.len() - 1
+            }
+        };
+        let root = if item.leading_colon.is_some() {
+            "::"
+        } else {
+            ""
+        };
+        groups[index].1.insert(&item.tree, root);
+    }
+    Some(
+        groups
+            .into_iter()
+            .flat_map(|(prefix, trie)| {
+                trie.children
+                    .into_iter()
+                    .map(move |(name, node)| format!("{}use {};", prefix, node.render(name)))
+            })
+            .collect(),
+    )
+}
+
+/// Imported paths sharing their leading segments.
+#[derive(Debug, Default)]
+struct UseTrie {
+    /// the path up to here is imported itself
+    leaf: bool,
+    children: BTreeMap<String, UseTrie>,
+}
+
+impl UseTrie {
+    fn insert(&mut self, tree: &syn::UseTree, prefix: &str) {
+        match tree {
+            syn::UseTree::Path(path) => self
+                .children
+                .entry(format!("{}{}", prefix, path.ident))
+                .or_default()
+                .insert(&path.tree, ""),
+            syn::UseTree::Name(name) if name.ident == "self" => self.leaf = true,
+            syn::UseTree::Name(name) => {
+                self.children
+                    .entry(format!("{}{}", prefix, name.ident))
+                    .or_default()
+                    .leaf = true
+            }
+            syn::UseTree::Rename(rename) => {
+                self.children
+                    .entry(format!("{}{} as {}", prefix, rename.ident, rename.rename))
+                    .or_default()
+                    .leaf = true
+            }
+            syn::UseTree::Glob(_) => {
+                self.children
+                    .entry(format!("{}*", prefix))
+                    .or_default()
+                    .leaf = true
+            }
+            syn::UseTree::Group(group) => {
+                for tree in group.items.iter() {
+                    self.insert(tree, prefix);
+                }
+            }
+        }
+    }
+
+    fn render(self, name: String) -> String {
+        if self.children.is_empty() {
+            return name;
+        }
+        if self.children.len() == 1 && !self.leaf {
+            let (child, node) = self.children.into_iter().next().unwrap();
+            return format!("{}::{}", name, node.render(child));
+        }
+        let mut entries = vec![];
+        if self.leaf {
+            entries.push("self".to_string());
+        }
+        entries.extend(
+            self.children
+                .into_iter()
+                .map(|(child, node)| node.render(child)),
+        );
+        format!("{}::{{{}}}", name, entries.join(", "))
+    }
+}
+
+fn line_offsets(content: &str) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+        .collect()
+}
+
+/// Byte offset of a 1-based line and 0-based char column.
+fn offset(content: &str, lines: &[usize], at: LineColumn) -> usize {
+    let Some(&line_start) = lines.get(at.line.saturating_sub(1)) else {
+        return content.len();
+    };
+    content[line_start..]
+        .char_indices()
+        .nth(at.column)
+        .map(|(i, _)| line_start + i)
+        .unwrap_or(content.len())
+}
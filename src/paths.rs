@@ -0,0 +1,102 @@
+//! Snippet locations as kept in the storage. Snippets inside the storage
+//! directory are stored relative to it with `/` separators, so a storage
+//! works after being copied to another machine or OS.
+
+use std::path::{Path, PathBuf};
+
+/// Directory of the storage that snippets are kept in.
+const SNIPPETS_DIR: &str = "snippets";
+
+/// Longest path the Windows file APIs accept without the `\\?\` prefix.
+const MAX_PATH: usize = 260;
+
+/// `path` without the `\\?\` prefix Windows puts in front of canonical and
+/// long paths, with `\\?\UNC\server\share` becoming `\\server\share`.
+pub fn normalize<P: AsRef<Path>>(path: P) -> PathBuf {
+    let path = path.as_ref();
+    let s = path.to_string_lossy();
+    if let Some(unc) = s.strip_prefix(r"\\?\UNC\") {
+        return PathBuf::from(format!(r"\\{}", unc));
+    }
+    if let Some(local) = s.strip_prefix(r"\\?\") {
+        return PathBuf::from(local);
+    }
+    path.to_path_buf()
+}
+
+/// `path` with the `\\?\` prefix when it is too long to be opened on Windows
+/// otherwise. Elsewhere, and for short paths, it is returned as it is.
+pub fn long<P: AsRef<Path>>(path: P) -> PathBuf {
+    let path = normalize(path);
+    if !cfg!(windows) || !path.is_absolute() || path.as_os_str().len() < MAX_PATH {
+        return path;
+    }
+    let s = path.to_string_lossy();
+    match s.strip_prefix(r"\\") {
+        Some(unc) => PathBuf::from(format!(r"\\?\UNC\{}", unc)),
+        None => PathBuf::from(format!(r"\\?\{}", s)),
+    }
+}
+
+/// Whether `stored` is an absolute path of another OS, like `C:\...` or
+/// `\\server\share` on Unix and `/home/...` on Windows.
+fn is_foreign(stored: &str) -> bool {
+    let drive = stored.len() >= 2
+        && stored.as_bytes()[0].is_ascii_alphabetic()
+        && stored.as_bytes()[1] == b':';
+    !Path::new(stored).is_absolute()
+        && (drive || stored.starts_with('/') || stored.starts_with('\\'))
+}
+
+/// `path` joined onto the current directory when it is relative.
+fn absolute(path: PathBuf) -> PathBuf {
+    if path.is_absolute() || is_foreign(&path.to_string_lossy()) {
+        return path;
+    }
+    match std::env::current_dir() {
+        Ok(cwd) => cwd.join(path),
+        Err(_) => path,
+    }
+}
+
+fn push<'a>(root: &Path, parts: impl IntoIterator<Item = &'a str>) -> PathBuf {
+    let mut path = root.to_path_buf();
+    for part in parts.into_iter().filter(|p| !p.is_empty() && *p != ".") {
+        path.push(part);
+    }
+    path
+}
+
+/// How the snippet at `path` is stored for the storage directory `root`:
+/// relative to it with `/` separators when it is inside, so the storage can
+/// be copied to another machine or OS, and absolute otherwise.
+pub fn to_stored<R: AsRef<Path>, P: AsRef<Path>>(root: R, path: P) -> String {
+    let root = absolute(normalize(root));
+    let path = absolute(normalize(path));
+    match path.strip_prefix(&root) {
+        Ok(relative) => relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+        Err(_) => path.to_string_lossy().to_string(),
+    }
+}
+
+/// The snippet path `stored` stands for in the storage directory `root`.
+/// Absolute paths written on another OS are looked up by their part after
+/// the `snippets` directory, and kept as they are if they have none.
+pub fn from_stored<R: AsRef<Path>>(root: R, stored: &str) -> PathBuf {
+    let root = root.as_ref();
+    if Path::new(stored).is_absolute() {
+        return long(stored);
+    }
+    if is_foreign(stored) {
+        let parts = stored.split(['/', '\\']).collect::<Vec<&str>>();
+        return match parts.iter().rposition(|p| *p == SNIPPETS_DIR) {
+            Some(i) => long(push(root, parts[i..].iter().copied())),
+            None => PathBuf::from(stored),
+        };
+    }
+    long(push(root, stored.split('/')))
+}
@@ -0,0 +1,175 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use serde::Deserialize;
+
+use crate::{error::LimpError, files};
+
+/// A compiler error reported by cargo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub message: String,
+    /// relative to the crate root
+    pub file: Option<PathBuf>,
+    pub line: Option<usize>,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(f, "{}:{}: {}", file.display(), line, self.message),
+            _ => write!(f, "{}", self.message),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CargoMessage {
+    reason: String,
+    message: Option<CompilerMessage>,
+}
+
+#[derive(Deserialize)]
+struct CompilerMessage {
+    level: String,
+    message: String,
+    #[serde(default)]
+    spans: Vec<CompilerSpan>,
+}
+
+#[derive(Deserialize)]
+struct CompilerSpan {
+    file_name: PathBuf,
+    line_start: usize,
+    is_primary: bool,
+}
+
+/// Runs `cargo <subcommand>` in `dir` and returns the errors it reported,
+/// which is empty when the build succeeded.
+pub fn cargo<P: AsRef<Path>>(dir: P, subcommand: &str) -> Result<Vec<Diagnostic>, LimpError> {
+    let output = Command::new("cargo")
+        .args([subcommand, "--message-format=json"])
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| LimpError::CargoFailed(e.to_string()))?;
+    let diagnostics = parse_messages(&String::from_utf8_lossy(&output.stdout));
+    if !output.status.success() && diagnostics.is_empty() {
+        return Err(LimpError::CargoFailed(format!(
+            "cargo {} exited with {}",
+            subcommand, output.status
+        )));
+    }
+    Ok(diagnostics)
+}
+
+/// Runs `rustfmt` on the Rust files under `dir`, leaving out `target`.
+pub fn rustfmt<P: AsRef<Path>>(dir: P, edition: &str) -> Result<(), LimpError> {
+    let dir = dir.as_ref();
+    let sources = files::walk_files(dir, &["target", ".git"])?
+        .into_iter()
+        .filter(|f| f.extension().is_some_and(|e| e == "rs"))
+        .collect::<Vec<PathBuf>>();
+    if sources.is_empty() {
+        return Ok(());
+    }
+    let output = Command::new("rustfmt")
+        .args(["--edition", edition])
+        .args(&sources)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => LimpError::FormatFailed(
+                "not found, install it with `rustup component add rustfmt`".to_string(),
+            ),
+            _ => LimpError::FormatFailed(e.to_string()),
+        })?;
+    if !output.status.success() {
+        return Err(LimpError::FormatFailed(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// The value of `key` in the git configuration, `None` when it is unset or
+/// git can't be run.
+pub fn git_config(key: &str) -> Option<String> {
+    let output = Command::new("git")
+        .args(["config", "--get", key])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !value.is_empty()).then_some(value)
+}
+
+/// Runs `cargo install <name> --locked`, showing cargo's output.
+pub fn install(name: &str) -> Result<(), LimpError> {
+    let status = Command::new("cargo")
+        .args(["install", name, "--locked"])
+        .stdin(Stdio::null())
+        .status()
+        .map_err(|e| LimpError::CargoFailed(e.to_string()))?;
+    if !status.success() {
+        return Err(LimpError::CargoFailed(format!(
+            "cargo install {} exited with {}",
+            name, status
+        )));
+    }
+    Ok(())
+}
+
+/// Collects the errors from cargo's `--message-format=json` output.
+pub fn parse_messages(output: &str) -> Vec<Diagnostic> {
+    output
+        .lines()
+        .filter_map(|l| serde_json::from_str::<CargoMessage>(l).ok())
+        .filter(|m| m.reason == "compiler-message")
+        .filter_map(|m| m.message)
+        .filter(|m| m.level == "error")
+        .map(|m| {
+            let span = m.spans.iter().find(|s| s.is_primary);
+            Diagnostic {
+                message: m.message,
+                file: span.map(|s| s.file_name.clone()),
+                line: span.map(|s| s.line_start),
+            }
+        })
+        .collect()
+}
+
+/// The snippets among `snippets` containing the line `diagnostic` points at in
+/// the crate at `dir`. Imports may have been regrouped while merging, so
+/// errors in them aren't always traced back.
+pub fn blame<P: AsRef<Path>>(
+    dir: P,
+    diagnostic: &Diagnostic,
+    snippets: &[PathBuf],
+) -> Vec<PathBuf> {
+    let (Some(file), Some(line)) = (&diagnostic.file, diagnostic.line) else {
+        return vec![];
+    };
+    let Some(text) = fs::read_to_string(dir.as_ref().join(file))
+        .ok()
+        .and_then(|c| {
+            c.lines()
+                .nth(line.saturating_sub(1))
+                .map(|l| l.trim().to_string())
+        })
+        .filter(|l| !l.is_empty())
+    else {
+        return vec![];
+    };
+    snippets
+        .iter()
+        .filter(|s| fs::read_to_string(s).is_ok_and(|c| c.lines().any(|l| l.trim() == text)))
+        .cloned()
+        .collect()
+}
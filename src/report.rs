@@ -0,0 +1,469 @@
+use std::{
+    io::{IsTerminal, Write},
+    sync::Mutex,
+    time::Instant,
+};
+
+use serde_json::json;
+
+use crate::{
+    crates::{Crate, CratesIoDependency},
+    error::LimpError,
+    storage::JsonDependency,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Text,
+    Table,
+    Json,
+}
+
+impl Format {
+    pub const NAMES: [&'static str; 3] = ["text", "table", "json"];
+
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "json" => Format::Json,
+            "table" => Format::Table,
+            _ => Format::Text,
+        }
+    }
+    pub fn reporter(&self, style: Style) -> Box<dyn Reporter> {
+        match self {
+            Format::Text => Box::new(TextReporter { style }),
+            Format::Table => Box::new(TableReporter { style }),
+            Format::Json => Box::new(JsonReporter),
+        }
+    }
+}
+
+/// Colors of the text output. Off with `--no-color`, when `NO_COLOR` is set,
+/// or when stdout isn't a terminal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Style {
+    pub enabled: bool,
+}
+
+impl Style {
+    pub fn detect(no_color: bool) -> Self {
+        let no_color = no_color || std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        Self {
+            enabled: !no_color && std::io::stdout().is_terminal(),
+        }
+    }
+
+    fn paint(&self, code: &str, text: &str) -> String {
+        if self.enabled {
+            format!("\x1b[{}m{}\x1b[0m", code, text)
+        } else {
+            text.to_string()
+        }
+    }
+    pub fn bold(&self, text: &str) -> String {
+        self.paint("1", text)
+    }
+    /// for metadata like indices and `(pinned)`
+    pub fn dim(&self, text: &str) -> String {
+        self.paint("2", text)
+    }
+    pub fn warning(&self, text: &str) -> String {
+        self.paint("1;33", text)
+    }
+    pub fn error(&self, text: &str) -> String {
+        self.paint("1;31", text)
+    }
+
+    /// `name = value` with the name in bold.
+    fn entry(&self, text: &str) -> String {
+        match text.split_once(" = ") {
+            Some((name, value)) => format!("{} = {}", self.bold(name), value),
+            None => self.bold(text),
+        }
+    }
+}
+
+/// How much limp prints about long running steps, from `-q`, `-v` and `-vv`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// nothing, the default for library use
+    #[default]
+    Quiet,
+    /// a progress bar, or a line per item when stderr isn't a terminal
+    Normal,
+    /// a line per item next to the progress bar
+    Verbose,
+    /// also the time each item finished at
+    Debug,
+}
+
+impl Verbosity {
+    pub fn from_flags(quiet: bool, verbose: u8) -> Self {
+        match (quiet, verbose) {
+            (true, _) => Verbosity::Quiet,
+            (false, 0) => Verbosity::Normal,
+            (false, 1) => Verbosity::Verbose,
+            (false, _) => Verbosity::Debug,
+        }
+    }
+}
+
+const BAR_WIDTH: usize = 24;
+
+/// Progress over the items of a network bound step, e.g. the dependencies
+/// `limp update` looks up. Drawn on stderr so it doesn't mix with the output
+/// of `--format json`, and cleared when dropped.
+#[derive(Debug)]
+pub struct Progress {
+    label: String,
+    total: usize,
+    done: Mutex<usize>,
+    verbosity: Verbosity,
+    tty: bool,
+    start: Instant,
+}
+
+impl Progress {
+    pub fn new(label: &str, total: usize, verbosity: Verbosity) -> Self {
+        Self {
+            label: label.to_string(),
+            total,
+            done: Mutex::new(0),
+            verbosity,
+            tty: std::io::stderr().is_terminal(),
+            start: Instant::now(),
+        }
+    }
+
+    /// Marks `item` as done. Safe to call from worker threads.
+    pub fn step(&self, item: &str) {
+        let mut done = self.done.lock().unwrap();
+        *done += 1;
+        if self.verbosity == Verbosity::Quiet {
+            return;
+        }
+        let mut stderr = std::io::stderr().lock();
+        let mut line = format!("{} {} ({}/{})", self.label, item, done, self.total);
+        if self.verbosity >= Verbosity::Debug {
+            line.push_str(&format!(" {}ms", self.start.elapsed().as_millis()));
+        }
+        if !self.tty {
+            let _ = writeln!(stderr, "{}", line);
+            return;
+        }
+        if self.verbosity >= Verbosity::Verbose {
+            let _ = writeln!(stderr, "\r\x1b[K{}", line);
+        }
+        let filled = BAR_WIDTH * *done / self.total.max(1);
+        let _ = write!(
+            stderr,
+            "\r\x1b[K{} [{}{}] {}/{} {}",
+            self.label,
+            "#".repeat(filled),
+            "-".repeat(BAR_WIDTH - filled),
+            done,
+            self.total,
+            item
+        );
+        let _ = stderr.flush();
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        let done = *self.done.lock().unwrap();
+        if self.tty && self.verbosity > Verbosity::Quiet && done > 0 {
+            let _ = write!(std::io::stderr(), "\r\x1b[K");
+        }
+    }
+}
+
+/// Everything the actions print goes through a reporter so output can be
+/// switched between human-readable text and JSON.
+pub trait Reporter {
+    fn message(&self, message: &str);
+    fn list(&self, items: &[String]);
+    /// Bare names, one per line in text, for scripts and shell completion.
+    fn names(&self, names: &[String]);
+    fn dependencies(&self, deps: &[&JsonDependency]);
+    /// Dependencies under the name of their group.
+    fn groups(&self, groups: &[(String, Vec<&JsonDependency>)]);
+    fn crates(&self, crates: &[Crate]);
+    fn crate_info(&self, dep: &CratesIoDependency, versions: usize);
+    fn warning(&self, message: &str);
+    fn error(&self, error: &LimpError);
+}
+
+#[derive(Default)]
+pub struct TextReporter {
+    pub style: Style,
+}
+
+impl TextReporter {
+    fn index(&self, i: usize) -> String {
+        self.style.dim(&format!("{}:", i + 1))
+    }
+    fn dependency(&self, i: usize, d: &JsonDependency) -> String {
+        let rust_version = d
+            .rust_version
+            .as_ref()
+            .map(|r| format!(" (rust {})", r))
+            .unwrap_or_default();
+        let pinned = match (d.pinned, d.allow_prerelease) {
+            (true, _) => " (pinned)",
+            (false, true) => " (pre-releases)",
+            (false, false) => "",
+        };
+        let constraint = d
+            .constraint
+            .as_ref()
+            .map(|c| format!(" (within {})", c))
+            .unwrap_or_default();
+        format!(
+            "{} {}{}",
+            self.index(i),
+            self.style.entry(&d.to_string()),
+            self.style
+                .dim(&format!("{}{}{}", rust_version, pinned, constraint))
+        )
+    }
+}
+
+impl Reporter for TextReporter {
+    fn message(&self, message: &str) {
+        println!("{}", message);
+    }
+    fn list(&self, items: &[String]) {
+        items
+            .iter()
+            .enumerate()
+            .for_each(|(i, item)| println!("{} {}", self.index(i), item));
+    }
+    fn names(&self, names: &[String]) {
+        names.iter().for_each(|name| println!("{}", name));
+    }
+    fn dependencies(&self, deps: &[&JsonDependency]) {
+        deps.iter()
+            .enumerate()
+            .for_each(|(i, d)| println!("{}", self.dependency(i, d)));
+    }
+    fn groups(&self, groups: &[(String, Vec<&JsonDependency>)]) {
+        for (name, deps) in groups.iter() {
+            println!(
+                "{} {}",
+                self.style.bold(name),
+                self.style.dim(&format!("({})", deps.len()))
+            );
+            deps.iter()
+                .enumerate()
+                .for_each(|(i, d)| println!("  {}", self.dependency(i, d)));
+        }
+    }
+    fn crates(&self, crates: &[Crate]) {
+        crates
+            .iter()
+            .enumerate()
+            .for_each(|(i, c)| println!("{} {}", self.index(i), self.style.entry(&c.to_string())));
+    }
+    fn crate_info(&self, dep: &CratesIoDependency, versions: usize) {
+        let info = &dep.crate_info;
+        println!("{} = \"{}\"", self.style.bold(&info.name), info.max_version);
+        if let Some(description) = &info.description {
+            println!("{}", description.trim());
+        }
+        let all_versions = dep.get_all_versions();
+        if let Some(license) = all_versions.first().and_then(|v| v.license.as_ref()) {
+            println!("{} {}", self.style.dim("license:"), license);
+        }
+        if let Some(repository) = &info.repository {
+            println!("{} {}", self.style.dim("repository:"), repository);
+        }
+        if let Some(homepage) = &info.homepage {
+            println!("{} {}", self.style.dim("homepage:"), homepage);
+        }
+        if let Some(documentation) = &info.documentation {
+            println!("{} {}", self.style.dim("documentation:"), documentation);
+        }
+        match info.recent_downloads {
+            Some(recent) => println!(
+                "{} {} (recent: {})",
+                self.style.dim("downloads:"),
+                info.downloads,
+                recent
+            ),
+            None => println!("{} {}", self.style.dim("downloads:"), info.downloads),
+        }
+        if let Some(updated) = info.updated() {
+            println!("{} {}", self.style.dim("updated:"), updated);
+        }
+        println!("{}", self.style.dim("versions:"));
+        all_versions.iter().take(versions).for_each(|v| {
+            let mut features = v.get_features().unwrap_or_default();
+            features.sort();
+            println!(
+                "  {}{}{} (downloads: {}): [{}]",
+                v.num,
+                if v.yanked { " (yanked)" } else { "" },
+                v.rust_version
+                    .as_ref()
+                    .map(|r| format!(" (rust {})", r))
+                    .unwrap_or_default(),
+                v.downloads,
+                features.join(", ")
+            )
+        });
+    }
+    fn warning(&self, message: &str) {
+        println!("{} {}", self.style.warning("warning:"), message);
+    }
+    fn error(&self, error: &LimpError) {
+        eprintln!("{} {error}", self.style.error("ERROR:"));
+    }
+}
+
+/// Same as `TextReporter` but prints dependencies as aligned columns.
+#[derive(Default)]
+pub struct TableReporter {
+    pub style: Style,
+}
+
+impl TableReporter {
+    fn text(&self) -> TextReporter {
+        TextReporter { style: self.style }
+    }
+}
+
+impl Reporter for TableReporter {
+    fn message(&self, message: &str) {
+        self.text().message(message)
+    }
+    fn list(&self, items: &[String]) {
+        self.text().list(items)
+    }
+    fn names(&self, names: &[String]) {
+        self.text().names(names)
+    }
+    fn dependencies(&self, deps: &[&JsonDependency]) {
+        let rows = deps
+            .iter()
+            .map(|d| {
+                [
+                    d.name.clone(),
+                    d.version.clone(),
+                    d.kind.name().to_string(),
+                    d.features.as_ref().map(|f| f.join(",")).unwrap_or_default(),
+                    d.rust_version.clone().unwrap_or_default(),
+                    d.snippet().cloned().unwrap_or_default(),
+                ]
+            })
+            .collect::<Vec<_>>();
+        print_table(
+            self.style,
+            ["NAME", "VERSION", "KIND", "FEATURES", "RUST", "SNIPPET"],
+            &rows,
+        );
+    }
+    fn groups(&self, groups: &[(String, Vec<&JsonDependency>)]) {
+        for (i, (name, deps)) in groups.iter().enumerate() {
+            if i > 0 {
+                println!();
+            }
+            println!("{}", self.style.bold(&format!("{} ({})", name, deps.len())));
+            self.dependencies(deps);
+        }
+    }
+    fn crates(&self, crates: &[Crate]) {
+        self.text().crates(crates)
+    }
+    fn crate_info(&self, dep: &CratesIoDependency, versions: usize) {
+        self.text().crate_info(dep, versions)
+    }
+    fn warning(&self, message: &str) {
+        self.text().warning(message)
+    }
+    fn error(&self, error: &LimpError) {
+        self.text().error(error)
+    }
+}
+
+fn print_table<const N: usize>(style: Style, header: [&str; N], rows: &[[String; N]]) {
+    let mut widths = header.map(str::len);
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |cells: [&str; N]| {
+        let padded = cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell))
+            .collect::<Vec<String>>();
+        padded.join("  ").trim_end().to_string()
+    };
+    println!("{}", style.bold(&line(header)));
+    for row in rows {
+        println!("{}", line(row.each_ref().map(String::as_str)));
+    }
+}
+
+pub struct JsonReporter;
+
+impl Reporter for JsonReporter {
+    fn message(&self, message: &str) {
+        println!("{}", json!({ "message": message }));
+    }
+    fn list(&self, items: &[String]) {
+        println!("{}", json!(items));
+    }
+    fn names(&self, names: &[String]) {
+        println!("{}", json!(names));
+    }
+    fn dependencies(&self, deps: &[&JsonDependency]) {
+        println!("{}", json!(deps));
+    }
+    fn groups(&self, groups: &[(String, Vec<&JsonDependency>)]) {
+        let groups = groups
+            .iter()
+            .map(|(name, deps)| (name.clone(), json!(deps)))
+            .collect::<serde_json::Map<String, serde_json::Value>>();
+        println!("{}", json!(groups));
+    }
+    fn crates(&self, crates: &[Crate]) {
+        println!("{}", json!(crates));
+    }
+    fn crate_info(&self, dep: &CratesIoDependency, versions: usize) {
+        let all_versions = dep.get_all_versions();
+        let versions = all_versions
+            .iter()
+            .take(versions)
+            .map(|v| {
+                let mut features = v.get_features().unwrap_or_default();
+                features.sort();
+                json!({
+                    "num": v.num,
+                    "license": v.license,
+                    "downloads": v.downloads,
+                    "yanked": v.yanked,
+                    "rust_version": v.rust_version,
+                    "features": features,
+                })
+            })
+            .collect::<Vec<serde_json::Value>>();
+        println!(
+            "{}",
+            json!({
+                "crate": dep.crate_info,
+                "license": all_versions.first().and_then(|v| v.license.as_ref()),
+                "versions": versions,
+            })
+        );
+    }
+    fn warning(&self, message: &str) {
+        println!("{}", json!({ "warning": message }));
+    }
+    fn error(&self, error: &LimpError) {
+        eprintln!("{}", json!({ "error": error.to_string() }));
+    }
+}
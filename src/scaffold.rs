@@ -0,0 +1,447 @@
+use std::{
+    io::Write,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    error::LimpError,
+    files::{open, ProjectKind},
+    parser::{substitute, Vars},
+};
+
+const MIT: &str = r#"MIT License
+
+Copyright (c) {{year}} {{author}}
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+"#;
+
+const ISC: &str = r#"ISC License
+
+Copyright (c) {{year}} {{author}}
+
+Permission to use, copy, modify, and/or distribute this software for any
+purpose with or without fee is hereby granted, provided that the above
+copyright notice and this permission notice appear in all copies.
+
+THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+"#;
+
+const BSD_3_CLAUSE: &str = r#"BSD 3-Clause License
+
+Copyright (c) {{year}}, {{author}}
+
+Redistribution and use in source and binary forms, with or without
+modification, are permitted provided that the following conditions are met:
+
+1. Redistributions of source code must retain the above copyright notice, this
+   list of conditions and the following disclaimer.
+
+2. Redistributions in binary form must reproduce the above copyright notice,
+   this list of conditions and the following disclaimer in the documentation
+   and/or other materials provided with the distribution.
+
+3. Neither the name of the copyright holder nor the names of its
+   contributors may be used to endorse or promote products derived from
+   this software without specific prior written permission.
+
+THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+"#;
+
+const UNLICENSE: &str = r#"This is free and unencumbered software released into the public domain.
+
+Anyone is free to copy, modify, publish, use, compile, sell, or
+distribute this software, either in source code form or as a compiled
+binary, for any purpose, commercial or non-commercial, and by any
+means.
+
+In jurisdictions that recognize copyright laws, the author or authors
+of this software dedicate any and all copyright interest in the
+software to the public domain. We make this dedication for the benefit
+of the public at large and to the detriment of our heirs and
+successors. We intend this dedication to be an overt act of
+relinquishment in perpetuity of all present and future rights to this
+software under copyright law.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,
+EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT.
+IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY CLAIM, DAMAGES OR
+OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE,
+ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR
+OTHER DEALINGS IN THE SOFTWARE.
+
+For more information, please refer to <https://unlicense.org>
+"#;
+
+const README: &str = r#"# {{project_name}}
+
+## Usage
+
+```bash
+cargo build --release
+```
+"#;
+
+const GITHUB_CI: &str = r#"name: CI
+
+on:
+  push:
+  pull_request:
+
+env:
+  CARGO_TERM_COLOR: always
+
+jobs:
+  check:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+      - uses: dtolnay/rust-toolchain@stable
+        with:
+          components: clippy, rustfmt
+      - run: cargo fmt --all -- --check
+      - run: cargo clippy --all-targets -- -D warnings
+      - run: cargo test
+"#;
+
+const GITLAB_CI: &str = r#"image: rust:latest
+
+check:
+  script:
+    - rustup component add clippy rustfmt
+    - cargo fmt --all -- --check
+    - cargo clippy --all-targets -- -D warnings
+    - cargo test
+"#;
+
+const CLI_MAIN: &str = r#"use clap::Parser;
+
+/// {{project_name}}
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// Who to greet
+    #[arg(short, long, default_value = "world")]
+    name: String,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    println!("Hello, {}!", args.name);
+    Ok(())
+}
+"#;
+
+const WEB_MAIN: &str = r#"use axum::{routing::get, Json, Router};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Health {
+    status: &'static str,
+}
+
+async fn health() -> Json<Health> {
+    Json(Health { status: "ok" })
+}
+
+#[tokio::main]
+async fn main() {
+    let app = Router::new().route("/health", get(health));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3000").await.unwrap();
+    axum::serve(listener, app).await.unwrap();
+}
+"#;
+
+const LIB_LIB: &str = r#"//! {{project_name}}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("invalid input: {0}")]
+    InvalidInput(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn it_works() {}
+}
+"#;
+
+const WASM_LIB: &str = r#"use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+pub fn greet(name: &str) -> String {
+    format!("Hello, {}!", name)
+}
+"#;
+
+const EXAMPLE: &str = r#"{{uses}}fn main() {
+    println!("{{project_name}} example");
+}
+"#;
+
+const BENCH: &str = r#"use std::{hint::black_box, time::Instant};
+
+{{uses}}// runs without a harness, see [[bench]] in Cargo.toml
+fn main() {
+    let iterations = 1_000_000u32;
+    let start = Instant::now();
+    for i in 0..iterations {
+        black_box(i);
+    }
+    println!("{:?} per iteration", start.elapsed() / iterations);
+}
+"#;
+
+const LIB_TEST: &str = r#"use {{crate_name}} as _;
+
+#[test]
+fn it_works() {}
+"#;
+
+const BIN_TEST: &str = r#"use std::process::Command;
+
+#[test]
+fn runs() {
+    let status = Command::new(env!("CARGO_BIN_EXE_{{project_name}}"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+"#;
+
+/// Licenses `limp init --license` can write, by SPDX identifier.
+pub const LICENSES: [&str; 4] = ["MIT", "ISC", "BSD-3-Clause", "Unlicense"];
+
+/// CI services `limp init --ci` can write a workflow for.
+pub const CI: [&str; 2] = ["github", "gitlab"];
+
+/// Project types `limp init --type` can start from.
+pub const PRESETS: [&str; 4] = ["cli", "web", "lib", "wasm"];
+
+/// A built-in project type with its dependencies and entry file. A saved
+/// template of the same name is used instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Preset {
+    pub name: &'static str,
+    pub kind: ProjectKind,
+    /// crates with the features they are added with unless stored
+    pub dependencies: &'static [(&'static str, &'static [&'static str])],
+    /// written to Cargo.toml after the dependencies
+    pub manifest_extras: Option<&'static str>,
+    /// replaces the default entry file, merged with dependency snippets
+    /// the same way
+    pub entry: &'static str,
+}
+
+impl Preset {
+    pub fn from_name(name: &str) -> Result<Self, LimpError> {
+        let preset = match name {
+            "cli" => Preset {
+                name: "cli",
+                kind: ProjectKind::Bin,
+                dependencies: &[("clap", &["derive"]), ("anyhow", &[])],
+                manifest_extras: None,
+                entry: CLI_MAIN,
+            },
+            "web" => Preset {
+                name: "web",
+                kind: ProjectKind::Bin,
+                dependencies: &[("tokio", &["full"]), ("axum", &[]), ("serde", &["derive"])],
+                manifest_extras: None,
+                entry: WEB_MAIN,
+            },
+            "lib" => Preset {
+                name: "lib",
+                kind: ProjectKind::Lib,
+                dependencies: &[("thiserror", &[])],
+                manifest_extras: None,
+                entry: LIB_LIB,
+            },
+            "wasm" => Preset {
+                name: "wasm",
+                kind: ProjectKind::Lib,
+                dependencies: &[("wasm-bindgen", &[])],
+                manifest_extras: Some("[lib]\ncrate-type = [\"cdylib\", \"rlib\"]\n"),
+                entry: WASM_LIB,
+            },
+            _ => {
+                return Err(LimpError::UnknownScaffold(format!(
+                    "type {}, expected one of: {}",
+                    name,
+                    PRESETS.join(", ")
+                )))
+            }
+        };
+        Ok(preset)
+    }
+}
+
+/// Optional files written next to a new project's Cargo.toml.
+#[derive(Debug, Clone, Default)]
+pub struct Scaffold {
+    /// SPDX identifier out of `LICENSES`
+    pub license: Option<String>,
+    pub readme: bool,
+    /// name out of `CI`
+    pub ci: Option<String>,
+    /// `examples/basic.rs`
+    pub examples: bool,
+    /// `benches/bench.rs` without the test harness
+    pub benches: bool,
+    /// `tests/integration.rs`
+    pub tests: bool,
+}
+
+impl Scaffold {
+    /// Writes the selected files into `dir`, rendering `{{year}}` and the
+    /// template variables on the way.
+    pub fn write<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        project_name: &str,
+        vars: &Vars,
+    ) -> Result<(), LimpError> {
+        let dir = dir.as_ref();
+        let mut values = vars.values(project_name);
+        values
+            .entry("year".to_string())
+            .or_insert_with(|| current_year().to_string());
+        let mut files = vec![];
+        if let Some(license) = &self.license {
+            files.push(("LICENSE".to_string(), license_text(license)?.to_string()));
+        }
+        if self.readme {
+            let mut readme = README.to_string();
+            if let Some(license) = &self.license {
+                readme.push_str(&format!("\n## License\n\n{}\n", license));
+            }
+            files.push(("README.md".to_string(), readme));
+        }
+        match self.ci.as_deref() {
+            Some("github") => files.push((
+                ".github/workflows/ci.yml".to_string(),
+                GITHUB_CI.to_string(),
+            )),
+            Some("gitlab") => files.push((".gitlab-ci.yml".to_string(), GITLAB_CI.to_string())),
+            Some(other) => return Err(LimpError::UnknownScaffold(format!("ci {}", other))),
+            None => {}
+        }
+        // existing files are kept
+        for (path, text) in files {
+            let path = dir.join(path);
+            if !path.exists() {
+                open(&path)?.write_all(substitute(&text, &values).as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the selected example, bench and integration test stubs into
+    /// the crate at `dir`, importing the crate when it is a library.
+    pub fn write_targets<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        project_name: &str,
+        kind: ProjectKind,
+        vars: &Vars,
+    ) -> Result<(), LimpError> {
+        let dir = dir.as_ref();
+        let mut values = vars.values(project_name);
+        let uses = match kind {
+            ProjectKind::Lib => format!("use {} as _;\n\n", project_name.replace('-', "_")),
+            ProjectKind::Bin => String::new(),
+        };
+        values.insert("uses".to_string(), uses);
+        let mut files = vec![];
+        if self.examples {
+            files.push(("examples/basic.rs", EXAMPLE));
+        }
+        if self.benches {
+            files.push(("benches/bench.rs", BENCH));
+        }
+        if self.tests {
+            let test = match kind {
+                ProjectKind::Lib => LIB_TEST,
+                ProjectKind::Bin => BIN_TEST,
+            };
+            files.push(("tests/integration.rs", test));
+        }
+        for (path, text) in files {
+            let path = dir.join(path);
+            if !path.exists() {
+                open(&path)?.write_all(substitute(text, &values).as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn license_text(license: &str) -> Result<&'static str, LimpError> {
+    match license {
+        "MIT" => Ok(MIT),
+        "ISC" => Ok(ISC),
+        "BSD-3-Clause" => Ok(BSD_3_CLAUSE),
+        "Unlicense" => Ok(UNLICENSE),
+        _ => Err(LimpError::UnknownScaffold(format!(
+            "license {}, expected one of: {}",
+            license,
+            LICENSES.join(", ")
+        ))),
+    }
+}
+
+fn current_year() -> i64 {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / 86_400)
+        .unwrap_or_default() as i64;
+    // civil date from days since 1970-01-01, only the year is needed
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let year = yoe + era * 400;
+    if mp >= 10 {
+        year + 1
+    } else {
+        year
+    }
+}
@@ -0,0 +1,720 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    cache,
+    crates::Registry,
+    error::LimpError,
+    parser::ast,
+    storage::{now, JsonStorage, DEFAULT_VARIANT},
+};
+
+/// A snippet file in the snippets directory and the dependencies linked to it.
+#[derive(Debug, Clone)]
+pub struct SnippetFile {
+    pub name: String,
+    pub path: PathBuf,
+    pub dependencies: Vec<String>,
+    pub tags: Vec<String>,
+}
+
+impl std::fmt::Display for SnippetFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // name [tag1, tag2]: dep1, dep2
+        // or
+        // name (unlinked)
+        write!(f, "{}", self.name)?;
+        if !self.tags.is_empty() {
+            write!(f, " [{}]", self.tags.join(", "))?;
+        }
+        if self.dependencies.is_empty() {
+            write!(f, " (unlinked)")
+        } else {
+            write!(f, ": {}", self.dependencies.join(", "))
+        }
+    }
+}
+
+/// A snippet file in the trash, see `trash`.
+#[derive(Debug, Clone)]
+pub struct Trashed {
+    pub name: String,
+    pub path: PathBuf,
+    /// unix time it was moved to the trash
+    pub deleted: u64,
+}
+
+impl std::fmt::Display for Trashed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // serde.rs, deleted 3d ago
+        let ago = Duration::from_secs(now().saturating_sub(self.deleted));
+        write!(f, "{}, deleted {} ago", self.name, cache::age(ago))
+    }
+}
+
+/// How a stored snippet compares to the checksum recorded when it was
+/// stored, see `verify`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnippetStatus {
+    Unchanged,
+    /// the stored copy was edited
+    Edited,
+    /// the stored copy is as it was, but its source changed since
+    SourceChanged,
+    /// stored before checksums were recorded, or written by hand
+    Untracked,
+    /// recorded in the storage, but the file is gone
+    Missing,
+    /// the source couldn't be read to compare with it
+    SourceUnavailable(String),
+}
+
+/// A snippet and its status, see `verify`.
+#[derive(Debug, Clone)]
+pub struct Verified {
+    pub name: String,
+    pub status: SnippetStatus,
+}
+
+impl std::fmt::Display for Verified {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.status {
+            SnippetStatus::Unchanged => write!(f, "{}: ok", self.name),
+            SnippetStatus::Edited => write!(f, "{}: edited since it was stored", self.name),
+            SnippetStatus::SourceChanged => write!(
+                f,
+                "{}: source changed, update with `limp snippet refresh`",
+                self.name
+            ),
+            SnippetStatus::Untracked => write!(f, "{}: no checksum recorded", self.name),
+            SnippetStatus::Missing => write!(f, "{}: file is missing", self.name),
+            SnippetStatus::SourceUnavailable(e) => {
+                write!(f, "{}: ok, source unavailable ({})", self.name, e)
+            }
+        }
+    }
+}
+
+impl Verified {
+    /// Whether the snippet isn't as it was stored.
+    pub fn is_drifted(&self) -> bool {
+        matches!(
+            self.status,
+            SnippetStatus::Edited | SnippetStatus::SourceChanged | SnippetStatus::Missing
+        )
+    }
+}
+
+/// A dependency's link to a snippet file that doesn't exist, see
+/// `collect_garbage`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DanglingLink {
+    pub dependency: String,
+    pub variant: String,
+    /// the missing file
+    pub path: String,
+    /// file of the same name in the snippets directory the link now points
+    /// to, unlinked when `None`
+    pub repaired: Option<PathBuf>,
+}
+
+impl std::fmt::Display for DanglingLink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // serde: /old/serde.rs is missing, relinked to /new/serde.rs
+        // or
+        // serde:axum: /old/axum.rs is missing, unlinked
+        write!(f, "{}", self.dependency)?;
+        if self.variant != DEFAULT_VARIANT {
+            write!(f, ":{}", self.variant)?;
+        }
+        write!(f, ": {} is missing, ", self.path)?;
+        match &self.repaired {
+            Some(path) => write!(f, "relinked to {}", path.display()),
+            None => write!(f, "unlinked"),
+        }
+    }
+}
+
+/// What `collect_garbage` found in the storage and the snippets directory.
+#[derive(Debug, Clone, Default)]
+pub struct Garbage {
+    /// files neither linked to a dependency nor recorded as stored on their
+    /// own, sorted
+    pub orphans: Vec<PathBuf>,
+    /// sorted by dependency and variant
+    pub dangling: Vec<DanglingLink>,
+    /// recorded snippets whose file is gone, sorted
+    pub forgotten: Vec<String>,
+}
+
+impl Garbage {
+    pub fn is_empty(&self) -> bool {
+        self.orphans.is_empty() && self.dangling.is_empty() && self.forgotten.is_empty()
+    }
+}
+
+/// FNV-1a checksum of `content` as 16 hex digits, ignoring carriage returns
+/// so that converting line endings isn't taken for an edit. It only detects
+/// changes, it isn't meant to be secure.
+pub fn checksum(content: &[u8]) -> String {
+    let hash = content
+        .iter()
+        .filter(|b| **b != b'\r')
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, b| {
+            (hash ^ u64::from(*b)).wrapping_mul(0x0000_0100_0000_01b3)
+        });
+    format!("{:016x}", hash)
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Dependencies linked to the snippet at `path`, sorted by name.
+fn linked(storage: &JsonStorage, path: &Path) -> Vec<String> {
+    let mut names = storage
+        .dependencies
+        .values()
+        .filter(|d| d.snippets.values().any(|p| same_file(Path::new(p), path)))
+        .map(|d| d.name.clone())
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+/// Points every snippet variant linked to `path` at `to`, or unlinks it when
+/// `None`. Returns the names of the dependencies linked to `path`.
+fn relink(storage: &mut JsonStorage, path: &Path, to: Option<&Path>) -> Vec<String> {
+    let names = linked(storage, path);
+    for name in names.iter() {
+        let Some(dep) = storage.get_mut(name) else {
+            continue;
+        };
+        let variants = dep
+            .snippets
+            .iter()
+            .filter(|(_, p)| same_file(Path::new(p), path))
+            .map(|(v, _)| v.clone())
+            .collect::<Vec<String>>();
+        for variant in variants {
+            match to {
+                Some(to) => dep.set_snippet(Some(&variant), to.to_string_lossy().to_string()),
+                None => {
+                    dep.snippets.remove(&variant);
+                }
+            }
+        }
+    }
+    names
+}
+
+/// Path of the snippet `name` in `dir`, where `name` may omit the `.rs`.
+pub fn path<P: AsRef<Path>>(dir: P, name: &str) -> PathBuf {
+    let dir = dir.as_ref();
+    let path = dir.join(name);
+    if path.extension().is_none() {
+        return dir.join(format!("{}.rs", name));
+    }
+    path
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Path of the snippet `name` in `dir`, failing when there's no such file.
+pub fn existing<P: AsRef<Path>>(dir: P, name: &str) -> Result<PathBuf, LimpError> {
+    let path = path(dir, name);
+    if !path.is_file() {
+        return Err(LimpError::SnippetNotFound(name.to_string()));
+    }
+    Ok(path)
+}
+
+/// All files in `dir` with the dependencies linked to them, sorted by name.
+pub fn list<P: AsRef<Path>>(dir: P, storage: &JsonStorage) -> Result<Vec<SnippetFile>, LimpError> {
+    let dir = dir.as_ref();
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut snippets = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if !path.is_file() {
+            continue;
+        }
+        let name = file_name(&path);
+        snippets.push(SnippetFile {
+            dependencies: linked(storage, &path),
+            tags: storage
+                .snippets
+                .get(&name)
+                .map(|m| m.tags.clone())
+                .unwrap_or_default(),
+            name,
+            path,
+        });
+    }
+    snippets.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(snippets)
+}
+
+/// Snippets tagged `keyword` or with it in their name or contents, ignoring
+/// case.
+pub fn find<P: AsRef<Path>>(
+    dir: P,
+    storage: &JsonStorage,
+    keyword: &str,
+) -> Result<Vec<SnippetFile>, LimpError> {
+    let keyword = keyword.to_lowercase();
+    let mut found = vec![];
+    for snippet in list(dir, storage)? {
+        if snippet.tags.iter().any(|t| t.to_lowercase() == keyword)
+            || snippet.name.to_lowercase().contains(&keyword)
+            || fs::read_to_string(&snippet.path).is_ok_and(|c| c.to_lowercase().contains(&keyword))
+        {
+            found.push(snippet);
+        }
+    }
+    Ok(found)
+}
+
+/// Copies the snippet at `source` into `dir` under its file name. Copying a
+/// file onto an identical one is a no-op.
+pub fn store<P: AsRef<Path>, Q: AsRef<Path>>(dir: P, source: Q) -> Result<PathBuf, LimpError> {
+    let source = source.as_ref();
+    if !source.is_file() {
+        return Err(LimpError::SnippetNotFound(source.display().to_string()));
+    }
+    let dir = dir.as_ref();
+    let target = dir.join(file_name(source));
+    if target.exists() {
+        if same_file(source, &target) || fs::read(source)? == fs::read(&target)? {
+            return Ok(target);
+        }
+        return Err(LimpError::SnippetConflict(file_name(source)));
+    }
+    fs::create_dir_all(dir)?;
+    fs::copy(source, &target)?;
+    Ok(target)
+}
+
+/// Copies the file at `source` into `dir` as the snippet `name`, e.g. an
+/// `error.rs` boilerplate that isn't tied to any dependency.
+pub fn add<P: AsRef<Path>, Q: AsRef<Path>>(
+    dir: P,
+    name: &str,
+    source: Q,
+) -> Result<PathBuf, LimpError> {
+    let source = source.as_ref();
+    if !source.is_file() {
+        return Err(LimpError::SnippetNotFound(source.display().to_string()));
+    }
+    let dir = dir.as_ref();
+    let target = path(dir, name);
+    if target.exists() {
+        return Err(LimpError::SnippetConflict(name.to_string()));
+    }
+    fs::create_dir_all(dir)?;
+    fs::copy(source, &target)?;
+    Ok(target)
+}
+
+/// Records that the snippet at `path` was copied from the local file
+/// `source`, with the checksum of its contents.
+pub fn record_copy(storage: &mut JsonStorage, path: &Path, source: &Path) -> Result<(), LimpError> {
+    let source = fs::canonicalize(source).unwrap_or_else(|_| source.to_path_buf());
+    let meta = storage.snippets.entry(file_name(path)).or_default();
+    meta.url = None;
+    meta.path = Some(
+        crate::paths::normalize(source)
+            .to_string_lossy()
+            .to_string(),
+    );
+    meta.hash = Some(checksum(&fs::read(path)?));
+    Ok(())
+}
+
+/// Replaces the tags of the snippet at `path`.
+pub fn set_tags(storage: &mut JsonStorage, path: &Path, tags: &[String]) {
+    storage.snippets.entry(file_name(path)).or_default().tags = tags.to_vec();
+}
+
+pub fn read<P: AsRef<Path>>(dir: P, name: &str) -> Result<String, LimpError> {
+    Ok(fs::read_to_string(existing(dir, name)?)?)
+}
+
+/// Renames the snippet `from` to `to` and moves the dependencies linked to it
+/// along. Returns the names of those dependencies.
+pub fn rename<P: AsRef<Path>>(
+    dir: P,
+    storage: &mut JsonStorage,
+    from: &str,
+    to: &str,
+) -> Result<Vec<String>, LimpError> {
+    let dir = dir.as_ref();
+    let from = existing(dir, from)?;
+    let target = path(dir, to);
+    if target.exists() {
+        return Err(LimpError::SnippetConflict(to.to_string()));
+    }
+    let names = relink(storage, &from, Some(&target));
+    fs::rename(&from, &target)?;
+    if let Some(meta) = storage.snippets.remove(&file_name(&from)) {
+        storage.snippets.insert(file_name(&target), meta);
+    }
+    Ok(names)
+}
+
+/// Moves the snippet `name` to the `trash` directory and unlinks it from its
+/// dependencies. Returns the names of those dependencies and the snippet's
+/// path in the trash.
+pub fn remove<P: AsRef<Path>, Q: AsRef<Path>>(
+    dir: P,
+    trash: Q,
+    storage: &mut JsonStorage,
+    name: &str,
+) -> Result<(Vec<String>, PathBuf), LimpError> {
+    let path = existing(dir, name)?;
+    let names = relink(storage, &path, None);
+    let trashed = self::trash(trash, &path)?;
+    storage.snippets.remove(&file_name(&path));
+    Ok((names, trashed))
+}
+
+/// Records the snippet at `path` as stored on its own, so it is kept by
+/// `collect_garbage` without any dependency linking to it.
+pub fn keep(storage: &mut JsonStorage, path: &Path) {
+    storage.snippets.entry(file_name(path)).or_default();
+}
+
+fn move_file(from: &Path, to: &Path) -> Result<(), LimpError> {
+    // renaming fails across file systems
+    if fs::rename(from, to).is_err() {
+        fs::copy(from, to)?;
+        fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+/// Moves the file at `path` into the `trash` directory, prefixed with the
+/// time it was deleted at so that several versions can be kept. Returns its
+/// path in the trash.
+pub fn trash<P: AsRef<Path>>(trash: P, path: &Path) -> Result<PathBuf, LimpError> {
+    let trash = trash.as_ref();
+    fs::create_dir_all(trash)?;
+    let target = trash.join(format!("{}-{}", now(), file_name(path)));
+    move_file(path, &target)?;
+    Ok(target)
+}
+
+/// The files in the `trash` directory, most recently deleted first.
+pub fn trashed<P: AsRef<Path>>(trash: P) -> Result<Vec<Trashed>, LimpError> {
+    let trash = trash.as_ref();
+    if !trash.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut files = vec![];
+    for entry in fs::read_dir(trash)? {
+        let path = entry?.path();
+        let file = file_name(&path);
+        let Some((deleted, name)) = file
+            .split_once('-')
+            .and_then(|(at, name)| Some((at.parse::<u64>().ok()?, name.to_string())))
+        else {
+            continue;
+        };
+        if path.is_file() {
+            files.push(Trashed {
+                name,
+                path,
+                deleted,
+            });
+        }
+    }
+    files.sort_by(|a, b| b.deleted.cmp(&a.deleted).then_with(|| a.name.cmp(&b.name)));
+    Ok(files)
+}
+
+/// Deletes the files that have been in the `trash` directory for longer
+/// than `keep`. Returns how many were deleted.
+pub fn purge_trash<P: AsRef<Path>>(trash: P, keep: Duration) -> Result<usize, LimpError> {
+    let cutoff = now().saturating_sub(keep.as_secs());
+    let mut purged = 0;
+    for file in trashed(trash)? {
+        if file.deleted < cutoff {
+            fs::remove_file(&file.path)?;
+            purged += 1;
+        }
+    }
+    Ok(purged)
+}
+
+/// Moves the most recently deleted version of the snippet `name` from the
+/// `trash` directory back into `dir`, as a snippet stored on its own.
+pub fn restore<P: AsRef<Path>, Q: AsRef<Path>>(
+    dir: P,
+    trash: Q,
+    storage: &mut JsonStorage,
+    name: &str,
+) -> Result<PathBuf, LimpError> {
+    let dir = dir.as_ref();
+    let target = path(dir, name);
+    let file = file_name(&target);
+    let trashed = trashed(trash)?
+        .into_iter()
+        .find(|t| t.name == file)
+        .ok_or_else(|| LimpError::SnippetNotFound(format!("{} in the trash", name)))?;
+    if target.exists() {
+        return Err(LimpError::SnippetConflict(name.to_string()));
+    }
+    fs::create_dir_all(dir)?;
+    move_file(&trashed.path, &target)?;
+    keep(storage, &target);
+    Ok(target)
+}
+
+/// Repairs what hand edits or interrupted saves leave behind in `storage`:
+/// links to missing snippet files point at a file of the same name in `dir`
+/// when there is one and are dropped otherwise, and the metadata of missing
+/// files is forgotten. The files in `dir` nothing refers to anymore are
+/// returned as orphans for the caller to delete.
+pub fn collect_garbage<P: AsRef<Path>>(
+    dir: P,
+    storage: &mut JsonStorage,
+) -> Result<Garbage, LimpError> {
+    let dir = dir.as_ref();
+    let mut garbage = Garbage::default();
+    for dep in storage.dependencies.values_mut() {
+        let missing = dep
+            .snippets
+            .iter()
+            .filter(|(_, p)| !Path::new(p).is_file())
+            .map(|(v, p)| (v.clone(), p.clone()))
+            .collect::<Vec<(String, String)>>();
+        for (variant, path) in missing {
+            let candidate = dir.join(file_name(Path::new(&path)));
+            let repaired = candidate.is_file().then_some(candidate);
+            match &repaired {
+                Some(to) => dep.set_snippet(Some(&variant), to.to_string_lossy().to_string()),
+                None => {
+                    dep.snippets.remove(&variant);
+                }
+            }
+            garbage.dangling.push(DanglingLink {
+                dependency: dep.name.clone(),
+                variant,
+                path,
+                repaired,
+            });
+        }
+    }
+    garbage
+        .dangling
+        .sort_by(|a, b| (&a.dependency, &a.variant).cmp(&(&b.dependency, &b.variant)));
+    garbage.forgotten = storage
+        .snippets
+        .keys()
+        .filter(|name| !dir.join(name).is_file())
+        .cloned()
+        .collect();
+    for name in garbage.forgotten.iter() {
+        storage.snippets.remove(name);
+    }
+    garbage.orphans = list(dir, storage)?
+        .into_iter()
+        .filter(|s| s.dependencies.is_empty() && !storage.snippets.contains_key(&s.name))
+        .map(|s| s.path)
+        .collect();
+    Ok(garbage)
+}
+
+pub fn is_url(s: &str) -> bool {
+    s.starts_with("https://") || s.starts_with("http://")
+}
+
+/// The URL serving the file itself for GitHub gist and blob pages, `url`
+/// unchanged otherwise.
+pub fn raw_url(url: &str) -> String {
+    let url = url.trim_end_matches('/');
+    if let Some(rest) = url.strip_prefix("https://gist.github.com/") {
+        if !rest.contains("/raw") {
+            return format!("https://gist.githubusercontent.com/{}/raw", rest);
+        }
+        return format!("https://gist.githubusercontent.com/{}", rest);
+    }
+    if let Some(rest) = url.strip_prefix("https://github.com/") {
+        if let Some((repo, path)) = rest.split_once("/blob/") {
+            return format!("https://raw.githubusercontent.com/{}/{}", repo, path);
+        }
+    }
+    url.to_string()
+}
+
+/// Downloads the snippet at `url` into `dir` as `name`, failing when it
+/// isn't valid Rust, and records where it came from.
+pub fn download<P: AsRef<Path>>(
+    registry: &Registry,
+    dir: P,
+    storage: &mut JsonStorage,
+    url: &str,
+    name: &str,
+) -> Result<PathBuf, LimpError> {
+    let content = registry.fetch_url(&raw_url(url))?;
+    if ast::items(&content).is_none() {
+        return Err(LimpError::InvalidSnippet(url.to_string()));
+    }
+    let dir = dir.as_ref();
+    let path = path(dir, name);
+    fs::create_dir_all(dir)?;
+    fs::write(&path, &content)?;
+    let meta = storage.snippets.entry(file_name(&path)).or_default();
+    meta.url = Some(url.to_string());
+    meta.path = None;
+    meta.hash = Some(checksum(content.as_bytes()));
+    Ok(path)
+}
+
+/// Compares the stored snippets with the checksums recorded when they were
+/// stored, and with their sources when `sources` is set, downloading the
+/// ones that came from a URL. Sorted by name.
+pub fn verify<P: AsRef<Path>>(
+    registry: &Registry,
+    dir: P,
+    storage: &JsonStorage,
+    sources: bool,
+) -> Result<Vec<Verified>, LimpError> {
+    let dir = dir.as_ref();
+    let mut names = list(dir, storage)?
+        .into_iter()
+        .map(|s| s.name)
+        .collect::<Vec<String>>();
+    names.extend(
+        storage
+            .snippets
+            .iter()
+            .filter(|(_, meta)| meta.hash.is_some())
+            .map(|(name, _)| name.clone()),
+    );
+    names.sort();
+    names.dedup();
+    let mut verified = vec![];
+    for name in names {
+        let meta = storage.snippets.get(&name).cloned().unwrap_or_default();
+        let status = match (fs::read(dir.join(&name)), &meta.hash) {
+            (Err(_), _) => SnippetStatus::Missing,
+            (Ok(_), None) => SnippetStatus::Untracked,
+            (Ok(content), Some(hash)) if checksum(&content) != *hash => SnippetStatus::Edited,
+            (Ok(_), Some(hash)) if sources => {
+                let source = match (&meta.url, &meta.path) {
+                    (Some(url), _) => {
+                        Some(registry.fetch_url(&raw_url(url)).map(String::into_bytes))
+                    }
+                    (None, Some(path)) => Some(fs::read(path).map_err(LimpError::from)),
+                    (None, None) => None,
+                };
+                match source {
+                    Some(Ok(source)) if checksum(&source) != *hash => SnippetStatus::SourceChanged,
+                    Some(Err(e)) => SnippetStatus::SourceUnavailable(e.to_string()),
+                    _ => SnippetStatus::Unchanged,
+                }
+            }
+            (Ok(_), Some(_)) => SnippetStatus::Unchanged,
+        };
+        verified.push(Verified { name, status });
+    }
+    Ok(verified)
+}
+
+/// Imports the snippet `name`, the snippets linked to the dependency `name`,
+/// or every snippet with a known source when it is `None`, again from the
+/// URL or local file it came from. Returns the names of the refreshed
+/// snippets.
+pub fn refresh<P: AsRef<Path>>(
+    registry: &Registry,
+    dir: P,
+    storage: &mut JsonStorage,
+    name: Option<&str>,
+) -> Result<Vec<String>, LimpError> {
+    let dir = dir.as_ref();
+    let has_source = |storage: &JsonStorage, file: &str| {
+        storage
+            .snippets
+            .get(file)
+            .is_some_and(|m| m.url.is_some() || m.path.is_some())
+    };
+    let files = match name {
+        Some(name) => {
+            let mut files = match storage.get(name).filter(|d| !d.snippets.is_empty()) {
+                Some(dep) => dep
+                    .snippets
+                    .values()
+                    .map(|p| file_name(Path::new(p)))
+                    .collect::<Vec<String>>(),
+                None => vec![file_name(&existing(dir, name)?)],
+            };
+            // variants may share a file
+            files.sort();
+            files.dedup();
+            if let Some(file) = files.iter().find(|f| !has_source(storage, f)) {
+                return Err(LimpError::SnippetNotFound(format!(
+                    "{} has no URL or file to refresh from",
+                    file
+                )));
+            }
+            files
+        }
+        None => storage
+            .snippets
+            .keys()
+            .filter(|file| has_source(storage, file))
+            .cloned()
+            .collect(),
+    };
+    let mut refreshed = vec![];
+    for file in files {
+        let meta = storage.snippets.get(&file).cloned().unwrap_or_default();
+        match (meta.url, meta.path) {
+            (Some(url), _) => {
+                download(registry, dir, storage, &url, &file)?;
+            }
+            (None, Some(source)) => {
+                let path = dir.join(&file);
+                fs::copy(&source, &path)
+                    .map_err(|e| LimpError::SnippetNotFound(format!("{}: {}", source, e)))?;
+                record_copy(storage, &path, Path::new(&source))?;
+            }
+            (None, None) => continue,
+        }
+        refreshed.push(file);
+    }
+    Ok(refreshed)
+}
+
+/// Opens `path` in `$VISUAL`, `$EDITOR`, or `vi` and waits for it to exit.
+pub fn edit<P: AsRef<Path>>(path: P) -> Result<(), LimpError> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut words = editor.split_whitespace();
+    let program = words
+        .next()
+        .ok_or_else(|| LimpError::EditorFailed(editor.clone()))?;
+    if !std::process::Command::new(program)
+        .args(words)
+        .arg(path.as_ref())
+        .status()?
+        .success()
+    {
+        return Err(LimpError::EditorFailed(editor));
+    }
+    Ok(())
+}
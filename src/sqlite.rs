@@ -0,0 +1,307 @@
+//! `dependencies.db` storage, built with the `sqlite` feature. Dependencies
+//! and snippet metadata are kept as rows, next to a log of every change made
+//! to the dependencies that `limp history` and `limp stats` query.
+
+use std::{collections::HashMap, fs, path::Path, time::Duration};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    cache,
+    error::LimpError,
+    storage::{now, JsonStorage},
+};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+CREATE TABLE IF NOT EXISTS dependencies (
+    name TEXT PRIMARY KEY,
+    version TEXT NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS snippets (file TEXT PRIMARY KEY, data TEXT NOT NULL);
+CREATE TABLE IF NOT EXISTS events (
+    id INTEGER PRIMARY KEY,
+    at INTEGER NOT NULL,
+    user TEXT,
+    action TEXT NOT NULL,
+    name TEXT NOT NULL,
+    version TEXT
+);
+";
+
+/// A logged change to a stored dependency.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    /// unix time of the change
+    pub at: u64,
+    pub user: Option<String>,
+    /// `added`, `updated` or `removed`
+    pub action: String,
+    pub name: String,
+    /// the new version, or the last one for `removed`
+    pub version: Option<String>,
+}
+
+fn ago(at: u64) -> String {
+    cache::age(Duration::from_secs(now().saturating_sub(at)))
+}
+
+impl std::fmt::Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // serde 1.0.210 updated by alice, 3d ago
+        write!(f, "{}", self.name)?;
+        if let Some(version) = &self.version {
+            write!(f, " {}", version)?;
+        }
+        write!(f, " {}", self.action)?;
+        if let Some(user) = &self.user {
+            write!(f, " by {}", user)?;
+        }
+        write!(f, ", {} ago", ago(self.at))
+    }
+}
+
+/// Counts of the stored rows and logged changes, see `stats`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub dependencies: usize,
+    pub snippets: usize,
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+    /// unix time of the first logged change
+    pub since: Option<u64>,
+    /// the most often updated dependencies and how often, most first
+    pub most_updated: Vec<(String, usize)>,
+}
+
+impl std::fmt::Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // 42 dependencies, 7 snippets
+        // 120 changes since 30d ago: 50 added, 60 updated, 10 removed
+        // most updated: serde (12), tokio (8)
+        write!(
+            f,
+            "{} dependencies, {} snippets",
+            self.dependencies, self.snippets
+        )?;
+        if let Some(since) = self.since {
+            write!(
+                f,
+                "\n{} changes since {} ago: {} added, {} updated, {} removed",
+                self.added + self.updated + self.removed,
+                ago(since),
+                self.added,
+                self.updated,
+                self.removed
+            )?;
+        }
+        if !self.most_updated.is_empty() {
+            let most = self
+                .most_updated
+                .iter()
+                .map(|(name, count)| format!("{} ({})", name, count))
+                .collect::<Vec<String>>();
+            write!(f, "\nmost updated: {}", most.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// How many of the most often updated dependencies `stats` names.
+const MOST_UPDATED: usize = 5;
+
+fn sql(e: rusqlite::Error) -> LimpError {
+    LimpError::InvalidStorage(e.to_string())
+}
+
+fn open(path: &Path) -> Result<Connection, LimpError> {
+    fs::create_dir_all(path.parent().unwrap_or(Path::new("./")))?;
+    let conn = Connection::open(path).map_err(sql)?;
+    conn.execute_batch(SCHEMA).map_err(sql)?;
+    Ok(conn)
+}
+
+fn user() -> Option<String> {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .ok()
+}
+
+/// The rows of `query` as its first column mapped to the JSON in its second.
+fn rows(conn: &Connection, query: &str) -> Result<Map<String, Value>, LimpError> {
+    let mut stmt = conn.prepare(query).map_err(sql)?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(sql)?;
+    let mut map = Map::new();
+    for row in rows {
+        let (key, data) = row.map_err(sql)?;
+        map.insert(key, serde_json::from_str(&data)?);
+    }
+    Ok(map)
+}
+
+/// Reads the database as the document a [`JsonStorage`] is deserialized
+/// from, `None` if nothing was written to it yet.
+pub fn read(path: &Path) -> Result<Option<Value>, LimpError> {
+    let conn = open(path)?;
+    let version: Option<String> = conn
+        .query_row(
+            "SELECT value FROM meta WHERE key = 'schema_version'",
+            [],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(sql)?;
+    let Some(version) = version else {
+        return Ok(None);
+    };
+    Ok(Some(json!({
+        "schema_version": version.parse::<u64>().unwrap_or_default(),
+        "dependencies": rows(&conn, "SELECT name, data FROM dependencies")?,
+        "snippets": rows(&conn, "SELECT file, data FROM snippets")?,
+    })))
+}
+
+/// Replaces the contents of the database with `storage`, logging the
+/// dependencies that were added, updated or removed.
+pub fn write(path: &Path, storage: &JsonStorage) -> Result<(), LimpError> {
+    let mut conn = open(path)?;
+    let tx = conn.transaction().map_err(sql)?;
+    let old = {
+        let mut stmt = tx
+            .prepare("SELECT name, version FROM dependencies")
+            .map_err(sql)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(sql)?;
+        rows.collect::<Result<HashMap<String, String>, _>>()
+            .map_err(sql)?
+    };
+
+    let mut events = vec![];
+    for (name, dep) in &storage.dependencies {
+        match old.get(name) {
+            None => events.push(("added", name, &dep.version)),
+            Some(version) if *version != dep.version => {
+                events.push(("updated", name, &dep.version))
+            }
+            _ => {}
+        }
+    }
+    for (name, version) in &old {
+        if !storage.dependencies.contains_key(name) {
+            events.push(("removed", name, version));
+        }
+    }
+    let (at, user) = (now(), user());
+    for (action, name, version) in events {
+        tx.execute(
+            "INSERT INTO events (at, user, action, name, version) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![at, user, action, name, version],
+        )
+        .map_err(sql)?;
+    }
+
+    tx.execute("DELETE FROM dependencies", []).map_err(sql)?;
+    for (name, dep) in &storage.dependencies {
+        tx.execute(
+            "INSERT INTO dependencies (name, version, data) VALUES (?1, ?2, ?3)",
+            params![name, dep.version, serde_json::to_string(dep)?],
+        )
+        .map_err(sql)?;
+    }
+    tx.execute("DELETE FROM snippets", []).map_err(sql)?;
+    for (file, meta) in &storage.snippets {
+        tx.execute(
+            "INSERT INTO snippets (file, data) VALUES (?1, ?2)",
+            params![file, serde_json::to_string(meta)?],
+        )
+        .map_err(sql)?;
+    }
+    tx.execute(
+        "INSERT OR REPLACE INTO meta (key, value) VALUES ('schema_version', ?1)",
+        params![storage.schema_version.to_string()],
+    )
+    .map_err(sql)?;
+    tx.commit().map_err(sql)
+}
+
+/// The logged changes, oldest first, only those of `name` if given.
+pub fn events(path: &Path, name: Option<&str>) -> Result<Vec<Event>, LimpError> {
+    let conn = open(path)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT at, user, action, name, version FROM events
+             WHERE ?1 IS NULL OR name = ?1 ORDER BY id",
+        )
+        .map_err(sql)?;
+    let rows = stmt
+        .query_map(params![name], |row| {
+            Ok(Event {
+                at: row.get(0)?,
+                user: row.get(1)?,
+                action: row.get(2)?,
+                name: row.get(3)?,
+                version: row.get(4)?,
+            })
+        })
+        .map_err(sql)?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(sql)
+}
+
+/// Counts the stored dependencies and snippets and their logged changes.
+pub fn stats(path: &Path) -> Result<Stats, LimpError> {
+    let conn = open(path)?;
+    let count = |table: &str| {
+        conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+            row.get::<_, usize>(0)
+        })
+        .map_err(sql)
+    };
+    let mut stats = Stats {
+        dependencies: count("dependencies")?,
+        snippets: count("snippets")?,
+        since: conn
+            .query_row("SELECT MIN(at) FROM events", [], |row| row.get(0))
+            .map_err(sql)?,
+        ..Default::default()
+    };
+
+    let mut stmt = conn
+        .prepare("SELECT action, COUNT(*) FROM events GROUP BY action")
+        .map_err(sql)?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, usize>(1)?))
+        })
+        .map_err(sql)?;
+    for row in rows {
+        let (action, count) = row.map_err(sql)?;
+        match action.as_str() {
+            "added" => stats.added = count,
+            "updated" => stats.updated = count,
+            "removed" => stats.removed = count,
+            _ => {}
+        }
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT name, COUNT(*) AS n FROM events WHERE action = 'updated'
+             GROUP BY name ORDER BY n DESC, name LIMIT ?1",
+        )
+        .map_err(sql)?;
+    let rows = stmt
+        .query_map(params![MOST_UPDATED], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(sql)?;
+    stats.most_updated = rows.collect::<Result<Vec<_>, _>>().map_err(sql)?;
+    Ok(stats)
+}
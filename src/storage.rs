@@ -1,51 +1,341 @@
-use std::{collections::HashMap, path::Path};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    ffi::OsString,
+    fs::{self, File},
+    io::{ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
+use semver::VersionReq;
 use serde::{Deserialize, Serialize};
+use toml_edit::{DocumentMut, Item, Table};
 
-use crate::{crates::CratesIoDependency, error::LimpError, files};
+use crate::{
+    crates::{CratesIoDependency, Registry},
+    error::LimpError,
+    files, logging,
+    migrations::{self, SCHEMA_VERSION},
+    paths,
+    report::Progress,
+};
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[cfg(feature = "sqlite")]
+use crate::sqlite;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyKind {
+    #[default]
+    Normal,
+    Dev,
+    Build,
+}
+
+impl DependencyKind {
+    pub const ALL: [DependencyKind; 3] = [
+        DependencyKind::Normal,
+        DependencyKind::Dev,
+        DependencyKind::Build,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DependencyKind::Normal => "normal",
+            DependencyKind::Dev => "dev",
+            DependencyKind::Build => "build",
+        }
+    }
+
+    pub fn table_name(&self) -> &'static str {
+        match self {
+            DependencyKind::Normal => "dependencies",
+            DependencyKind::Dev => "dev-dependencies",
+            DependencyKind::Build => "build-dependencies",
+        }
+    }
+}
+
+/// Where a dependency comes from.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum DependencySource {
+    /// crates.io or the registry named by `JsonDependency::registry`
+    #[default]
+    Registry,
+    Git {
+        url: String,
+        #[serde(default)]
+        branch: Option<String>,
+        #[serde(default)]
+        tag: Option<String>,
+        #[serde(default)]
+        rev: Option<String>,
+    },
+    Path {
+        path: String,
+    },
+}
+
+/// Version keyword for the latest release, the same as giving no version.
+pub const LATEST: &str = "latest";
+
+/// `version` unless it is the `latest` keyword.
+fn requirement(version: Option<&str>) -> Option<&str> {
+    version.filter(|v| !v.trim().eq_ignore_ascii_case(LATEST))
+}
+
+/// Snippet variant linked when none is named, e.g. by `limp new -p`.
+pub const DEFAULT_VARIANT: &str = "default";
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct JsonDependency {
     pub name: String,
     pub version: String,
     #[serde(default)]
     pub features: Option<Vec<String>>,
+    /// Linked snippets by variant name, see [`DEFAULT_VARIANT`].
+    #[serde(default)]
+    pub snippets: BTreeMap<String, String>,
+    #[serde(default)]
+    pub kind: DependencyKind,
+    #[serde(default)]
+    pub rust_version: Option<String>,
+    /// Unix time of the last change to the stored version.
+    #[serde(default)]
+    pub updated: Option<u64>,
+    /// Name of the registry the crate comes from, crates.io when unset.
+    #[serde(default)]
+    pub registry: Option<String>,
+    /// `default-features` as written to Cargo.toml, left out when unset.
     #[serde(default)]
-    pub path_to_snippet: Option<String>,
+    pub default_features: Option<bool>,
+    #[serde(default)]
+    pub optional: bool,
+    /// Key to declare the crate under in Cargo.toml, with `package` naming
+    /// the crate.
+    #[serde(default)]
+    pub rename: Option<String>,
+    #[serde(default)]
+    pub source: DependencySource,
+    /// Kept at its version by `limp update`.
+    #[serde(default)]
+    pub pinned: bool,
+    /// Requirement `limp update` stays within, e.g. `<1` or `^1`.
+    #[serde(default)]
+    pub constraint: Option<String>,
+    /// Lets `limp update` move to pre-releases.
+    #[serde(default)]
+    pub allow_prerelease: bool,
 }
 
 impl std::fmt::Display for JsonDependency {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         // name = version
         // or
-        // name {"version" = version, "features" = [...features]}
+        // name {"version" = version, "git" = url, "package" = name, "default-features" = false, "features" = [...features], "optional" = true, "registry" = registry}
+        if self.is_plain() {
+            return write!(f, "{} = \"{}\"", &self.name, &self.version);
+        }
+        let mut fields = self
+            .source_fields()
+            .iter()
+            .map(|(key, value)| format!("{} = \"{}\"", key, value))
+            .collect::<Vec<String>>();
+        if self.rename.is_some() {
+            fields.push(format!("package = \"{}\"", self.name));
+        }
+        if let Some(default_features) = self.default_features {
+            fields.push(format!("default-features = {}", default_features));
+        }
         if let Some(features) = &self.features {
             let deps = features
                 .iter()
                 .map(|f| format!("\"{f}\""))
                 .collect::<Vec<String>>()
                 .join(", ");
-            let res = format!(
-                "{} = (version = \"{}\", features = [{}])",
-                &self.name, &self.version, deps
+            fields.push(format!("features = [{}]", deps));
+        }
+        if self.optional {
+            fields.push("optional = true".to_string());
+        }
+        if let Some(registry) = &self.registry {
+            fields.push(format!("registry = \"{}\"", registry));
+        }
+        write!(f, "{} = {{{}}}", self.toml_name(), fields.join(", "))
+    }
+}
+
+/// Member-side entry of a dependency declared in `[workspace.dependencies]`.
+pub struct WorkspaceDependency<'a>(&'a JsonDependency);
+
+impl std::fmt::Display for WorkspaceDependency<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // name = {workspace = true}
+        // or
+        // name = {workspace = true, optional = true}
+        if self.0.optional {
+            write!(
+                f,
+                "{} = {{ workspace = true, optional = true }}",
+                self.0.toml_name()
             )
-            .replace("(", "{")
-            .replace(")", "}");
-            write!(f, "{}", &res)
         } else {
-            write!(f, "{} = \"{}\"", &self.name, &self.version)
+            write!(f, "{} = {{ workspace = true }}", self.0.toml_name())
         }
     }
 }
 
+fn default_snippet(path: Option<&str>) -> BTreeMap<String, String> {
+    path.map(|p| (DEFAULT_VARIANT.to_string(), p.to_string()))
+        .into_iter()
+        .collect()
+}
+
 impl JsonDependency {
-    pub fn new(name: &str) -> Result<Self, LimpError> {
-        let crateiodep = CratesIoDependency::from_cratesio(name)?;
+    pub fn as_workspace(&self) -> WorkspaceDependency<'_> {
+        WorkspaceDependency(self)
+    }
+    /// The key of the dependency in Cargo.toml.
+    pub fn toml_name(&self) -> &str {
+        self.rename.as_deref().unwrap_or(&self.name)
+    }
+    /// Path of the default snippet.
+    pub fn snippet(&self) -> Option<&String> {
+        self.snippets.get(DEFAULT_VARIANT)
+    }
+    /// Links `path` as `variant`, or as the default snippet when `None`.
+    pub fn set_snippet(&mut self, variant: Option<&str>, path: String) {
+        self.snippets
+            .insert(variant.unwrap_or(DEFAULT_VARIANT).to_string(), path);
+    }
+    /// The dependency with its `variant` snippet as the only, default one,
+    /// for `name:variant` in `limp init -d`.
+    pub fn with_variant(self, variant: &str) -> Result<Self, LimpError> {
+        let path = self
+            .snippets
+            .get(variant)
+            .cloned()
+            .ok_or_else(|| LimpError::SnippetNotFound(format!("{}:{}", self.name, variant)))?;
+        Ok(Self {
+            snippets: default_snippet(Some(&path)),
+            ..self
+        })
+    }
+    /// Whether both entries would be written to Cargo.toml the same way.
+    pub fn same_as(&self, other: &JsonDependency) -> bool {
+        self.version == other.version
+            && self.features == other.features
+            && self.kind == other.kind
+            && self.registry == other.registry
+            && self.default_features == other.default_features
+            && self.optional == other.optional
+            && self.rename == other.rename
+            && self.source == other.source
+    }
+    /// Whether the dependency can be written as `name = "version"`.
+    pub fn is_plain(&self) -> bool {
+        self.source == DependencySource::Registry
+            && self.features.is_none()
+            && self.registry.is_none()
+            && self.default_features.is_none()
+            && !self.optional
+            && self.rename.is_none()
+    }
+    /// The `version`, `git`, `branch`, `tag`, `rev`, and `path` keys of the
+    /// dependency's table that are set.
+    pub fn source_fields(&self) -> Vec<(&'static str, &str)> {
+        let mut fields = vec![];
+        if !self.version.is_empty() {
+            fields.push(("version", self.version.as_str()));
+        }
+        match &self.source {
+            DependencySource::Registry => {}
+            DependencySource::Git {
+                url,
+                branch,
+                tag,
+                rev,
+            } => {
+                fields.push(("git", url.as_str()));
+                for (key, value) in [("branch", branch), ("tag", tag), ("rev", rev)] {
+                    if let Some(value) = value {
+                        fields.push((key, value.as_str()));
+                    }
+                }
+            }
+            DependencySource::Path { path } => fields.push(("path", path.as_str())),
+        }
+        fields
+    }
+    /// A dependency that isn't resolved through a registry. `version` is only
+    /// written to Cargo.toml when given.
+    pub fn from_source(
+        name: &str,
+        source: DependencySource,
+        version: Option<&str>,
+        features: Option<&[String]>,
+        path_to_snippet: Option<&str>,
+    ) -> Result<Self, LimpError> {
+        if let Some(path) = path_to_snippet {
+            if !Path::new(path).exists() {
+                return Err(LimpError::SnippetNotFound(path.to_string()));
+            }
+        }
+        let version = requirement(version);
+        if let Some(version) = version {
+            VersionReq::parse(version)
+                .map_err(|_| LimpError::InvalidVersion(version.to_string()))?;
+        }
         Ok(Self {
             name: name.to_string(),
-            version: crateiodep.get_version(0)?.num.clone(),
+            version: version.unwrap_or_default().to_string(),
+            features: features.map(|f| f.to_vec()),
+            snippets: default_snippet(path_to_snippet),
+            source,
+            ..Default::default()
+        })
+    }
+    pub fn new(name: &str, registry: &Registry) -> Result<Self, LimpError> {
+        Self::from_crate(name, &registry.get_crate(name)?, registry)
+    }
+    /// Resolves the latest versions of `names` with up to `jobs` concurrent
+    /// lookups. Returns the dependencies that resolved, in order, and the
+    /// errors of those that didn't.
+    pub fn new_many(
+        names: &[String],
+        registry: &Registry,
+        jobs: usize,
+    ) -> (Vec<Self>, Vec<(String, LimpError)>) {
+        let mut deps = vec![];
+        let mut errors = vec![];
+        for (name, result) in names.iter().zip(registry.get_crates(names, jobs)) {
+            match result.and_then(|c| Self::from_crate(name, &c, registry)) {
+                Ok(dep) => deps.push(dep),
+                Err(e) => errors.push((name.clone(), e)),
+            }
+        }
+        (deps, errors)
+    }
+    fn from_crate(
+        name: &str,
+        crateiodep: &CratesIoDependency,
+        registry: &Registry,
+    ) -> Result<Self, LimpError> {
+        let latest = crateiodep.latest(registry.msrv.as_ref())?;
+        Ok(Self {
+            name: name.to_string(),
+            version: latest.num,
             features: None,
-            path_to_snippet: None,
+            snippets: BTreeMap::new(),
+            kind: DependencyKind::default(),
+            rust_version: latest.rust_version,
+            updated: None,
+            registry: registry.default.clone(),
+            source: DependencySource::Registry,
+            ..Default::default()
         })
     }
     pub fn new_full(
@@ -53,8 +343,10 @@ impl JsonDependency {
         version: Option<&str>,
         features: Option<&[String]>,
         path_to_snippet: Option<&str>,
+        allow_yanked: bool,
+        registry: &Registry,
     ) -> Result<Self, LimpError> {
-        let crateiodep = CratesIoDependency::from_cratesio(name)?;
+        let crateiodep = registry.get_crate(name)?;
 
         if let Some(path) = path_to_snippet {
             if !Path::new(path).exists() {
@@ -62,25 +354,32 @@ impl JsonDependency {
             }
         }
 
-        if let Some(version) = version {
-            let all_versions = crateiodep.get_all_versions();
-            let finded_version = all_versions
-                .iter()
-                .find(|v| v.num == version)
-                .ok_or_else(|| LimpError::VersionNotFound(format!("{}/{}", name, version)))?;
-
-            if let Some(features) = features {
-                let finded_features = finded_version.get_features().ok_or_else(|| {
-                    LimpError::IncompatibleFeatures(format!("{}/{}", name, version))
-                })?;
-
-                if !features.iter().all(|f| finded_features.contains(f)) {
-                    return Err(LimpError::IncompatibleFeatures(format!(
-                        "{}/{}",
-                        name, version
-                    )));
+        let msrv = registry.msrv.as_ref();
+        let version = requirement(version);
+        let resolved = if let Some(version) = version {
+            let req = VersionReq::parse(version)
+                .map_err(|_| LimpError::InvalidVersion(version.to_string()))?;
+            let finded_version = match crateiodep.find_matching(&req, allow_yanked, msrv) {
+                Some(v) => v,
+                None if crateiodep.find_matching(&req, allow_yanked, None).is_some() => {
+                    return Err(LimpError::IncompatibleRustVersion(format!(
+                        "{}/{} with rust {}",
+                        name,
+                        version,
+                        msrv.map(|m| m.to_string()).unwrap_or_default()
+                    )))
                 }
-            }
+                None if crateiodep.find_matching(&req, true, msrv).is_some() => {
+                    return Err(LimpError::YankedVersion(format!("{}/{}", name, version)))
+                }
+                None => return Err(LimpError::VersionNotFound(format!("{}/{}", name, version))),
+            };
+            finded_version
+        } else {
+            crateiodep.latest(msrv)?
+        };
+        if let Some(features) = features {
+            resolved.check_features(features)?;
         }
 
         // if let Some(unwrapped_version) = version {
@@ -116,39 +415,514 @@ impl JsonDependency {
 
         Ok(Self {
             name: name.to_string(),
-            version: version
-                .unwrap_or(&crateiodep.get_version(0)?.num)
-                .to_string(),
+            version: version.map(String::from).unwrap_or(resolved.num),
             features: features.map(|f| f.to_vec()),
-            path_to_snippet: path_to_snippet.map(String::from),
+            snippets: default_snippet(path_to_snippet),
+            kind: DependencyKind::default(),
+            rust_version: resolved.rust_version,
+            updated: None,
+            registry: registry.default.clone(),
+            source: DependencySource::Registry,
+            ..Default::default()
         })
     }
-    pub fn update(&mut self) -> Result<(), LimpError> {
-        let crateiodep = CratesIoDependency::from_cratesio(&self.name)?;
-        self.version = crateiodep.get_version(0)?.num.clone();
-        Ok(())
+    /// Moves the dependency to the latest version, or the newest one
+    /// satisfying its constraint, but never to a lower version than the
+    /// stored one. Dependencies on a pre-release may move to newer
+    /// pre-releases. Git and path dependencies are left as they are.
+    /// Returns the stored features the latest version doesn't have, which
+    /// are handled as `policy` says.
+    pub fn update(
+        &mut self,
+        registry: &Registry,
+        policy: UpdatePolicy,
+    ) -> Result<Vec<String>, LimpError> {
+        if self.source != DependencySource::Registry {
+            return Ok(vec![]);
+        }
+        let krate = registry.get_crate_from(&self.name, self.registry.as_deref())?;
+        let latest = match &self.constraint {
+            Some(constraint) => krate
+                .find_matching(&constraint_req(constraint)?, false, registry.msrv.as_ref())
+                .ok_or_else(|| {
+                    LimpError::VersionNotFound(format!("{}/{}", self.name, constraint))
+                })?,
+            None => krate.latest_release(
+                registry.msrv.as_ref(),
+                policy.allow_prerelease || self.allow_prerelease || self.is_prerelease(),
+            )?,
+        };
+        // e.g. a backport published after the stored version
+        let older = semver::Version::parse(&latest.num)
+            .ok()
+            .zip(semver::Version::parse(&self.version).ok())
+            .is_some_and(|(latest, stored)| latest <= stored);
+        if older {
+            return Ok(vec![]);
+        }
+        let missing = match &self.features {
+            Some(features) if self.version != latest.num => {
+                let graph = latest.feature_graph();
+                features
+                    .iter()
+                    .filter(|f| !graph.contains(f))
+                    .cloned()
+                    .collect::<Vec<String>>()
+            }
+            _ => vec![],
+        };
+        if !missing.is_empty() {
+            match policy.on_feature_mismatch {
+                FeatureMismatch::Keep => return Ok(missing),
+                FeatureMismatch::Drop => {
+                    if let Some(features) = self.features.as_mut() {
+                        features.retain(|f| !missing.contains(f));
+                    }
+                }
+                FeatureMismatch::Fail => {
+                    latest.check_features(self.features.as_deref().unwrap_or_default())?
+                }
+            }
+        }
+        if self.version != latest.num {
+            self.updated = Some(now());
+        }
+        self.version = latest.num;
+        self.rust_version = latest.rust_version;
+        Ok(missing)
+    }
+    /// Whether the stored version is an alpha, beta, rc or other pre-release.
+    pub fn is_prerelease(&self) -> bool {
+        semver::Version::parse(&self.version).is_ok_and(|v| !v.pre.is_empty())
+    }
+}
+
+fn constraint_req(constraint: &str) -> Result<VersionReq, LimpError> {
+    VersionReq::parse(constraint).map_err(|_| LimpError::InvalidVersion(constraint.to_string()))
+}
+
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// What `update` does with a dependency whose stored features don't all
+/// exist in its latest version.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FeatureMismatch {
+    /// stay on the current version
+    #[default]
+    Keep,
+    /// update and forget the missing features
+    Drop,
+    /// fail the update of the dependency
+    Fail,
+}
+
+impl FeatureMismatch {
+    pub const NAMES: [&'static str; 3] = ["keep", "drop", "fail"];
+
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "drop" => FeatureMismatch::Drop,
+            "fail" => FeatureMismatch::Fail,
+            _ => FeatureMismatch::Keep,
+        }
+    }
+}
+
+/// How `JsonDependency::update` picks and checks the new version.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UpdatePolicy {
+    pub on_feature_mismatch: FeatureMismatch,
+    /// move every dependency to pre-releases, not only those allowing them
+    pub allow_prerelease: bool,
+}
+
+/// What `JsonStorage::update_many` did besides updating.
+#[derive(Debug, Default)]
+pub struct UpdateOutcome {
+    /// `(name, features)` of the dependencies with stored features their
+    /// latest version doesn't have, sorted by name
+    pub mismatches: Vec<(String, Vec<String>)>,
+    pub errors: Vec<(String, LimpError)>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortKey {
+    /// alphabetical
+    #[default]
+    Name,
+    /// newest version first
+    Version,
+    /// most recently changed first
+    Updated,
+}
+
+impl SortKey {
+    pub const NAMES: [&'static str; 3] = ["name", "version", "updated"];
+
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "version" => SortKey::Version,
+            "updated" => SortKey::Updated,
+            _ => SortKey::Name,
+        }
     }
 }
 
-#[derive(Deserialize, Serialize, Debug, Default)]
+/// What `limp list --tree` groups dependencies by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GroupKey {
+    /// normal, dev or build
+    #[default]
+    Kind,
+    /// first letter of the name
+    Letter,
+    /// whether a snippet is linked
+    Snippet,
+    /// tags of the linked snippets, a dependency with several is in each
+    Tag,
+}
+
+impl GroupKey {
+    pub const NAMES: [&'static str; 4] = ["kind", "letter", "snippet", "tag"];
+
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "letter" => GroupKey::Letter,
+            "snippet" => GroupKey::Snippet,
+            "tag" => GroupKey::Tag,
+            _ => GroupKey::Kind,
+        }
+    }
+}
+
+/// Outcome of `JsonStorage::merge`, by dependency name.
+#[derive(Debug, Default)]
+pub struct MergeSummary {
+    pub added: Vec<String>,
+    pub replaced: Vec<String>,
+    pub kept: Vec<String>,
+}
+
+impl std::fmt::Display for MergeSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} added, {} replaced, {} kept",
+            self.added.len(),
+            self.replaced.len(),
+            self.kept.len()
+        )
+    }
+}
+
+/// File format of the dependency storage, picked with the `storage_format`
+/// setting.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageFormat {
+    /// `dependencies.json`
+    #[default]
+    Json,
+    /// `dependencies.toml`, which keeps the comments written into it
+    Toml,
+    /// `dependencies.db`, which also logs the changes to the dependencies
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+}
+
+impl StorageFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Toml => "toml",
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite => "db",
+        }
+    }
+
+    /// The format of the storage file at `path`, by its extension.
+    pub fn of<P: AsRef<Path>>(path: P) -> Self {
+        match path.as_ref().extension() {
+            Some(ext) if ext == "toml" => Self::Toml,
+            #[cfg(feature = "sqlite")]
+            Some(ext) if ext == "db" => Self::Sqlite,
+            _ => Self::Json,
+        }
+    }
+
+    pub fn all() -> Vec<Self> {
+        let mut all = vec![Self::Json, Self::Toml];
+        #[cfg(feature = "sqlite")]
+        all.push(Self::Sqlite);
+        all
+    }
+
+    /// Reads the storage document at `path`, `None` if it is empty.
+    fn read(self, path: &Path) -> Result<Option<serde_json::Value>, LimpError> {
+        #[cfg(feature = "sqlite")]
+        if self == Self::Sqlite {
+            return sqlite::read(path);
+        }
+        let mut content = String::new();
+        files::open(path)?.read_to_string(&mut content)?;
+        if content.trim().is_empty() {
+            return Ok(None);
+        }
+        match self {
+            Self::Toml => toml::from_str(&content)
+                .map(Some)
+                .map_err(|e| LimpError::InvalidStorage(e.to_string())),
+            _ => Ok(Some(serde_json::from_str(&content)?)),
+        }
+    }
+}
+
+/// Copies the comments and formatting of the keys and tables of `old` that
+/// are still in `new`.
+fn keep_comments(old: &Table, new: &mut Table) {
+    *new.decor_mut() = old.decor().clone();
+    for (mut key, item) in new.iter_mut() {
+        let Some((old_key, old_item)) = old.get_key_value(key.get()) else {
+            continue;
+        };
+        *key.leaf_decor_mut() = old_key.leaf_decor().clone();
+        match (old_item, item) {
+            (Item::Table(old), Item::Table(new)) => keep_comments(old, new),
+            (Item::Value(old), Item::Value(new)) => *new.decor_mut() = old.decor().clone(),
+            _ => {}
+        }
+    }
+}
+
+/// The storage directory of the storage file at `path`.
+fn root(path: &Path) -> &Path {
+    path.parent().unwrap_or(Path::new(""))
+}
+
+/// `path` with `suffix` appended to its file name.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct JsonStorage {
+    #[serde(default)]
+    pub schema_version: u64,
     #[serde(default)]
     pub dependencies: HashMap<String, JsonDependency>,
+    /// Metadata of the files in the snippets directory, by file name.
+    #[serde(default)]
+    pub snippets: BTreeMap<String, SnippetMeta>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct SnippetMeta {
+    /// URL the snippet was downloaded from, fetched again by
+    /// `limp snippet refresh`.
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// local file the snippet was copied from, copied again by
+    /// `limp snippet refresh`
+    #[serde(default)]
+    pub path: Option<String>,
+    /// checksum of the snippet as it was stored, see `snippets::checksum`
+    #[serde(default)]
+    pub hash: Option<String>,
+}
+
+impl Default for JsonStorage {
+    fn default() -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            dependencies: HashMap::new(),
+            snippets: BTreeMap::new(),
+        }
+    }
+}
+
+/// How long to wait for another limp to release the storage.
+pub const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+const LOCK_RETRY: Duration = Duration::from_millis(50);
+/// Locks older than this were left behind by a limp that didn't exit cleanly.
+/// Commands hold the lock while they look crates up, which the registry's
+/// timeouts keep well below this.
+const LOCK_STALE: Duration = Duration::from_secs(600);
+
+/// Advisory lock on a storage file, held as a `<file>.lock` file next to it
+/// and released when dropped.
+#[derive(Debug)]
+pub struct StorageLock {
+    path: PathBuf,
+}
+
+impl StorageLock {
+    /// Takes the lock on the storage at `path`, waiting up to `timeout` for
+    /// another holder to release it.
+    pub fn acquire<P: AsRef<Path>>(path: P, timeout: Duration) -> Result<Self, LimpError> {
+        let path = with_suffix(path.as_ref(), ".lock");
+        fs::create_dir_all(path.parent().unwrap_or(Path::new("./")))?;
+        let start = Instant::now();
+        loop {
+            match File::options().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    writeln!(file, "{}", std::process::id())?;
+                    return Ok(Self { path });
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    let stale = fs::metadata(&path)
+                        .and_then(|m| m.modified())
+                        .ok()
+                        .and_then(|m| m.elapsed().ok())
+                        .is_some_and(|age| age > LOCK_STALE);
+                    if stale {
+                        let _ = fs::remove_file(&path);
+                        continue;
+                    }
+                    if start.elapsed() >= timeout {
+                        return Err(LimpError::StorageLocked(path.display().to_string()));
+                    }
+                    thread::sleep(LOCK_RETRY);
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+impl Drop for StorageLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
 }
 
 impl JsonStorage {
+    /// Loads the storage, upgrading files written in an older format in
+    /// place. An empty or missing file is an empty storage, unless the
+    /// storage is still kept in another [`StorageFormat`] next to it: that
+    /// file is converted to `path` and kept as a `.bak`.
     pub fn load<P: AsRef<Path>>(path: P) -> Result<JsonStorage, LimpError> {
-        let file = files::open(path)?;
-        Ok(serde_json::from_reader(file).unwrap_or(JsonStorage::default()))
+        let lock = StorageLock::acquire(&path, LOCK_TIMEOUT)?;
+        Self::load_locked(path, &lock)
+    }
+    /// Same as `load`, under a `lock` the caller holds until the changed
+    /// storage is saved with `save_locked`, so that no other limp saves in
+    /// between and has its changes overwritten.
+    pub fn load_locked<P: AsRef<Path>>(
+        path: P,
+        _lock: &StorageLock,
+    ) -> Result<JsonStorage, LimpError> {
+        let path = path.as_ref();
+        let format = StorageFormat::of(path);
+        let mut doc = format.read(path)?;
+        let mut converted = None;
+        for other in StorageFormat::all().into_iter().filter(|f| *f != format) {
+            let previous = path.with_extension(other.extension());
+            if doc.is_some() || !previous.is_file() {
+                continue;
+            }
+            doc = other.read(&previous)?;
+            converted = Some(previous);
+        }
+        let Some(mut doc) = doc else {
+            return Ok(JsonStorage::default());
+        };
+        let migrated = migrations::migrate(&mut doc, root(path))?;
+        let mut storage: JsonStorage = serde_json::from_value(doc)?;
+        storage.resolve_paths(root(path));
+        if migrated || converted.is_some() {
+            storage.write(path)?;
+        }
+        if let Some(previous) = converted {
+            fs::rename(&previous, with_suffix(&previous, ".bak"))?;
+        }
+        Ok(storage)
     }
     pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), LimpError> {
+        let lock = StorageLock::acquire(&path, LOCK_TIMEOUT)?;
+        self.save_locked(path, &lock)
+    }
+    /// Same as `save`, under a `lock` taken before `load_locked`.
+    pub fn save_locked<P: AsRef<Path>>(
+        &self,
+        path: P,
+        _lock: &StorageLock,
+    ) -> Result<(), LimpError> {
+        self.write(&path)?;
+        logging::info(
+            "storage",
+            &format!(
+                "wrote {} dependencies to {}",
+                self.dependencies.len(),
+                path.as_ref().display()
+            ),
+        );
+        Ok(())
+    }
+    fn write<P: AsRef<Path>>(&self, path: P) -> Result<(), LimpError> {
+        let path = path.as_ref();
+        let stored = self.stored_paths(root(path));
+        #[cfg(feature = "sqlite")]
+        if StorageFormat::of(path) == StorageFormat::Sqlite {
+            return sqlite::write(path, &stored);
+        }
+        if StorageFormat::of(path) == StorageFormat::Toml {
+            let invalid = |e: &dyn std::fmt::Display| LimpError::InvalidStorage(e.to_string());
+            let mut doc = toml::to_string_pretty(&stored)
+                .map_err(|e| invalid(&e))?
+                .parse::<DocumentMut>()
+                .map_err(|e| invalid(&e))?;
+            if let Ok(old) = fs::read_to_string(path)
+                .unwrap_or_default()
+                .parse::<DocumentMut>()
+            {
+                keep_comments(old.as_table(), doc.as_table_mut());
+            }
+            fs::create_dir_all(path.parent().unwrap_or(Path::new("./")))?;
+            fs::write(path, doc.to_string())?;
+            return Ok(());
+        }
         let file = files::open(path)?;
         file.set_len(0)?;
-        serde_json::to_writer(file, self)?;
+        serde_json::to_writer(file, &stored)?;
         Ok(())
     }
 
+    /// Turns the snippet paths as stored into paths in the storage
+    /// directory `root`, see [`paths::from_stored`].
+    pub fn resolve_paths(&mut self, root: &Path) {
+        for dep in self.dependencies.values_mut() {
+            for path in dep.snippets.values_mut() {
+                *path = paths::from_stored(root, path).to_string_lossy().to_string();
+            }
+        }
+    }
+
+    /// The storage with its snippet paths as they are stored for the storage
+    /// directory `root`, see [`paths::to_stored`].
+    fn stored_paths(&self, root: &Path) -> Self {
+        let mut stored = self.clone();
+        for dep in stored.dependencies.values_mut() {
+            for path in dep.snippets.values_mut() {
+                *path = paths::to_stored(root, &*path);
+            }
+        }
+        stored
+    }
+
     pub fn add(&mut self, dep: JsonDependency) {
+        let dep = JsonDependency {
+            updated: Some(now()),
+            ..dep
+        };
         self.dependencies.insert(dep.name.clone(), dep);
     }
 
@@ -163,4 +937,233 @@ impl JsonStorage {
     pub fn get_mut(&mut self, name: &str) -> Option<&mut JsonDependency> {
         self.dependencies.get_mut(name)
     }
+
+    /// Pins or unpins a stored dependency.
+    pub fn set_pinned(&mut self, name: &str, pinned: bool) -> Result<(), LimpError> {
+        let dep = self
+            .get_mut(name)
+            .ok_or_else(|| LimpError::DependencyNotFound(name.to_string()))?;
+        dep.pinned = pinned;
+        Ok(())
+    }
+
+    /// Sets or clears the requirement `limp update` keeps a stored
+    /// dependency within.
+    pub fn set_constraint(
+        &mut self,
+        name: &str,
+        constraint: Option<&str>,
+    ) -> Result<(), LimpError> {
+        if let Some(constraint) = constraint {
+            constraint_req(constraint)?;
+        }
+        let dep = self
+            .get_mut(name)
+            .ok_or_else(|| LimpError::DependencyNotFound(name.to_string()))?;
+        dep.constraint = constraint.map(String::from);
+        Ok(())
+    }
+
+    /// Lets `limp update` move a stored dependency to pre-releases, or
+    /// stops it from doing so.
+    pub fn set_allow_prerelease(&mut self, name: &str, allow: bool) -> Result<(), LimpError> {
+        let dep = self
+            .get_mut(name)
+            .ok_or_else(|| LimpError::DependencyNotFound(name.to_string()))?;
+        dep.allow_prerelease = allow;
+        Ok(())
+    }
+
+    /// Names of the pinned dependencies among `names`, or among all of them
+    /// when `names` is empty, sorted.
+    pub fn pinned(&self, names: &[String]) -> Vec<String> {
+        let mut pinned = self
+            .dependencies
+            .values()
+            .filter(|d| d.pinned && (names.is_empty() || names.contains(&d.name)))
+            .map(|d| d.name.clone())
+            .collect::<Vec<String>>();
+        pinned.sort();
+        pinned
+    }
+
+    /// Adds `deps`, calling `replace` with the stored and the new entry for
+    /// every dependency already stored with different settings. A replaced
+    /// dependency keeps its linked snippets unless the new one has its own
+    /// variant of the same name.
+    pub fn merge<F>(
+        &mut self,
+        deps: Vec<JsonDependency>,
+        mut replace: F,
+    ) -> Result<MergeSummary, LimpError>
+    where
+        F: FnMut(&JsonDependency, &JsonDependency) -> Result<bool, LimpError>,
+    {
+        let mut summary = MergeSummary::default();
+        for dep in deps {
+            match self.get(&dep.name) {
+                None => summary.added.push(dep.name.clone()),
+                Some(stored) if stored.same_as(&dep) => continue,
+                Some(stored) if replace(stored, &dep)? => {
+                    summary.replaced.push(dep.name.clone());
+                    let mut snippets = stored.snippets.clone();
+                    snippets.extend(dep.snippets);
+                    self.add(JsonDependency { snippets, ..dep });
+                    continue;
+                }
+                Some(_) => {
+                    summary.kept.push(dep.name.clone());
+                    continue;
+                }
+            }
+            self.add(dep);
+        }
+        Ok(summary)
+    }
+
+    /// Dependencies whose name contains `filter`, optionally only those with
+    /// a linked snippet, ordered by `sort` and then by name.
+    pub fn list(
+        &self,
+        sort: SortKey,
+        filter: Option<&str>,
+        with_snippets: bool,
+    ) -> Vec<&JsonDependency> {
+        let mut deps = self
+            .dependencies
+            .values()
+            .filter(|d| match filter {
+                Some(f) => d.name.contains(f),
+                None => true,
+            })
+            .filter(|d| !with_snippets || !d.snippets.is_empty())
+            .collect::<Vec<_>>();
+        deps.sort_by(|a, b| {
+            let order = match sort {
+                SortKey::Name => std::cmp::Ordering::Equal,
+                SortKey::Version => semver::Version::parse(&b.version)
+                    .ok()
+                    .cmp(&semver::Version::parse(&a.version).ok()),
+                SortKey::Updated => b.updated.cmp(&a.updated),
+            };
+            order.then_with(|| a.name.cmp(&b.name))
+        });
+        deps
+    }
+
+    /// Splits `deps` into groups sorted by name, keeping the order of `deps`
+    /// within each group.
+    pub fn group<'a>(
+        &self,
+        deps: &[&'a JsonDependency],
+        by: GroupKey,
+    ) -> Vec<(String, Vec<&'a JsonDependency>)> {
+        let mut groups = BTreeMap::<String, Vec<&JsonDependency>>::new();
+        for dep in deps.iter() {
+            let names = match by {
+                GroupKey::Kind => vec![dep.kind.name().to_string()],
+                GroupKey::Letter => vec![dep
+                    .name
+                    .chars()
+                    .next()
+                    .map(|c| c.to_uppercase().to_string())
+                    .unwrap_or_default()],
+                GroupKey::Snippet if dep.snippets.is_empty() => vec!["without snippet".to_string()],
+                GroupKey::Snippet => vec!["with snippet".to_string()],
+                GroupKey::Tag => {
+                    let tags = dep
+                        .snippets
+                        .values()
+                        .filter_map(|path| Path::new(path).file_name())
+                        .filter_map(|file| self.snippets.get(&*file.to_string_lossy()))
+                        .flat_map(|meta| meta.tags.iter().cloned())
+                        .collect::<BTreeSet<String>>();
+                    if tags.is_empty() {
+                        vec!["untagged".to_string()]
+                    } else {
+                        tags.into_iter().collect()
+                    }
+                }
+            };
+            for name in names {
+                groups.entry(name).or_default().push(dep);
+            }
+        }
+        groups.into_iter().collect()
+    }
+
+    /// Updates every dependency using at most `jobs` worker threads. Failed
+    /// updates don't stop the others and are returned by dependency name.
+    /// Dependencies whose features went missing keep their version.
+    pub fn update_all(&mut self, registry: &Registry, jobs: usize) -> Vec<(String, LimpError)> {
+        self.update_many(&[], registry, jobs, UpdatePolicy::default())
+            .errors
+    }
+
+    /// Same as `update_all` but only for the dependencies in `names`, or all
+    /// of them when `names` is empty. Pinned dependencies are skipped.
+    pub fn update_many(
+        &mut self,
+        names: &[String],
+        registry: &Registry,
+        jobs: usize,
+        policy: UpdatePolicy,
+    ) -> UpdateOutcome {
+        let mut missing = names
+            .iter()
+            .filter(|n| !self.dependencies.contains_key(*n))
+            .map(|n| (n.clone(), LimpError::DependencyNotFound(n.clone())))
+            .collect::<Vec<_>>();
+        let deps = self
+            .dependencies
+            .values_mut()
+            .filter(|d| names.is_empty() || names.contains(&d.name))
+            .filter(|d| !d.pinned)
+            .collect::<Vec<_>>();
+        let progress = Progress::new("updating", deps.len(), registry.verbosity);
+        let queue = Mutex::new(deps.into_iter());
+        let errors = Mutex::new(vec![]);
+        let mismatches = Mutex::new(vec![]);
+        thread::scope(|s| {
+            for _ in 0..jobs.max(1) {
+                s.spawn(|| loop {
+                    let next = queue.lock().unwrap().next();
+                    let Some(dep) = next else {
+                        break;
+                    };
+                    match dep.update(registry, policy) {
+                        Ok(features) if !features.is_empty() => mismatches
+                            .lock()
+                            .unwrap()
+                            .push((dep.name.clone(), features)),
+                        Ok(_) => {}
+                        Err(e) => errors.lock().unwrap().push((dep.name.clone(), e)),
+                    }
+                    progress.step(&dep.name);
+                });
+            }
+        });
+        let mut errors = errors.into_inner().unwrap();
+        errors.append(&mut missing);
+        errors.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let mut mismatches = mismatches.into_inner().unwrap();
+        mismatches.sort();
+        UpdateOutcome { mismatches, errors }
+    }
+
+    /// Dependencies whose version differs in `updated`, as
+    /// `(name, old version, new version)` sorted by name.
+    pub fn version_changes(&self, updated: &JsonStorage) -> Vec<(String, String, String)> {
+        let mut changes = self
+            .dependencies
+            .values()
+            .filter_map(|d| {
+                let new = updated.get(&d.name)?;
+                (new.version != d.version)
+                    .then(|| (d.name.clone(), d.version.clone(), new.version.clone()))
+            })
+            .collect::<Vec<_>>();
+        changes.sort();
+        changes
+    }
 }
@@ -0,0 +1,38 @@
+/// Levenshtein distance between `a` and `b`, treating `-` and `_` as equal
+/// since crate and feature names mix them freely.
+pub fn distance(a: &str, b: &str) -> usize {
+    let normalize = |c: char| {
+        if c == '_' {
+            '-'
+        } else {
+            c.to_ascii_lowercase()
+        }
+    };
+    let a = a.chars().map(normalize).collect::<Vec<char>>();
+    let b = b.chars().map(normalize).collect::<Vec<char>>();
+    let mut row = (0..=b.len()).collect::<Vec<usize>>();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// The candidate `name` was most likely meant to be: one within a few edits,
+/// or one starting with it.
+pub fn closest<'a, S: AsRef<str>>(name: &str, candidates: &'a [S]) -> Option<&'a str> {
+    let max = (name.chars().count() / 3).max(1);
+    candidates
+        .iter()
+        .map(AsRef::as_ref)
+        .filter(|c| *c != name)
+        .map(|c| (distance(name, c), c))
+        .filter(|(d, c)| *d <= max || (name.len() >= 3 && c.starts_with(name)))
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, c)| c)
+}
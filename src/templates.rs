@@ -0,0 +1,116 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::LimpError,
+    files::{copy_dir, copy_rendered, open},
+    manifest::CargoManifest,
+    parser::Vars,
+    storage::JsonDependency,
+};
+
+const TEMPLATE_FILE: &str = "template.json";
+const FILES_DIR: &str = "files";
+// never copied into a template
+const SKIPPED: [&str; 4] = ["target", ".git", "Cargo.toml", "Cargo.lock"];
+
+/// A saved project skeleton stored as `templates/<name>/template.json` plus the
+/// file tree under `templates/<name>/files/`.
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct Template {
+    pub name: String,
+    #[serde(default)]
+    pub dependencies: Vec<JsonDependency>,
+    #[serde(default)]
+    pub manifest_extras: Option<String>,
+    /// `templates/<name>`
+    #[serde(skip)]
+    dir: PathBuf,
+}
+
+impl Template {
+    /// Loads the template `name` from the `templates` directory.
+    pub fn load<P: AsRef<Path>>(templates: P, name: &str) -> Result<Self, LimpError> {
+        let dir = templates.as_ref().join(name);
+        let path = dir.join(TEMPLATE_FILE);
+        if !path.exists() {
+            return Err(LimpError::TemplateNotFound(name.to_string()));
+        }
+        Ok(Self {
+            dir,
+            ..serde_json::from_str(&fs::read_to_string(path)?)?
+        })
+    }
+
+    /// Saves the project owning `manifest` as a template, replacing any
+    /// template with the same name.
+    pub fn save_from_project<P: AsRef<Path>>(
+        templates: P,
+        name: &str,
+        manifest: &CargoManifest,
+    ) -> Result<Self, LimpError> {
+        let root = manifest.path().parent().unwrap_or(Path::new("./"));
+        let extras = manifest.extras();
+        let template = Self {
+            dir: templates.as_ref().join(name),
+            name: name.to_string(),
+            dependencies: manifest.dependencies(),
+            manifest_extras: if extras.trim().is_empty() {
+                None
+            } else {
+                Some(extras)
+            },
+        };
+
+        if template.dir.exists() {
+            fs::remove_dir_all(&template.dir)?;
+        }
+        copy_dir(root, template.dir.join(FILES_DIR), &SKIPPED)?;
+        fs::write(
+            template.dir.join(TEMPLATE_FILE),
+            serde_json::to_string_pretty(&template)?,
+        )?;
+        Ok(template)
+    }
+
+    /// Copies the template files over a freshly created project, rendering
+    /// placeholders in text files, and appends the manifest extras to its
+    /// `Cargo.toml`.
+    pub fn apply<P: AsRef<Path>>(
+        &self,
+        project_name: &str,
+        project: P,
+        vars: &Vars,
+    ) -> Result<(), LimpError> {
+        let project = project.as_ref();
+        let files = self.dir.join(FILES_DIR);
+        if files.exists() {
+            copy_rendered(&files, project, &[], project_name, vars)?;
+        }
+        if let Some(extras) = &self.manifest_extras {
+            let mut toml = open(project.join("Cargo.toml"))?;
+            writeln!(toml)?;
+            write!(toml, "{}", vars.render(project_name, extras))?;
+        }
+        Ok(())
+    }
+
+    pub fn list<P: AsRef<Path>>(templates: P) -> Result<Vec<String>, LimpError> {
+        let dir = templates.as_ref();
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+        let mut names = fs::read_dir(dir)?
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().join(TEMPLATE_FILE).exists())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect::<Vec<String>>();
+        names.sort();
+        Ok(names)
+    }
+}
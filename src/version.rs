@@ -0,0 +1,20 @@
+//! The version of limp and what it was built from, see `limp version`.
+
+/// The version from Cargo.toml.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// The commit limp was built from, unless it was built outside a git checkout,
+/// e.g. by `cargo install`.
+pub const GIT_SHA: Option<&str> = option_env!("LIMP_GIT_SHA");
+/// The target triple limp was built for.
+pub const TARGET: &str = env!("LIMP_TARGET");
+pub const USER_AGENT: &str = concat!("limp/", env!("CARGO_PKG_VERSION"));
+
+/// The version followed by the commit and target, one per line.
+pub fn verbose() -> String {
+    format!(
+        "limp {}\ncommit: {}\ntarget: {}",
+        VERSION,
+        GIT_SHA.unwrap_or("unknown"),
+        TARGET
+    )
+}
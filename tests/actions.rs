@@ -1,13 +1,22 @@
 use actions::{Action, CommandHandler};
 use error::LimpError;
 
-use crate::files::{self, open};
-use crate::storage::{JsonDependency, JsonStorage};
+use crate::context::Context;
+use crate::crates::{MockClient, SearchSort};
+use crate::files::{self, open, ProjectKind};
+use crate::ops::SyncChoice;
+use crate::report::{Format, Style, Verbosity};
+use crate::scaffold::Scaffold;
+use crate::storage::{
+    DependencyKind, DependencySource, FeatureMismatch, GroupKey, JsonDependency, JsonStorage,
+    SortKey,
+};
 use limp::*;
 use std::collections::HashMap;
 use std::fs::{self, File};
 use std::io::Write;
 use std::path::Path;
+use std::sync::Arc;
 
 // Mock for JsonStorage
 #[derive(Default)]
@@ -41,6 +50,22 @@ fn mock_create_project(name: &str, dependencies: Option<&[String]>) -> Result<()
     Ok(()) // No-op for testing
 }
 
+// Context with an empty storage in a temporary directory and a registry that only knows tokio
+fn mock_context(name: &str) -> Context {
+    let root = std::env::temp_dir().join(name);
+    let _ = fs::remove_dir_all(&root);
+    let client = MockClient::new().with_crate(serde_json::json!({
+        "crate": { "name": "tokio", "max_version": "1.41.1" },
+        "versions": [
+            { "crate": "tokio", "num": "1.41.1", "features": { "full": ["macros"], "macros": [] } },
+            { "crate": "tokio", "num": "1.0.0" },
+        ]
+    }));
+    Context::load(root, false)
+        .unwrap()
+        .with_client(Arc::new(client))
+}
+
 // Test for `CommandHandler::build` method
 #[test]
 fn test_command_handler_build() {
@@ -63,12 +88,327 @@ fn test_command_handler_parse_init() {
     let matches = CommandHandler::build().get_matches_from(args);
     let handler = CommandHandler::parse(&matches);
 
-    if let Some(Action::Init { name, dependencies }) = handler.action {
+    if let Some(Action::Init {
+        name,
+        dependencies,
+        kind,
+        check,
+        ..
+    }) = handler.action
+    {
         assert_eq!(name, "my_project");
         assert_eq!(
             dependencies.unwrap(),
             vec!["dep1".to_string(), "dep2".to_string()]
         );
+        assert_eq!(kind, ProjectKind::Bin);
+        assert!(!check);
+    } else {
+        panic!("Failed to parse init action");
+    }
+}
+
+// Test for parsing `init --lib` action from CLI args
+#[test]
+fn test_command_handler_parse_init_lib() {
+    let args = vec!["limp", "init", "my_lib", "--lib"];
+    let matches = CommandHandler::build().get_matches_from(args);
+    let handler = CommandHandler::parse(&matches);
+
+    if let Some(Action::Init { name, kind, .. }) = handler.action {
+        assert_eq!(name, "my_lib");
+        assert_eq!(kind, ProjectKind::Lib);
+    } else {
+        panic!("Failed to parse init action");
+    }
+}
+
+// Test for parsing the scaffold options of `init`
+#[test]
+fn test_command_handler_parse_init_scaffold() {
+    let args = vec![
+        "limp",
+        "init",
+        "myapp",
+        "--license",
+        "MIT",
+        "--readme",
+        "--ci",
+        "github",
+    ];
+    let matches = CommandHandler::build().get_matches_from(args);
+    let handler = CommandHandler::parse(&matches);
+
+    if let Some(Action::Init { scaffold, .. }) = handler.action {
+        assert_eq!(scaffold.license.as_deref(), Some("MIT"));
+        assert!(scaffold.readme);
+        assert_eq!(scaffold.ci.as_deref(), Some("github"));
+    } else {
+        panic!("Failed to parse init action");
+    }
+
+    let result =
+        CommandHandler::build().try_get_matches_from(["limp", "init", "myapp", "--ci", "jenkins"]);
+    assert!(result.is_err());
+}
+
+// Test for parsing `init --type`, which only accepts known project types
+#[test]
+fn test_command_handler_parse_init_type() {
+    let matches =
+        CommandHandler::build().get_matches_from(["limp", "init", "myapp", "--type", "web"]);
+    if let Some(Action::Init { preset, .. }) = CommandHandler::parse(&matches).action {
+        assert_eq!(preset.as_deref(), Some("web"));
+    } else {
+        panic!("Failed to parse init action");
+    }
+
+    for args in [
+        vec!["limp", "init", "myapp", "--type", "game"],
+        vec!["limp", "init", "myapp", "--type", "cli", "--lib"],
+    ] {
+        assert!(CommandHandler::build().try_get_matches_from(args).is_err());
+    }
+}
+
+// Test for parsing `init --bins` as a comma separated list
+#[test]
+fn test_command_handler_parse_init_bins() {
+    let matches = CommandHandler::build().get_matches_from([
+        "limp",
+        "init",
+        "tools",
+        "--bins",
+        "cli,server:tcp-server",
+    ]);
+    if let Some(Action::Init { bins, .. }) = CommandHandler::parse(&matches).action {
+        assert_eq!(bins, vec!["cli", "server:tcp-server"]);
+    } else {
+        panic!("Failed to parse init action");
+    }
+}
+
+// Test for parsing the package metadata of `init`
+#[test]
+fn test_command_handler_parse_init_metadata() {
+    let matches = CommandHandler::build().get_matches_from([
+        "limp",
+        "init",
+        "tools",
+        "--description",
+        "Handy tools",
+        "--repository",
+        "https://github.com/me/tools",
+        "--keywords",
+        "cli,tools",
+    ]);
+    if let Some(Action::Init {
+        description,
+        repository,
+        keywords,
+        ..
+    }) = CommandHandler::parse(&matches).action
+    {
+        assert_eq!(description.as_deref(), Some("Handy tools"));
+        assert_eq!(repository.as_deref(), Some("https://github.com/me/tools"));
+        assert_eq!(keywords, vec!["cli", "tools"]);
+    } else {
+        panic!("Failed to parse init action");
+    }
+}
+
+// Test for parsing `sync` with and without a preferred side
+#[test]
+fn test_command_handler_parse_sync() {
+    let parse = |args: &[&str]| {
+        let matches = CommandHandler::build().get_matches_from(args);
+        match CommandHandler::parse(&matches).action {
+            Some(Action::Sync { choice }) => choice,
+            _ => panic!("Failed to parse sync action"),
+        }
+    };
+
+    assert_eq!(parse(&["limp", "sync"]), None);
+    assert_eq!(
+        parse(&["limp", "sync", "--apply-all"]),
+        Some(SyncChoice::BumpProject)
+    );
+    assert_eq!(
+        parse(&["limp", "sync", "--prefer", "project"]),
+        Some(SyncChoice::UpdateStorage)
+    );
+}
+
+// Test for parsing `pin` and `unpin`
+#[test]
+fn test_command_handler_parse_pin() {
+    for (command, expected) in [("pin", true), ("unpin", false)] {
+        let matches = CommandHandler::build().get_matches_from(["limp", command, "serde"]);
+        if let Some(Action::Pin { name, pinned }) = CommandHandler::parse(&matches).action {
+            assert_eq!(name, "serde");
+            assert_eq!(pinned, expected);
+        } else {
+            panic!("Failed to parse {} action", command);
+        }
+    }
+}
+
+// Test for parsing `prerelease`
+#[test]
+fn test_command_handler_parse_prerelease() {
+    for (args, expected) in [
+        (vec!["limp", "prerelease", "axum"], true),
+        (vec!["limp", "prerelease", "axum", "--off"], false),
+    ] {
+        let matches = CommandHandler::build().get_matches_from(args);
+        if let Some(Action::Prerelease { name, allow }) = CommandHandler::parse(&matches).action {
+            assert_eq!(name, "axum");
+            assert_eq!(allow, expected);
+        } else {
+            panic!("Failed to parse prerelease action");
+        }
+    }
+}
+
+// Test for parsing `constrain` and `unconstrain`
+#[test]
+fn test_command_handler_parse_constrain() {
+    let matches = CommandHandler::build().get_matches_from(["limp", "constrain", "rand", "<0.9"]);
+    assert!(matches!(
+        CommandHandler::parse(&matches).action,
+        Some(Action::Constrain { name, constraint: Some(c) }) if name == "rand" && c == "<0.9"
+    ));
+
+    let matches = CommandHandler::build().get_matches_from(["limp", "unconstrain", "rand"]);
+    assert!(matches!(
+        CommandHandler::parse(&matches).action,
+        Some(Action::Constrain {
+            constraint: None,
+            ..
+        })
+    ));
+}
+
+// Test for parsing `rename` action from CLI args
+#[test]
+fn test_command_handler_parse_rename() {
+    let args = vec!["limp", "rename", "serde", "serde_json", "--resolve"];
+    let matches = CommandHandler::build().get_matches_from(args);
+    if let Some(Action::Rename { old, new, resolve }) = CommandHandler::parse(&matches).action {
+        assert_eq!(old, "serde");
+        assert_eq!(new, "serde_json");
+        assert!(resolve);
+    } else {
+        panic!("Failed to parse rename action");
+    }
+}
+
+// Test for parsing `version --verbose` and that the version comes from Cargo.toml
+#[test]
+fn test_command_handler_parse_version() {
+    assert_eq!(
+        CommandHandler::build().get_version(),
+        Some(env!("CARGO_PKG_VERSION"))
+    );
+    let matches = CommandHandler::build().get_matches_from(["limp", "version", "--verbose"]);
+    assert!(matches!(
+        CommandHandler::parse(&matches).action,
+        Some(Action::Version { verbose: true })
+    ));
+    let verbose = version::verbose();
+    assert!(verbose.starts_with(&format!("limp {}\ncommit: ", env!("CARGO_PKG_VERSION"))));
+    assert!(verbose.ends_with(&format!("target: {}", version::TARGET)));
+}
+
+// Test for parsing `self update` and `self version` actions from CLI args
+#[test]
+fn test_command_handler_parse_self() {
+    let matches = CommandHandler::build().get_matches_from(["limp", "self", "update", "--install"]);
+    assert!(matches!(
+        CommandHandler::parse(&matches).action,
+        Some(Action::SelfUpdate { install: true })
+    ));
+
+    let matches = CommandHandler::build().get_matches_from(["limp", "self", "version", "--check"]);
+    assert!(matches!(
+        CommandHandler::parse(&matches).action,
+        Some(Action::SelfVersion { check: true })
+    ));
+}
+
+// Test for parsing the `cache` subcommands from CLI args
+#[test]
+fn test_command_handler_parse_cache() {
+    let matches = CommandHandler::build().get_matches_from(["limp", "cache", "info"]);
+    assert!(matches!(
+        CommandHandler::parse(&matches).action,
+        Some(Action::CacheInfo)
+    ));
+
+    let matches = CommandHandler::build().get_matches_from(["limp", "cache", "clear"]);
+    assert!(matches!(
+        CommandHandler::parse(&matches).action,
+        Some(Action::CacheClear)
+    ));
+
+    let matches = CommandHandler::build().get_matches_from(["limp", "cache", "warm", "-j", "4"]);
+    assert!(matches!(
+        CommandHandler::parse(&matches).action,
+        Some(Action::CacheWarm { jobs: Some(4) })
+    ));
+}
+
+// Test for parsing `init --workspace` action from CLI args
+#[test]
+fn test_command_handler_parse_init_workspace() {
+    let args = vec![
+        "limp",
+        "init",
+        "my_ws",
+        "--workspace",
+        "member_a",
+        "member_b",
+    ];
+    let matches = CommandHandler::build().get_matches_from(args);
+    let handler = CommandHandler::parse(&matches);
+
+    if let Some(Action::Init {
+        name, workspace, ..
+    }) = handler.action
+    {
+        assert_eq!(name, "my_ws");
+        assert_eq!(
+            workspace.unwrap(),
+            vec!["member_a".to_string(), "member_b".to_string()]
+        );
+    } else {
+        panic!("Failed to parse init action");
+    }
+}
+
+// Test for parsing `init --var` action from CLI args
+#[test]
+fn test_command_handler_parse_init_vars() {
+    let args = vec![
+        "limp",
+        "init",
+        "my_app",
+        "--var",
+        "license=MIT",
+        "--var",
+        "port=8080",
+    ];
+    let matches = CommandHandler::build().get_matches_from(args);
+    let handler = CommandHandler::parse(&matches);
+
+    if let Some(Action::Init { vars, .. }) = handler.action {
+        assert_eq!(
+            vars,
+            vec![
+                ("license".to_string(), "MIT".to_string()),
+                ("port".to_string(), "8080".to_string())
+            ]
+        );
     } else {
         panic!("Failed to parse init action");
     }
@@ -89,27 +429,694 @@ fn test_command_handler_parse_new_dependency() {
     }
 }
 
+// Test for parsing `new` with a semver requirement
+#[test]
+fn test_command_handler_parse_new_version_req() {
+    let args = vec!["limp", "new", "dep_name", "--version", ">=2, <3"];
+    let matches = CommandHandler::build().get_matches_from(args);
+    let handler = CommandHandler::parse(&matches);
+
+    if let Some(Action::NewDependency { version, .. }) = handler.action {
+        assert_eq!(version, Some(">=2, <3".to_string()));
+    } else {
+        panic!("Failed to parse new dependency action");
+    }
+}
+
+// Test for parsing `new --git` action from CLI args
+#[test]
+fn test_command_handler_parse_new_git() {
+    let args = vec![
+        "limp",
+        "new",
+        "mylib",
+        "--git",
+        "https://github.com/me/mylib",
+        "--branch",
+        "main",
+    ];
+    let matches = CommandHandler::build().get_matches_from(args);
+    let handler = CommandHandler::parse(&matches);
+
+    if let Some(Action::NewDependency { source, .. }) = handler.action {
+        assert_eq!(
+            source,
+            DependencySource::Git {
+                url: "https://github.com/me/mylib".to_string(),
+                branch: Some("main".to_string()),
+                tag: None,
+                rev: None,
+            }
+        );
+    } else {
+        panic!("Failed to parse new dependency action");
+    }
+
+    let matches = CommandHandler::build()
+        .get_matches_from(vec!["limp", "new", "local", "--path", "../local"]);
+    if let Some(Action::NewDependency { source, .. }) = CommandHandler::parse(&matches).action {
+        assert_eq!(
+            source,
+            DependencySource::Path {
+                path: "../local".to_string()
+            }
+        );
+    } else {
+        panic!("Failed to parse new dependency action");
+    }
+}
+
+// Test for parsing `new --force` and `new --if-missing`, which conflict
+#[test]
+fn test_command_handler_parse_new_dependency_existing() {
+    let matches = CommandHandler::build().get_matches_from(["limp", "new", "serde", "--force"]);
+    if let Some(Action::NewDependency {
+        force, if_missing, ..
+    }) = CommandHandler::parse(&matches).action
+    {
+        assert!(force);
+        assert!(!if_missing);
+    } else {
+        panic!("Failed to parse new dependency action");
+    }
+
+    let matches =
+        CommandHandler::build().get_matches_from(["limp", "new", "serde", "--if-missing"]);
+    if let Some(Action::NewDependency { if_missing, .. }) = CommandHandler::parse(&matches).action {
+        assert!(if_missing);
+    } else {
+        panic!("Failed to parse new dependency action");
+    }
+
+    assert!(CommandHandler::build()
+        .try_get_matches_from(["limp", "new", "serde", "--force", "--if-missing"])
+        .is_err());
+}
+
+// Test for parsing `new --from-file` and `new -` for stdin
+#[test]
+fn test_command_handler_parse_new_dependency_list() {
+    let matches = CommandHandler::build().get_matches_from([
+        "limp",
+        "new",
+        "--from-file",
+        "deps.txt",
+        "--if-missing",
+    ]);
+    if let Some(Action::NewDependency { from_file, .. }) = CommandHandler::parse(&matches).action {
+        assert_eq!(from_file.as_deref(), Some("deps.txt"));
+    } else {
+        panic!("Failed to parse new dependency action");
+    }
+
+    let matches = CommandHandler::build().get_matches_from(["limp", "new", "-"]);
+    if let Some(Action::NewDependency { from_file, .. }) = CommandHandler::parse(&matches).action {
+        assert_eq!(from_file.as_deref(), Some("-"));
+    } else {
+        panic!("Failed to parse new dependency action");
+    }
+
+    assert!(CommandHandler::build()
+        .try_get_matches_from(["limp", "new", "serde", "--from-file", "deps.txt"])
+        .is_err());
+}
+
+// Test for parsing `add --dev` action from CLI args
+#[test]
+fn test_command_handler_parse_add_dev() {
+    let args = vec!["limp", "add", "pretty_assertions", "--dev"];
+    let matches = CommandHandler::build().get_matches_from(args);
+    let handler = CommandHandler::parse(&matches);
+
+    if let Some(Action::Add { names, kind, .. }) = handler.action {
+        assert_eq!(names, vec!["pretty_assertions".to_string()]);
+        assert_eq!(kind, Some(DependencyKind::Dev));
+    } else {
+        panic!("Failed to parse add action");
+    }
+}
+
+// Test for parsing `add` with several crates, versions and per-crate features
+#[test]
+fn test_command_handler_parse_add_many() {
+    let args = vec![
+        "limp",
+        "add",
+        "tokio",
+        "serde@1.0",
+        "anyhow",
+        "--features",
+        "serde:derive,tokio:full",
+    ];
+    let matches = CommandHandler::build().get_matches_from(args);
+    if let Some(Action::Add {
+        names, features, ..
+    }) = CommandHandler::parse(&matches).action
+    {
+        assert_eq!(names, vec!["tokio", "serde@1.0", "anyhow"]);
+        assert_eq!(features, vec!["serde:derive", "tokio:full"]);
+    } else {
+        panic!("Failed to parse add action");
+    }
+}
+
+// Test for parsing `add --package --workspace` from CLI args
+#[test]
+fn test_command_handler_parse_add_package() {
+    let args = vec!["limp", "add", "serde", "-p", "core", "--workspace"];
+    let matches = CommandHandler::build().get_matches_from(args);
+    if let Some(Action::Add {
+        package, workspace, ..
+    }) = CommandHandler::parse(&matches).action
+    {
+        assert_eq!(package, Some("core".to_string()));
+        assert!(workspace);
+    } else {
+        panic!("Failed to parse add action");
+    }
+}
+
+// Test for parsing `init --from-git` from CLI args
+#[test]
+fn test_command_handler_parse_init_from_git() {
+    let args = vec![
+        "limp",
+        "init",
+        "app",
+        "--from-git",
+        "https://example.com/template.git",
+        "--branch",
+        "v1",
+        "--template-hooks",
+    ];
+    let matches = CommandHandler::build().get_matches_from(args);
+    if let Some(Action::Init {
+        from_git,
+        branch,
+        template_hooks,
+        ..
+    }) = CommandHandler::parse(&matches).action
+    {
+        assert_eq!(
+            from_git.as_deref(),
+            Some("https://example.com/template.git")
+        );
+        assert_eq!(branch.as_deref(), Some("v1"));
+        assert!(template_hooks);
+    } else {
+        panic!("Failed to parse init action");
+    }
+
+    let args = vec!["limp", "init", "app", "--from-git", "url", "--type", "cli"];
+    assert!(CommandHandler::build().try_get_matches_from(args).is_err());
+}
+
+// Test for parsing `snippet verify` from CLI args
+#[test]
+fn test_command_handler_parse_snippet_verify() {
+    let matches =
+        CommandHandler::build().get_matches_from(["limp", "snippet", "verify", "--sources"]);
+    assert!(matches!(
+        CommandHandler::parse(&matches).action,
+        Some(Action::SnippetVerify { sources: true })
+    ));
+}
+
+// Test for parsing `template save` action from CLI args
+#[test]
+fn test_command_handler_parse_template_save() {
+    let args = vec!["limp", "template", "save", "axum-api"];
+    let matches = CommandHandler::build().get_matches_from(args);
+    let handler = CommandHandler::parse(&matches);
+
+    if let Some(Action::TemplateSave { name }) = handler.action {
+        assert_eq!(name, "axum-api");
+    } else {
+        panic!("Failed to parse template save action");
+    }
+}
+
+// Test for parsing `update --jobs` action from CLI args
+#[test]
+fn test_command_handler_parse_update_jobs() {
+    let matches = CommandHandler::build().get_matches_from(vec!["limp", "update"]);
+    assert!(matches!(
+        CommandHandler::parse(&matches).action,
+        Some(Action::Update {
+            jobs: None,
+            dry_run: false,
+            ..
+        })
+    ));
+
+    let matches = CommandHandler::build().get_matches_from(vec!["limp", "update", "-j", "2"]);
+    assert!(matches!(
+        CommandHandler::parse(&matches).action,
+        Some(Action::Update { jobs: Some(2), .. })
+    ));
+
+    let matches = CommandHandler::build().get_matches_from(vec![
+        "limp",
+        "update",
+        "--on-feature-mismatch",
+        "drop",
+    ]);
+    assert!(matches!(
+        CommandHandler::parse(&matches).action,
+        Some(Action::Update {
+            on_feature_mismatch: FeatureMismatch::Drop,
+            ..
+        })
+    ));
+
+    let matches = CommandHandler::build().get_matches_from(vec!["limp", "update", "--pre"]);
+    assert!(matches!(
+        CommandHandler::parse(&matches).action,
+        Some(Action::Update { pre: true, .. })
+    ));
+}
+
+// Test for parsing `config set` action from CLI args
+#[test]
+fn test_command_handler_parse_config_set() {
+    let args = vec!["limp", "config", "set", "edition", "2024"];
+    let matches = CommandHandler::build().get_matches_from(args);
+    let handler = CommandHandler::parse(&matches);
+
+    if let Some(Action::ConfigSet { key, value }) = handler.action {
+        assert_eq!(key, "edition");
+        assert_eq!(value, "2024");
+    } else {
+        panic!("Failed to parse config set action");
+    }
+}
+
+// Test for parsing `stats` action from CLI args
+#[test]
+fn test_command_handler_parse_stats() {
+    let matches = CommandHandler::build().get_matches_from(vec!["limp", "stats"]);
+    let handler = CommandHandler::parse(&matches);
+
+    assert!(matches!(handler.action, Some(Action::Stats)));
+}
+
+// Test for parsing `completions` action from CLI args
+#[test]
+fn test_command_handler_parse_completions() {
+    let args = vec!["limp", "completions", "fish"];
+    let matches = CommandHandler::build().get_matches_from(args);
+    let handler = CommandHandler::parse(&matches);
+
+    assert!(matches!(
+        handler.action,
+        Some(Action::Completions {
+            shell: clap_complete::Shell::Fish
+        })
+    ));
+}
+
+// Test for the generated completion scripts
+#[test]
+fn test_completions_generate() {
+    let mut out = vec![];
+    completions::generate(clap_complete::Shell::Bash, &mut out).unwrap();
+    let script = String::from_utf8(out).unwrap();
+    assert!(script.contains("_limp()"));
+    assert!(script.contains("limp __complete"));
+    assert!(script.contains(" add del link rename pin unpin constrain unconstrain prerelease "));
+
+    let mut out = vec![];
+    completions::generate(clap_complete::Shell::PowerShell, &mut out).unwrap();
+    assert!(!String::from_utf8(out).unwrap().contains("limp __complete"));
+}
+
+// Test for parsing `update <names> --dry-run` action from CLI args
+#[test]
+fn test_command_handler_parse_update_names() {
+    let args = vec!["limp", "update", "serde", "tokio", "--dry-run"];
+    let matches = CommandHandler::build().get_matches_from(args);
+    let handler = CommandHandler::parse(&matches);
+
+    if let Some(Action::Update { names, dry_run, .. }) = handler.action {
+        assert_eq!(names, vec!["serde".to_string(), "tokio".to_string()]);
+        assert!(dry_run);
+    } else {
+        panic!("Failed to parse update action");
+    }
+}
+
+// Test for parsing `list` options from CLI args
+#[test]
+fn test_command_handler_parse_list() {
+    let args = vec!["limp", "list", "--sort", "updated", "--filter", "serde"];
+    let matches = CommandHandler::build().get_matches_from(args);
+    let handler = CommandHandler::parse(&matches);
+
+    if let Some(Action::List {
+        sort,
+        filter,
+        with_snippets,
+        tree,
+        names_only,
+    }) = handler.action
+    {
+        assert_eq!(sort, SortKey::Updated);
+        assert_eq!(filter, Some("serde".to_string()));
+        assert!(!with_snippets);
+        assert_eq!(tree, None);
+        assert!(!names_only);
+    } else {
+        panic!("Failed to parse list action");
+    }
+
+    let parse = |args: &[&str]| match CommandHandler::parse(
+        &CommandHandler::build().get_matches_from(args),
+    )
+    .action
+    {
+        Some(Action::List {
+            tree, names_only, ..
+        }) => (tree, names_only),
+        _ => panic!("Failed to parse list action"),
+    };
+    assert_eq!(
+        parse(&["limp", "list", "--tree"]),
+        (Some(GroupKey::Kind), false)
+    );
+    assert_eq!(
+        parse(&["limp", "list", "--tree", "tag"]),
+        (Some(GroupKey::Tag), false)
+    );
+    assert_eq!(parse(&["limp", "list", "--names-only"]), (None, true));
+}
+
+// Test for parsing `info` action from CLI args
+#[test]
+fn test_command_handler_parse_info() {
+    let args = vec!["limp", "info", "tokio", "-n", "3"];
+    let matches = CommandHandler::build().get_matches_from(args);
+    let handler = CommandHandler::parse(&matches);
+
+    if let Some(Action::Info { name, versions }) = handler.action {
+        assert_eq!(name, "tokio");
+        assert_eq!(versions, 3);
+    } else {
+        panic!("Failed to parse info action");
+    }
+}
+
+// Test for parsing `import` action from CLI args
+#[test]
+fn test_command_handler_parse_import() {
+    let args = vec!["limp", "import", "../other/Cargo.toml", "--keep"];
+    let matches = CommandHandler::build().get_matches_from(args);
+    let handler = CommandHandler::parse(&matches);
+
+    if let Some(Action::Import {
+        path, on_conflict, ..
+    }) = handler.action
+    {
+        assert_eq!(path, Some("../other/Cargo.toml".to_string()));
+        assert_eq!(on_conflict, Some(false));
+    } else {
+        panic!("Failed to parse import action");
+    }
+}
+
+// Test for parsing `import --bundle` action from CLI args
+#[test]
+fn test_command_handler_parse_import_bundle() {
+    let args = vec!["limp", "import", "--bundle", "team-presets"];
+    let matches = CommandHandler::build().get_matches_from(args);
+    let handler = CommandHandler::parse(&matches);
+
+    if let Some(Action::Import { path, bundle, .. }) = handler.action {
+        assert_eq!(path, None);
+        assert_eq!(bundle, Some("team-presets".to_string()));
+    } else {
+        panic!("Failed to parse import action");
+    }
+}
+
+// Test for parsing `snippet mv` action from CLI args
+#[test]
+fn test_command_handler_parse_snippet_rename() {
+    let args = vec!["limp", "snippet", "mv", "serde", "serde_derive"];
+    let matches = CommandHandler::build().get_matches_from(args);
+    let handler = CommandHandler::parse(&matches);
+
+    if let Some(Action::SnippetRename { from, to }) = handler.action {
+        assert_eq!(from, "serde");
+        assert_eq!(to, "serde_derive");
+    } else {
+        panic!("Failed to parse snippet mv action");
+    }
+}
+
+// Test for parsing `apply` action from CLI args
+#[test]
+fn test_command_handler_parse_apply() {
+    let args = vec!["limp", "apply", "error", "-m", "errors"];
+    let matches = CommandHandler::build().get_matches_from(args);
+    if let Some(Action::Apply {
+        name,
+        module,
+        entry,
+    }) = CommandHandler::parse(&matches).action
+    {
+        assert_eq!(name, "error");
+        assert_eq!(module, Some("errors".to_string()));
+        assert!(!entry);
+    } else {
+        panic!("Failed to parse apply action");
+    }
+    assert!(CommandHandler::build()
+        .try_get_matches_from(["limp", "apply", "error", "-m", "errors", "--entry"])
+        .is_err());
+}
+
+// Test for parsing `link` action with tags from CLI args
+#[test]
+fn test_command_handler_parse_link() {
+    let args = vec![
+        "limp",
+        "link",
+        "axum",
+        "router.rs",
+        "--tags",
+        "web,http",
+        "--as",
+        "router",
+    ];
+    let matches = CommandHandler::build().get_matches_from(args);
+    let handler = CommandHandler::parse(&matches);
+
+    if let Some(Action::Link {
+        name,
+        source,
+        tags,
+        variant,
+    }) = handler.action
+    {
+        assert_eq!(name, "axum");
+        assert_eq!(source, "router.rs");
+        assert_eq!(tags, Some(vec!["web".to_string(), "http".to_string()]));
+        assert_eq!(variant, Some("router".to_string()));
+    } else {
+        panic!("Failed to parse link action");
+    }
+}
+
+// Test for parsing `search` action from CLI args
+#[test]
+fn test_command_handler_parse_search() {
+    let args = vec!["limp", "search", "serde", "--sort", "downloads"];
+    let matches = CommandHandler::build().get_matches_from(args);
+    let handler = CommandHandler::parse(&matches);
+
+    if let Some(Action::Search { query, mine, sort }) = handler.action {
+        assert_eq!(query, Some("serde".to_string()));
+        assert!(!mine);
+        assert_eq!(sort, SearchSort::Downloads);
+    } else {
+        panic!("Failed to parse search action");
+    }
+
+    let matches = CommandHandler::build().get_matches_from(vec!["limp", "search", "--mine"]);
+    if let Some(Action::Search { query, mine, sort }) = CommandHandler::parse(&matches).action {
+        assert_eq!(query, None);
+        assert!(mine);
+        assert_eq!(sort, SearchSort::Relevance);
+    } else {
+        panic!("Failed to parse search --mine action");
+    }
+    assert!(CommandHandler::build()
+        .try_get_matches_from(vec!["limp", "search"])
+        .is_err());
+}
+
+// Test for parsing `login` action from CLI args
+#[test]
+fn test_command_handler_parse_login() {
+    let args = vec!["limp", "login", "cio_token"];
+    let matches = CommandHandler::build().get_matches_from(args);
+    let handler = CommandHandler::parse(&matches);
+
+    if let Some(Action::Login { token }) = handler.action {
+        assert_eq!(token, Some("cio_token".to_string()));
+    } else {
+        panic!("Failed to parse login action");
+    }
+}
+
+// Test for parsing the global `--offline` flag
+#[test]
+fn test_command_handler_parse_offline() {
+    let args = vec!["limp", "list", "--offline"];
+    let matches = CommandHandler::build().get_matches_from(args);
+    let handler = CommandHandler::parse(&matches);
+
+    assert!(handler.offline);
+    assert!(matches!(handler.action, Some(Action::List { .. })));
+}
+
+// Test for parsing the `-q` and `-v` verbosity flags
+#[test]
+fn test_command_handler_parse_verbosity() {
+    let parse = |args: &[&str]| {
+        CommandHandler::parse(&CommandHandler::build().get_matches_from(args)).verbosity
+    };
+    assert_eq!(parse(&["limp", "update"]), Verbosity::Normal);
+    assert_eq!(parse(&["limp", "-v", "update"]), Verbosity::Verbose);
+    assert_eq!(parse(&["limp", "-vv", "update"]), Verbosity::Debug);
+    assert_eq!(parse(&["limp", "update", "-q"]), Verbosity::Quiet);
+    assert_eq!(CommandHandler::default().verbosity, Verbosity::Quiet);
+}
+
+// Test for parsing `--no-color` and the styles it turns off
+#[test]
+fn test_command_handler_parse_no_color() {
+    let matches = CommandHandler::build().get_matches_from(["limp", "list", "--no-color"]);
+    let handler = CommandHandler::parse(&matches);
+    assert!(handler.no_color);
+    assert!(!Style::detect(handler.no_color).enabled);
+
+    assert_eq!(Style { enabled: true }.bold("serde"), "\x1b[1mserde\x1b[0m");
+    assert_eq!(Style::default().error("ERROR:"), "ERROR:");
+}
+
+// Test for parsing the global `--config-dir` flag
+#[test]
+fn test_command_handler_parse_config_dir() {
+    let matches =
+        CommandHandler::build().get_matches_from(["limp", "list", "--config-dir", "/tmp/limp_ci"]);
+    assert_eq!(
+        CommandHandler::parse(&matches).config_dir,
+        Some(Path::new("/tmp/limp_ci").to_path_buf())
+    );
+    let matches = CommandHandler::build().get_matches_from(["limp", "list"]);
+    assert_eq!(CommandHandler::parse(&matches).config_dir, None);
+}
+
+// Test for parsing the global `--yes` and `--frozen` flags
+#[test]
+fn test_command_handler_parse_yes_frozen() {
+    let matches = CommandHandler::build().get_matches_from(["limp", "import", "-y", "--frozen"]);
+    let handler = CommandHandler::parse(&matches);
+    assert!(handler.yes);
+    assert!(handler.frozen);
+
+    let matches = CommandHandler::build().get_matches_from(["limp", "import"]);
+    let handler = CommandHandler::parse(&matches);
+    assert!(!handler.yes && !handler.frozen);
+}
+
+// Test for parsing the global `--timeout` flag
+#[test]
+fn test_command_handler_parse_timeout() {
+    let matches = CommandHandler::build().get_matches_from(["limp", "update", "--timeout", "5"]);
+    assert_eq!(CommandHandler::parse(&matches).timeout, Some(5));
+
+    let matches = CommandHandler::build().get_matches_from(["limp", "update"]);
+    assert_eq!(CommandHandler::parse(&matches).timeout, None);
+    assert!(CommandHandler::build()
+        .try_get_matches_from(["limp", "update", "--timeout", "soon"])
+        .is_err());
+}
+
+// Test for parsing the global `--format` flag
+#[test]
+fn test_command_handler_parse_format() {
+    let matches = CommandHandler::build().get_matches_from(vec!["limp", "list"]);
+    assert_eq!(CommandHandler::parse(&matches).format, Format::Text);
+
+    let matches =
+        CommandHandler::build().get_matches_from(vec!["limp", "list", "--format", "json"]);
+    assert_eq!(CommandHandler::parse(&matches).format, Format::Json);
+}
+
 // Test for the `make_action` method (init action)
 #[test]
 fn test_make_action_init() {
+    let ctx = mock_context("limp_actions_init");
     let handler_err = CommandHandler {
         action: Some(Action::Init {
             name: "my_project".to_string(),
             dependencies: Some(vec!["dep1".to_string(), "dep2".to_string()]),
+            kind: ProjectKind::Bin,
+            workspace: None,
+            template: None,
+            vars: vec![],
+            check: false,
+            package: None,
+            git: None,
+            scaffold: Scaffold::default(),
+            snippets: vec![],
+            bins: vec![],
+            preset: None,
+            manifest_extras: vec![],
+            description: None,
+            repository: None,
+            keywords: vec![],
+            fmt: false,
+            from_git: None,
+            branch: None,
+            template_hooks: false,
         }),
+        ..Default::default()
     };
 
     // Mock the `create_project` function
-    assert!(handler_err.make_action().is_err());
+    assert!(handler_err.make_action_in(&ctx).is_err());
 
     let handler_ok = CommandHandler {
         action: Some(Action::Init {
             name: "my_project".to_string(),
             dependencies: None,
+            kind: ProjectKind::Bin,
+            workspace: None,
+            template: None,
+            vars: vec![],
+            check: false,
+            package: None,
+            git: None,
+            scaffold: Scaffold::default(),
+            snippets: vec![],
+            bins: vec![],
+            preset: None,
+            manifest_extras: vec![],
+            description: None,
+            repository: None,
+            keywords: vec![],
+            fmt: false,
+            from_git: None,
+            branch: None,
+            template_hooks: false,
         }),
+        ..Default::default()
     };
 
-    assert!(handler_ok.make_action().is_ok());
+    assert!(handler_ok.make_action_in(&ctx).is_ok());
 
     fs::remove_dir_all("my_project").unwrap();
 }
@@ -117,17 +1124,32 @@ fn test_make_action_init() {
 // Test for the `make_action` method (new dependency action)
 #[test]
 fn test_make_action_new_dependency() {
+    let ctx = mock_context("limp_actions_new_dependency");
     let handler = CommandHandler {
         action: Some(Action::NewDependency {
             name: "non_existing_dep".to_string(),
             version: Some("1.0.0".to_string()),
             features: None,
             path_to_snippet: None,
+            kind: DependencyKind::Normal,
+            allow_yanked: false,
+            msrv: None,
+            registry: None,
+            source: DependencySource::Registry,
+            no_default_features: false,
+            optional: false,
+            rename: None,
+            pick_features: false,
+            allow_license_violation: false,
+            force: false,
+            if_missing: false,
+            from_file: None,
         }),
+        ..Default::default()
     };
 
     // Mock the actions and test
-    assert!(handler.make_action().is_err());
+    assert!(handler.make_action_in(&ctx).is_err());
 
     let handler = CommandHandler {
         action: Some(Action::NewDependency {
@@ -135,69 +1157,251 @@ fn test_make_action_new_dependency() {
             version: Some("1.0.0".to_string()),
             features: None,
             path_to_snippet: None,
+            kind: DependencyKind::Normal,
+            allow_yanked: false,
+            msrv: None,
+            registry: None,
+            source: DependencySource::Registry,
+            no_default_features: false,
+            optional: false,
+            rename: None,
+            pick_features: false,
+            allow_license_violation: false,
+            force: false,
+            if_missing: false,
+            from_file: None,
         }),
+        ..Default::default()
     };
 
     // Mock the actions and test
-    assert!(handler.make_action().is_ok());
+    assert!(handler.make_action_in(&ctx).is_ok());
 }
 
 // Test for `make_action` (list dependencies action)
 #[test]
 fn test_make_action_list() {
+    let ctx = mock_context("limp_actions_list");
     let handler = CommandHandler {
-        action: Some(Action::List),
+        action: Some(Action::List {
+            sort: SortKey::Name,
+            filter: None,
+            with_snippets: false,
+            tree: None,
+            names_only: false,
+        }),
+        ..Default::default()
     };
 
     // Mock the behavior of the list action
     handler
-        .make_action()
+        .make_action_in(&ctx)
         .expect("Expected list action to succeed");
 }
 
 // Test for `make_action` (delete dependency action)
 #[test]
 fn test_make_action_delete() {
+    let ctx = mock_context("limp_actions_delete");
     let handler = CommandHandler {
         action: Some(Action::Delete {
             name: "dep_to_delete".to_string(),
+            keep_snippet: false,
         }),
+        ..Default::default()
     };
 
     // Mock delete logic
     handler
-        .make_action()
+        .make_action_in(&ctx)
         .expect("Expected delete action to succeed");
 }
 
+// Test that `--yes` deletes a stored dependency and snippet without asking
+#[test]
+fn test_make_action_delete_yes() {
+    let ctx = mock_context("limp_actions_delete_yes");
+    let mut js = JsonStorage::default();
+    js.add(JsonDependency {
+        name: "tokio".to_string(),
+        version: "1.41.1".to_string(),
+        ..Default::default()
+    });
+    js.save(ctx.config_path()).unwrap();
+    fs::create_dir_all(ctx.snippets_dir()).unwrap();
+    fs::write(ctx.snippets_dir().join("tokio.rs"), "fn main() {}\n").unwrap();
+
+    for action in [
+        Action::Delete {
+            name: "tokio".to_string(),
+            keep_snippet: false,
+        },
+        Action::SnippetRemove {
+            name: "tokio".to_string(),
+        },
+    ] {
+        let handler = CommandHandler {
+            action: Some(action),
+            yes: true,
+            ..Default::default()
+        };
+        handler.make_action_in(&ctx).unwrap();
+    }
+    assert!(JsonStorage::load(ctx.config_path())
+        .unwrap()
+        .get("tokio")
+        .is_none());
+    assert!(!ctx.snippets_dir().join("tokio.rs").exists());
+
+    let _ = fs::remove_dir_all(&ctx.root);
+}
+
 // Test for `make_action` (add dependency to project action)
 #[test]
 fn test_make_action_add() {
+    let ctx = mock_context("limp_actions_add");
     let handler_err = CommandHandler {
         action: Some(Action::Add {
-            name: "dep_to_add".to_string(),
+            names: vec!["dep_to_add".to_string()],
+            features: vec![],
+            kind: None,
+            no_default_features: false,
+            optional: false,
+            rename: None,
+            with_snippet: false,
+            module: None,
+            allow_license_violation: false,
+            package: None,
+            workspace: false,
         }),
+        ..Default::default()
     };
 
     // Mock the add dependency logic
-    assert!(handler_err.make_action().is_err());
+    assert!(handler_err.make_action_in(&ctx).is_err());
 
     let handler_create = CommandHandler {
         action: Some(Action::Init {
             name: "my_project_add".to_string(),
             dependencies: None,
+            kind: ProjectKind::Bin,
+            workspace: None,
+            template: None,
+            vars: vec![],
+            check: false,
+            package: None,
+            git: None,
+            scaffold: Scaffold::default(),
+            snippets: vec![],
+            bins: vec![],
+            preset: None,
+            manifest_extras: vec![],
+            description: None,
+            repository: None,
+            keywords: vec![],
+            fmt: false,
+            from_git: None,
+            branch: None,
+            template_hooks: false,
         }),
+        ..Default::default()
     };
 
-    assert!(handler_create.make_action().is_ok());
+    assert!(handler_create.make_action_in(&ctx).is_ok());
 
     let handler_ok = CommandHandler {
         action: Some(Action::Add {
-            name: "tokio".to_string(),
+            names: vec!["tokio".to_string()],
+            features: vec![],
+            kind: None,
+            no_default_features: false,
+            optional: false,
+            rename: None,
+            with_snippet: false,
+            module: None,
+            allow_license_violation: false,
+            package: None,
+            workspace: false,
         }),
+        ..Default::default()
     };
 
-    assert!(handler_ok.make_action().is_ok());
+    assert!(handler_ok.make_action_in(&ctx).is_ok());
 
     fs::remove_dir_all("my_project_add").unwrap();
 }
+
+// Test for parsing `--no-default-features` on `new` and `add`
+#[test]
+fn test_command_handler_parse_no_default_features() {
+    let matches =
+        CommandHandler::build().get_matches_from(["limp", "new", "tokio", "--no-default-features"]);
+    if let Some(Action::NewDependency {
+        no_default_features,
+        ..
+    }) = CommandHandler::parse(&matches).action
+    {
+        assert!(no_default_features);
+    } else {
+        panic!("Failed to parse new dependency action");
+    }
+
+    let matches = CommandHandler::build().get_matches_from(["limp", "add", "tokio"]);
+    if let Some(Action::Add {
+        no_default_features,
+        ..
+    }) = CommandHandler::parse(&matches).action
+    {
+        assert!(!no_default_features);
+    } else {
+        panic!("Failed to parse add action");
+    }
+}
+
+// Test for parsing `init` with cargo new style flags
+#[test]
+fn test_command_handler_parse_init_cargo_flags() {
+    let args = vec![
+        "limp",
+        "init",
+        "path/to/dir",
+        "--name",
+        "actual",
+        "--vcs",
+        "none",
+        "--bin",
+    ];
+    let matches = CommandHandler::build().get_matches_from(args);
+    if let Some(Action::Init {
+        name,
+        package,
+        git,
+        kind,
+        ..
+    }) = CommandHandler::parse(&matches).action
+    {
+        assert_eq!(name, "path/to/dir");
+        assert_eq!(package, Some("actual".to_string()));
+        assert_eq!(git, Some(false));
+        assert_eq!(kind, ProjectKind::Bin);
+    } else {
+        panic!("Failed to parse init action");
+    }
+    assert!(CommandHandler::build()
+        .try_get_matches_from(["limp", "init", "x", "--bin", "--lib"])
+        .is_err());
+}
+
+// Test for parsing `which` action from CLI args
+#[test]
+fn test_command_handler_parse_which() {
+    let args = vec!["limp", "which", "serde"];
+    let matches = CommandHandler::build().get_matches_from(args);
+    let handler = CommandHandler::parse(&matches);
+
+    if let Some(Action::Which { name }) = handler.action {
+        assert_eq!(name, "serde");
+    } else {
+        panic!("Failed to parse which action");
+    }
+}
@@ -0,0 +1,47 @@
+use limp::bundle;
+use limp::storage::{JsonDependency, JsonStorage, DEFAULT_VARIANT};
+use std::fs;
+
+// Test that an exported bundle merges into another storage with its snippets
+#[test]
+fn test_export_import() {
+    let root = std::env::temp_dir().join("limp_test_bundle");
+    let snippet = root.join("serde_snippet.rs");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(&snippet, "use serde::Serialize;\n").unwrap();
+
+    let mut storage = JsonStorage::default();
+    storage.add(JsonDependency {
+        name: "serde".to_string(),
+        version: "1.0.210".to_string(),
+        snippets: [(
+            DEFAULT_VARIANT.to_string(),
+            snippet.to_string_lossy().to_string(),
+        )]
+        .into(),
+        ..Default::default()
+    });
+    assert_eq!(bundle::export(&storage, root.join("bundle")).unwrap(), 1);
+
+    let snippets = root.join("snippets");
+    fs::create_dir_all(&snippets).unwrap();
+    fs::write(snippets.join("serde.rs"), "// taken\n").unwrap();
+
+    let mut other = JsonStorage::default();
+    let summary =
+        bundle::import(&mut other, root.join("bundle"), &snippets, |_, _| Ok(true)).unwrap();
+    assert_eq!(summary.added, vec!["serde".to_string()]);
+
+    let imported = other.get("serde").unwrap().snippet().cloned().unwrap();
+    assert!(imported.ends_with("serde-1.rs"));
+    assert_eq!(
+        fs::read_to_string(imported).unwrap(),
+        "use serde::Serialize;\n"
+    );
+    assert_eq!(
+        fs::read_to_string(snippets.join("serde.rs")).unwrap(),
+        "// taken\n"
+    );
+
+    fs::remove_dir_all(root).unwrap();
+}
@@ -0,0 +1,61 @@
+use limp::cache::Cache;
+use limp::crates::{Registry, SearchSort};
+use limp::error::LimpError;
+use std::fs;
+use std::time::Duration;
+
+// Test that fresh entries are returned and expired ones are only served when stale data is allowed
+#[test]
+fn test_cache_ttl() {
+    let dir = std::env::temp_dir().join("limp_cache_ttl");
+    let fresh = Cache::new(&dir, Duration::from_secs(60));
+    fresh.put("serde", "{}").unwrap();
+    assert_eq!(fresh.get("serde", false), Some("{}".to_string()));
+
+    let expired = Cache::new(&dir, Duration::ZERO);
+    assert_eq!(expired.get("serde", false), None);
+    assert_eq!(expired.get("serde", true), Some("{}".to_string()));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+// Test that offline registry lookups fail clearly when nothing is cached
+#[test]
+fn test_offline_registry_missing_entry() {
+    let dir = std::env::temp_dir().join("limp_cache_offline");
+    let registry = Registry::with_cache(true, Cache::new(&dir, Duration::from_secs(60)));
+
+    assert!(matches!(
+        registry.get_crate("serde"),
+        Err(LimpError::OfflineUnavailable(_))
+    ));
+    assert!(matches!(
+        registry.search("serde", SearchSort::default()),
+        Err(LimpError::OfflineUnavailable(_))
+    ));
+}
+
+// Test that cache info counts entries and expired ones, and that clearing removes them
+#[test]
+fn test_cache_info_clear() {
+    let dir = std::env::temp_dir().join("limp_cache_info");
+    let _ = fs::remove_dir_all(&dir);
+    let cache = Cache::new(&dir, Duration::from_secs(60));
+    assert_eq!(cache.info().unwrap().entries, 0);
+
+    cache.put("serde", "{}").unwrap();
+    cache.put("anyhow", "{\"a\": 1}").unwrap();
+    let info = cache.info().unwrap();
+    assert_eq!(info.entries, 2);
+    assert_eq!(info.bytes, 10);
+    assert_eq!(info.expired, 0);
+    assert!(info.oldest >= info.newest);
+    assert!(info.to_string().starts_with("2 entries"));
+
+    let expired = Cache::new(&dir, Duration::ZERO);
+    assert_eq!(expired.info().unwrap().expired, 2);
+
+    assert_eq!(cache.clear().unwrap(), 2);
+    assert_eq!(cache.info().unwrap().entries, 0);
+    fs::remove_dir_all(dir).unwrap();
+}
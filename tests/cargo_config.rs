@@ -0,0 +1,70 @@
+use limp::cargo_config::CargoConfig;
+use std::fs;
+
+const CONFIG: &str = r#"
+[http]
+proxy = "http://proxy.example.com:3128"
+
+[registries.company]
+index = "sparse+https://crates.example.com/index/"
+
+[source.crates-io]
+replace-with = "vendored-mirror"
+
+[source.vendored-mirror]
+replace-with = "mirror"
+
+[source.mirror]
+registry = "sparse+https://mirror.example.com/index/"
+"#;
+
+// Test that the proxy, the registries and the replacement of crates.io are read
+#[test]
+fn test_parse() {
+    let config = CargoConfig::parse(CONFIG).unwrap();
+    assert_eq!(
+        config.http_proxy.as_deref(),
+        Some("http://proxy.example.com:3128")
+    );
+    assert_eq!(config.proxy(), config.http_proxy);
+    assert_eq!(
+        config.indexes["company"],
+        "sparse+https://crates.example.com/index/"
+    );
+    assert_eq!(
+        config.crates_io_index(),
+        Some("sparse+https://mirror.example.com/index/")
+    );
+
+    assert_eq!(CargoConfig::parse("").unwrap().crates_io_index(), None);
+    // a vendored directory can't answer registry lookups
+    let vendored = CargoConfig::parse(
+        "[source.crates-io]\nreplace-with = \"vendor\"\n\n[source.vendor]\ndirectory = \"vendor\"\n",
+    )
+    .unwrap();
+    assert_eq!(vendored.crates_io_index(), None);
+    let cycle = CargoConfig::parse(
+        "[source.crates-io]\nreplace-with = \"a\"\n\n[source.a]\nreplace-with = \"crates-io\"\n",
+    )
+    .unwrap();
+    assert_eq!(cycle.crates_io_index(), None);
+}
+
+// Test that `config.toml` is read from cargo's home, and a missing one is empty
+#[test]
+fn test_load() {
+    let home = std::env::temp_dir().join("limp_cargo_home");
+    let _ = fs::remove_dir_all(&home);
+    assert_eq!(CargoConfig::load(&home).unwrap(), CargoConfig::default());
+
+    fs::create_dir_all(&home).unwrap();
+    fs::write(home.join("config.toml"), CONFIG).unwrap();
+    assert_eq!(
+        CargoConfig::load(&home).unwrap(),
+        CargoConfig::parse(CONFIG).unwrap()
+    );
+    fs::write(home.join("config.toml"), "[http\n").unwrap();
+    assert!(CargoConfig::load(&home).is_err());
+
+    fs::remove_dir_all(home).unwrap();
+}
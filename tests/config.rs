@@ -0,0 +1,112 @@
+use limp::config::{Config, SnippetMerge};
+use limp::storage::StorageFormat;
+use std::time::Duration;
+
+// Test for `Config::set` and `Config::get`
+#[test]
+fn test_config_set_get() {
+    let mut config = Config::default();
+    assert_eq!(config.get("edition").unwrap(), Some("2021".to_string()));
+    assert_eq!(config.get("author").unwrap(), None);
+
+    config.set("edition", "2024").unwrap();
+    config.set("author", "Jane Doe").unwrap();
+    config.set("git_init", "false").unwrap();
+    config.set("snippet_merge", "module").unwrap();
+    config.set("update_jobs", "2").unwrap();
+    config.set("storage_format", "toml").unwrap();
+
+    assert_eq!(config.edition, "2024");
+    assert_eq!(config.author, Some("Jane Doe".to_string()));
+    assert!(!config.git_init);
+    assert_eq!(config.snippet_merge, SnippetMerge::Module);
+    assert_eq!(config.update_jobs, 2);
+    assert_eq!(config.storage_format, StorageFormat::Toml);
+    assert_eq!(config.get("update_jobs").unwrap(), Some("2".to_string()));
+}
+
+// Test for resetting a key with an empty value
+#[test]
+fn test_config_reset() {
+    let mut config = Config::default();
+    config.set("update_jobs", "16").unwrap();
+    config.set("update_jobs", "").unwrap();
+    assert_eq!(config.update_jobs, Config::default().update_jobs);
+}
+
+// Test for invalid keys and values
+#[test]
+fn test_config_invalid() {
+    let mut config = Config::default();
+    assert!(config.get("registry_token").is_err());
+    assert!(config.set("update_jobs", "many").is_err());
+    assert!(config.set("snippet_merge", "inline").is_err());
+}
+
+// Test for saving and loading the settings file
+#[test]
+fn test_config_save_load() {
+    let path = std::env::temp_dir()
+        .join("limp_test_config")
+        .join("config.toml");
+    let mut config = Config::default();
+    config.set("cache_ttl_hours", "1").unwrap();
+    config.save(&path).unwrap();
+
+    let loaded = Config::load(&path).unwrap();
+    assert_eq!(loaded.cache_ttl_hours, 1);
+    assert_eq!(loaded.edition, "2021");
+
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+// Test that a token is saved but kept out of `limp config` and other users' reach
+#[test]
+fn test_config_token() {
+    let path = std::env::temp_dir()
+        .join("limp_test_config_token")
+        .join("config.toml");
+    let config = Config {
+        token: Some("cio_token".to_string()),
+        ..Default::default()
+    };
+    config.save(&path).unwrap();
+
+    let loaded = Config::load(&path).unwrap();
+    assert_eq!(loaded.token, Some("cio_token".to_string()));
+    assert!(loaded
+        .entries()
+        .unwrap()
+        .iter()
+        .all(|(_, v)| v.as_deref() != Some("cio_token")));
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        // a file saved before the token was set is restricted as well
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        config.save(&path).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+// Test that the timeout and retry settings make up the HTTP options
+#[test]
+fn test_config_http_options() {
+    let mut config = Config::default();
+    config.set("read_timeout_secs", "5").unwrap();
+    config.set("retries", "0").unwrap();
+    config.set("retry_backoff_ms", "100").unwrap();
+
+    let options = config.http_options();
+    assert_eq!(options.connect_timeout, Duration::from_secs(10));
+    assert_eq!(options.read_timeout, Duration::from_secs(5));
+    assert_eq!(options.retries, 0);
+    assert_eq!(options.backoff, Duration::from_millis(100));
+    assert!(config.set("retries", "-1").is_err());
+}
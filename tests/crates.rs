@@ -0,0 +1,282 @@
+use limp::cache::Cache;
+use limp::cargo_config::CargoConfig;
+use limp::crates::{
+    bypasses_proxy, parse_rust_version, CratesIoDependency, CratesIoSearch, FeatureGraph,
+    MockClient, Registry, SearchSort,
+};
+use limp::error::LimpError;
+use semver::VersionReq;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn demo_crate() -> CratesIoDependency {
+    serde_json::from_value(json!({
+        "crate": { "name": "demo", "max_version": "2.0.0" },
+        "versions": [
+            { "crate": "demo", "num": "2.0.0-rc.1" },
+            { "crate": "demo", "num": "1.2.0", "features": { "derive": [] }, "rust_version": "1.80" },
+            { "crate": "demo", "num": "1.1.0", "yanked": true },
+            { "crate": "demo", "num": "1.0.0" },
+            { "crate": "demo", "num": "0.12.3" },
+        ]
+    }))
+    .unwrap()
+}
+
+// Test that requirements resolve to the newest matching version
+#[test]
+fn test_find_matching() {
+    let dep = demo_crate();
+    let find = |req: &str| {
+        dep.find_matching(&VersionReq::parse(req).unwrap(), false, None)
+            .map(|v| v.num)
+    };
+
+    assert_eq!(find("1.0.0"), Some("1.2.0".to_string()));
+    assert_eq!(find("^1"), Some("1.2.0".to_string()));
+    assert_eq!(find("=1.0.0"), Some("1.0.0".to_string()));
+    assert_eq!(find("~0.12"), Some("0.12.3".to_string()));
+    assert_eq!(find(">=2, <3"), None);
+    assert_eq!(find(">=2.0.0-rc.1"), Some("2.0.0-rc.1".to_string()));
+    assert_eq!(find("=1.1.0"), None);
+}
+
+// Test that stored versions and requirements resolve to a published version
+#[test]
+fn test_resolve() {
+    let dep = demo_crate();
+    let resolve = |version: &str| dep.resolve(version, None).map(|v| v.num);
+
+    assert_eq!(resolve("1.0.0"), Some("1.0.0".to_string()));
+    assert_eq!(resolve("1.1.0"), Some("1.1.0".to_string()));
+    assert_eq!(resolve("^1"), Some("1.2.0".to_string()));
+    assert_eq!(resolve("1.0"), Some("1.2.0".to_string()));
+    assert_eq!(resolve("^3"), None);
+}
+
+// Test that yanked versions are skipped unless allowed
+#[test]
+fn test_yanked_versions() {
+    let dep = demo_crate();
+    let req = VersionReq::parse("=1.1.0").unwrap();
+
+    assert_eq!(
+        dep.find_matching(&req, true, None).map(|v| v.num),
+        Some("1.1.0".to_string())
+    );
+    assert_eq!(dep.latest(None).unwrap().num, "1.2.0");
+    assert_eq!(dep.latest_release(None, true).unwrap().num, "2.0.0-rc.1");
+}
+
+// Test that the highest version is the latest, not the last published backport
+#[test]
+fn test_latest_highest() {
+    let dep: CratesIoDependency = serde_json::from_value(json!({
+        "crate": { "name": "demo", "max_version": "2.1.0" },
+        "versions": [
+            { "crate": "demo", "num": "1.9.9" },
+            { "crate": "demo", "num": "2.1.0" },
+            { "crate": "demo", "num": "2.0.0" },
+        ]
+    }))
+    .unwrap();
+    assert_eq!(dep.latest(None).unwrap().num, "2.1.0");
+}
+
+// Test that pre-releases are only the latest version of crates without releases
+#[test]
+fn test_latest_prerelease_only() {
+    let dep: CratesIoDependency = serde_json::from_value(json!({
+        "crate": { "name": "demo", "max_version": "0.1.0-alpha.2" },
+        "versions": [
+            { "crate": "demo", "num": "0.1.0-alpha.2" },
+            { "crate": "demo", "num": "0.1.0-alpha.1" },
+        ]
+    }))
+    .unwrap();
+    assert_eq!(dep.latest(None).unwrap().num, "0.1.0-alpha.2");
+}
+
+// Test that versions requiring a newer rust are skipped
+#[test]
+fn test_msrv() {
+    let dep = demo_crate();
+    let msrv = parse_rust_version("1.70").unwrap();
+    let req = VersionReq::parse("^1").unwrap();
+
+    assert_eq!(
+        dep.find_matching(&req, false, Some(&msrv)).map(|v| v.num),
+        Some("1.0.0".to_string())
+    );
+    assert_eq!(
+        dep.find_matching(&req, false, parse_rust_version("1.80.1").as_ref())
+            .map(|v| v.num),
+        Some("1.2.0".to_string())
+    );
+    assert_eq!(parse_rust_version("1.70.0"), Some(msrv));
+    assert_eq!(parse_rust_version("1.x"), None);
+}
+
+// Test that unknown features are named with suggestions
+#[test]
+fn test_check_features() {
+    let version = demo_crate()
+        .find_matching(&VersionReq::parse("=1.2.0").unwrap(), false, None)
+        .unwrap();
+    assert!(version.check_features(&["derive".to_string()]).is_ok());
+
+    let error = version
+        .check_features(&["derives".to_string(), "json".to_string()])
+        .unwrap_err()
+        .to_string();
+    assert_eq!(
+        error,
+        "Incompatible features: demo/1.2.0 has no `derives` (did you mean `derive`?), `json`; available: derive"
+    );
+}
+
+// Test that implicit features of optional dependencies are known and meta
+// features are expanded
+#[test]
+fn test_feature_graph() {
+    let graph = FeatureGraph::new(
+        serde_json::from_value(json!({
+            "default": ["macros"],
+            "full": ["fs", "macros", "rt", "dep:bytes"],
+            "fs": [],
+            "macros": ["tokio-macros?/default"],
+            "rt": ["parking_lot/send_guard"],
+        }))
+        .unwrap(),
+    );
+
+    assert!(graph.contains("parking_lot"));
+    assert!(graph.contains("tokio-macros"));
+    assert!(!graph.contains("bytes"));
+    assert_eq!(
+        graph
+            .expand(&["rt".to_string()])
+            .into_iter()
+            .collect::<Vec<_>>(),
+        vec!["parking_lot", "rt"]
+    );
+    assert!(graph.is_meta("full"));
+    assert!(!graph.is_meta("macros"));
+}
+
+// Test that owned crates are only listed with a token
+#[test]
+fn test_owned_crates() {
+    let client = MockClient::new()
+        .with_crate(json!({ "crate": { "name": "serde", "max_version": "1.0.0" }, "versions": [] }))
+        .with_owned_crate(
+            json!({ "crate": { "name": "mine", "max_version": "0.1.0" }, "versions": [] }),
+        );
+    let registry = Registry::new(false).with_client(Arc::new(client));
+    assert!(matches!(registry.owned(), Err(LimpError::NotLoggedIn(_))));
+
+    let registry = registry.with_token(Some("cio_token".to_string()));
+    let owned = registry.owned().unwrap();
+    assert_eq!(owned.crates.len(), 1);
+    assert_eq!(owned.crates[0].name, "mine");
+}
+
+// Test that crates.io and cargo's registries are looked up in the mirror
+// cargo's config points at
+#[test]
+fn test_cargo_config_registries() {
+    let cache = std::env::temp_dir().join("limp_test_cargo_registries");
+    let _ = std::fs::remove_dir_all(&cache);
+    let config = CargoConfig::parse(
+        r#"
+[registries.company]
+index = "sparse+https://crates.example.com/index/"
+
+[registries.git]
+index = "https://github.com/company/index"
+
+[source.crates-io]
+replace-with = "mirror"
+
+[source.mirror]
+registry = "sparse+https://mirror.example.com/index/"
+"#,
+    )
+    .unwrap();
+    let client = MockClient::new()
+        .with_file(
+            "https://mirror.example.com/index/config.json",
+            r#"{"dl": "https://mirror.example.com/dl", "api": "https://mirror.example.com/"}"#,
+        )
+        .with_file(
+            "https://crates.example.com/index/config.json",
+            r#"{"dl": "https://crates.example.com/dl", "api": "https://crates.example.com"}"#,
+        );
+    let registry = Registry::with_cache(false, Cache::new(&cache, Duration::from_secs(60)))
+        .with_client(Arc::new(client))
+        .with_cargo_config(&config);
+    assert_eq!(
+        registry.api().unwrap(),
+        "https://mirror.example.com/api/v1/crates"
+    );
+    assert_eq!(
+        registry
+            .clone()
+            .using(Some("company"))
+            .unwrap()
+            .api()
+            .unwrap(),
+        "https://crates.example.com/api/v1/crates"
+    );
+    assert!(matches!(
+        registry.clone().using(Some("git")).unwrap().api(),
+        Err(LimpError::UnknownRegistry(_))
+    ));
+    assert!(matches!(
+        registry.using(Some("unknown")),
+        Err(LimpError::UnknownRegistry(_))
+    ));
+
+    // the api is remembered for offline use
+    let offline = Registry::with_cache(true, Cache::new(&cache, Duration::from_secs(60)))
+        .with_cargo_config(&config);
+    assert_eq!(
+        offline.api().unwrap(),
+        "https://mirror.example.com/api/v1/crates"
+    );
+
+    std::fs::remove_dir_all(cache).unwrap();
+}
+
+// Test which hosts bypass the proxy
+#[test]
+fn test_bypasses_proxy() {
+    let no_proxy = vec!["localhost".to_string(), ".example.com".to_string()];
+    assert!(bypasses_proxy(&no_proxy, "localhost"));
+    assert!(bypasses_proxy(&no_proxy, "example.com"));
+    assert!(bypasses_proxy(&no_proxy, "crates.example.com"));
+    assert!(!bypasses_proxy(&no_proxy, "crates.io"));
+    assert!(!bypasses_proxy(&no_proxy, "notexample.com"));
+    assert!(bypasses_proxy(&["*".to_string()], "crates.io"));
+}
+
+// Test that search results sort by downloads or last update and show both
+#[test]
+fn test_search_sort() {
+    let mut search: CratesIoSearch = serde_json::from_value(json!({ "crates": [
+        { "name": "old", "max_version": "1.0.0", "downloads": 900, "updated_at": "2019-01-01T00:00:00Z" },
+        { "name": "new", "max_version": "0.2.0", "downloads": 10, "recent_downloads": 8,
+          "updated_at": "2024-09-06T21:12:45.123Z", "repository": "https://github.com/u/new" },
+    ]}))
+    .unwrap();
+
+    search.sort(SearchSort::Downloads);
+    assert_eq!(search.crates[0].name, "old");
+    search.sort(SearchSort::Recent);
+    assert_eq!(search.crates[0].name, "new");
+    assert_eq!(
+        search.crates[0].to_string(),
+        "new = \"0.2.0\" (downloads: 10, recent: 8, updated: 2024-09-06)\n    https://github.com/u/new"
+    );
+}
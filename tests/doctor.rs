@@ -0,0 +1,52 @@
+use limp::doctor::{snippets, storage_dir};
+use limp::storage::{JsonDependency, JsonStorage};
+use std::fs;
+
+// Test for the storage directory check
+#[test]
+fn test_storage_dir() {
+    let dir = std::env::temp_dir().join("limp_doctor_storage");
+    fs::create_dir_all(&dir).unwrap();
+    assert!(storage_dir(&dir).passed());
+
+    let missing = storage_dir(&dir.join("missing"));
+    assert!(!missing.passed());
+    assert!(missing.to_string().contains("fix: "));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+// Test that dependencies linked to missing snippets are reported
+#[test]
+fn test_missing_snippets() {
+    let dir = std::env::temp_dir().join("limp_doctor_snippets");
+    fs::create_dir_all(&dir).unwrap();
+    let present = dir.join("present.rs");
+    fs::write(&present, "fn main() {}\n").unwrap();
+
+    let mut storage = JsonStorage::default();
+    let dep = |name: &str, snippet: Option<String>| {
+        let mut dep = JsonDependency {
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            ..Default::default()
+        };
+        if let Some(snippet) = snippet {
+            dep.set_snippet(None, snippet);
+        }
+        dep
+    };
+    storage.add(dep("plain", None));
+    storage.add(dep("good", Some(present.display().to_string())));
+    assert!(snippets(&storage).passed());
+
+    storage.add(dep(
+        "stale",
+        Some(dir.join("gone.rs").display().to_string()),
+    ));
+    let check = snippets(&storage);
+    assert!(!check.passed());
+    assert_eq!(check.details, "missing for stale");
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,100 @@
+use limp::files::{config_path, create_project, storage_path, ProjectKind, ProjectOptions};
+use limp::scaffold::Scaffold;
+use std::fs;
+use std::path::PathBuf;
+
+// Test that LIMP_HOME overrides and XDG_CONFIG_HOME is respected on Linux.
+// Both live in one test since they mutate the process environment.
+#[test]
+fn test_storage_path_env() {
+    std::env::set_var("LIMP_HOME", "/tmp/limp_home");
+    assert_eq!(storage_path(), PathBuf::from("/tmp/limp_home"));
+    assert_eq!(
+        config_path(),
+        PathBuf::from("/tmp/limp_home/dependencies.json")
+    );
+    std::env::remove_var("LIMP_HOME");
+
+    if std::env::consts::OS == "linux" {
+        std::env::set_var("XDG_CONFIG_HOME", "/tmp/limp_xdg");
+        assert_eq!(storage_path(), PathBuf::from("/tmp/limp_xdg/limp"));
+        std::env::remove_var("XDG_CONFIG_HOME");
+    }
+}
+
+// Test that projects can be created in a nested directory under another name
+#[test]
+fn test_create_project_in_path() {
+    let root = std::env::temp_dir().join("limp_files_nested");
+    let dir = root.join("path").join("to").join("dir");
+    let options = ProjectOptions {
+        name: Some("actual_name".to_string()),
+        git: false,
+        ..Default::default()
+    };
+    create_project(dir.to_str().unwrap(), None, &options).unwrap();
+
+    let toml = fs::read_to_string(dir.join("Cargo.toml")).unwrap();
+    assert!(toml.contains("name = \"actual_name\""));
+    assert!(dir.join("src").join("main.rs").exists());
+    assert!(!dir.join(".git").exists());
+    assert_eq!(ProjectOptions::default().package_name(&dir), "dir");
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+// Test that a project can be created in an existing directory of a repository
+#[test]
+fn test_create_project_in_existing_repo() {
+    let dir = std::env::temp_dir().join("limp_files_existing");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join(".git")).unwrap();
+    fs::write(dir.join("README.md"), "# existing\n").unwrap();
+    fs::write(dir.join(".gitignore"), "*.log").unwrap();
+
+    let options = ProjectOptions::default();
+    let path = dir.join(".");
+    assert_eq!(options.package_name(&path), "limp_files_existing");
+    create_project(path.to_str().unwrap(), None, &options).unwrap();
+
+    let toml = fs::read_to_string(dir.join("Cargo.toml")).unwrap();
+    assert!(toml.contains("name = \"limp_files_existing\""));
+    assert!(dir.join("README.md").exists());
+    assert_eq!(
+        fs::read_to_string(dir.join(".gitignore")).unwrap(),
+        "*.log\n/target"
+    );
+    // the repository is kept as it is instead of running `git init` again
+    assert_eq!(fs::read_dir(dir.join(".git")).unwrap().count(), 0);
+
+    assert!(create_project(path.to_str().unwrap(), None, &options).is_err());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+// Test that a library gets example, bench and test stubs with the bench harness turned off
+#[test]
+fn test_create_project_with_targets() {
+    let dir = std::env::temp_dir().join("limp_files_targets");
+    let _ = fs::remove_dir_all(&dir);
+    let options = ProjectOptions {
+        kind: ProjectKind::Lib,
+        git: false,
+        scaffold: Scaffold {
+            examples: true,
+            benches: true,
+            tests: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    create_project(dir.to_str().unwrap(), None, &options).unwrap();
+
+    let toml = fs::read_to_string(dir.join("Cargo.toml")).unwrap();
+    assert!(toml.contains("[[bench]]\nname = \"bench\"\nharness = false\n"));
+    assert!(dir.join("examples").join("basic.rs").exists());
+    assert!(dir.join("benches").join("bench.rs").exists());
+    let test = fs::read_to_string(dir.join("tests").join("integration.rs")).unwrap();
+    assert!(test.contains("use limp_files_targets as _;"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
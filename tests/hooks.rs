@@ -0,0 +1,64 @@
+use std::fs;
+
+use limp::hooks::{self, Hook, HookContext};
+
+#[cfg(unix)]
+fn write_hook(path: &std::path::Path, script: &str) {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::write(path, script).unwrap();
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+// Test that hooks get the project context on stdin and in the environment
+#[cfg(unix)]
+#[test]
+fn test_run_hook_with_context() {
+    let dir = std::env::temp_dir().join("limp_hooks_context");
+    let _ = fs::remove_dir_all(&dir);
+    let project = dir.join("myapp");
+    fs::create_dir_all(&project).unwrap();
+    write_hook(
+        &dir.join("post-init.sh"),
+        "#!/bin/sh\ncat > context.json\necho \"$LIMP_HOOK $LIMP_PROJECT_NAME $LIMP_DEPENDENCIES\" > env.txt\n",
+    );
+    write_hook(&dir.join("pre-add"), "#!/bin/sh\nexit 1\n");
+
+    let context = HookContext::new(Hook::PostInit, &project, "myapp")
+        .with_dependencies(vec!["serde".to_string(), "tokio".to_string()]);
+    let ran = hooks::run_in(&dir, &context).unwrap();
+    assert_eq!(ran, vec![dir.join("post-init.sh")]);
+
+    let input = fs::read_to_string(project.join("context.json")).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&input).unwrap();
+    assert_eq!(json["hook"], "post-init");
+    assert_eq!(json["project_name"], "myapp");
+    assert_eq!(json["dependencies"][1], "tokio");
+    let env = fs::read_to_string(project.join("env.txt")).unwrap();
+    assert_eq!(env.trim(), "post-init myapp serde,tokio");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+// Test that a failing hook is reported as an error
+#[cfg(unix)]
+#[test]
+fn test_run_failing_hook() {
+    let dir = std::env::temp_dir().join("limp_hooks_failing");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    write_hook(&dir.join("pre-add"), "#!/bin/sh\nexit 1\n");
+
+    let context = HookContext::new(Hook::PreAdd, &dir, "myapp");
+    assert!(hooks::run_in(&dir, &context).is_err());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+// Test that a missing hooks directory runs nothing
+#[test]
+fn test_run_without_hooks() {
+    let dir = std::env::temp_dir().join("limp_hooks_missing");
+    let context = HookContext::new(Hook::PreInit, &dir, "myapp");
+    assert!(hooks::run_in(&dir, &context).unwrap().is_empty());
+}
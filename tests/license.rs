@@ -0,0 +1,48 @@
+use limp::license::LicensePolicy;
+
+fn policy(allow: &[&str], deny: &[&str]) -> LicensePolicy {
+    LicensePolicy {
+        allow: allow.iter().map(|s| s.to_string()).collect(),
+        deny: deny.iter().map(|s| s.to_string()).collect(),
+        fail: false,
+    }
+}
+
+// Test that denied licenses match their -only, -or-later and + forms
+#[test]
+fn test_denylist() {
+    let policy = policy(&[], &["GPL-3.0"]);
+    for license in ["GPL-3.0", "GPL-3.0-only", "GPL-3.0-or-later", "gpl-3.0+"] {
+        assert!(!policy.accepts(license), "{}", license);
+    }
+    assert!(policy.accepts("LGPL-3.0"));
+    assert!(policy.accepts("MIT"));
+}
+
+// Test that OR needs one accepted side and AND needs both
+#[test]
+fn test_expressions() {
+    let policy = policy(&["MIT", "Apache-2.0", "Unicode-DFS-2016"], &[]);
+    assert!(policy.accepts("MIT OR Apache-2.0"));
+    assert!(policy.accepts("GPL-3.0 OR MIT"));
+    assert!(policy.accepts("MIT/Apache-2.0"));
+    assert!(policy.accepts("(MIT OR Apache-2.0) AND Unicode-DFS-2016"));
+    assert!(policy.accepts("Apache-2.0 WITH LLVM-exception"));
+    assert!(!policy.accepts("MIT AND GPL-3.0"));
+    assert!(!policy.accepts("(MIT OR Apache-2.0) AND BSD-3-Clause"));
+}
+
+// Test that crates without a license only violate an allowlist
+#[test]
+fn test_violation() {
+    assert_eq!(policy(&[], &[]).violation("gpl", Some("GPL-3.0")), None);
+    assert_eq!(policy(&[], &["GPL-3.0"]).violation("nolicense", None), None);
+    assert_eq!(
+        policy(&[], &["GPL-3.0"]).violation("gpl", Some("GPL-3.0-or-later")),
+        Some("gpl is licensed under GPL-3.0-or-later".to_string())
+    );
+    assert_eq!(
+        policy(&["MIT"], &[]).violation("nolicense", None),
+        Some("nolicense has no license".to_string())
+    );
+}
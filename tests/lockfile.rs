@@ -0,0 +1,58 @@
+use limp::lockfile::{is_older, Lockfile};
+use std::fs;
+
+const LOCK: &str = r#"# This file is automatically @generated by Cargo.
+version = 3
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = ["rand 0.8.5", "rand 0.9.0"]
+
+[[package]]
+name = "rand"
+version = "0.8.5"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "rand"
+version = "0.9.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+
+// Test that every locked version of a crate is found
+#[test]
+fn test_lockfile_versions() {
+    let lock = Lockfile::parse(LOCK).unwrap();
+    assert_eq!(lock.versions("rand"), vec!["0.8.5", "0.9.0"]);
+    assert_eq!(lock.packages[0].source, None);
+    assert!(lock.versions("serde").is_empty());
+}
+
+// Test that the lockfile of a workspace member is the workspace's
+#[test]
+fn test_lockfile_in_workspace() {
+    let dir = std::env::temp_dir().join("limp_lockfile_workspace");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("app")).unwrap();
+    fs::write(dir.join("Cargo.toml"), "[workspace]\nmembers = [\"app\"]\n").unwrap();
+    fs::write(dir.join("app/Cargo.toml"), "[package]\nname = \"app\"\n").unwrap();
+
+    let member = dir.join("app/Cargo.toml");
+    assert_eq!(Lockfile::path_of(&member).unwrap(), dir.join("Cargo.lock"));
+    assert!(Lockfile::find(&member).unwrap().is_none());
+
+    fs::write(dir.join("Cargo.lock"), LOCK).unwrap();
+    assert_eq!(Lockfile::find(&member).unwrap().unwrap().packages.len(), 3);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+// Test that locked versions are compared with the lowest version a requirement allows
+#[test]
+fn test_is_older() {
+    assert!(is_older("1.0.100", "1.0.210"));
+    assert!(is_older("1.0.100", "^1.1"));
+    assert!(!is_older("1.0.210", "1.0.210"));
+    assert!(!is_older("1.2.0", "1.0"));
+    assert!(!is_older("1.0.0", "*"));
+}
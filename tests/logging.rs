@@ -0,0 +1,43 @@
+use limp::logging::{self, Level};
+use std::fs;
+
+// Test that `RUST_LOG` filters set limp's level, preferring directives for limp
+#[test]
+fn test_level_from_env_filter() {
+    assert_eq!(Level::from_env_filter("debug"), Some(Level::Debug));
+    assert_eq!(Level::from_env_filter("limp=trace"), Some(Level::Trace));
+    assert_eq!(
+        Level::from_env_filter("limp::crates=info,warn"),
+        Some(Level::Info)
+    );
+    assert_eq!(Level::from_env_filter("warn,ureq=trace"), Some(Level::Warn));
+    assert_eq!(Level::from_env_filter("ureq=trace"), None);
+    assert_eq!(Level::from_env_filter("off"), None);
+}
+
+// Test that records up to the level are appended to the log file, spans with
+// their duration
+#[test]
+fn test_log_file() {
+    let dir = std::env::temp_dir().join("limp_test_logging");
+    let _ = fs::remove_dir_all(&dir);
+    let path = dir.join(logging::FILE_NAME);
+    logging::init(Level::Debug, Some(&path)).unwrap();
+    assert!(logging::enabled(Level::Info));
+    assert!(!logging::enabled(Level::Trace));
+
+    logging::info("storage", "wrote dependencies.json");
+    logging::trace("cache", "serde: hit");
+    drop(logging::span(Level::Debug, "registry", || {
+        "GET serde".to_string()
+    }));
+
+    let log = fs::read_to_string(&path).unwrap();
+    let lines = log.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].ends_with(" INFO  storage: wrote dependencies.json"));
+    assert!(lines[1].ends_with(" DEBUG registry: GET serde started"));
+    assert!(lines[2].contains(" DEBUG registry: GET serde finished in "));
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,525 @@
+use limp::manifest::CargoManifest;
+use limp::storage::{DependencyKind, DependencySource, JsonDependency};
+use std::fs;
+use std::path::PathBuf;
+
+const TOML: &str = r#"[package]
+name = "manifest_test"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+serde = "1.0.0"
+
+[dev-dependencies]
+tempfile = "3.0.0"
+"#;
+
+fn write_manifest(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("Cargo.toml");
+    fs::write(&path, TOML).unwrap();
+    path
+}
+
+// Dependencies must land in [dependencies] even when other tables follow
+#[test]
+fn test_add_dependency_before_dev_dependencies() {
+    let path = write_manifest("limp_manifest_add");
+    let mut manifest = CargoManifest::load(&path).unwrap();
+    manifest
+        .add_dependency(&JsonDependency {
+            name: "tokio".to_string(),
+            version: "1.0.0".to_string(),
+            features: Some(vec!["full".to_string()]),
+            ..Default::default()
+        })
+        .unwrap();
+    manifest.save().unwrap();
+
+    let content = fs::read_to_string(&path).unwrap();
+    let deps = content.find("[dependencies]").unwrap();
+    let tokio = content
+        .find(r#"tokio = { version = "1.0.0", features = ["full"] }"#)
+        .unwrap();
+    let dev_deps = content.find("[dev-dependencies]").unwrap();
+    assert!(deps < tokio && tokio < dev_deps);
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+// Re-adding an existing dependency replaces it instead of duplicating it
+#[test]
+fn test_add_dependency_replaces_existing() {
+    let path = write_manifest("limp_manifest_replace");
+    let mut manifest = CargoManifest::load(&path).unwrap();
+    manifest
+        .add_dependency(&JsonDependency {
+            name: "serde".to_string(),
+            version: "1.0.210".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+
+    let content = manifest.to_string();
+    assert_eq!(content.matches("serde =").count(), 1);
+    assert!(content.contains(r#"serde = "1.0.210""#));
+    assert!(manifest.contains_dependency("serde", DependencyKind::Normal));
+    assert!(!manifest.contains_dependency("tempfile", DependencyKind::Normal));
+    assert!(manifest.contains_dependency("tempfile", DependencyKind::Dev));
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+// Dev-dependencies go to [dev-dependencies] instead of [dependencies]
+#[test]
+fn test_add_dev_dependency() {
+    let path = write_manifest("limp_manifest_dev");
+    let mut manifest = CargoManifest::load(&path).unwrap();
+    manifest
+        .add_dependency(&JsonDependency {
+            name: "pretty_assertions".to_string(),
+            version: "1.4.0".to_string(),
+            kind: DependencyKind::Dev,
+            ..Default::default()
+        })
+        .unwrap();
+
+    assert!(!manifest.contains_dependency("pretty_assertions", DependencyKind::Normal));
+    assert_eq!(
+        manifest.dependency_names(DependencyKind::Dev),
+        vec!["pretty_assertions".to_string(), "tempfile".to_string()]
+    );
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+// Removing a dependency drops it from whichever tables contain it
+#[test]
+fn test_remove_dependency() {
+    let path = write_manifest("limp_manifest_remove");
+    let mut manifest = CargoManifest::load(&path).unwrap();
+
+    assert_eq!(
+        manifest.remove_dependency("tempfile"),
+        vec![DependencyKind::Dev]
+    );
+    assert!(manifest.remove_dependency("tempfile").is_empty());
+    assert!(manifest.contains_dependency("serde", DependencyKind::Normal));
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+// Setting a version keeps the rest of the entry as written
+#[test]
+fn test_set_version() {
+    let path = write_manifest("limp_manifest_set_version");
+    fs::write(
+        &path,
+        format!(
+            "{}tokio = {{ version = \"1.0.0\", features = [\"full\"] }}\n",
+            TOML
+        ),
+    )
+    .unwrap();
+    let mut manifest = CargoManifest::load(&path).unwrap();
+
+    assert!(manifest.set_version("serde", DependencyKind::Normal, "1.0.210"));
+    assert!(manifest.set_version("tokio", DependencyKind::Dev, "1.41.1"));
+    assert!(!manifest.set_version("serde", DependencyKind::Dev, "1.0.210"));
+    let toml = manifest.to_string();
+    assert!(toml.contains("serde = \"1.0.210\""));
+    assert!(toml.contains("tokio = { version = \"1.41.1\", features = [\"full\"] }"));
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+// Dependencies and extra tables can be read back for templates and imports
+#[test]
+fn test_dependencies_and_extras() {
+    let path = write_manifest("limp_manifest_read");
+    fs::write(&path, format!("{}\n[profile.release]\nlto = true\n", TOML)).unwrap();
+    let manifest = CargoManifest::load(&path).unwrap();
+
+    let deps = manifest.dependencies();
+    assert_eq!(deps.len(), 2);
+    assert_eq!(deps[0].name, "serde");
+    assert_eq!(deps[1].kind, DependencyKind::Dev);
+
+    let extras = manifest.extras();
+    assert!(extras.contains("[profile.release]"));
+    assert!(!extras.contains("[package]"));
+    assert!(!extras.contains("serde"));
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+// Dependencies from alternative registries keep their `registry` key
+#[test]
+fn test_add_registry_dependency() {
+    let path = write_manifest("limp_manifest_registry");
+    let mut manifest = CargoManifest::load(&path).unwrap();
+    manifest
+        .add_dependency(&JsonDependency {
+            name: "internal".to_string(),
+            version: "0.3.0".to_string(),
+            registry: Some("company".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+
+    assert!(manifest
+        .to_string()
+        .contains(r#"internal = { version = "0.3.0", registry = "company" }"#));
+    let internal = manifest
+        .dependencies()
+        .into_iter()
+        .find(|d| d.name == "internal")
+        .unwrap();
+    assert_eq!(internal.registry, Some("company".to_string()));
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+// Git and path dependencies are written without a registry version
+#[test]
+fn test_add_source_dependency() {
+    let path = write_manifest("limp_manifest_source");
+    let mut manifest = CargoManifest::load(&path).unwrap();
+    manifest
+        .add_dependency(&JsonDependency {
+            name: "local".to_string(),
+            source: DependencySource::Path {
+                path: "../local".to_string(),
+            },
+            ..Default::default()
+        })
+        .unwrap();
+
+    assert!(manifest
+        .to_string()
+        .contains(r#"local = { path = "../local" }"#));
+    let local = manifest
+        .dependencies()
+        .into_iter()
+        .find(|d| d.name == "local")
+        .unwrap();
+    assert_eq!(
+        local.source,
+        DependencySource::Path {
+            path: "../local".to_string()
+        }
+    );
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+// `default-features = false` is written and read back
+#[test]
+fn test_add_dependency_without_default_features() {
+    let path = write_manifest("limp_manifest_default_features");
+    let mut manifest = CargoManifest::load(&path).unwrap();
+    manifest
+        .add_dependency(&JsonDependency {
+            name: "tokio".to_string(),
+            version: "1.0.0".to_string(),
+            default_features: Some(false),
+            features: Some(vec!["rt".to_string()]),
+            ..Default::default()
+        })
+        .unwrap();
+
+    assert!(manifest
+        .to_string()
+        .contains(r#"tokio = { version = "1.0.0", default-features = false, features = ["rt"] }"#));
+    let tokio = manifest
+        .dependencies()
+        .into_iter()
+        .find(|d| d.name == "tokio")
+        .unwrap();
+    assert_eq!(tokio.default_features, Some(false));
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+// Renamed dependencies are keyed by their alias and read back with `package`
+#[test]
+fn test_add_renamed_dependency() {
+    let path = write_manifest("limp_manifest_rename");
+    let mut manifest = CargoManifest::load(&path).unwrap();
+    manifest
+        .add_dependency(&JsonDependency {
+            name: "tokio".to_string(),
+            version: "1.0.0".to_string(),
+            rename: Some("tok".to_string()),
+            optional: true,
+            ..Default::default()
+        })
+        .unwrap();
+
+    assert!(manifest
+        .to_string()
+        .contains(r#"tok = { version = "1.0.0", package = "tokio", optional = true }"#));
+    let tokio = manifest
+        .dependencies()
+        .into_iter()
+        .find(|d| d.name == "tokio")
+        .unwrap();
+    assert_eq!(tokio.rename, Some("tok".to_string()));
+    assert!(tokio.optional);
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+// New dependencies are inserted alphabetically into a sorted table
+#[test]
+fn test_add_dependency_keeps_sorted_order() {
+    let path = write_manifest("limp_manifest_sorted");
+    let mut manifest = CargoManifest::load(&path).unwrap();
+    for name in ["tokio", "anyhow"] {
+        manifest
+            .add_dependency(&JsonDependency {
+                name: name.to_string(),
+                version: "1.0.0".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+    }
+
+    assert_eq!(
+        manifest.dependency_names(DependencyKind::Normal),
+        vec!["anyhow", "serde", "tokio"]
+    );
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+// An unsorted table is left in its order and the dependency appended
+#[test]
+fn test_add_dependency_appends_to_unsorted() {
+    let path = write_manifest("limp_manifest_unsorted");
+    fs::write(
+        &path,
+        TOML.replace("serde = \"1.0.0\"", "serde = \"1.0.0\"\nclap = \"4.0.0\""),
+    )
+    .unwrap();
+    let mut manifest = CargoManifest::load(&path).unwrap();
+    manifest
+        .add_dependency(&JsonDependency {
+            name: "anyhow".to_string(),
+            version: "1.0.0".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+
+    assert_eq!(
+        manifest.dependency_names(DependencyKind::Normal),
+        vec!["serde", "clap", "anyhow"]
+    );
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+// Updating an existing dependency keeps its comments and its table form
+#[test]
+fn test_add_dependency_updates_in_place() {
+    let path = write_manifest("limp_manifest_in_place");
+    fs::write(
+        &path,
+        TOML.replace(
+            "serde = \"1.0.0\"",
+            "# serialization\nserde = \"1.0.0\" # pinned\n\n[dependencies.tokio]\nversion = \"1.0.0\"\nfeatures = [\"rt\"]",
+        ),
+    )
+    .unwrap();
+    let mut manifest = CargoManifest::load(&path).unwrap();
+    for (name, version) in [("serde", "1.0.210"), ("tokio", "1.40.0")] {
+        manifest
+            .add_dependency(&JsonDependency {
+                name: name.to_string(),
+                version: version.to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+    }
+
+    let content = manifest.to_string();
+    assert!(content.contains("# serialization\nserde = \"1.0.210\" # pinned"));
+    assert!(content.contains("[dependencies.tokio]\nversion = \"1.40.0\"\n"));
+    assert!(!content.contains("features"));
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+// Every kind lands in its own table when dev-dependencies and profiles follow,
+// and cargo reads the same crate lists back
+#[test]
+fn test_add_dependencies_between_tables() {
+    let path = write_manifest("limp_manifest_sections");
+    fs::write(&path, format!("{}\n[profile.release]\nlto = true\n", TOML)).unwrap();
+    let mut manifest = CargoManifest::load(&path).unwrap();
+    for (name, kind) in [
+        ("tokio", DependencyKind::Normal),
+        ("insta", DependencyKind::Dev),
+        ("cc", DependencyKind::Build),
+    ] {
+        manifest
+            .add_dependency(&JsonDependency {
+                name: name.to_string(),
+                version: "1.0.0".to_string(),
+                kind,
+                ..Default::default()
+            })
+            .unwrap();
+    }
+    manifest.save().unwrap();
+
+    let content = fs::read_to_string(&path).unwrap();
+    let table = content.parse::<toml::Table>().unwrap();
+    let names = |key: &str| {
+        table[key]
+            .as_table()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names("dependencies"), vec!["serde", "tokio"]);
+    assert_eq!(names("dev-dependencies"), vec!["insta", "tempfile"]);
+    assert_eq!(names("build-dependencies"), vec!["cc"]);
+    assert_eq!(table["profile"]["release"].as_table().unwrap().len(), 1);
+    assert!(
+        content.find("[build-dependencies]").unwrap() < content.find("[profile.release]").unwrap()
+    );
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+// A missing [dependencies] table is created after [package], not at the end
+#[test]
+fn test_add_dependency_creates_table_after_package() {
+    let path = write_manifest("limp_manifest_create");
+    fs::write(
+        &path,
+        "[package]\nname = \"manifest_test\"\n\n[profile.release]\nlto = true\n",
+    )
+    .unwrap();
+    let mut manifest = CargoManifest::load(&path).unwrap();
+    manifest
+        .add_dependency(&JsonDependency {
+            name: "serde".to_string(),
+            version: "1.0.0".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+
+    let content = manifest.to_string();
+    let deps = content.find("[dependencies]").unwrap();
+    let profile = content.find("[profile.release]").unwrap();
+    assert!(deps < profile);
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+// Extras are merged into existing tables and new tables go to the end
+#[test]
+fn test_merge_extras() {
+    let path = write_manifest("limp_manifest_extras");
+    fs::write(
+        &path,
+        "[package]\nname = \"manifest_test\"\n\n[dependencies]\nserde = \"1.0.0\"\n\n[profile.release]\nopt-level = 3\n",
+    )
+    .unwrap();
+    let mut manifest = CargoManifest::load(&path).unwrap();
+    let extras = "package.license = \"MIT\"\nprofile.release.lto = true\nlints.rust.unsafe_code = \"forbid\"\n"
+        .parse::<toml::Table>()
+        .unwrap();
+    manifest.merge_extras(&extras).unwrap();
+
+    let content = manifest.to_string();
+    assert!(content.contains("[package]\nname = \"manifest_test\"\nlicense = \"MIT\"\n"));
+    assert!(content.contains("[profile.release]\nopt-level = 3\nlto = true\n"));
+    let deps = content.find("[dependencies]").unwrap();
+    let lints = content
+        .find("[lints.rust]\nunsafe_code = \"forbid\"")
+        .unwrap();
+    assert!(deps < lints);
+    assert!(content.find("[profile.release]").unwrap() < lints);
+
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+fn write_workspace(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("crates/core")).unwrap();
+    fs::create_dir_all(dir.join("app")).unwrap();
+    fs::write(
+        dir.join("Cargo.toml"),
+        "[workspace]\nresolver = \"2\"\nmembers = [\"app\", \"crates/*\"]\n\n[profile.release]\nlto = true\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("app/Cargo.toml"),
+        "[package]\nname = \"app\"\nversion = \"0.1.0\"\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("crates/core/Cargo.toml"),
+        "[package]\nname = \"my-core\"\nversion = \"0.1.0\"\n",
+    )
+    .unwrap();
+    dir
+}
+
+// Test that members are found by package or directory name, including globbed ones
+#[test]
+fn test_workspace_members() {
+    let dir = write_workspace("limp_manifest_members");
+    let root = CargoManifest::load(dir.join("Cargo.toml")).unwrap();
+    assert!(root.is_virtual());
+    assert_eq!(root.members().len(), 2);
+    assert_eq!(
+        root.member("my-core").unwrap().package_name(),
+        Some("my-core")
+    );
+    assert_eq!(root.member("core").unwrap().package_name(), Some("my-core"));
+    assert_eq!(root.member("app").unwrap().package_name(), Some("app"));
+    assert!(root.member("missing").is_err());
+
+    let found = CargoManifest::workspace_of(dir.join("crates/core/Cargo.toml"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.path(), dir.join("Cargo.toml"));
+    assert!(!found.member("app").unwrap().is_virtual());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+// Test that workspace dependencies go to [workspace.dependencies] and members inherit them
+#[test]
+fn test_add_workspace_dependency() {
+    let dir = write_workspace("limp_manifest_workspace_dep");
+    let dep = JsonDependency {
+        name: "serde".to_string(),
+        version: "1.0.0".to_string(),
+        features: Some(vec!["derive".to_string()]),
+        optional: true,
+        ..Default::default()
+    };
+
+    let mut root = CargoManifest::load(dir.join("Cargo.toml")).unwrap();
+    root.add_workspace_dependency(&dep).unwrap();
+    let content = root.to_string();
+    assert!(content.contains(
+        "[workspace.dependencies]\nserde = { version = \"1.0.0\", features = [\"derive\"] }"
+    ));
+    assert!(content.find("[workspace.dependencies]") < content.find("[profile.release]"));
+
+    let mut member = root.member("app").unwrap();
+    member.add_inherited_dependency(&dep).unwrap();
+    assert!(member.to_string().contains(
+        "[dependencies]\nserde = { workspace = true, features = [\"derive\"], optional = true }"
+    ));
+    fs::remove_dir_all(dir).unwrap();
+}
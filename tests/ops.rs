@@ -0,0 +1,1090 @@
+use std::{fs, process::Command, sync::Arc};
+
+use limp::{
+    context::Context,
+    crates::{MockClient, Version},
+    error::LimpError,
+    history,
+    ops::{self, InitOptions, LinkOptions, NewOptions},
+    snippets,
+    storage::{JsonDependency, JsonStorage},
+};
+use serde_json::json;
+
+fn context(name: &str) -> Context {
+    let root = std::env::temp_dir().join(name);
+    let _ = fs::remove_dir_all(&root);
+    let client = MockClient::new().with_crate(json!({
+        "crate": { "name": "serde", "max_version": "1.0.210" },
+        "versions": [
+            {
+                "crate": "serde",
+                "num": "1.0.210",
+                "features": { "derive": [], "std": [] },
+                "created_at": "2024-09-06T21:12:45.123Z"
+            },
+            { "crate": "serde", "num": "1.0.0" },
+        ]
+    }));
+    let mut ctx = Context::load(root.join("home"), false)
+        .unwrap()
+        .with_client(Arc::new(client));
+    ctx.config.git_init = false;
+    ctx
+}
+
+// Test that projects can be created without the CLI and the user's storage
+#[test]
+fn test_init_in_process() {
+    let ctx = context("limp_ops_init");
+    let project = ctx.root.with_file_name("limp_ops_init_project");
+    let _ = fs::remove_dir_all(&project);
+
+    let options = InitOptions {
+        path: project.to_str().unwrap().to_string(),
+        dependencies: Some(vec!["serde".to_string()]),
+        ..Default::default()
+    };
+    let report = ops::init(&ctx, &options).unwrap();
+    assert_eq!(report.dependencies[0].version, "1.0.210");
+    assert!(report.errors.is_empty());
+    let toml = fs::read_to_string(project.join("Cargo.toml")).unwrap();
+    assert!(toml.contains("serde = \"1.0.210\""));
+    assert!(!project.join(".git").exists());
+
+    assert!(matches!(
+        ops::init(&ctx, &options),
+        Err(LimpError::CrateExistsNotEmpty(_))
+    ));
+
+    fs::remove_dir_all(project).unwrap();
+    fs::remove_dir_all(ctx.root).unwrap();
+}
+
+// Test that dependencies are resolved from the mock registry and stored in the context
+#[test]
+fn test_new_dependency_in_process() {
+    let ctx = context("limp_ops_new");
+
+    let options = NewOptions {
+        name: "serde".to_string(),
+        version: Some("1.0.0".to_string()),
+        features: Some(vec!["derive".to_string()]),
+        ..Default::default()
+    };
+    let report = ops::new_dependency(&ctx, &options).unwrap();
+    assert_eq!(report.dependency.version, "1.0.210");
+    let js = JsonStorage::load(ctx.config_path()).unwrap();
+    assert_eq!(js.get("serde").unwrap().version, "1.0.210");
+
+    let options = NewOptions {
+        name: "serd".to_string(),
+        ..Default::default()
+    };
+    assert!(matches!(
+        ops::new_dependency(&ctx, &options),
+        Err(LimpError::CrateNotFound(e)) if e.contains("did you mean `serde`")
+    ));
+
+    fs::remove_dir_all(ctx.root).unwrap();
+}
+
+// Test that a stored dependency is only replaced with `force`
+#[test]
+fn test_new_dependency_existing() {
+    let ctx = context("limp_ops_new_existing");
+    let mut options = NewOptions {
+        name: "serde".to_string(),
+        features: Some(vec!["derive".to_string()]),
+        ..Default::default()
+    };
+    ops::new_dependency(&ctx, &options).unwrap();
+    let features = || {
+        JsonStorage::load(ctx.config_path())
+            .unwrap()
+            .get("serde")
+            .unwrap()
+            .features
+            .clone()
+    };
+
+    options.features = None;
+    assert!(matches!(
+        ops::new_dependency(&ctx, &options),
+        Err(LimpError::DependencyAlreadyExists(_))
+    ));
+    assert_eq!(features(), Some(vec!["derive".to_string()]));
+
+    options.if_missing = true;
+    let report = ops::new_dependency(&ctx, &options).unwrap();
+    assert!(report.already_stored);
+    assert_eq!(report.dependency.features, Some(vec!["derive".to_string()]));
+    assert_eq!(features(), Some(vec!["derive".to_string()]));
+
+    options.if_missing = false;
+    options.force = true;
+    let report = ops::new_dependency(&ctx, &options).unwrap();
+    assert!(!report.already_stored);
+    assert_eq!(features(), None);
+
+    fs::remove_dir_all(ctx.root).unwrap();
+}
+
+// Test that a list of dependencies is stored with failures collected per entry
+#[test]
+fn test_new_dependencies_from_list() {
+    let ctx = context("limp_ops_new_list");
+    let base = NewOptions {
+        if_missing: true,
+        ..Default::default()
+    };
+    let list = ops::new_list(
+        &base,
+        "# seed\nserde@1.0.0 derive std\n\nserd\nserde # again\n",
+    );
+    assert_eq!(list.len(), 3);
+    assert_eq!(list[0].version.as_deref(), Some("1.0.0"));
+    assert_eq!(
+        list[0].features,
+        Some(vec!["derive".to_string(), "std".to_string()])
+    );
+    assert!(list[1].features.is_none());
+    assert!(list.iter().all(|o| o.if_missing));
+
+    let report = ops::new_dependencies(&ctx, &list, 2);
+    assert_eq!(report.reports.len(), 2);
+    assert!(!report.reports[0].already_stored);
+    assert!(report.reports[1].already_stored);
+    assert_eq!(report.errors.len(), 1);
+    assert_eq!(report.errors[0].0, "serd");
+    assert!(matches!(report.errors[0].1, LimpError::CrateNotFound(_)));
+    let js = JsonStorage::load(ctx.config_path()).unwrap();
+    assert_eq!(js.get("serde").unwrap().features.as_ref().unwrap().len(), 2);
+
+    fs::remove_dir_all(ctx.root).unwrap();
+}
+
+// Test that the releases between two versions are listed with dates and links
+#[test]
+fn test_changelog() {
+    let ctx = context("limp_ops_changelog");
+
+    let changelog = ops::changelog(&ctx, "serde", "1.0.0", "1.0.210").unwrap();
+    assert_eq!(changelog.versions.len(), 1);
+    assert_eq!(
+        changelog.to_string(),
+        "serde: 1.0.0 -> 1.0.210\n  1.0.210  2024-09-06  https://crates.io/crates/serde/1.0.210"
+    );
+    assert!(ops::changelog(&ctx, "serde", "1.0.210", "1.0.210")
+        .unwrap()
+        .versions
+        .is_empty());
+
+    fs::remove_dir_all(ctx.root).unwrap();
+}
+
+// Test that snippets linked from a gist are downloaded, validated and refreshed
+#[test]
+fn test_link_url() {
+    let ctx = context("limp_ops_link");
+    let options = NewOptions {
+        name: "serde".to_string(),
+        ..Default::default()
+    };
+    ops::new_dependency(&ctx, &options).unwrap();
+
+    let gist = "https://gist.github.com/someone/abc123";
+    let raw = "https://gist.githubusercontent.com/someone/abc123/raw";
+    let client = MockClient::new()
+        .with_file(raw, "use serde::Serialize;\n")
+        .with_file("https://example.com/notes.rs", "not rust {");
+    let ctx = ctx.with_client(Arc::new(client));
+
+    let options = LinkOptions {
+        name: "serde".to_string(),
+        source: gist.to_string(),
+        tags: Some(vec!["serde".to_string(), "derive".to_string()]),
+        variant: None,
+    };
+    let path = ops::link(&ctx, &options).unwrap();
+    assert_eq!(path, ctx.snippets_dir().join("serde.rs"));
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "use serde::Serialize;\n"
+    );
+    let js = JsonStorage::load(ctx.config_path()).unwrap();
+    assert_eq!(
+        js.get("serde").unwrap().snippet().map(String::as_str),
+        path.to_str()
+    );
+    assert_eq!(js.snippets["serde.rs"].url.as_deref(), Some(gist));
+    assert_eq!(js.snippets["serde.rs"].tags, vec!["serde", "derive"]);
+
+    assert!(matches!(
+        ops::link(
+            &ctx,
+            &LinkOptions {
+                name: "serde".to_string(),
+                source: "https://example.com/notes.rs".to_string(),
+                ..Default::default()
+            }
+        ),
+        Err(LimpError::InvalidSnippet(_))
+    ));
+
+    fs::write(&path, "// edited\n").unwrap();
+    assert_eq!(
+        ops::refresh_snippets(&ctx, None).unwrap(),
+        vec!["serde.rs".to_string()]
+    );
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "use serde::Serialize;\n"
+    );
+
+    fs::remove_dir_all(ctx.root).unwrap();
+}
+
+// Test that `name:variant` picks one of the snippets linked to a dependency
+#[test]
+fn test_init_snippet_variant() {
+    let ctx = context("limp_ops_variant");
+    let options = NewOptions {
+        name: "serde".to_string(),
+        ..Default::default()
+    };
+    ops::new_dependency(&ctx, &options).unwrap();
+
+    let sources = ctx.root.join("sources");
+    fs::create_dir_all(&sources).unwrap();
+    fs::write(sources.join("plain.rs"), "fn plain() {}\n").unwrap();
+    fs::write(sources.join("derive.rs"), "fn derived() {}\n").unwrap();
+    for (file, variant) in [("plain.rs", None), ("derive.rs", Some("derive"))] {
+        let options = LinkOptions {
+            name: "serde".to_string(),
+            source: sources.join(file).to_string_lossy().to_string(),
+            variant: variant.map(String::from),
+            ..Default::default()
+        };
+        ops::link(&ctx, &options).unwrap();
+    }
+    let js = JsonStorage::load(ctx.config_path()).unwrap();
+    assert_eq!(js.get("serde").unwrap().snippets.len(), 2);
+
+    let project = ctx.root.with_file_name("limp_ops_variant_project");
+    let _ = fs::remove_dir_all(&project);
+    let options = InitOptions {
+        path: project.to_str().unwrap().to_string(),
+        dependencies: Some(vec!["serde:derive".to_string()]),
+        ..Default::default()
+    };
+    ops::init(&ctx, &options).unwrap();
+    let main = fs::read_to_string(project.join("src/main.rs")).unwrap();
+    assert!(main.contains("fn derived()"));
+    assert!(!main.contains("fn plain()"));
+    fs::remove_dir_all(&project).unwrap();
+
+    let options = InitOptions {
+        path: project.to_str().unwrap().to_string(),
+        dependencies: Some(vec!["serde:missing".to_string()]),
+        ..Default::default()
+    };
+    assert!(matches!(
+        ops::init(&ctx, &options),
+        Err(LimpError::SnippetNotFound(e)) if e == "serde:missing"
+    ));
+
+    fs::remove_dir_all(ctx.root).unwrap();
+}
+
+// Test that standalone snippets are stored and merged into new projects
+#[test]
+fn test_init_standalone_snippets() {
+    let ctx = context("limp_ops_standalone");
+    let source = ctx.root.join("error_boilerplate.rs");
+    fs::create_dir_all(&ctx.root).unwrap();
+    fs::write(&source, "pub enum AppError {}\n").unwrap();
+    snippets::add(ctx.snippets_dir(), "error", &source).unwrap();
+    assert!(matches!(
+        snippets::add(ctx.snippets_dir(), "error", &source),
+        Err(LimpError::SnippetConflict(_))
+    ));
+
+    let project = ctx.root.with_file_name("limp_ops_standalone_project");
+    let _ = fs::remove_dir_all(&project);
+    let options = InitOptions {
+        path: project.to_str().unwrap().to_string(),
+        snippets: vec!["error".to_string()],
+        ..Default::default()
+    };
+    ops::init(&ctx, &options).unwrap();
+    let main = fs::read_to_string(project.join("src/main.rs")).unwrap();
+    assert!(main.contains("pub enum AppError {}"));
+    assert!(main.contains("fn main()"));
+    fs::remove_dir_all(&project).unwrap();
+
+    let options = InitOptions {
+        path: project.to_str().unwrap().to_string(),
+        snippets: vec!["cli".to_string()],
+        ..Default::default()
+    };
+    assert!(matches!(
+        ops::init(&ctx, &options),
+        Err(LimpError::SnippetNotFound(_))
+    ));
+
+    fs::remove_dir_all(ctx.root).unwrap();
+}
+
+// Test that extra binaries get a file in src/bin and a [[bin]] section
+#[test]
+fn test_init_bins() {
+    let ctx = context("limp_ops_bins");
+    let source = ctx.root.join("server.rs");
+    fs::create_dir_all(&ctx.root).unwrap();
+    fs::write(&source, "use std::net::TcpListener;\n").unwrap();
+    snippets::add(ctx.snippets_dir(), "tcp-server", &source).unwrap();
+
+    let project = ctx.root.with_file_name("limp_ops_bins_project");
+    let _ = fs::remove_dir_all(&project);
+    let options = InitOptions {
+        path: project.to_str().unwrap().to_string(),
+        bins: vec!["cli".to_string(), "server:tcp-server".to_string()],
+        ..Default::default()
+    };
+    ops::init(&ctx, &options).unwrap();
+    let toml = fs::read_to_string(project.join("Cargo.toml")).unwrap();
+    assert!(toml.contains("[[bin]]\nname = \"cli\"\npath = \"src/bin/cli.rs\"\n"));
+    assert!(toml.contains("[[bin]]\nname = \"server\"\npath = \"src/bin/server.rs\"\n"));
+    assert!(project.join("src/main.rs").is_file());
+    let cli = fs::read_to_string(project.join("src/bin/cli.rs")).unwrap();
+    assert!(cli.contains("fn main()"));
+    let server = fs::read_to_string(project.join("src/bin/server.rs")).unwrap();
+    assert!(server.contains("use std::net::TcpListener;"));
+    assert!(server.contains("fn main()"));
+    fs::remove_dir_all(&project).unwrap();
+
+    for bins in [vec!["web:missing".to_string()], vec!["a/b".to_string()]] {
+        let options = InitOptions {
+            path: project.to_str().unwrap().to_string(),
+            bins,
+            ..Default::default()
+        };
+        assert!(ops::init(&ctx, &options).is_err());
+    }
+    assert!(!project.exists());
+
+    fs::remove_dir_all(ctx.root).unwrap();
+}
+
+// Test that project types bring their dependencies, manifest extras and
+// entry file unless a saved template of the same name replaces them
+#[test]
+fn test_init_preset() {
+    let client = MockClient::new().with_crate(json!({
+        "crate": { "name": "wasm-bindgen", "max_version": "0.2.95" },
+        "versions": [{ "crate": "wasm-bindgen", "num": "0.2.95" }]
+    }));
+    let ctx = context("limp_ops_preset").with_client(Arc::new(client));
+    let project = ctx.root.with_file_name("limp_ops_preset_project");
+    let _ = fs::remove_dir_all(&project);
+    let options = InitOptions {
+        path: project.to_str().unwrap().to_string(),
+        preset: Some("wasm".to_string()),
+        ..Default::default()
+    };
+    let report = ops::init(&ctx, &options).unwrap();
+    assert_eq!(report.dependencies[0].name, "wasm-bindgen");
+    let toml = fs::read_to_string(project.join("Cargo.toml")).unwrap();
+    assert!(toml.contains("wasm-bindgen = \"0.2.95\""));
+    assert!(toml.contains("[lib]\ncrate-type = [\"cdylib\", \"rlib\"]"));
+    let lib = fs::read_to_string(project.join("src/lib.rs")).unwrap();
+    assert!(lib.contains("#[wasm_bindgen]"));
+    assert!(!project.join("src/main.rs").exists());
+    fs::remove_dir_all(&project).unwrap();
+
+    let template = ctx.templates_dir().join("cli");
+    fs::create_dir_all(template.join("files/src")).unwrap();
+    fs::write(template.join("template.json"), r#"{"name": "cli"}"#).unwrap();
+    fs::write(template.join("files/src/main.rs"), "fn main() {}\n").unwrap();
+    let options = InitOptions {
+        preset: Some("cli".to_string()),
+        ..options
+    };
+    let report = ops::init(&ctx, &options).unwrap();
+    assert!(report.dependencies.is_empty());
+    assert_eq!(
+        fs::read_to_string(project.join("src/main.rs")).unwrap(),
+        "fn main() {}\n"
+    );
+    fs::remove_dir_all(&project).unwrap();
+
+    let options = InitOptions {
+        preset: Some("game".to_string()),
+        ..options
+    };
+    assert!(matches!(
+        ops::init(&ctx, &options),
+        Err(LimpError::UnknownScaffold(_))
+    ));
+
+    fs::remove_dir_all(ctx.root).unwrap();
+}
+
+// Test that the [manifest] setting and its overrides end up in Cargo.toml
+#[test]
+fn test_init_manifest_extras() {
+    let mut ctx = context("limp_ops_manifest_extras");
+    ctx.config.manifest = "profile.release.lto = true\nprofile.release.codegen-units = 1\n"
+        .parse::<toml::Table>()
+        .unwrap();
+    let project = ctx.root.with_file_name("limp_ops_manifest_extras_project");
+    let _ = fs::remove_dir_all(&project);
+    let options = InitOptions {
+        path: project.to_str().unwrap().to_string(),
+        manifest_extras: vec![
+            ("profile.release.lto".to_string(), "\"thin\"".to_string()),
+            ("package.description".to_string(), "A tool".to_string()),
+        ],
+        ..Default::default()
+    };
+    ops::init(&ctx, &options).unwrap();
+    let toml = fs::read_to_string(project.join("Cargo.toml")).unwrap();
+    assert!(toml.contains("description = \"A tool\""));
+    assert!(toml.contains("[profile.release]\ncodegen-units = 1\nlto = \"thin\"\n"));
+    fs::remove_dir_all(&project).unwrap();
+
+    let options = InitOptions {
+        manifest_extras: vec![("bad key".to_string(), "1".to_string())],
+        ..options
+    };
+    assert!(matches!(
+        ops::init(&ctx, &options),
+        Err(LimpError::InvalidArguments(_))
+    ));
+    assert!(!project.exists());
+
+    fs::remove_dir_all(ctx.root).unwrap();
+}
+
+// Test that `init` writes the author and the publishing fields
+#[test]
+fn test_init_metadata() {
+    let mut ctx = context("limp_ops_metadata");
+    ctx.config.author = Some("Jane Doe".to_string());
+    ctx.config.email = Some("jane@example.com".to_string());
+    let project = ctx.root.with_file_name("limp_ops_metadata_project");
+    let _ = fs::remove_dir_all(&project);
+    let options = InitOptions {
+        path: project.to_str().unwrap().to_string(),
+        description: Some("A \"quoted\" tool".to_string()),
+        repository: Some("https://github.com/jane/tool".to_string()),
+        keywords: vec!["cli".to_string(), "tool".to_string()],
+        ..Default::default()
+    };
+    ops::init(&ctx, &options).unwrap();
+    let toml = fs::read_to_string(project.join("Cargo.toml")).unwrap();
+    assert!(toml.contains(
+        "edition = \"2021\"\nauthors = [\"Jane Doe <jane@example.com>\"]\ndescription = 'A \"quoted\" tool'\nrepository = \"https://github.com/jane/tool\"\nkeywords = [\"cli\", \"tool\"]\n"
+    ));
+    fs::remove_dir_all(&project).unwrap();
+
+    let options = InitOptions {
+        keywords: vec!["1st".to_string()],
+        ..options
+    };
+    assert!(matches!(
+        ops::init(&ctx, &options),
+        Err(LimpError::InvalidArguments(_))
+    ));
+    assert!(!project.exists());
+
+    fs::remove_dir_all(ctx.root).unwrap();
+}
+
+// Test that picked features are stored relative to the default features
+#[test]
+fn test_new_dependency_picked_features() {
+    let ctx = context("limp_ops_pick").with_client(Arc::new(MockClient::new().with_crate(json!({
+        "crate": { "name": "tokio", "max_version": "1.41.1" },
+        "versions": [{
+            "crate": "tokio",
+            "num": "1.41.1",
+            "features": { "default": ["rt"], "rt": [], "net": [], "macros": [] }
+        }]
+    }))));
+    let options = NewOptions {
+        name: "tokio".to_string(),
+        force: true,
+        ..Default::default()
+    };
+
+    let report = ops::new_dependency_with(
+        &ctx,
+        &options,
+        Some(|version: &Version| {
+            assert_eq!(version.feature_graph().defaults(), vec!["rt".to_string()]);
+            Ok(vec!["net".to_string(), "rt".to_string()])
+        }),
+    )
+    .unwrap();
+    assert_eq!(report.dependency.features, Some(vec!["net".to_string()]));
+    assert_eq!(report.dependency.default_features, None);
+
+    let report = ops::new_dependency_with(
+        &ctx,
+        &options,
+        Some(|_: &Version| Ok(vec!["macros".to_string()])),
+    )
+    .unwrap();
+    assert_eq!(report.dependency.features, Some(vec!["macros".to_string()]));
+    assert_eq!(report.dependency.default_features, Some(false));
+
+    // features are picked from the version a requirement resolves to
+    let required = NewOptions {
+        version: Some("^1".to_string()),
+        ..options.clone()
+    };
+    let report = ops::new_dependency_with(
+        &ctx,
+        &required,
+        Some(|version: &Version| {
+            assert_eq!(version.num, "1.41.1");
+            Ok(vec!["rt".to_string(), "macros".to_string()])
+        }),
+    )
+    .unwrap();
+    assert_eq!(report.dependency.version, "^1");
+    assert_eq!(report.dependency.features, Some(vec!["macros".to_string()]));
+
+    assert!(matches!(
+        ops::new_dependency_with(
+            &ctx,
+            &options,
+            Some(|_: &Version| Ok(vec!["fs".to_string()]))
+        ),
+        Err(LimpError::IncompatibleFeatures(_))
+    ));
+
+    fs::remove_dir_all(ctx.root).unwrap();
+}
+
+// Test that meta features are found for a version requirement too
+#[test]
+fn test_new_dependency_meta_features() {
+    let ctx = context("limp_ops_meta").with_client(Arc::new(MockClient::new().with_crate(json!({
+        "crate": { "name": "tokio", "max_version": "1.41.1" },
+        "versions": [{
+            "crate": "tokio",
+            "num": "1.41.1",
+            "features": { "full": ["fs", "net", "rt"], "fs": [], "net": [], "rt": [] }
+        }]
+    }))));
+    let options = NewOptions {
+        name: "tokio".to_string(),
+        version: Some("^1".to_string()),
+        features: Some(vec!["full".to_string()]),
+        ..Default::default()
+    };
+
+    let report = ops::new_dependency(&ctx, &options).unwrap();
+    assert_eq!(report.meta_features, vec!["full".to_string()]);
+
+    fs::remove_dir_all(ctx.root).unwrap();
+}
+
+// Test that storage changes are journaled and undone newest first
+#[test]
+fn test_history_undo() {
+    let ctx = context("limp_ops_undo");
+    let options = NewOptions {
+        name: "serde".to_string(),
+        ..Default::default()
+    };
+    ops::new_dependency(&ctx, &options).unwrap();
+    let source = ctx.root.join("derive.rs");
+    fs::write(&source, "use serde::Serialize;\n").unwrap();
+    let linked = ops::link(
+        &ctx,
+        &LinkOptions {
+            name: "serde".to_string(),
+            source: source.to_str().unwrap().to_string(),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    ops::delete(&ctx, "serde", false).unwrap();
+    let stored = || JsonStorage::load(ctx.config_path()).unwrap();
+    assert!(stored().get("serde").is_none());
+
+    let undone = ops::undo(&ctx).unwrap().unwrap();
+    assert_eq!(undone.action, "del serde");
+    assert!(stored().get("serde").unwrap().snippet().is_some());
+
+    ops::undo(&ctx).unwrap();
+    assert!(stored().get("serde").unwrap().snippet().is_none());
+    assert!(!linked.exists());
+
+    ops::undo(&ctx).unwrap();
+    assert!(stored().get("serde").is_none());
+    assert!(ops::undo(&ctx).unwrap().is_none());
+
+    let actions = history::load(ctx.history_path())
+        .unwrap()
+        .into_iter()
+        .map(|e| e.action)
+        .collect::<Vec<String>>();
+    assert_eq!(actions[0], "new serde");
+    assert!(actions[1].starts_with("link serde "));
+    assert_eq!(actions[2], "del serde");
+    assert_eq!(actions.len(), 6);
+
+    fs::remove_dir_all(ctx.root).unwrap();
+}
+
+// Test that deleting a dependency trashes its own snippets unless they are kept
+#[test]
+fn test_delete_snippets() {
+    let ctx = context("limp_ops_delete_snippets");
+    let options = NewOptions {
+        name: "serde".to_string(),
+        ..Default::default()
+    };
+    ops::new_dependency(&ctx, &options).unwrap();
+    let mut js = JsonStorage::load(ctx.config_path()).unwrap();
+    js.add(JsonDependency {
+        name: "serde_json".to_string(),
+        version: "1.0.0".to_string(),
+        ..Default::default()
+    });
+    js.save(ctx.config_path()).unwrap();
+    let source = ctx.root.join("derive.rs");
+    fs::write(&source, "use serde::Serialize;\n").unwrap();
+    let link = |name: &str| LinkOptions {
+        name: name.to_string(),
+        source: source.to_str().unwrap().to_string(),
+        ..Default::default()
+    };
+    let snippet = ops::link(&ctx, &link("serde")).unwrap();
+    ops::link(&ctx, &link("serde_json")).unwrap();
+
+    assert!(ops::delete(&ctx, "serde_json", false).unwrap().is_empty());
+    assert!(snippet.exists());
+
+    assert_eq!(
+        ops::delete(&ctx, "serde", false).unwrap(),
+        vec![snippet.clone()]
+    );
+    assert!(!snippet.exists());
+    assert_eq!(snippets::trashed(ctx.trash_dir()).unwrap().len(), 1);
+
+    ops::undo(&ctx).unwrap();
+    assert!(snippet.exists());
+    assert!(snippets::trashed(ctx.trash_dir()).unwrap().is_empty());
+    let js = JsonStorage::load(ctx.config_path()).unwrap();
+    assert!(js.snippets["derive.rs"].hash.is_some());
+    assert!(ops::delete(&ctx, "serde", true).unwrap().is_empty());
+    assert!(snippet.exists());
+    let js = JsonStorage::load(ctx.config_path()).unwrap();
+    assert!(js.snippets.contains_key("derive.rs"));
+
+    fs::remove_dir_all(ctx.root).unwrap();
+}
+
+// Test that the snippet commands are journaled and undone
+#[test]
+fn test_snippet_commands_undo() {
+    let ctx = context("limp_ops_snippet_undo");
+    let options = NewOptions {
+        name: "serde".to_string(),
+        ..Default::default()
+    };
+    ops::new_dependency(&ctx, &options).unwrap();
+    let source = ctx.root.join("derive.rs");
+    fs::write(&source, "use serde::Serialize;\n").unwrap();
+    let link = LinkOptions {
+        name: "serde".to_string(),
+        source: source.to_str().unwrap().to_string(),
+        ..Default::default()
+    };
+    let snippet = ops::link(&ctx, &link).unwrap();
+    let stored = || JsonStorage::load(ctx.config_path()).unwrap();
+
+    assert_eq!(
+        ops::rename_snippet(&ctx, "derive", "serde_derive").unwrap(),
+        vec!["serde".to_string()]
+    );
+    assert!(!snippet.exists());
+    assert_eq!(
+        ops::undo(&ctx).unwrap().unwrap().action,
+        "snippet mv derive serde_derive"
+    );
+    assert!(snippet.exists());
+    assert!(!ctx.snippets_dir().join("serde_derive.rs").exists());
+    assert!(stored().get("serde").unwrap().snippet().is_some());
+
+    ops::remove_snippet(&ctx, "derive").unwrap();
+    assert!(stored().get("serde").unwrap().snippet().is_none());
+    ops::undo(&ctx).unwrap();
+    assert!(snippet.exists());
+    assert!(snippets::trashed(ctx.trash_dir()).unwrap().is_empty());
+    assert!(stored().get("serde").unwrap().snippet().is_some());
+    assert!(stored().snippets["derive.rs"].path.is_some());
+
+    fs::write(&snippet, "// edited\n").unwrap();
+    ops::refresh_snippets(&ctx, None).unwrap();
+    ops::undo(&ctx).unwrap();
+    assert_eq!(fs::read_to_string(&snippet).unwrap(), "// edited\n");
+
+    let error = ops::add_snippet(&ctx, "error", source.to_str().unwrap()).unwrap();
+    ops::undo(&ctx).unwrap();
+    assert!(!error.exists());
+    assert!(!stored().snippets.contains_key("error.rs"));
+
+    ops::remove_snippet(&ctx, "derive").unwrap();
+    ops::restore_snippet(&ctx, "derive").unwrap();
+    assert!(snippet.exists());
+    ops::undo(&ctx).unwrap();
+    assert!(!snippet.exists());
+    assert_eq!(snippets::trashed(ctx.trash_dir()).unwrap().len(), 1);
+
+    fs::remove_dir_all(ctx.root).unwrap();
+}
+
+// Test that `gc` removes orphaned snippets only without `--dry-run`, undoably
+#[test]
+fn test_gc() {
+    let ctx = context("limp_ops_gc");
+    let orphan = ctx.snippets_dir().join("orphan.rs");
+    fs::create_dir_all(ctx.snippets_dir()).unwrap();
+    fs::write(&orphan, "fn orphan() {}\n").unwrap();
+
+    let garbage = ops::gc(&ctx, true).unwrap();
+    assert_eq!(garbage.orphans, vec![orphan.clone()]);
+    assert!(orphan.exists());
+
+    ops::gc(&ctx, false).unwrap();
+    assert!(!orphan.exists());
+    assert!(ops::gc(&ctx, false).unwrap().is_empty());
+
+    assert_eq!(ops::undo(&ctx).unwrap().unwrap().action, "gc");
+    assert!(orphan.exists());
+
+    fs::remove_dir_all(ctx.root).unwrap();
+}
+
+// Test that a frozen context doesn't resolve dependencies that aren't stored
+#[test]
+fn test_init_frozen() {
+    let mut ctx = context("limp_ops_frozen");
+    ctx.frozen = true;
+    let project = ctx.root.with_file_name("limp_ops_frozen_project");
+    let _ = fs::remove_dir_all(&project);
+
+    let options = InitOptions {
+        path: project.to_str().unwrap().to_string(),
+        dependencies: Some(vec!["serde".to_string()]),
+        ..Default::default()
+    };
+    assert!(matches!(
+        ops::init(&ctx, &options),
+        Err(LimpError::Frozen(e)) if e.contains("serde")
+    ));
+    assert!(!project.exists());
+
+    ctx.frozen = false;
+    let new = NewOptions {
+        name: "serde".to_string(),
+        ..Default::default()
+    };
+    ops::new_dependency(&ctx, &new).unwrap();
+    ctx.frozen = true;
+    ops::init(&ctx, &options).unwrap();
+
+    fs::remove_dir_all(project).unwrap();
+    fs::remove_dir_all(ctx.root).unwrap();
+}
+
+// Test that a dependency is looked up in the storage, the project and the registry
+#[test]
+fn test_which() {
+    let ctx = context("limp_ops_which");
+    let options = NewOptions {
+        name: "serde".to_string(),
+        version: Some("1.0.0".to_string()),
+        ..Default::default()
+    };
+    ops::new_dependency(&ctx, &options).unwrap();
+    let manifest = ctx.root.join("Cargo.toml");
+    fs::write(
+        &manifest,
+        "[package]\nname = \"which\"\n\n[dev-dependencies]\nserde = \"1.0.0\"\n",
+    )
+    .unwrap();
+
+    let which = ops::which(&ctx, "serde", Some(&manifest)).unwrap();
+    assert_eq!(
+        which.to_string(),
+        "serde\n  stored: serde = \"1.0.210\"\n  project: [dev-dependencies] serde = \"1.0.0\"\n  latest: 1.0.210"
+    );
+    assert!(which.stale_locks().is_empty());
+
+    fs::write(
+        ctx.root.join("Cargo.lock"),
+        "version = 3\n\n[[package]]\nname = \"serde\"\nversion = \"1.0.100\"\n",
+    )
+    .unwrap();
+    let which = ops::which(&ctx, "serde", Some(&manifest)).unwrap();
+    assert!(which.to_string().contains("\n  locked: 1.0.100\n"));
+    assert_eq!(which.stale_locks(), vec!["1.0.100"]);
+
+    let which = ops::which(&ctx, "tokio", None).unwrap();
+    assert!(which.stored.is_none());
+    assert!(which.project.is_none());
+    assert!(which.latest.is_err());
+    assert!(which.to_string().contains("  project: no Cargo.toml found"));
+
+    fs::remove_dir_all(ctx.root).unwrap();
+}
+
+// Test that renaming a dependency moves the snippets named after it along
+#[test]
+fn test_rename() {
+    let ctx = context("limp_ops_rename");
+    let options = NewOptions {
+        name: "serde".to_string(),
+        version: Some("1.0.0".to_string()),
+        ..Default::default()
+    };
+    ops::new_dependency(&ctx, &options).unwrap();
+    let source = ctx.root.join("serde.rs");
+    fs::write(&source, "use serde::Serialize;\n").unwrap();
+    let link = LinkOptions {
+        name: "serde".to_string(),
+        source: source.to_string_lossy().to_string(),
+        ..Default::default()
+    };
+    ops::link(&ctx, &link).unwrap();
+
+    let moved = ops::rename(&ctx, "serde", "json", false).unwrap();
+    let target = ctx.snippets_dir().join("json.rs");
+    assert_eq!(moved, vec![target.clone()]);
+    assert!(target.is_file());
+    assert!(!ctx.snippets_dir().join("serde.rs").exists());
+    let js = JsonStorage::load(ctx.config_path()).unwrap();
+    assert!(js.get("serde").is_none());
+    let json = js.get("json").unwrap();
+    assert_eq!(json.version, "1.0.0");
+    assert_eq!(json.snippet().map(String::as_str), target.to_str());
+
+    assert!(matches!(
+        ops::rename(&ctx, "tokio", "async", false),
+        Err(LimpError::DependencyNotFound(_))
+    ));
+    ops::new_dependency(&ctx, &options).unwrap();
+    assert!(matches!(
+        ops::rename(&ctx, "json", "serde", false),
+        Err(LimpError::DependencyAlreadyExists(_))
+    ));
+
+    ops::delete(&ctx, "serde", false).unwrap();
+    ops::rename(&ctx, "json", "serde", true).unwrap();
+    let js = JsonStorage::load(ctx.config_path()).unwrap();
+    assert_eq!(js.get("serde").unwrap().version, "1.0.210");
+    assert!(ctx.snippets_dir().join("serde.rs").is_file());
+
+    fs::remove_dir_all(ctx.root).unwrap();
+}
+
+// Test that a crate's dependencies are listed and optionally resolved as a tree
+#[test]
+fn test_deps() {
+    let mut client = MockClient::new();
+    for (name, version) in [("app", "1.0.0"), ("serde", "1.0.210"), ("log", "0.4.22")] {
+        client = client.with_crate(json!({
+            "crate": { "name": name, "max_version": version },
+            "versions": [{ "crate": name, "num": version }]
+        }));
+    }
+    let client = client
+        .with_dependencies(
+            "app",
+            "1.0.0",
+            json!({ "dependencies": [
+                { "crate_id": "serde", "req": "^1.0", "kind": "normal" },
+                { "crate_id": "log", "req": "^0.4", "kind": "normal" },
+                { "crate_id": "tempfile", "req": "^3", "kind": "dev" },
+                { "crate_id": "serde_json", "req": "^1", "kind": "normal", "optional": true },
+            ]}),
+        )
+        .with_dependencies(
+            "serde",
+            "1.0.210",
+            json!({ "dependencies": [{ "crate_id": "log", "req": "^0.4", "kind": "normal" }] }),
+        );
+    let ctx = context("limp_ops_deps").with_client(Arc::new(client));
+
+    let tree = ops::deps(&ctx, "app", None, false).unwrap();
+    assert_eq!(
+        tree.to_string(),
+        "app 1.0.0\n  serde ^1.0\n  log ^0.4\n  tempfile ^3 (dev)\n  serde_json ^1 (optional)"
+    );
+
+    let tree = ops::deps(&ctx, "app", Some("1.0.0"), true).unwrap();
+    assert_eq!(
+        tree.to_string(),
+        "app 1.0.0\n  serde ^1.0 -> 1.0.210\n    log ^0.4 -> 0.4.22\n  log ^0.4 -> 0.4.22 (*)\n  tempfile ^3 (dev)\n  serde_json ^1 (optional)"
+    );
+    assert_eq!(tree.count(), 4);
+    assert!(matches!(
+        ops::deps(&ctx, "app", Some("2.0.0"), false),
+        Err(LimpError::VersionNotFound(_))
+    ));
+
+    fs::remove_dir_all(ctx.root).unwrap();
+}
+
+// Test that the license policy warns or fails unless violations are allowed
+#[test]
+fn test_new_dependency_license_policy() {
+    let client = MockClient::new().with_crate(json!({
+        "crate": { "name": "readline", "max_version": "2.0.0" },
+        "versions": [{ "crate": "readline", "num": "2.0.0", "license": "GPL-3.0-or-later" }]
+    }));
+    let mut ctx = context("limp_ops_license").with_client(Arc::new(client));
+    ctx.config.licenses.deny = vec!["GPL-3.0".to_string()];
+    let mut options = NewOptions {
+        name: "readline".to_string(),
+        ..Default::default()
+    };
+
+    let report = ops::new_dependency(&ctx, &options).unwrap();
+    assert_eq!(
+        report.license_violations,
+        vec!["readline is licensed under GPL-3.0-or-later".to_string()]
+    );
+
+    ops::delete(&ctx, "readline", false).unwrap();
+    ctx.config.licenses.fail = true;
+    assert!(matches!(
+        ops::new_dependency(&ctx, &options),
+        Err(LimpError::LicenseViolation(_))
+    ));
+    assert!(JsonStorage::load(ctx.config_path())
+        .unwrap()
+        .get("readline")
+        .is_none());
+
+    // a requirement is checked against the version it resolves to
+    options.version = Some("^2".to_string());
+    assert!(matches!(
+        ops::new_dependency(&ctx, &options),
+        Err(LimpError::LicenseViolation(_))
+    ));
+
+    options.allow_license_violation = true;
+    let report = ops::new_dependency(&ctx, &options).unwrap();
+    assert_eq!(report.license_violations.len(), 1);
+
+    fs::remove_dir_all(ctx.root).unwrap();
+}
+
+// Test that the running limp is compared with its latest release
+#[test]
+fn test_self_version() {
+    let limp = |latest: &str| {
+        MockClient::new().with_crate(json!({
+            "crate": { "name": "limp", "max_version": latest },
+            "versions": [{ "crate": "limp", "num": latest }]
+        }))
+    };
+    let ctx = context("limp_ops_self_version").with_client(Arc::new(limp("99.0.0")));
+    let version = ops::self_version(&ctx).unwrap();
+    assert_eq!(version.current, env!("CARGO_PKG_VERSION"));
+    assert!(version.is_outdated());
+    assert!(version.to_string().ends_with("(latest: 99.0.0)"));
+    fs::remove_dir_all(ctx.root).unwrap();
+
+    let ctx = context("limp_ops_self_update").with_client(Arc::new(limp("0.0.1")));
+    let version = ops::self_update(&ctx, true).unwrap();
+    assert!(!version.is_outdated());
+    assert!(version.to_string().ends_with("(up to date)"));
+
+    fs::remove_dir_all(ctx.root).unwrap();
+}
+
+// Test that warming the cache fetches every stored registry dependency again
+#[test]
+fn test_warm_cache() {
+    let ctx = context("limp_ops_warm_cache");
+    let options = NewOptions {
+        name: "serde".to_string(),
+        ..Default::default()
+    };
+    ops::new_dependency(&ctx, &options).unwrap();
+    assert!(ctx.registry.cache().clear().unwrap() > 0);
+    assert_eq!(ctx.registry.cache().info().unwrap().entries, 0);
+
+    let report = ops::warm_cache(&ctx, 2).unwrap();
+    assert_eq!(report.warmed, vec!["serde".to_string()]);
+    assert!(report.errors.is_empty());
+    assert_eq!(ctx.registry.cache().info().unwrap().entries, 1);
+}
+
+// Test that projects can start from a git template with stored dependencies on top
+#[test]
+fn test_init_from_git() {
+    let ctx = context("limp_ops_from_git");
+    let template = ctx.root.with_file_name("limp_ops_from_git_template");
+    let _ = fs::remove_dir_all(&template);
+    fs::create_dir_all(template.join("src")).unwrap();
+    fs::create_dir_all(template.join(".limp/hooks")).unwrap();
+    fs::write(
+        template.join("Cargo.toml"),
+        "[package]\nname = \"{{ project_name }}\"\nversion = \"0.1.0\"\n\n[dependencies]\n",
+    )
+    .unwrap();
+    fs::write(template.join("src/main.rs"), "fn main() {}\n").unwrap();
+    fs::write(
+        template.join("{{ crate_name }}.md"),
+        "# {{ project_name }}\n",
+    )
+    .unwrap();
+    fs::write(template.join(".limp/hooks/post-init"), "#!/bin/sh\n").unwrap();
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(["-c", "user.name=limp", "-c", "user.email=limp@example.com"])
+            .args(args)
+            .current_dir(&template)
+            .output()
+            .is_ok_and(|o| o.status.success())
+    };
+    // git is needed to clone the template
+    if !(git(&["init", "--quiet"]) && git(&["add", "."]) && git(&["commit", "-qm", "template"])) {
+        return;
+    }
+
+    let project = ctx.root.with_file_name("limp_ops_from_git_project");
+    let _ = fs::remove_dir_all(&project);
+    let options = InitOptions {
+        path: project.to_str().unwrap().to_string(),
+        dependencies: Some(vec!["serde".to_string()]),
+        from_git: Some(format!("file://{}", template.display())),
+        ..Default::default()
+    };
+    let report = ops::init(&ctx, &options).unwrap();
+    let manifest = fs::read_to_string(project.join("Cargo.toml")).unwrap();
+    assert!(manifest.contains("name = \"limp_ops_from_git_project\""));
+    assert!(manifest.contains("serde = \"1.0.210\""));
+    assert_eq!(
+        fs::read_to_string(project.join("limp_ops_from_git_project.md")).unwrap(),
+        "# limp_ops_from_git_project\n"
+    );
+    assert!(!project.join(".git").exists());
+    assert!(!project.join(".limp").exists());
+    assert_eq!(report.skipped_hooks.len(), 1);
+
+    fs::remove_dir_all(&project).unwrap();
+    fs::remove_dir_all(&template).unwrap();
+    fs::remove_dir_all(ctx.root).unwrap();
+}
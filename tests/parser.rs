@@ -0,0 +1,222 @@
+use limp::error::LimpError;
+use limp::parser::{inject, substitute, SnippetEntity, Vars};
+use std::collections::HashMap;
+use std::fs;
+
+const SNIPPET: &str = r#"use serde::{
+    Deserialize,
+    Serialize,
+};
+use std::fs;
+
+#[derive(Serialize, Deserialize)]
+struct Config {
+    name: String,
+}
+
+fn main() {
+    println!("{{project_name}}");
+}
+"#;
+
+// Test that imports, body and main detection are split correctly
+#[test]
+fn test_snippet_parse() {
+    let snippet = SnippetEntity::parse("config.rs", SNIPPET);
+    assert_eq!(snippet.imports.len(), 5);
+    assert_eq!(
+        snippet.body.first().unwrap(),
+        "#[derive(Serialize, Deserialize)]"
+    );
+    assert_eq!(snippet.body.last().unwrap(), "}");
+    assert!(snippet.is_main);
+}
+
+// Test that known placeholders are substituted and unknown ones kept
+#[test]
+fn test_substitute() {
+    let values = HashMap::from([("name".to_string(), "limp".to_string())]);
+    assert_eq!(
+        substitute("{{name}} {{ name }} {{other}} {{", &values),
+        "limp limp {{other}} {{"
+    );
+}
+
+// Test that user variables are rendered next to the built-in ones
+#[test]
+fn test_vars_render() {
+    let pair = Vars::parse_assignment("license=MIT").unwrap();
+    assert!(Vars::parse_assignment("license").is_err());
+
+    let vars = Vars::from_pairs(&[pair]);
+    assert_eq!(
+        vars.render("my-app", "{{project_name}} {{crate_name}} {{license}}"),
+        "my-app my_app MIT"
+    );
+}
+
+// Test that injecting merges only missing imports and appends the body
+#[test]
+fn test_inject_into_entry() {
+    let dir = std::env::temp_dir().join("limp_parser_inject");
+    fs::create_dir_all(&dir).unwrap();
+    let entry = dir.join("main.rs");
+    fs::write(&entry, "use std::fs;\n\nfn main() {}\n").unwrap();
+
+    let snippet = SnippetEntity::parse("", "use std::fs;\nuse std::io;\n\nfn helper() {}\n");
+    let injection = inject(&snippet, &entry, None).unwrap();
+    assert_eq!(injection.imports, vec!["use std::io;".to_string()]);
+    assert_eq!(
+        fs::read_to_string(&entry).unwrap(),
+        "use std::fs;\nuse std::io;\n\nfn main() {}\n\nfn helper() {}\n"
+    );
+
+    let main_snippet = SnippetEntity::parse("", "fn main() {}\n");
+    assert!(inject(&main_snippet, &entry, None).is_err());
+    assert!(inject(&main_snippet, &entry, Some("extra")).is_ok());
+    assert!(fs::read_to_string(&entry)
+        .unwrap()
+        .starts_with("mod extra;\n"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+// Test that injecting into an existing module merges into it and declares it once
+#[test]
+fn test_inject_existing_module() {
+    let dir = std::env::temp_dir().join("limp_parser_inject_module");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let entry = dir.join("lib.rs");
+    fs::write(&entry, "pub mod net;\n").unwrap();
+    fs::write(dir.join("net.rs"), "use std::io;\n\npub fn connect() {}\n").unwrap();
+
+    let snippet = SnippetEntity::parse("", "use std::net::TcpListener;\n\npub fn serve() {}\n");
+    let injection = inject(&snippet, &entry, Some("net")).unwrap();
+    assert_eq!(injection.module, Some("net".to_string()));
+    assert_eq!(
+        injection.imports,
+        vec!["use std::net::TcpListener;".to_string()]
+    );
+    assert_eq!(fs::read_to_string(&entry).unwrap(), "pub mod net;\n");
+    assert_eq!(
+        fs::read_to_string(dir.join("net.rs")).unwrap(),
+        "use std::io;\nuse std::net::TcpListener;\n\npub fn connect() {}\n\npub fn serve() {}\n"
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+// Test that cfg-gated imports, nested braces and comments don't confuse the split
+#[test]
+fn test_snippet_parse_syntax() {
+    let snippet = SnippetEntity::parse(
+        "",
+        r#"use std::{collections::{HashMap, HashSet}, fs};
+/* a block comment
+use not::an::import;
+*/
+#[cfg(test)]
+use std::io;
+
+fn helper() -> HashMap<String, HashSet<String>> {
+    // fn main is only mentioned here
+    HashMap::new()
+}
+"#,
+    );
+    assert_eq!(
+        snippet.imports,
+        vec![
+            "use std::{collections::{HashMap, HashSet}, fs};",
+            "/* a block comment",
+            "use not::an::import;",
+            "*/",
+            "#[cfg(test)]",
+            "use std::io;",
+        ]
+    );
+    assert_eq!(
+        snippet.body.first().unwrap(),
+        "fn helper() -> HashMap<String, HashSet<String>> {"
+    );
+    assert!(!snippet.is_main);
+
+    let snippet = SnippetEntity::parse("", "fn run() {}\n\nuse std::fs;\n\nfn main() {}\n");
+    assert_eq!(snippet.imports, vec!["use std::fs;"]);
+    assert_eq!(snippet.body, vec!["fn run() {}", "", "fn main() {}"]);
+    assert!(snippet.is_main);
+}
+
+// Test that merging snippets dedupes and groups their imports
+#[test]
+fn test_snippet_merge_imports() {
+    let a = SnippetEntity::parse("", "use std::fs;\nuse std::io::Read;\n\nfn a() {}\n");
+    let b = SnippetEntity::parse(
+        "",
+        "use std::{fs, io};\nuse serde::Serialize;\n#[cfg(test)]\nuse std::fs;\n\nfn b() {}\n",
+    );
+    let merged = SnippetEntity::merge(&[a, b]).unwrap();
+    assert_eq!(
+        merged.imports,
+        vec![
+            "use serde::Serialize;",
+            "use std::{fs, io::{self, Read}};",
+            "#[cfg(test)]\nuse std::fs;",
+        ]
+    );
+    assert_eq!(merged.body, vec!["fn a() {}", "", "fn b() {}"]);
+}
+
+// Test that the mains of several snippets are combined into one
+#[test]
+fn test_snippet_merge_mains() {
+    let a = SnippetEntity::parse(
+        "a.rs",
+        "#[tokio::main]\nasync fn main() {\n    let a = 1;\n}\n\nfn helper() {}\n",
+    );
+    let b = SnippetEntity::parse("b.rs", "#[tokio::main]\nasync fn main() { let b = 2; }\n");
+    let merged = SnippetEntity::merge(&[a, b]).unwrap();
+    assert_eq!(
+        merged.to_string(),
+        "fn helper() {}\n\n#[tokio::main]\nasync fn main() {\n    let a = 1;\n\n    let b = 2;\n}\n"
+    );
+
+    let sync = SnippetEntity::parse("c.rs", "fn main() {}\n");
+    let b = SnippetEntity::parse("b.rs", "#[tokio::main]\nasync fn main() {}\n");
+    assert!(SnippetEntity::merge(&[sync, b]).is_err());
+}
+
+// Test that include directives pull in other snippets once and reject cycles
+#[test]
+fn test_snippet_includes() {
+    let dir = std::env::temp_dir().join("limp_parser_includes");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("logging.rs"), "use std::io;\n\nfn log() {}\n").unwrap();
+    fs::write(
+        dir.join("config.rs"),
+        "// limp:include logging\nuse std::fs;\n\nfn config() {}\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("app.rs"),
+        "// limp:include config\n// limp:include logging\nuse std::io;\n\nfn app() {}\n",
+    )
+    .unwrap();
+
+    let snippet = SnippetEntity::load(dir.join("app.rs"), &dir).unwrap();
+    assert_eq!(snippet.imports, vec!["use std::{fs, io};"]);
+    let body = snippet.body.join("\n");
+    assert_eq!(body.matches("fn log() {}").count(), 1);
+    assert!(body.contains("fn config() {}") && body.contains("fn app() {}"));
+    assert!(!body.contains("limp:include"));
+
+    fs::write(dir.join("logging.rs"), "// limp:include app\nfn log() {}\n").unwrap();
+    match SnippetEntity::load(dir.join("app.rs"), &dir) {
+        Err(LimpError::IncludeCycle(chain)) => {
+            assert_eq!(chain, "app -> config -> logging -> app")
+        }
+        other => panic!("expected an include cycle, got {:?}", other),
+    }
+}
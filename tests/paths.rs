@@ -0,0 +1,51 @@
+use limp::paths::{from_stored, normalize, to_stored};
+use std::path::{Path, PathBuf};
+
+// Test that the verbatim prefixes of Windows paths are dropped
+#[test]
+fn test_normalize() {
+    assert_eq!(
+        normalize(r"\\?\C:\Users\me\snippets\serde.rs"),
+        PathBuf::from(r"C:\Users\me\snippets\serde.rs")
+    );
+    assert_eq!(
+        normalize(r"\\?\UNC\server\share\serde.rs"),
+        PathBuf::from(r"\\server\share\serde.rs")
+    );
+    assert_eq!(normalize("/tmp/serde.rs"), PathBuf::from("/tmp/serde.rs"));
+}
+
+// Test that snippets in the storage are stored relative to it and back
+#[test]
+fn test_stored_round_trip() {
+    let root = std::env::temp_dir().join("limp_paths");
+    let inside = root.join("snippets").join("serde.rs");
+    assert_eq!(to_stored(&root, &inside), "snippets/serde.rs");
+    assert_eq!(from_stored(&root, "snippets/serde.rs"), inside);
+
+    let outside = std::env::temp_dir().join("elsewhere.rs");
+    let stored = to_stored(&root, &outside);
+    assert!(Path::new(&stored).is_absolute());
+    assert_eq!(from_stored(&root, &stored), outside);
+}
+
+// Test that absolute paths written on another OS are found in the storage
+#[test]
+fn test_foreign_paths() {
+    let root = std::env::temp_dir().join("limp_paths_foreign");
+    let foreign = if cfg!(windows) {
+        "/home/me/.config/limp/snippets/serde.rs"
+    } else {
+        r"C:\Users\me\AppData\Roaming\limp\snippets\serde.rs"
+    };
+    assert_eq!(
+        from_stored(&root, foreign),
+        root.join("snippets").join("serde.rs")
+    );
+    let unknown = if cfg!(windows) {
+        "/srv/serde.rs"
+    } else {
+        r"D:\serde.rs"
+    };
+    assert_eq!(from_stored(&root, unknown), PathBuf::from(unknown));
+}
@@ -0,0 +1,73 @@
+use limp::error::LimpError;
+use limp::process::{blame, parse_messages, rustfmt, Diagnostic};
+use std::fs;
+use std::path::PathBuf;
+
+const OUTPUT: &str = r#"{"reason":"compiler-artifact","package_id":"demo"}
+{"reason":"compiler-message","message":{"level":"warning","message":"unused variable: `x`","spans":[]}}
+{"reason":"compiler-message","message":{"level":"error","message":"cannot find value `y` in this scope","spans":[{"file_name":"src/main.rs","line_start":4,"is_primary":true}]}}
+{"reason":"build-finished","success":false}
+"#;
+
+// Test that only errors are collected from cargo's JSON output
+#[test]
+fn test_parse_messages() {
+    assert_eq!(
+        parse_messages(OUTPUT),
+        vec![Diagnostic {
+            message: "cannot find value `y` in this scope".to_string(),
+            file: Some(PathBuf::from("src/main.rs")),
+            line: Some(4),
+        }]
+    );
+}
+
+// Test that errors are traced back to the snippet containing the line
+#[test]
+fn test_blame() {
+    let dir = std::env::temp_dir().join("limp_process_blame");
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(
+        dir.join("src").join("main.rs"),
+        "fn helper() {}\n\nfn main() {\n    println!(\"{}\", y);\n}\n",
+    )
+    .unwrap();
+    let good = dir.join("good.rs");
+    let stale = dir.join("stale.rs");
+    fs::write(&good, "fn helper() {}\n").unwrap();
+    fs::write(&stale, "fn main() {\n    println!(\"{}\", y);\n}\n").unwrap();
+
+    let diagnostic = &parse_messages(OUTPUT)[0];
+    assert_eq!(blame(&dir, diagnostic, &[good, stale.clone()]), vec![stale]);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+// Test that rustfmt reindents generated sources, or reports why it can't
+#[test]
+fn test_rustfmt() {
+    let dir = std::env::temp_dir().join("limp_process_rustfmt");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::create_dir_all(dir.join("target")).unwrap();
+    fs::write(
+        dir.join("src").join("main.rs"),
+        "fn main() {\n        println!(\"hi\");\n  }\n",
+    )
+    .unwrap();
+    fs::write(dir.join("target").join("skipped.rs"), "fn  skipped() {}\n").unwrap();
+
+    match rustfmt(&dir, "2021") {
+        Ok(()) => assert_eq!(
+            fs::read_to_string(dir.join("src").join("main.rs")).unwrap(),
+            "fn main() {\n    println!(\"hi\");\n}\n"
+        ),
+        Err(e) => assert!(matches!(e, LimpError::FormatFailed(_))),
+    }
+    assert_eq!(
+        fs::read_to_string(dir.join("target").join("skipped.rs")).unwrap(),
+        "fn  skipped() {}\n"
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
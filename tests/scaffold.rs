@@ -0,0 +1,101 @@
+use std::fs;
+
+use limp::{files::ProjectKind, parser::Vars, scaffold::Scaffold};
+
+// Test that the selected files are written with the variables substituted
+#[test]
+fn test_scaffold_write() {
+    let dir = std::env::temp_dir().join("limp_scaffold_write");
+    let _ = fs::remove_dir_all(&dir);
+    let scaffold = Scaffold {
+        license: Some("MIT".to_string()),
+        readme: true,
+        ci: Some("github".to_string()),
+        ..Default::default()
+    };
+    let vars = Vars::default().with_author(Some("Jane Doe".to_string()));
+    scaffold.write(&dir, "myapp", &vars).unwrap();
+
+    let license = fs::read_to_string(dir.join("LICENSE")).unwrap();
+    assert!(license.starts_with("MIT License"));
+    assert!(license.contains("Jane Doe"));
+    assert!(!license.contains("{{year}}"));
+    let readme = fs::read_to_string(dir.join("README.md")).unwrap();
+    assert!(readme.starts_with("# myapp"));
+    assert!(readme.contains("MIT"));
+    assert!(dir
+        .join(".github")
+        .join("workflows")
+        .join("ci.yml")
+        .exists());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+// Test that existing files are not overwritten
+#[test]
+fn test_scaffold_keeps_existing() {
+    let dir = std::env::temp_dir().join("limp_scaffold_keep");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("README.md"), "custom").unwrap();
+    let scaffold = Scaffold {
+        readme: true,
+        ..Default::default()
+    };
+    scaffold.write(&dir, "myapp", &Vars::default()).unwrap();
+
+    assert_eq!(fs::read_to_string(dir.join("README.md")).unwrap(), "custom");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+// Test that the example, bench and test stubs import a library crate by name
+#[test]
+fn test_scaffold_write_targets() {
+    let dir = std::env::temp_dir().join("limp_scaffold_targets");
+    let _ = fs::remove_dir_all(&dir);
+    let scaffold = Scaffold {
+        examples: true,
+        benches: true,
+        tests: true,
+        ..Default::default()
+    };
+    scaffold
+        .write_targets(&dir, "my-lib", ProjectKind::Lib, &Vars::default())
+        .unwrap();
+
+    for file in [
+        "examples/basic.rs",
+        "benches/bench.rs",
+        "tests/integration.rs",
+    ] {
+        let text = fs::read_to_string(dir.join(file)).unwrap();
+        assert!(text.contains("use my_lib as _;"), "{}", file);
+        assert!(!text.contains("{{"), "{}", file);
+    }
+    fs::remove_dir_all(&dir).unwrap();
+
+    scaffold
+        .write_targets(&dir, "my-app", ProjectKind::Bin, &Vars::default())
+        .unwrap();
+    let test = fs::read_to_string(dir.join("tests/integration.rs")).unwrap();
+    assert!(test.contains("env!(\"CARGO_BIN_EXE_my-app\")"));
+    assert!(!fs::read_to_string(dir.join("examples/basic.rs"))
+        .unwrap()
+        .contains("use "));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+// Test that unknown licenses are rejected
+#[test]
+fn test_scaffold_unknown_license() {
+    let dir = std::env::temp_dir().join("limp_scaffold_unknown");
+    let scaffold = Scaffold {
+        license: Some("WTFPL".to_string()),
+        ..Default::default()
+    };
+    assert!(scaffold.write(&dir, "myapp", &Vars::default()).is_err());
+    assert!(!dir.join("LICENSE").exists());
+}
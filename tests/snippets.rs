@@ -0,0 +1,290 @@
+use limp::cache::Cache;
+use limp::crates::Registry;
+use limp::error::LimpError;
+use limp::snippets::{self, SnippetStatus};
+use limp::storage::{JsonDependency, JsonStorage, SnippetMeta};
+use std::fs;
+use std::time::Duration;
+
+// Test that renaming and removing snippets keeps dependency links in sync
+#[test]
+fn test_rename_remove() {
+    let dir = std::env::temp_dir().join("limp_test_snippets");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("serde.rs"), "use serde::Serialize;\n").unwrap();
+    fs::write(dir.join("unused.rs"), "fn unused() {}\n").unwrap();
+
+    let mut storage = JsonStorage::default();
+    storage.add(JsonDependency {
+        name: "serde".to_string(),
+        version: "1.0.210".to_string(),
+        ..Default::default()
+    });
+    storage
+        .get_mut("serde")
+        .unwrap()
+        .set_snippet(None, dir.join("serde.rs").to_string_lossy().to_string());
+
+    let listed = snippets::list(&dir, &storage).unwrap();
+    assert_eq!(
+        listed.iter().map(|s| s.to_string()).collect::<Vec<_>>(),
+        vec![
+            "serde.rs: serde".to_string(),
+            "unused.rs (unlinked)".to_string()
+        ]
+    );
+
+    let relinked = snippets::rename(&dir, &mut storage, "serde", "serde_derive").unwrap();
+    assert_eq!(relinked, vec!["serde".to_string()]);
+    assert!(storage
+        .get("serde")
+        .unwrap()
+        .snippet()
+        .unwrap()
+        .ends_with("serde_derive.rs"));
+    assert_eq!(
+        snippets::read(&dir, "serde_derive.rs").unwrap(),
+        "use serde::Serialize;\n"
+    );
+
+    let trash = dir.join("trash");
+    let (unlinked, trashed) = snippets::remove(&dir, &trash, &mut storage, "serde_derive").unwrap();
+    assert_eq!(unlinked, vec!["serde".to_string()]);
+    assert!(trashed.is_file());
+    assert_eq!(storage.get("serde").unwrap().snippet(), None);
+    assert!(snippets::read(&dir, "serde_derive").is_err());
+    assert_eq!(
+        snippets::trashed(&trash).unwrap()[0].name,
+        "serde_derive.rs"
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+// Test that trashed snippets are restored newest first and purged when old
+#[test]
+fn test_trash_restore() {
+    let dir = std::env::temp_dir().join("limp_test_snippets_trash");
+    let _ = fs::remove_dir_all(&dir);
+    let trash = dir.join("trash");
+    fs::create_dir_all(&trash).unwrap();
+    fs::write(trash.join("100-error.rs"), "// old\n").unwrap();
+    fs::write(trash.join("200-error.rs"), "// new\n").unwrap();
+    fs::write(trash.join("notes.txt"), "not trashed by limp\n").unwrap();
+    let mut storage = JsonStorage::default();
+
+    let trashed = snippets::trashed(&trash).unwrap();
+    assert_eq!(
+        trashed.iter().map(|t| t.deleted).collect::<Vec<_>>(),
+        vec![200, 100]
+    );
+    assert!(matches!(
+        snippets::restore(&dir, &trash, &mut storage, "serde"),
+        Err(LimpError::SnippetNotFound(_))
+    ));
+
+    let restored = snippets::restore(&dir, &trash, &mut storage, "error").unwrap();
+    assert_eq!(fs::read_to_string(&restored).unwrap(), "// new\n");
+    assert!(storage.snippets.contains_key("error.rs"));
+    assert!(matches!(
+        snippets::restore(&dir, &trash, &mut storage, "error"),
+        Err(LimpError::SnippetConflict(_))
+    ));
+
+    let path = snippets::trash(&trash, &restored).unwrap();
+    assert!(!restored.exists());
+    assert_eq!(
+        snippets::purge_trash(&trash, Duration::from_secs(60)).unwrap(),
+        1
+    );
+    assert!(path.exists());
+    assert!(trash.join("notes.txt").exists());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+// Test that orphaned files are found and dangling links and metadata repaired
+#[test]
+fn test_collect_garbage() {
+    let dir = std::env::temp_dir().join("limp_test_snippets_gc");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    for name in ["serde.rs", "error.rs", "orphan.rs", "tokio.rs"] {
+        fs::write(dir.join(name), "fn f() {}\n").unwrap();
+    }
+
+    let mut storage = JsonStorage::default();
+    for name in ["serde", "tokio"] {
+        storage.add(JsonDependency {
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            ..Default::default()
+        });
+    }
+    let serde = storage.get_mut("serde").unwrap();
+    serde.set_snippet(None, dir.join("serde.rs").to_string_lossy().to_string());
+    serde.set_snippet(Some("derive"), "/moved/away/derive.rs".to_string());
+    storage
+        .get_mut("tokio")
+        .unwrap()
+        .set_snippet(None, "/old/storage/snippets/tokio.rs".to_string());
+    storage
+        .snippets
+        .insert("error.rs".to_string(), SnippetMeta::default());
+    storage
+        .snippets
+        .insert("gone.rs".to_string(), SnippetMeta::default());
+
+    let garbage = snippets::collect_garbage(&dir, &mut storage).unwrap();
+    assert_eq!(garbage.orphans, vec![dir.join("orphan.rs")]);
+    assert_eq!(garbage.forgotten, vec!["gone.rs".to_string()]);
+    assert_eq!(
+        garbage
+            .dangling
+            .iter()
+            .map(|l| l.to_string())
+            .collect::<Vec<_>>(),
+        vec![
+            "serde:derive: /moved/away/derive.rs is missing, unlinked".to_string(),
+            format!(
+                "tokio: /old/storage/snippets/tokio.rs is missing, relinked to {}",
+                dir.join("tokio.rs").display()
+            ),
+        ]
+    );
+    assert!(storage
+        .get("serde")
+        .unwrap()
+        .snippets
+        .get("derive")
+        .is_none());
+    assert_eq!(
+        storage.get("tokio").unwrap().snippet(),
+        Some(&dir.join("tokio.rs").to_string_lossy().to_string())
+    );
+    assert!(snippets::collect_garbage(&dir, &mut storage)
+        .unwrap()
+        .dangling
+        .is_empty());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+// Test that GitHub gist and blob pages are rewritten to their raw files
+#[test]
+fn test_raw_url() {
+    assert_eq!(
+        snippets::raw_url("https://gist.github.com/someone/abc123"),
+        "https://gist.githubusercontent.com/someone/abc123/raw"
+    );
+    assert_eq!(
+        snippets::raw_url("https://github.com/someone/repo/blob/main/src/lib.rs"),
+        "https://raw.githubusercontent.com/someone/repo/main/src/lib.rs"
+    );
+    assert_eq!(
+        snippets::raw_url("https://example.com/snippet.rs"),
+        "https://example.com/snippet.rs"
+    );
+}
+
+// Test that snippets are found by tag, name and contents
+#[test]
+fn test_find() {
+    let dir = std::env::temp_dir().join("limp_test_snippets_find");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("router.rs"), "let app = Router::new();\n").unwrap();
+    fs::write(
+        dir.join("tcp.rs"),
+        "let listener = TcpListener::bind(addr);\n",
+    )
+    .unwrap();
+
+    let mut storage = JsonStorage::default();
+    snippets::set_tags(&mut storage, &dir.join("router.rs"), &["web".to_string()]);
+
+    let names = |keyword: &str| {
+        snippets::find(&dir, &storage, keyword)
+            .unwrap()
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names("WEB"), vec!["router.rs [web] (unlinked)".to_string()]);
+    assert_eq!(names("tcp"), vec!["tcp.rs (unlinked)".to_string()]);
+    assert_eq!(names("listener"), vec!["tcp.rs (unlinked)".to_string()]);
+    assert!(names("grpc").is_empty());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+// Test that checksums ignore line endings but not other changes
+#[test]
+fn test_checksum() {
+    let unix = snippets::checksum(b"fn a() {}\nfn b() {}\n");
+    assert_eq!(unix.len(), 16);
+    assert_eq!(unix, snippets::checksum(b"fn a() {}\r\nfn b() {}\r\n"));
+    assert_ne!(unix, snippets::checksum(b"fn a() {}\nfn c() {}\n"));
+}
+
+// Test that edited, missing and untracked snippets and changed sources are reported,
+// and that refreshing a dependency copies its snippet from the source again
+#[test]
+fn test_verify_refresh() {
+    let root = std::env::temp_dir().join("limp_test_snippets_verify");
+    let _ = fs::remove_dir_all(&root);
+    let dir = root.join("snippets");
+    let source = root.join("source");
+    fs::create_dir_all(&dir).unwrap();
+    fs::create_dir_all(&source).unwrap();
+    let registry = Registry::with_cache(true, Cache::new(root.join("cache"), Duration::ZERO));
+
+    let mut storage = JsonStorage::default();
+    storage.add(JsonDependency {
+        name: "serde".to_string(),
+        version: "1.0.210".to_string(),
+        ..Default::default()
+    });
+    for name in ["serde.rs", "edited.rs", "gone.rs"] {
+        fs::write(source.join(name), "fn stored() {}\n").unwrap();
+        let path = snippets::store(&dir, source.join(name)).unwrap();
+        snippets::record_copy(&mut storage, &path, &source.join(name)).unwrap();
+    }
+    storage
+        .get_mut("serde")
+        .unwrap()
+        .set_snippet(None, dir.join("serde.rs").to_string_lossy().to_string());
+    fs::write(dir.join("edited.rs"), "fn edited() {}\n").unwrap();
+    fs::remove_file(dir.join("gone.rs")).unwrap();
+    fs::write(dir.join("manual.rs"), "fn manual() {}\n").unwrap();
+    fs::write(source.join("serde.rs"), "fn changed() {}\n").unwrap();
+
+    let statuses = |storage: &JsonStorage, sources: bool| {
+        snippets::verify(&registry, &dir, storage, sources)
+            .unwrap()
+            .into_iter()
+            .map(|v| (v.name, v.status))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        statuses(&storage, false),
+        vec![
+            ("edited.rs".to_string(), SnippetStatus::Edited),
+            ("gone.rs".to_string(), SnippetStatus::Missing),
+            ("manual.rs".to_string(), SnippetStatus::Untracked),
+            ("serde.rs".to_string(), SnippetStatus::Unchanged),
+        ]
+    );
+    assert_eq!(statuses(&storage, true)[3].1, SnippetStatus::SourceChanged);
+
+    let refreshed = snippets::refresh(&registry, &dir, &mut storage, Some("serde")).unwrap();
+    assert_eq!(refreshed, vec!["serde.rs".to_string()]);
+    assert_eq!(
+        fs::read_to_string(dir.join("serde.rs")).unwrap(),
+        "fn changed() {}\n"
+    );
+    assert_eq!(statuses(&storage, true)[3].1, SnippetStatus::Unchanged);
+    assert!(snippets::refresh(&registry, &dir, &mut storage, Some("manual")).is_err());
+
+    fs::remove_dir_all(root).unwrap();
+}
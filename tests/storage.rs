@@ -0,0 +1,657 @@
+use limp::cache::Cache;
+use limp::crates::{MockClient, Registry};
+use limp::error::LimpError;
+use limp::migrations;
+use limp::storage::{
+    DependencyKind, DependencySource, FeatureMismatch, GroupKey, JsonDependency, JsonStorage,
+    SnippetMeta, SortKey, StorageFormat, StorageLock, UpdatePolicy, DEFAULT_VARIANT,
+};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn dep(name: &str, version: &str) -> JsonDependency {
+    JsonDependency {
+        name: name.to_string(),
+        version: version.to_string(),
+        ..Default::default()
+    }
+}
+
+// Test for `JsonStorage::version_changes`
+#[test]
+fn test_version_changes() {
+    let mut old = JsonStorage::default();
+    old.add(dep("tokio", "1.40.0"));
+    old.add(dep("serde", "1.0.190"));
+    old.add(dep("rand", "0.8.5"));
+
+    let mut new = old.clone();
+    new.get_mut("serde").unwrap().version = "1.0.204".to_string();
+    new.get_mut("tokio").unwrap().version = "1.42.0".to_string();
+
+    assert_eq!(
+        old.version_changes(&new),
+        vec![
+            (
+                "serde".to_string(),
+                "1.0.190".to_string(),
+                "1.0.204".to_string()
+            ),
+            (
+                "tokio".to_string(),
+                "1.40.0".to_string(),
+                "1.42.0".to_string()
+            ),
+        ]
+    );
+}
+
+// Test that pinned dependencies are left alone by updates
+#[test]
+fn test_update_skips_pinned() {
+    let dir = std::env::temp_dir().join("limp_storage_pinned");
+    let mut client = MockClient::new();
+    for name in ["serde", "tokio"] {
+        client = client.with_crate(json!({
+            "crate": { "name": name, "max_version": "2.0.0" },
+            "versions": [{ "crate": name, "num": "2.0.0" }]
+        }));
+    }
+    let registry =
+        Registry::with_cache(false, Cache::new(&dir, Duration::ZERO)).with_client(Arc::new(client));
+    let mut storage = JsonStorage::default();
+    storage.add(dep("serde", "1.0.0"));
+    storage.add(dep("tokio", "1.0.0"));
+    storage.set_pinned("serde", true).unwrap();
+    assert!(storage.set_pinned("rand", true).is_err());
+
+    assert!(storage.update_all(&registry, 2).is_empty());
+    assert_eq!(storage.get("serde").unwrap().version, "1.0.0");
+    assert_eq!(storage.get("tokio").unwrap().version, "2.0.0");
+    assert_eq!(storage.pinned(&[]), vec!["serde".to_string()]);
+    assert!(storage.pinned(&["tokio".to_string()]).is_empty());
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+// Test that updates stay within a dependency's constraint
+#[test]
+fn test_update_within_constraint() {
+    let dir = std::env::temp_dir().join("limp_storage_constraint");
+    let client = MockClient::new().with_crate(json!({
+        "crate": { "name": "rand", "max_version": "0.9.0" },
+        "versions": [
+            { "crate": "rand", "num": "0.9.0" },
+            { "crate": "rand", "num": "0.8.5" },
+            { "crate": "rand", "num": "0.8.0" },
+        ]
+    }));
+    let registry =
+        Registry::with_cache(false, Cache::new(&dir, Duration::ZERO)).with_client(Arc::new(client));
+    let mut storage = JsonStorage::default();
+    storage.add(dep("rand", "0.8.0"));
+    assert!(matches!(
+        storage.set_constraint("rand", Some("not a requirement")),
+        Err(LimpError::InvalidVersion(_))
+    ));
+    assert!(matches!(
+        storage.set_constraint("tokio", Some("^1")),
+        Err(LimpError::DependencyNotFound(_))
+    ));
+
+    storage.set_constraint("rand", Some("<0.9")).unwrap();
+    assert!(storage.update_all(&registry, 1).is_empty());
+    assert_eq!(storage.get("rand").unwrap().version, "0.8.5");
+
+    storage.set_constraint("rand", Some(">=1")).unwrap();
+    assert!(matches!(
+        storage.update_all(&registry, 1).as_slice(),
+        [(_, LimpError::VersionNotFound(_))]
+    ));
+
+    storage.set_constraint("rand", None).unwrap();
+    assert!(storage.update_all(&registry, 1).is_empty());
+    assert_eq!(storage.get("rand").unwrap().version, "0.9.0");
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+// Test that pre-releases are only updated to when allowed
+#[test]
+fn test_update_prerelease() {
+    let dir = std::env::temp_dir().join("limp_storage_prerelease");
+    let client = MockClient::new().with_crate(json!({
+        "crate": { "name": "axum", "max_version": "0.8.0-rc.1" },
+        "versions": [
+            { "crate": "axum", "num": "0.8.0-rc.1" },
+            { "crate": "axum", "num": "0.7.9" },
+        ]
+    }));
+    let registry =
+        Registry::with_cache(false, Cache::new(&dir, Duration::ZERO)).with_client(Arc::new(client));
+    let mut storage = JsonStorage::default();
+    storage.add(dep("axum", "0.7.0"));
+    let names = ["axum".to_string()];
+
+    let mut stable = storage.clone();
+    assert!(stable.update_all(&registry, 1).is_empty());
+    assert_eq!(stable.get("axum").unwrap().version, "0.7.9");
+
+    let mut pre = storage.clone();
+    let policy = UpdatePolicy {
+        allow_prerelease: true,
+        ..Default::default()
+    };
+    assert!(pre
+        .update_many(&names, &registry, 1, policy)
+        .errors
+        .is_empty());
+    assert_eq!(pre.get("axum").unwrap().version, "0.8.0-rc.1");
+
+    storage.set_allow_prerelease("axum", true).unwrap();
+    assert!(storage.update_all(&registry, 1).is_empty());
+    assert_eq!(storage.get("axum").unwrap().version, "0.8.0-rc.1");
+
+    // a version newer than the latest release is kept
+    let mut newer = JsonStorage::default();
+    newer.add(dep("axum", "0.8.0"));
+    assert!(newer.update_all(&registry, 1).is_empty());
+    assert_eq!(newer.get("axum").unwrap().version, "0.8.0");
+
+    // a dependency stored at a pre-release isn't moved back to a release
+    let mut stored = JsonStorage::default();
+    stored.add(dep("axum", "0.8.0-rc.1"));
+    assert!(stored.update_all(&registry, 1).is_empty());
+    assert_eq!(stored.get("axum").unwrap().version, "0.8.0-rc.1");
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+// Test that stored features missing from the latest version follow the policy
+#[test]
+fn test_update_feature_mismatch() {
+    let dir = std::env::temp_dir().join("limp_storage_feature_mismatch");
+    let client = MockClient::new().with_crate(json!({
+        "crate": { "name": "serde", "max_version": "2.0.0" },
+        "versions": [{ "crate": "serde", "num": "2.0.0", "features": { "std": [] } }]
+    }));
+    let registry =
+        Registry::with_cache(false, Cache::new(&dir, Duration::ZERO)).with_client(Arc::new(client));
+    let mut storage = JsonStorage::default();
+    storage.add(JsonDependency {
+        features: Some(vec!["derive".to_string(), "std".to_string()]),
+        ..dep("serde", "1.0.0")
+    });
+    let names = ["serde".to_string()];
+    let policy = |on_feature_mismatch| UpdatePolicy {
+        on_feature_mismatch,
+        ..Default::default()
+    };
+
+    let mut kept = storage.clone();
+    let outcome = kept.update_many(&names, &registry, 1, policy(FeatureMismatch::Keep));
+    assert!(outcome.errors.is_empty());
+    assert_eq!(
+        outcome.mismatches,
+        vec![("serde".to_string(), vec!["derive".to_string()])]
+    );
+    assert_eq!(kept.get("serde").unwrap().version, "1.0.0");
+
+    let mut dropped = storage.clone();
+    let outcome = dropped.update_many(&names, &registry, 1, policy(FeatureMismatch::Drop));
+    assert_eq!(outcome.mismatches.len(), 1);
+    let serde = dropped.get("serde").unwrap();
+    assert_eq!(serde.version, "2.0.0");
+    assert_eq!(serde.features, Some(vec!["std".to_string()]));
+
+    let mut failed = storage.clone();
+    let outcome = failed.update_many(&names, &registry, 1, policy(FeatureMismatch::Fail));
+    assert!(matches!(
+        outcome.errors.as_slice(),
+        [(_, LimpError::IncompatibleFeatures(_))]
+    ));
+    assert_eq!(failed.get("serde").unwrap().version, "1.0.0");
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+// Test for `JsonStorage::list` ordering and filtering
+#[test]
+fn test_list() {
+    let mut storage = JsonStorage::default();
+    storage.add(dep("tokio", "1.40.0"));
+    storage.add(dep("serde_json", "1.0.128"));
+    storage.add(JsonDependency {
+        snippets: [(DEFAULT_VARIANT.to_string(), "snippets/serde.rs".to_string())].into(),
+        ..dep("serde", "1.0.210")
+    });
+    storage.get_mut("tokio").unwrap().updated = Some(u64::MAX);
+
+    let names = |deps: Vec<&JsonDependency>| {
+        deps.iter()
+            .map(|d| d.name.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    assert_eq!(
+        names(storage.list(SortKey::Name, None, false)),
+        "serde serde_json tokio"
+    );
+    assert_eq!(
+        names(storage.list(SortKey::Version, None, false)),
+        "tokio serde serde_json"
+    );
+    assert_eq!(
+        names(storage.list(SortKey::Updated, None, false))
+            .split(' ')
+            .next(),
+        Some("tokio")
+    );
+    assert_eq!(
+        names(storage.list(SortKey::Name, Some("serde"), false)),
+        "serde serde_json"
+    );
+    assert_eq!(names(storage.list(SortKey::Name, None, true)), "serde");
+}
+
+// Test for `JsonStorage::list` by version with pre-release and build suffixes
+#[test]
+fn test_list_prerelease_versions() {
+    let mut storage = JsonStorage::default();
+    storage.add(dep("axum", "0.7.5"));
+    storage.add(dep("clap", "5.0.0-beta.1"));
+    storage.add(dep("tokio", "1.40.0+build.7"));
+    storage.add(dep("rand", "1.40.0-rc.2"));
+
+    let names = storage
+        .list(SortKey::Version, None, false)
+        .iter()
+        .map(|d| d.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["clap", "tokio", "rand", "axum"]);
+}
+
+// Test for `JsonStorage::group` by each key
+#[test]
+fn test_group() {
+    let mut storage = JsonStorage::default();
+    storage.add(dep("tokio", "1.40.0"));
+    storage.add(JsonDependency {
+        kind: DependencyKind::Dev,
+        ..dep("tempfile", "3.0.0")
+    });
+    storage.add(JsonDependency {
+        snippets: [(DEFAULT_VARIANT.to_string(), "snippets/serde.rs".to_string())].into(),
+        ..dep("serde", "1.0.210")
+    });
+    storage.snippets.insert(
+        "serde.rs".to_string(),
+        SnippetMeta {
+            tags: vec!["web".to_string(), "json".to_string()],
+            ..Default::default()
+        },
+    );
+    let deps = storage.list(SortKey::Name, None, false);
+
+    let groups = |by: GroupKey| {
+        storage
+            .group(&deps, by)
+            .into_iter()
+            .map(|(name, deps)| {
+                let names = deps.iter().map(|d| d.name.as_str()).collect::<Vec<_>>();
+                format!("{}: {}", name, names.join(" "))
+            })
+            .collect::<Vec<String>>()
+    };
+    assert_eq!(
+        groups(GroupKey::Kind),
+        ["dev: tempfile", "normal: serde tokio"]
+    );
+    assert_eq!(groups(GroupKey::Letter), ["S: serde", "T: tempfile tokio"]);
+    assert_eq!(
+        groups(GroupKey::Snippet),
+        ["with snippet: serde", "without snippet: tempfile tokio"]
+    );
+    assert_eq!(
+        groups(GroupKey::Tag),
+        ["json: serde", "untagged: tempfile tokio", "web: serde"]
+    );
+}
+
+// Test for rendering git and path dependencies
+#[test]
+fn test_display_sources() {
+    let git = JsonDependency {
+        source: DependencySource::Git {
+            url: "https://github.com/me/mylib".to_string(),
+            branch: Some("main".to_string()),
+            tag: None,
+            rev: None,
+        },
+        ..dep("mylib", "")
+    };
+    assert_eq!(
+        git.to_string(),
+        r#"mylib = {git = "https://github.com/me/mylib", branch = "main"}"#
+    );
+
+    let path = JsonDependency {
+        source: DependencySource::Path {
+            path: "../local".to_string(),
+        },
+        features: Some(vec!["cli".to_string()]),
+        ..dep("local", "0.1")
+    };
+    assert_eq!(
+        path.to_string(),
+        r#"local = {version = "0.1", path = "../local", features = ["cli"]}"#
+    );
+}
+
+// Test for `JsonStorage::merge` conflict handling
+#[test]
+fn test_merge() {
+    let mut storage = JsonStorage::default();
+    storage.add(JsonDependency {
+        snippets: [(DEFAULT_VARIANT.to_string(), "snippets/serde.rs".to_string())].into(),
+        ..dep("serde", "1.0.190")
+    });
+    storage.add(dep("tokio", "1.40.0"));
+    storage.add(dep("rand", "0.8.5"));
+
+    let mut asked = vec![];
+    let summary = storage
+        .merge(
+            vec![
+                dep("serde", "1.0.210"),
+                dep("tokio", "1.42.0"),
+                dep("rand", "0.8.5"),
+                dep("clap", "4.5.23"),
+            ],
+            |stored, _| {
+                asked.push(stored.name.clone());
+                Ok(stored.name == "serde")
+            },
+        )
+        .unwrap();
+
+    assert_eq!(asked, vec!["serde".to_string(), "tokio".to_string()]);
+    assert_eq!(summary.added, vec!["clap".to_string()]);
+    assert_eq!(summary.replaced, vec!["serde".to_string()]);
+    assert_eq!(summary.kept, vec!["tokio".to_string()]);
+
+    let serde = storage.get("serde").unwrap();
+    assert_eq!(serde.version, "1.0.210");
+    assert_eq!(serde.snippet(), Some(&"snippets/serde.rs".to_string()));
+    assert_eq!(storage.get("tokio").unwrap().version, "1.40.0");
+}
+
+// Test that unversioned files are migrated in place
+#[test]
+fn test_load_migrates_legacy_file() {
+    let dir = std::env::temp_dir().join("limp_test_migrate");
+    let path = dir.join("dependencies.json");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        &path,
+        r#"{"dependencies":{"serde":{"version":"1.0.210","features":["derive"],"path_to_snippet":"serde.rs"}}}"#,
+    )
+    .unwrap();
+
+    let storage = JsonStorage::load(&path).unwrap();
+    assert_eq!(storage.schema_version, migrations::SCHEMA_VERSION);
+    assert_eq!(storage.get("serde").unwrap().name, "serde");
+    assert_eq!(
+        storage.get("serde").unwrap().snippet().map(String::as_str),
+        dir.join("serde.rs").to_str()
+    );
+
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert!(saved.contains("\"schema_version\""));
+
+    std::fs::write(&path, r#"{"schema_version":999,"dependencies":{}}"#).unwrap();
+    assert!(JsonStorage::load(&path).is_err());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+// Test for rendering optional and renamed dependencies
+#[test]
+fn test_display_optional_rename() {
+    let dep = JsonDependency {
+        optional: true,
+        rename: Some("tok".to_string()),
+        ..dep("tokio", "1.0.0")
+    };
+    assert_eq!(
+        dep.to_string(),
+        r#"tok = {version = "1.0.0", package = "tokio", optional = true}"#
+    );
+    assert_eq!(
+        dep.as_workspace().to_string(),
+        "tok = { workspace = true, optional = true }"
+    );
+}
+
+// Test that `latest` resolves the newest release and malformed versions are errors
+#[test]
+fn test_new_full_version_keywords() {
+    let dir = std::env::temp_dir().join("limp_test_version_keywords");
+    let client = MockClient::new().with_crate(json!({
+        "crate": { "name": "serde", "max_version": "1.0.210" },
+        "versions": [
+            { "crate": "serde", "num": "1.0.210" },
+            { "crate": "serde", "num": "1.0.0" }
+        ]
+    }));
+    let registry =
+        Registry::with_cache(false, Cache::new(&dir, Duration::ZERO)).with_client(Arc::new(client));
+
+    let latest = JsonDependency::new_full("serde", Some("latest"), None, None, false, &registry);
+    assert_eq!(latest.unwrap().version, "1.0.210");
+    for version in ["1.0.0.0", "newest"] {
+        assert!(matches!(
+            JsonDependency::new_full("serde", Some(version), None, None, false, &registry),
+            Err(LimpError::InvalidVersion(v)) if v == version
+        ));
+    }
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+// Test that snippet paths are stored relative to the storage directory
+#[test]
+fn test_snippet_paths_are_portable() {
+    let dir = std::env::temp_dir().join("limp_test_portable");
+    let _ = std::fs::remove_dir_all(&dir);
+    let path = dir.join("dependencies.json");
+    let snippet = dir.join("snippets").join("serde.rs");
+    let mut storage = JsonStorage::default();
+    storage.add(JsonDependency {
+        snippets: [(
+            DEFAULT_VARIANT.to_string(),
+            snippet.to_string_lossy().to_string(),
+        )]
+        .into(),
+        ..dep("serde", "1.0.210")
+    });
+    storage.save(&path).unwrap();
+    assert!(std::fs::read_to_string(&path)
+        .unwrap()
+        .contains(r#""default":"snippets/serde.rs""#));
+
+    // the storage keeps working after moving the whole directory
+    let moved = std::env::temp_dir().join("limp_test_portable_moved");
+    let _ = std::fs::remove_dir_all(&moved);
+    std::fs::rename(&dir, &moved).unwrap();
+    let storage = JsonStorage::load(moved.join("dependencies.json")).unwrap();
+    assert_eq!(
+        storage.get("serde").unwrap().snippet().map(String::as_str),
+        moved.join("snippets").join("serde.rs").to_str()
+    );
+
+    std::fs::remove_dir_all(moved).unwrap();
+}
+
+// Test that absolute snippet paths of older files become relative
+#[test]
+fn test_load_migrates_absolute_snippet_paths() {
+    let dir = std::env::temp_dir().join("limp_test_migrate_paths");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("dependencies.json");
+    let inside = dir.join("snippets").join("serde.rs");
+    let doc = json!({
+        "schema_version": 2,
+        "dependencies": {
+            "serde": {
+                "name": "serde",
+                "version": "1.0.210",
+                "snippets": { "default": inside.to_string_lossy() }
+            },
+            "tokio": {
+                "name": "tokio",
+                "version": "1.40.0",
+                "snippets": { "default": "/home/alice/.config/limp/snippets/tokio.rs" }
+            }
+        }
+    });
+    std::fs::write(&path, doc.to_string()).unwrap();
+
+    let storage = JsonStorage::load(&path).unwrap();
+    assert_eq!(
+        storage.get("serde").unwrap().snippet().map(String::as_str),
+        inside.to_str()
+    );
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert!(saved.contains(r#""default":"snippets/serde.rs""#));
+    if cfg!(windows) {
+        // a path written on another OS is found in the snippets folder
+        assert!(saved.contains(r#""default":"snippets/tokio.rs""#));
+    } else {
+        assert!(saved.contains("/home/alice/.config/limp/snippets/tokio.rs"));
+    }
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+// Test that a held storage lock makes other users wait and then fail
+#[test]
+fn test_storage_lock() {
+    let dir = std::env::temp_dir().join("limp_test_storage_lock");
+    let _ = std::fs::remove_dir_all(&dir);
+    let path = dir.join("dependencies.json");
+
+    let lock = StorageLock::acquire(&path, Duration::ZERO).unwrap();
+    assert!(dir.join("dependencies.json.lock").exists());
+    assert!(matches!(
+        StorageLock::acquire(&path, Duration::from_millis(100)),
+        Err(LimpError::StorageLocked(_))
+    ));
+
+    drop(lock);
+    assert!(!dir.join("dependencies.json.lock").exists());
+    let mut storage = JsonStorage::load(&path).unwrap();
+    storage.add(dep("serde", "1.0.0"));
+    storage.save(&path).unwrap();
+    assert!(JsonStorage::load(&path)
+        .unwrap()
+        .dependencies
+        .contains_key("serde"));
+}
+
+// Test that changes made under a held lock don't overwrite each other
+#[test]
+fn test_storage_lock_across_changes() {
+    let dir = std::env::temp_dir().join("limp_test_storage_lock_changes");
+    let _ = std::fs::remove_dir_all(&dir);
+    let path = dir.join("dependencies.json");
+
+    std::thread::scope(|s| {
+        for name in ["serde", "tokio"] {
+            let path = &path;
+            s.spawn(move || {
+                let lock = StorageLock::acquire(path, Duration::from_secs(10)).unwrap();
+                let mut storage = JsonStorage::load_locked(path, &lock).unwrap();
+                std::thread::sleep(Duration::from_millis(100));
+                storage.add(dep(name, "1.0.0"));
+                storage.save_locked(path, &lock).unwrap();
+            });
+        }
+    });
+
+    let storage = JsonStorage::load(&path).unwrap();
+    assert!(storage.dependencies.contains_key("serde"));
+    assert!(storage.dependencies.contains_key("tokio"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+// Test for the TOML storage, converting from JSON and keeping comments
+#[test]
+fn test_toml_storage() {
+    let dir = std::env::temp_dir().join("limp_test_toml_storage");
+    let _ = std::fs::remove_dir_all(&dir);
+    let json = dir.join("dependencies.json");
+    let path = dir.join("dependencies.toml");
+    assert_eq!(StorageFormat::of(&path), StorageFormat::Toml);
+
+    let mut storage = JsonStorage::default();
+    storage.add(dep("serde", "1.0.0"));
+    storage.save(&json).unwrap();
+
+    let mut storage = JsonStorage::load(&path).unwrap();
+    assert!(storage.dependencies.contains_key("serde"));
+    assert!(!json.exists());
+    assert!(dir.join("dependencies.json.bak").exists());
+
+    let content = std::fs::read_to_string(&path).unwrap();
+    let content = content.replace(
+        "[dependencies.serde]",
+        "# pinned for the derive macros\n[dependencies.serde]",
+    );
+    std::fs::write(&path, content).unwrap();
+    storage.add(dep("tokio", "1.0.0"));
+    storage.save(&path).unwrap();
+
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert!(content.contains("# pinned for the derive macros\n[dependencies.serde]"));
+    let storage = JsonStorage::load(&path).unwrap();
+    assert_eq!(storage.dependencies.len(), 2);
+    assert_eq!(storage.dependencies["tokio"].version, "1.0.0");
+}
+
+// Test for the SQLite storage and its log of changes
+#[cfg(feature = "sqlite")]
+#[test]
+fn test_sqlite_storage() {
+    let dir = std::env::temp_dir().join("limp_test_sqlite_storage");
+    let _ = std::fs::remove_dir_all(&dir);
+    let path = dir.join("dependencies.db");
+    assert_eq!(StorageFormat::of(&path), StorageFormat::Sqlite);
+
+    let mut storage = JsonStorage::load(&path).unwrap();
+    storage.add(dep("serde", "1.0.0"));
+    storage.add(dep("tokio", "1.0.0"));
+    storage.save(&path).unwrap();
+    storage.add(dep("serde", "1.0.1"));
+    storage.dependencies.remove("tokio");
+    storage.save(&path).unwrap();
+
+    let storage = JsonStorage::load(&path).unwrap();
+    assert_eq!(storage.dependencies["serde"].version, "1.0.1");
+    assert!(!storage.dependencies.contains_key("tokio"));
+    let actions = limp::sqlite::events(&path, Some("serde"))
+        .unwrap()
+        .into_iter()
+        .map(|e| e.action)
+        .collect::<Vec<_>>();
+    assert_eq!(actions, vec!["added", "updated"]);
+    assert_eq!(limp::sqlite::events(&path, None).unwrap().len(), 4);
+
+    let stats = limp::sqlite::stats(&path).unwrap();
+    assert_eq!(stats.dependencies, 1);
+    assert_eq!((stats.added, stats.updated, stats.removed), (2, 1, 1));
+    assert_eq!(stats.most_updated, vec![("serde".to_string(), 1)]);
+    assert!(stats.since.is_some());
+}
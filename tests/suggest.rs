@@ -0,0 +1,21 @@
+use limp::suggest::{closest, distance};
+
+// Test for the edit distance
+#[test]
+fn test_distance() {
+    assert_eq!(distance("serde", "serde"), 0);
+    assert_eq!(distance("serd", "serde"), 1);
+    assert_eq!(distance("rt_multi_thread", "rt-multi-thread"), 0);
+    assert_eq!(distance("tokio", "toki0"), 1);
+    assert_eq!(distance("", "abc"), 3);
+}
+
+// Test that only likely typos are suggested
+#[test]
+fn test_closest() {
+    let features = ["full", "macros", "rt", "rt-multi-thread"];
+    assert_eq!(closest("macro", &features), Some("macros"));
+    assert_eq!(closest("rt-multi", &features), Some("rt-multi-thread"));
+    assert_eq!(closest("fs", &features), None);
+    assert_eq!(closest("derive", &features), None);
+}